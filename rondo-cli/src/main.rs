@@ -1,6 +1,7 @@
 //! CLI for the rondo time-series storage engine.
 //!
-//! Provides commands for inspecting, querying, and benchmarking rondo stores.
+//! Provides commands for inspecting, querying, importing, exporting, and
+//! benchmarking rondo stores.
 
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
@@ -43,6 +44,63 @@ enum Commands {
         /// Output format.
         #[arg(long, default_value = "csv")]
         format: OutputFormat,
+
+        /// Downsample the result to at most this many points.
+        #[arg(long)]
+        max_points: Option<usize>,
+    },
+
+    /// Evaluate a PromQL expression against a store.
+    Promql {
+        /// Path to the store directory.
+        store_path: PathBuf,
+
+        /// PromQL expression (e.g., "sum by (host) (rate(requests_total[5m]))").
+        expr: String,
+
+        /// Evaluate over this range ending now (e.g., "1h") instead of
+        /// once at the current time.
+        #[arg(long)]
+        range: Option<String>,
+
+        /// Evaluation step for range queries; also selects the storage tier.
+        #[arg(long, default_value = "15s")]
+        step: String,
+
+        /// Output format.
+        #[arg(long, default_value = "csv")]
+        format: OutputFormat,
+    },
+
+    /// Import InfluxDB line protocol into a store.
+    ///
+    /// Each field becomes a series named `<measurement>_<field>` with the
+    /// tags as labels; a `value` field becomes `<measurement>`, so the output
+    /// of `export` imports back into the same series.
+    Import {
+        /// Path to the store directory.
+        store_path: PathBuf,
+
+        /// Line protocol file to read ("-" or omitted for stdin).
+        file: Option<PathBuf>,
+
+        /// Timestamp precision of the input (ns, us, ms, or s).
+        #[arg(long, default_value = "ns")]
+        precision: String,
+    },
+
+    /// Export series from a store as InfluxDB line protocol.
+    Export {
+        /// Path to the store directory.
+        store_path: PathBuf,
+
+        /// Only export this series (e.g., "cpu" or "cpu{host=web1}").
+        #[arg(long)]
+        series: Option<String>,
+
+        /// Time range to export (e.g., "1h", "30m", "7d", or "all").
+        #[arg(long, default_value = "1h")]
+        range: String,
     },
 
     /// Run a write-path microbenchmark.
@@ -77,7 +135,25 @@ fn main() {
             range,
             tier,
             format,
-        } => cmd_query(&store_path, &series, &range, &tier, &format),
+            max_points,
+        } => cmd_query(&store_path, &series, &range, &tier, &format, max_points),
+        Commands::Promql {
+            store_path,
+            expr,
+            range,
+            step,
+            format,
+        } => cmd_promql(&store_path, &expr, range.as_deref(), &step, &format),
+        Commands::Import {
+            store_path,
+            file,
+            precision,
+        } => cmd_import(&store_path, file.as_ref(), &precision),
+        Commands::Export {
+            store_path,
+            series,
+            range,
+        } => cmd_export(&store_path, series.as_deref(), &range),
         Commands::Bench { points, series } => cmd_bench(points, series),
     };
 
@@ -221,17 +297,9 @@ fn cmd_query(
     range: &str,
     tier_str: &str,
     format: &OutputFormat,
+    max_points: Option<usize>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let meta_path = store_path.join("meta.json");
-    if !meta_path.exists() {
-        return Err(format!("No store found at '{}'", store_path.display()).into());
-    }
-
-    let meta_data = std::fs::read_to_string(&meta_path)?;
-    let meta: serde_json::Value = serde_json::from_str(&meta_data)?;
-    let schemas = reconstruct_schemas(&meta);
-
-    let store = rondo::Store::open(store_path, schemas)?;
+    let store = open_store(store_path)?;
 
     // Parse series selector: "name" or "name{key=value,...}"
     let (metric_name, label_filter) = parse_series_selector(series_name);
@@ -260,11 +328,16 @@ fn cmd_query(
     };
 
     // Query data
+    let mut options = rondo::QueryOptions::new();
+    if let Some(max_points) = max_points {
+        options = options.with_max_points(max_points);
+    }
+
     let result = if tier_str == "auto" {
-        store.query_auto(*handle, start_ns, end_ns)?
+        store.query_auto_with(*handle, start_ns, end_ns, &options)?
     } else {
         let tier: usize = tier_str.parse()?;
-        store.query(*handle, tier, start_ns, end_ns)?
+        store.query_with(*handle, tier, start_ns, end_ns, &options)?
     };
 
     let tier_used = result.tier_used();
@@ -306,6 +379,184 @@ fn cmd_query(
     Ok(())
 }
 
+/// Implements `rondo promql <store_path> <expr>`.
+fn cmd_promql(
+    store_path: &PathBuf,
+    expr: &str,
+    range: Option<&str>,
+    step: &str,
+    format: &OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use rondo::promql::{Engine, Value};
+
+    let store = open_store(store_path)?;
+    let engine = Engine::new(&store);
+
+    #[allow(clippy::cast_possible_truncation)] // Epoch nanos fit in u64 until year 2554
+    let now_ns = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_nanos() as u64;
+
+    let value = match range {
+        Some(range) => {
+            let range_ns = parse_duration(range)?;
+            let step = rondo::promql::parse_duration(step)?;
+            engine.range_query(expr, now_ns.saturating_sub(range_ns), now_ns, step)?
+        }
+        None => engine.instant_query(expr, now_ns)?,
+    };
+
+    // Flatten every result type into (labels, timestamp, value) rows
+    let rows: Vec<(String, u64, f64)> = match value {
+        Value::Scalar {
+            timestamp_ns,
+            value,
+        } => vec![(String::new(), timestamp_ns, value)],
+        Value::Vector(samples) => samples
+            .into_iter()
+            .map(|s| (format_labels(&s.labels), s.timestamp_ns, s.value))
+            .collect(),
+        Value::Matrix(series) => series
+            .into_iter()
+            .flat_map(|s| {
+                let labels = format_labels(&s.labels);
+                s.points
+                    .into_iter()
+                    .map(move |(ts, val)| (labels.clone(), ts, val))
+            })
+            .collect(),
+    };
+
+    match format {
+        OutputFormat::Csv => {
+            println!("# expr={expr}, points={}", rows.len());
+            println!("series,timestamp_ns,value");
+            for (labels, ts, val) in &rows {
+                println!("\"{}\",{ts},{val}", labels.replace('"', "\"\""));
+            }
+        }
+        OutputFormat::Json => {
+            let data: Vec<serde_json::Value> = rows
+                .iter()
+                .map(|(labels, ts, val)| {
+                    serde_json::json!({
+                        "series": labels,
+                        "timestamp_ns": ts,
+                        "value": val,
+                    })
+                })
+                .collect();
+
+            let output = serde_json::json!({
+                "expr": expr,
+                "count": rows.len(),
+                "data": data,
+            });
+
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Implements `rondo import <store_path> [file]`.
+fn cmd_import(
+    store_path: &PathBuf,
+    file: Option<&PathBuf>,
+    precision: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Read;
+
+    let precision = rondo::line_protocol::Precision::from_name(precision)
+        .ok_or_else(|| format!("Unknown precision: '{precision}'. Use ns, us, ms, or s."))?;
+
+    let input = match file {
+        Some(path) if path.as_os_str() != "-" => std::fs::read_to_string(path)?,
+        _ => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            input
+        }
+    };
+
+    let mut store = open_store(store_path)?;
+    let points = rondo::line_protocol::parse_with_precision(&input, precision)?;
+
+    #[allow(clippy::cast_possible_truncation)] // Epoch nanos fit in u64 until year 2554
+    let now_ns = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_nanos() as u64;
+    let summary = rondo::line_protocol::write(&mut store, &points, now_ns);
+
+    println!(
+        "Imported {} values from {} lines ({} rejected)",
+        summary.accepted,
+        points.len(),
+        summary.rejected
+    );
+    if let Some(error) = summary.error {
+        eprintln!("First rejection: {error}");
+    }
+
+    Ok(())
+}
+
+/// Implements `rondo export <store_path>`.
+fn cmd_export(
+    store_path: &PathBuf,
+    series: Option<&str>,
+    range: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let store = open_store(store_path)?;
+
+    let range_ns = parse_duration(range)?;
+    let (start_ns, end_ns) = if range_ns == u64::MAX {
+        (0, u64::MAX)
+    } else {
+        #[allow(clippy::cast_possible_truncation)] // Epoch nanos fit in u64 until year 2554
+        let now_ns = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_nanos() as u64;
+        (now_ns.saturating_sub(range_ns), now_ns)
+    };
+
+    let selector = series.map(parse_series_selector);
+    let mut exports = Vec::new();
+    for handle in store.handles() {
+        let Some((name, labels)) = store.series_info(&handle) else {
+            continue;
+        };
+        if let Some((metric_name, label_filter)) = &selector
+            && (name != *metric_name || !matches_labels(labels, label_filter))
+        {
+            continue;
+        }
+
+        let result =
+            store.query_auto_with(handle, start_ns, end_ns, &rondo::QueryOptions::new())?;
+        let segments = result.segments().to_vec();
+        let points: Vec<_> = result.collect();
+
+        // One export per segment, so each batch names the tier it came from
+        for segment in segments {
+            exports.push(rondo::export::SeriesExport {
+                handle,
+                points: points
+                    .iter()
+                    .copied()
+                    .filter(|&(ts, _)| ts >= segment.start_ns && ts < segment.end_ns)
+                    .collect(),
+                tier: segment.tier,
+            });
+        }
+    }
+
+    print!("{}", rondo::line_protocol::encode(&exports, &store)?);
+
+    Ok(())
+}
+
 /// Implements `rondo bench`.
 #[allow(clippy::cast_precision_loss)] // Benchmark stats are fine with f64 precision
 fn cmd_bench(points: u64, series_count: u32) -> Result<(), Box<dyn std::error::Error>> {
@@ -373,6 +624,38 @@ fn cmd_bench(points: u64, series_count: u32) -> Result<(), Box<dyn std::error::E
     Ok(())
 }
 
+/// Opens an existing store using the schemas recorded in its metadata.
+fn open_store(store_path: &PathBuf) -> Result<rondo::Store, Box<dyn std::error::Error>> {
+    let meta_path = store_path.join("meta.json");
+    if !meta_path.exists() {
+        return Err(format!("No store found at '{}'", store_path.display()).into());
+    }
+
+    let meta_data = std::fs::read_to_string(&meta_path)?;
+    let meta: serde_json::Value = serde_json::from_str(&meta_data)?;
+    let schemas = reconstruct_schemas(&meta);
+
+    Ok(rondo::Store::open(store_path, schemas)?)
+}
+
+/// Formats a PromQL label set as `name{key="value",...}`.
+fn format_labels(labels: &rondo::promql::Labels) -> String {
+    let name = labels
+        .get(rondo::promql::METRIC_NAME_LABEL)
+        .map_or("", String::as_str);
+    let pairs: Vec<String> = labels
+        .iter()
+        .filter(|(k, _)| k.as_str() != rondo::promql::METRIC_NAME_LABEL)
+        .map(|(k, v)| format!("{k}={v:?}"))
+        .collect();
+
+    if pairs.is_empty() && !name.is_empty() {
+        name.to_string()
+    } else {
+        format!("{name}{{{}}}", pairs.join(","))
+    }
+}
+
 /// Parses a human-readable duration string (e.g., "1h", "30m", "7d") to nanoseconds.
 ///
 /// Special value `"all"` returns `u64::MAX` to indicate "query all data".
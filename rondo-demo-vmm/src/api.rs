@@ -3,18 +3,56 @@
 //! Uses `std::net::TcpListener` — no external HTTP framework needed.
 //! Endpoints:
 //!
+//! - `GET /metrics`         — latest value of every series in Prometheus text
+//!   format (OpenMetrics when the `Accept` header asks for it)
 //! - `GET /metrics/health`  — liveness check
 //! - `GET /metrics/info`    — store metadata (JSON)
-//! - `GET /metrics/query?series=<name>&start=<ns>&end=<ns>` — time-series data (JSON)
+//! - `GET /metrics/query?series=<name>&start=<ns>&end=<ns>[&max_points=<n>]` — time-series data (JSON)
+//! - `GET|POST /api/v1/...` — Prometheus HTTP query API (PromQL), so Grafana
+//!   can use the VMM as a Prometheus data source
+//! - `POST /api/v1/read`    — Prometheus remote-read (snappy protobuf), so a
+//!   central Prometheus can pull history on demand
+//! - `POST /api/v1/write`   — Prometheus remote-write receiver (snappy
+//!   protobuf), so local agents can push into the `remote_write` schema
+//! - `POST /v1/metrics`     — OTLP/HTTP metrics receiver (protobuf), so guest
+//!   sidecars can push into the `guest` schema
 
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::net::TcpListener;
 use std::sync::{Arc, Mutex};
 
-use crate::metrics::VmMetrics;
+use rondo::otlp_receiver::{OtlpReceiver, OtlpReceiverConfig};
+use rondo::remote_write_receiver::{RemoteWriteReceiver, RemoteWriteReceiverConfig};
+
+use crate::metrics::{GUEST_MAX_SERIES, REMOTE_WRITE_MAX_SERIES, VmMetrics};
+
+/// Largest request body accepted for `POST` queries.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Path of the Prometheus remote-read endpoint.
+const REMOTE_READ_PATH: &str = "/api/v1/read";
+
+/// Path of the Prometheus remote-write endpoint.
+const REMOTE_WRITE_PATH: &str = "/api/v1/write";
+
+/// Path of the OTLP/HTTP metrics endpoint.
+const OTLP_METRICS_PATH: &str = "/v1/metrics";
+
+/// Maximum number of guest series per metric name accepted over OTLP.
+const GUEST_MAX_SERIES_PER_METRIC: u32 = 50;
+
+/// Maximum number of pushed series per metric name accepted over remote-write.
+const REMOTE_WRITE_MAX_SERIES_PER_METRIC: u32 = 50;
 
 /// Runs the HTTP API server (blocking — intended for a dedicated thread).
-pub fn run_api_server(metrics: Arc<Mutex<VmMetrics>>, port: u16) {
+///
+/// `external_labels` are attached to series returned by remote-read, matching
+/// what the remote-write export thread pushes.
+pub fn run_api_server(
+    metrics: Arc<Mutex<VmMetrics>>,
+    port: u16,
+    external_labels: &[(String, String)],
+) {
     let addr = format!("0.0.0.0:{port}");
     let listener = match TcpListener::bind(&addr) {
         Ok(l) => l,
@@ -24,6 +62,22 @@ pub fn run_api_server(metrics: Arc<Mutex<VmMetrics>>, port: u16) {
         }
     };
 
+    // Tagging guest series routes them to the `guest` schema
+    let mut receiver = OtlpReceiver::new(
+        OtlpReceiverConfig::new()
+            .with_label("source", "otlp")
+            .with_max_series(GUEST_MAX_SERIES)
+            .with_max_series_per_metric(GUEST_MAX_SERIES_PER_METRIC),
+    );
+
+    // Likewise, pushed series go to the `remote_write` schema
+    let mut remote_write = RemoteWriteReceiver::new(
+        RemoteWriteReceiverConfig::new()
+            .with_label("source", "remote_write")
+            .with_max_series(REMOTE_WRITE_MAX_SERIES)
+            .with_max_series_per_metric(REMOTE_WRITE_MAX_SERIES_PER_METRIC),
+    );
+
     for stream in listener.incoming() {
         let stream = match stream {
             Ok(s) => s,
@@ -36,7 +90,13 @@ pub fn run_api_server(metrics: Arc<Mutex<VmMetrics>>, port: u16) {
         // Set a short read timeout so we don't block forever on slow clients
         let _ = stream.set_read_timeout(Some(std::time::Duration::from_secs(5)));
 
-        if let Err(e) = handle_request(&stream, &metrics) {
+        if let Err(e) = handle_request(
+            &stream,
+            &metrics,
+            &mut receiver,
+            &mut remote_write,
+            external_labels,
+        ) {
             tracing::debug!("request error: {e}");
         }
     }
@@ -46,6 +106,9 @@ pub fn run_api_server(metrics: Arc<Mutex<VmMetrics>>, port: u16) {
 fn handle_request(
     stream: &std::net::TcpStream,
     metrics: &Arc<Mutex<VmMetrics>>,
+    receiver: &mut OtlpReceiver,
+    remote_write: &mut RemoteWriteReceiver,
+    external_labels: &[(String, String)],
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut reader = BufReader::new(stream);
     let mut request_line = String::new();
@@ -57,21 +120,84 @@ fn handle_request(
         return send_response(stream, 400, "Bad Request");
     }
 
+    let method = parts[0];
     let (path, query) = match parts[1].split_once('?') {
         Some((p, q)) => (p, q),
         None => (parts[1], ""),
     };
 
-    // Drain remaining headers (we don't need them)
+    // Drain remaining headers, keeping only those the handlers need
+    let mut content_length = 0usize;
+    let mut accept = String::new();
+    let mut content_type = String::new();
+    let mut content_encoding = None;
     loop {
         let mut line = String::new();
         reader.read_line(&mut line)?;
         if line.trim().is_empty() {
             break;
         }
+        if let Some((name, value)) = line.split_once(':') {
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("accept") {
+                accept = value.trim().to_string();
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = value.trim().to_string();
+            } else if name.eq_ignore_ascii_case("content-encoding") {
+                content_encoding = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let mut body = Vec::new();
+    if method == "POST" {
+        if content_length > MAX_BODY_BYTES {
+            return send_response(stream, 413, r#"{"error":"request body too large"}"#);
+        }
+        body.resize(content_length, 0);
+        reader.read_exact(&mut body)?;
+    }
+
+    if path == REMOTE_READ_PATH && method == "POST" {
+        return handle_remote_read(stream, metrics, &body, external_labels);
+    }
+
+    if path == REMOTE_WRITE_PATH && method == "POST" {
+        return handle_remote_write(stream, metrics, remote_write, &body);
+    }
+
+    if path == OTLP_METRICS_PATH && method == "POST" {
+        // OTLP/HTTP JSON is not supported
+        if !content_type.starts_with(rondo::otlp_receiver::CONTENT_TYPE) {
+            return send_with_content_type(
+                stream,
+                415,
+                "text/plain",
+                "only application/x-protobuf is supported",
+            );
+        }
+        return handle_otlp(
+            stream,
+            metrics,
+            receiver,
+            &body,
+            content_encoding.as_deref(),
+        );
+    }
+
+    if path.starts_with(rondo::prometheus_api::API_PREFIX) {
+        // Grafana POSTs queries as form bodies by default
+        let mut params = rondo::prometheus_api::parse_form(query);
+        params.extend(rondo::prometheus_api::parse_form(&String::from_utf8_lossy(
+            &body,
+        )));
+        return handle_prometheus_api(stream, metrics, path, &params);
     }
 
     match path {
+        "/metrics" => handle_exposition(stream, metrics, &accept),
         "/metrics/health" => send_response(stream, 200, r#"{"status":"ok"}"#),
         "/metrics/info" => handle_info(stream, metrics),
         "/metrics/query" => handle_query(stream, metrics, query),
@@ -101,7 +227,11 @@ fn handle_info(
     send_json(stream, 200, &body.to_string())
 }
 
-/// `GET /metrics/query?series=<name>&start=<ns>&end=<ns>` — returns data points.
+/// `GET /metrics/query?series=<name>&start=<ns>&end=<ns>[&max_points=<n>]` — returns data points.
+///
+/// When `max_points` is given the result is downsampled to at most that many points.
+/// A missing series name, an unparsable `max_points`, or a query the store
+/// rejects (e.g. `max_points=0`) is answered with 400 and the error message.
 fn handle_query(
     stream: &std::net::TcpStream,
     metrics: &Arc<Mutex<VmMetrics>>,
@@ -109,7 +239,13 @@ fn handle_query(
 ) -> Result<(), Box<dyn std::error::Error>> {
     let params = parse_query(query);
 
-    let series_name = params.get("series").ok_or("missing 'series' parameter")?;
+    let Some(series_name) = params.get("series") else {
+        return send_json(
+            stream,
+            400,
+            &serde_json::json!({"error": "missing 'series' parameter"}).to_string(),
+        );
+    };
     let start: u64 = params
         .get("start")
         .and_then(|s| s.parse().ok())
@@ -118,6 +254,18 @@ fn handle_query(
         .get("end")
         .and_then(|s| s.parse().ok())
         .unwrap_or(u64::MAX);
+    let max_points = match params.get("max_points").map(|s| s.parse::<usize>()) {
+        None => None,
+        Some(Ok(max_points)) => Some(max_points),
+        Some(Err(e)) => {
+            return send_json(
+                stream,
+                400,
+                &serde_json::json!({"error": format!("invalid 'max_points' parameter: {e}")})
+                    .to_string(),
+            );
+        }
+    };
 
     let m = metrics.lock().map_err(|e| format!("lock: {e}"))?;
     let store = m.store();
@@ -141,7 +289,20 @@ fn handle_query(
     };
 
     // Query tier 0 (highest resolution)
-    let result = store.query(handle, 0, start, end)?;
+    let mut options = rondo::QueryOptions::new();
+    if let Some(max_points) = max_points {
+        options = options.with_max_points(max_points);
+    }
+    let result = match store.query_with(handle, 0, start, end, &options) {
+        Ok(result) => result,
+        Err(e) => {
+            return send_json(
+                stream,
+                400,
+                &serde_json::json!({"error": e.to_string()}).to_string(),
+            );
+        }
+    };
     let points: Vec<serde_json::Value> = result
         .map(|(ts, val)| serde_json::json!({"t": ts, "v": val}))
         .collect();
@@ -155,30 +316,180 @@ fn handle_query(
     send_json(stream, 200, &body.to_string())
 }
 
+/// `GET /metrics` — Prometheus/OpenMetrics text exposition for scrapers.
+fn handle_exposition(
+    stream: &std::net::TcpStream,
+    metrics: &Arc<Mutex<VmMetrics>>,
+    accept: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let format = rondo::exposition::ExpositionFormat::from_accept(accept);
+    let encoded = {
+        let m = metrics.lock().map_err(|e| format!("lock: {e}"))?;
+        m.text_encoder(format).encode(m.store())
+    };
+
+    match encoded {
+        Ok(body) => send_with_content_type(stream, 200, format.content_type(), &body),
+        // E.g. two series that render the same once sanitized
+        Err(e) => send_with_content_type(stream, 500, "text/plain", &e.to_string()),
+    }
+}
+
+/// `GET|POST /api/v1/<endpoint>` — Prometheus-compatible query API.
+fn handle_prometheus_api(
+    stream: &std::net::TcpStream,
+    metrics: &Arc<Mutex<VmMetrics>>,
+    path: &str,
+    params: &[(String, String)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    #[allow(clippy::cast_possible_truncation)] // Epoch nanos fit in u64 until year 2554
+    let now_ns = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_nanos() as u64;
+
+    let response = {
+        let m = metrics.lock().map_err(|e| format!("lock: {e}"))?;
+        rondo::prometheus_api::handle(m.store(), path, params, now_ns)
+    };
+
+    send_json(stream, response.status, &response.body)
+}
+
+/// `POST /api/v1/read` — Prometheus remote-read.
+fn handle_remote_read(
+    stream: &std::net::TcpStream,
+    metrics: &Arc<Mutex<VmMetrics>>,
+    body: &[u8],
+    external_labels: &[(String, String)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let result = {
+        let m = metrics.lock().map_err(|e| format!("lock: {e}"))?;
+        rondo::remote_read::handle(m.store(), body, external_labels)
+    };
+
+    match result {
+        Ok(response) => send_bytes(
+            stream,
+            200,
+            &[
+                ("Content-Type", rondo::remote_read::RESPONSE_CONTENT_TYPE),
+                ("Content-Encoding", "snappy"),
+            ],
+            &response,
+        ),
+        // Malformed requests are the client's fault, everything else ours
+        Err(e @ rondo::RondoError::RemoteRead(_)) => {
+            send_with_content_type(stream, 400, "text/plain", &e.to_string())
+        }
+        Err(e) => send_with_content_type(stream, 500, "text/plain", &e.to_string()),
+    }
+}
+
+/// `POST /api/v1/write` — Prometheus remote-write ingestion.
+///
+/// Rejected samples are answered with 400, which senders do not retry.
+fn handle_remote_write(
+    stream: &std::net::TcpStream,
+    metrics: &Arc<Mutex<VmMetrics>>,
+    receiver: &mut RemoteWriteReceiver,
+    body: &[u8],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let result = {
+        let mut m = metrics.lock().map_err(|e| format!("lock: {e}"))?;
+        receiver.handle(m.store_mut(), body)
+    };
+
+    match result {
+        Ok(summary) => match summary.error {
+            None => send_bytes(stream, 204, &[], &[]),
+            Some(error) => send_with_content_type(
+                stream,
+                400,
+                "text/plain",
+                &format!("{} samples rejected: {error}", summary.rejected),
+            ),
+        },
+        Err(e) => send_with_content_type(stream, 400, "text/plain", &e.to_string()),
+    }
+}
+
+/// `POST /v1/metrics` — OTLP/HTTP metrics ingestion.
+fn handle_otlp(
+    stream: &std::net::TcpStream,
+    metrics: &Arc<Mutex<VmMetrics>>,
+    receiver: &mut OtlpReceiver,
+    body: &[u8],
+    content_encoding: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let result = {
+        let mut m = metrics.lock().map_err(|e| format!("lock: {e}"))?;
+        receiver.handle(m.store_mut(), body, content_encoding)
+    };
+
+    match result {
+        Ok(response) => send_bytes(
+            stream,
+            200,
+            &[("Content-Type", rondo::otlp_receiver::CONTENT_TYPE)],
+            &response,
+        ),
+        Err(e) => send_with_content_type(stream, 400, "text/plain", &e.to_string()),
+    }
+}
+
 /// Sends a plain-text HTTP response.
 fn send_response(
-    mut stream: &std::net::TcpStream,
+    stream: &std::net::TcpStream,
     status: u16,
     body: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    send_with_content_type(stream, status, "application/json", body)
+}
+
+/// Sends an HTTP response with the given `Content-Type`.
+fn send_with_content_type(
+    stream: &std::net::TcpStream,
+    status: u16,
+    content_type: &str,
+    body: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    send_bytes(
+        stream,
+        status,
+        &[("Content-Type", content_type)],
+        body.as_bytes(),
+    )
+}
+
+/// Sends an HTTP response with arbitrary headers and a binary body.
+fn send_bytes(
+    mut stream: &std::net::TcpStream,
+    status: u16,
+    headers: &[(&str, &str)],
+    body: &[u8],
 ) -> Result<(), Box<dyn std::error::Error>> {
     let status_text = match status {
         200 => "OK",
+        204 => "No Content",
         400 => "Bad Request",
         404 => "Not Found",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
         500 => "Internal Server Error",
         _ => "Unknown",
     };
 
+    write!(stream, "HTTP/1.1 {status} {status_text}\r\n")?;
+    for (name, value) in headers {
+        write!(stream, "{name}: {value}\r\n")?;
+    }
     write!(
         stream,
-        "HTTP/1.1 {status} {status_text}\r\n\
-         Content-Type: application/json\r\n\
-         Content-Length: {}\r\n\
-         Connection: close\r\n\
-         \r\n\
-         {body}",
-        body.len(),
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
     )?;
+    stream.write_all(body)?;
 
     Ok(())
 }
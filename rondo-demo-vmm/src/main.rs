@@ -55,9 +55,35 @@ struct Cli {
 
     /// Prometheus remote-write endpoint URL (e.g., http://localhost:9090/api/v1/write).
     /// When set, the VMM periodically pushes metrics to this endpoint.
-    #[arg(long)]
+    #[arg(long, conflicts_with = "remote_write_config")]
     remote_write: Option<String>,
 
+    /// Path to a JSON remote-write config with the endpoint URL plus
+    /// authentication, TLS, and batching settings. Use instead of --remote-write.
+    #[arg(long)]
+    remote_write_config: Option<PathBuf>,
+
+    /// OTLP/HTTP metrics endpoint URL (e.g., http://localhost:4318/v1/metrics).
+    /// When set, the VMM periodically pushes metrics to this endpoint.
+    #[arg(long)]
+    otlp_endpoint: Option<String>,
+
+    /// Graphite/carbon plaintext endpoint (e.g., localhost:2003).
+    /// When set, the VMM periodically pushes metrics to this endpoint.
+    #[arg(long)]
+    graphite_endpoint: Option<String>,
+
+    /// Spool batches a sink could not deliver to disk, up to this many MiB
+    /// per sink, and replay them once the sink recovers. Covers outages
+    /// longer than the 10-minute raw retention.
+    #[arg(long)]
+    export_spool_mib: Option<u64>,
+
+    /// UDP port for a StatsD/DogStatsD listener (e.g., 8125).
+    /// When set, the VMM aggregates StatsD packets into the `statsd` schema.
+    #[arg(long)]
+    statsd_port: Option<u16>,
+
     /// Extra labels added to every remote-write time series (format: key=value,key=value,...).
     /// Useful for distinguishing multiple VMM instances in Prometheus.
     #[arg(long)]
@@ -100,6 +126,15 @@ fn run_vmm(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         .map(parse_external_labels)
         .unwrap_or_default();
 
+    let remote_write = match (cli.remote_write, cli.remote_write_config) {
+        (_, Some(path)) => Some(load_remote_write_config(&path)?),
+        (Some(url), None) => Some(vmm::RemoteWriteSink {
+            config: rondo::remote_write::RemoteWriteConfig::new(url),
+            tiers: vec![0],
+        }),
+        (None, None) => None,
+    };
+
     let config = vmm::VmmConfig {
         kernel_path: cli.kernel,
         initramfs_path: cli.initramfs,
@@ -107,7 +142,11 @@ fn run_vmm(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
         memory_mib: cli.memory_mib,
         metrics_store_path: cli.metrics_store,
         api_port: cli.api_port,
-        remote_write_endpoint: cli.remote_write,
+        remote_write,
+        otlp_endpoint: cli.otlp_endpoint,
+        graphite_endpoint: cli.graphite_endpoint,
+        export_spool_bytes: cli.export_spool_mib.map(|mib| mib * 1024 * 1024),
+        statsd_port: cli.statsd_port,
         external_labels,
         disk_path: cli.disk,
     };
@@ -130,3 +169,82 @@ fn parse_external_labels(s: &str) -> Vec<(String, String)> {
         })
         .collect()
 }
+
+/// Remote-write settings read from `--remote-write-config`.
+///
+/// ```json
+/// {
+///   "url": "https://prometheus.example/api/v1/write",
+///   "auth": { "bearer_token_file": "/run/secrets/prometheus-token" },
+///   "tls_config": { "cert_file": "client.pem", "key_file": "client-key.pem" },
+///   "protocol": "v2",
+///   "tiers": [2],
+///   "rollup_naming": "label"
+/// }
+/// ```
+///
+/// `tiers` selects the store tiers to push (default: the 1s tier); series
+/// from rollup tiers are marked as `__rollup__="avg_5m"` (`"label"`) or
+/// with a name suffix (`"suffix"`).
+#[cfg(target_os = "linux")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RemoteWriteFile {
+    url: String,
+    #[serde(default)]
+    headers: std::collections::BTreeMap<String, String>,
+    auth: Option<rondo::remote_write::RemoteWriteAuth>,
+    tls_config: Option<rondo::remote_write::TlsConfig>,
+    protocol: Option<rondo::remote_write::RemoteWriteProtocol>,
+    timeout_secs: Option<u64>,
+    max_samples_per_send: Option<usize>,
+    shards: Option<usize>,
+    #[serde(default)]
+    write_relabel_configs: Vec<rondo::relabel::RelabelConfig>,
+    tiers: Option<Vec<usize>>,
+    rollup_naming: Option<rondo::export::RollupNaming>,
+}
+
+/// Reads a `--remote-write-config` file into a remote-write sink.
+#[cfg(target_os = "linux")]
+fn load_remote_write_config(
+    path: &std::path::Path,
+) -> Result<vmm::RemoteWriteSink, Box<dyn std::error::Error>> {
+    let data = std::fs::read_to_string(path)
+        .map_err(|e| format!("reading remote-write config {}: {e}", path.display()))?;
+    let file: RemoteWriteFile = serde_json::from_str(&data)
+        .map_err(|e| format!("parsing remote-write config {}: {e}", path.display()))?;
+
+    let mut config = rondo::remote_write::RemoteWriteConfig::new(file.url);
+    for (name, value) in file.headers {
+        config = config.with_header(name, value);
+    }
+    if let Some(auth) = file.auth {
+        config = config.with_auth(auth);
+    }
+    if let Some(tls) = file.tls_config {
+        config = config.with_tls(tls);
+    }
+    if let Some(protocol) = file.protocol {
+        config = config.with_protocol(protocol);
+    }
+    if let Some(secs) = file.timeout_secs {
+        config = config.with_timeout(std::time::Duration::from_secs(secs));
+    }
+    if let Some(max) = file.max_samples_per_send {
+        config = config.with_max_samples_per_send(max);
+    }
+    if let Some(shards) = file.shards {
+        config = config.with_shards(shards);
+    }
+    for rule in file.write_relabel_configs {
+        config = config.with_write_relabel_config(rule);
+    }
+    if let Some(rollup_naming) = file.rollup_naming {
+        config = config.with_rollup_naming(rollup_naming);
+    }
+    Ok(vmm::RemoteWriteSink {
+        config,
+        tiers: file.tiers.unwrap_or_else(|| vec![0]),
+    })
+}
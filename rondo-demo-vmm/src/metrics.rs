@@ -8,10 +8,20 @@
 use std::path::Path;
 use std::time::Duration;
 
+use rondo::exposition::{ExpositionFormat, TextEncoder};
 use rondo::schema::{ConsolidationFn, LabelMatcher, SchemaConfig, TierConfig};
 use rondo::series::SeriesHandle;
 use rondo::store::Store;
 
+/// Maximum number of guest series accepted over OTLP.
+pub const GUEST_MAX_SERIES: u32 = 200;
+
+/// Maximum number of series accepted over Prometheus remote-write.
+pub const REMOTE_WRITE_MAX_SERIES: u32 = 200;
+
+/// Maximum number of series accepted over StatsD.
+pub const STATSD_MAX_SERIES: u32 = 200;
+
 /// Pre-registered series handles for all VMM metrics.
 ///
 /// Created once at VMM startup. Each field is a `SeriesHandle` that can be
@@ -69,28 +79,56 @@ impl VmMetrics {
     /// - Tier 1: 10s interval, 6h retention (consolidated average)
     /// - Tier 2: 5min interval, 7d retention (consolidated average)
     ///
+    /// Pushed metrics get their own schemas with the same tiers, selected by
+    /// the `source` label: `guest` (`source="otlp"`), `remote_write`
+    /// (`source="remote_write"`) and `statsd` (`source="statsd"`). Everything
+    /// else is VMM-internal.
+    ///
     /// # Errors
     ///
     /// Returns an error if the store cannot be opened or series registration fails.
     pub fn open<P: AsRef<Path>>(store_path: P) -> rondo::Result<Self> {
-        let schemas = vec![SchemaConfig {
-            name: "vmm".to_string(),
-            label_matcher: LabelMatcher::any(),
-            tiers: vec![
-                TierConfig::new(Duration::from_secs(1), Duration::from_secs(600), None)?,
-                TierConfig::new(
-                    Duration::from_secs(10),
-                    Duration::from_secs(21600),
-                    Some(ConsolidationFn::Average),
-                )?,
-                TierConfig::new(
-                    Duration::from_secs(300),
-                    Duration::from_secs(604800),
-                    Some(ConsolidationFn::Average),
-                )?,
-            ],
-            max_series: 30,
-        }];
+        let tiers = vec![
+            TierConfig::new(Duration::from_secs(1), Duration::from_secs(600), None)?,
+            TierConfig::new(
+                Duration::from_secs(10),
+                Duration::from_secs(21600),
+                Some(ConsolidationFn::Average),
+            )?,
+            TierConfig::new(
+                Duration::from_secs(300),
+                Duration::from_secs(604800),
+                Some(ConsolidationFn::Average),
+            )?,
+        ];
+        let schemas = vec![
+            // Guest metrics pushed over OTLP get their own schema, so a noisy
+            // guest can neither exhaust nor reorder the VMM's own series
+            SchemaConfig {
+                name: "guest".to_string(),
+                label_matcher: LabelMatcher::new([("source", "otlp")]),
+                tiers: tiers.clone(),
+                max_series: GUEST_MAX_SERIES,
+            },
+            SchemaConfig {
+                name: "remote_write".to_string(),
+                label_matcher: LabelMatcher::new([("source", "remote_write")]),
+                tiers: tiers.clone(),
+                max_series: REMOTE_WRITE_MAX_SERIES,
+            },
+            SchemaConfig {
+                name: "statsd".to_string(),
+                label_matcher: LabelMatcher::new([("source", "statsd")]),
+                tiers: tiers.clone(),
+                max_series: STATSD_MAX_SERIES,
+            },
+            SchemaConfig {
+                name: "vmm".to_string(),
+                label_matcher: LabelMatcher::any(),
+                tiers,
+                max_series: 30,
+            },
+        ];
 
         let mut store = Store::open(store_path, schemas)?;
 
@@ -262,6 +300,30 @@ impl VmMetrics {
         &self.store
     }
 
+    /// Returns a text encoder for the `/metrics` scrape endpoint, with help
+    /// text for every VMM metric family.
+    pub fn text_encoder(&self, format: ExpositionFormat) -> TextEncoder {
+        TextEncoder::new(format)
+            .with_help("vcpu_exits_total", "vCPU exits by reason")
+            .with_help(
+                "vcpu_exit_duration_ns",
+                "Nanoseconds spent processing a vCPU exit",
+            )
+            .with_help("vcpu_run_duration_ns", "Nanoseconds spent in KVM_RUN")
+            .with_help("blk_requests_total", "virtio-blk requests by operation")
+            .with_help(
+                "blk_bytes_total",
+                "virtio-blk bytes transferred by direction",
+            )
+            .with_help(
+                "blk_request_duration_ns",
+                "Nanoseconds spent serving a virtio-blk request",
+            )
+            .with_help("vmm_rss_bytes", "Resident set size of the VMM process")
+            .with_help("vmm_open_fds", "Open file descriptors of the VMM process")
+            .with_help("vmm_uptime_seconds", "Seconds since the VMM started")
+    }
+
     /// Returns a mutable reference to the underlying store.
     pub fn store_mut(&mut self) -> &mut Store {
         &mut self.store
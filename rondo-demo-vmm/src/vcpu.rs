@@ -5,11 +5,14 @@
 //! with serial console output and metrics recording for every vCPU exit.
 
 use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use kvm_bindings::{KVM_MAX_CPUID_ENTRIES, kvm_dtable, kvm_regs, kvm_segment};
 use kvm_ioctls::{Kvm, VcpuExit, VcpuFd, VmFd};
+use rondo::exporter::{ExportDriver, SinkReport};
 use vm_memory::{Bytes, GuestAddress, GuestMemoryMmap};
 
 use crate::devices::block::{self, VirtioBlock};
@@ -237,6 +240,24 @@ extern "C" fn noop_handler(_sig: libc::c_int) {
     // Intentionally empty — just needs to interrupt KVM_RUN.
 }
 
+/// Set by SIGTERM/SIGINT; the vCPU loop stops at its next iteration.
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Installs SIGTERM and SIGINT handlers that stop the vCPU loop, so the
+/// VMM shuts down (and flushes its exports) instead of dying mid-window.
+fn setup_stop_signals() {
+    // SAFETY: the handler only stores to an atomic.
+    unsafe {
+        for sig in [libc::SIGTERM, libc::SIGINT] {
+            libc::signal(sig, stop_handler as *const () as libc::sighandler_t);
+        }
+    }
+}
+
+extern "C" fn stop_handler(_sig: libc::c_int) {
+    STOP_REQUESTED.store(true, Ordering::Relaxed);
+}
+
 /// Checks if the guest vCPU is halted with interrupts disabled (IF=0).
 fn is_guest_halted(vcpu: &VcpuFd) -> bool {
     if let Ok(regs) = vcpu.get_regs() {
@@ -254,7 +275,8 @@ const HALT_CONSECUTIVE_THRESHOLD: u32 = 3;
 
 /// Runs the KVM_RUN loop, handling vCPU exits and recording metrics.
 ///
-/// Blocks until the guest shuts down or an unrecoverable error occurs.
+/// Blocks until the guest shuts down, the VMM receives SIGTERM or SIGINT,
+/// or an unrecoverable error occurs.
 /// When a `block_device` is provided, MMIO accesses to the virtio-mmio
 /// region are dispatched to it, and IRQs are injected via `vm_fd`.
 pub fn run_vcpu_loop(
@@ -270,8 +292,13 @@ pub fn run_vcpu_loop(
 
     // Set up periodic SIGALRM to interrupt KVM_RUN when guest halts
     setup_vcpu_timer();
+    setup_stop_signals();
 
     loop {
+        if STOP_REQUESTED.load(Ordering::Relaxed) {
+            tracing::info!("termination signal received, stopping vCPU");
+            return Ok(());
+        }
         let run_start = Instant::now();
 
         match vcpu.run() {
@@ -446,71 +473,135 @@ fn handle_io_in(port: u16, data: &mut [u8]) {
     }
 }
 
-// ── Export loop (remote-write) ──────────────────────────────────────
+// ── Export loop ─────────────────────────────────────────────────────
 
-/// Interval between remote-write pushes.
+/// Interval between export runs.
 const EXPORT_INTERVAL: Duration = Duration::from_secs(10);
 
-/// Runs a periodic export loop: drain tier 0 → push to Prometheus remote-write.
+/// Time allowed for the final export when the VMM shuts down.
+pub const EXPORT_FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Runs a periodic export loop: the driver drains each configured sink's
+/// tiers and pushes them from that sink's own cursor.
 ///
-/// This runs in its own thread and never returns (daemon thread).
+/// A sink that fails keeps its cursor and gets the same data again on the
+/// next run, unless it has a spool: then the batch is spooled and replayed
+/// once the sink recovers. This runs in its own thread until `shutdown`
+/// fires (or its sender is dropped), then runs a final consolidate, drain,
+/// and push within [`EXPORT_FLUSH_TIMEOUT`] and returns.
 pub fn export_loop(
     metrics: Arc<Mutex<VmMetrics>>,
-    endpoint: &str,
-    cursor_path: &std::path::Path,
-    external_labels: &[(String, String)],
+    mut driver: ExportDriver,
+    shutdown: Receiver<()>,
 ) {
-    use rondo::export::ExportCursor;
-    use rondo::remote_write::{RemoteWriteConfig, push};
+    tracing::info!(
+        "export loop started ({} sinks, interval: {EXPORT_INTERVAL:?})",
+        driver.len()
+    );
+
+    // Any message or a dropped sender means shutdown
+    while let Err(RecvTimeoutError::Timeout) = shutdown.recv_timeout(EXPORT_INTERVAL) {
+        // Drain and encode under the metrics lock, push without it
+        let run = {
+            let Ok(m) = metrics.lock() else {
+                tracing::warn!("export: failed to acquire metrics lock");
+                continue;
+            };
+            driver.prepare(m.store())
+        };
+        log_reports(driver.deliver(run));
+    }
+
+    // Final export: consolidate, then push the last window
+    let deadline = Instant::now() + EXPORT_FLUSH_TIMEOUT;
+    let run = {
+        let Ok(mut m) = metrics.lock() else {
+            tracing::warn!("export: failed to acquire metrics lock for the final export");
+            return;
+        };
+        if let Err(e) = m.store_mut().consolidate() {
+            tracing::warn!("final consolidation failed: {e}");
+        }
+        driver.prepare(m.store())
+    };
+    log_reports(driver.deliver_before(run, deadline));
+    tracing::info!("export loop stopped");
+}
+
+/// Logs the outcome of an export run for each sink.
+fn log_reports(reports: Vec<SinkReport>) {
+    for report in reports {
+        let name = &report.name;
+        match report.error {
+            Some(e) if report.dropped > 0 => {
+                tracing::warn!("{name}: dropped {} rejected batches: {e}", report.dropped);
+            }
+            Some(e) if report.delivered => {
+                tracing::warn!("{name}: failed to save cursor: {e}");
+            }
+            Some(e) if report.spooled => tracing::warn!(
+                "{name}: push failed, spooled {} series ({} evicted): {e}",
+                report.series,
+                report.evicted
+            ),
+            Some(e) => tracing::warn!("{name}: push failed: {e}"),
+            None if report.delivered => tracing::info!(
+                "{name}: pushed {} series ({} spooled batches replayed)",
+                report.series,
+                report.replayed
+            ),
+            None if report.replayed > 0 => {
+                tracing::info!("{name}: replayed {} spooled batches", report.replayed);
+            }
+            None => tracing::debug!("{name}: no new data to export"),
+        }
+    }
+}
+
+// ── StatsD listener ─────────────────────────────────────────────────
 
-    let config = RemoteWriteConfig::new(endpoint);
+/// Interval between StatsD flushes into the store.
+const STATSD_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
 
-    let mut cursor = match ExportCursor::load_or_new(cursor_path) {
-        Ok(c) => c,
+/// StatsD listener loop: aggregates UDP packets and flushes them into the
+/// `statsd` schema every [`STATSD_FLUSH_INTERVAL`].
+pub fn statsd_loop(metrics: Arc<Mutex<VmMetrics>>, port: u16) {
+    use rondo::statsd::{StatsdConfig, StatsdListener};
+
+    // Tagging StatsD series routes them to the `statsd` schema
+    let config = StatsdConfig::new()
+        .with_flush_interval(STATSD_FLUSH_INTERVAL)
+        .with_label("source", "statsd")
+        .with_max_series(crate::metrics::STATSD_MAX_SERIES);
+    let mut listener = match StatsdListener::bind(("0.0.0.0", port), config) {
+        Ok(l) => l,
         Err(e) => {
-            tracing::error!("failed to load export cursor: {e}");
+            tracing::error!("{e}");
             return;
         }
     };
 
-    tracing::info!("remote-write export loop started (interval: {EXPORT_INTERVAL:?})");
-
     loop {
-        std::thread::sleep(EXPORT_INTERVAL);
-
-        // Drain and push under the metrics lock
-        let result = if let Ok(m) = metrics.lock() {
-            let store = m.store();
-            match store.drain(0, &mut cursor) {
-                Ok(exports) if exports.is_empty() => {
-                    tracing::debug!("remote-write: no new data to export");
-                    continue;
-                }
-                Ok(exports) => {
-                    let count = exports.len();
-                    match push(&config, &exports, store, external_labels) {
-                        Ok(n) => {
-                            tracing::info!("remote-write: pushed {n} series ({count} with data)");
-                            Ok(())
-                        }
-                        Err(e) => Err(format!("push failed: {e}")),
-                    }
-                }
-                Err(e) => Err(format!("drain failed: {e}")),
-            }
-        } else {
-            Err("failed to acquire metrics lock".to_string())
+        if let Err(e) = listener.wait_for_flush() {
+            tracing::warn!("statsd: {e}");
+            continue;
+        }
+
+        let Ok(mut m) = metrics.lock() else {
+            tracing::warn!("statsd: failed to acquire metrics lock");
+            continue;
         };
+        let summary = listener.flush(m.store_mut(), timestamp_ns());
+        drop(m);
 
-        match result {
-            Ok(()) => {
-                if let Err(e) = cursor.save() {
-                    tracing::warn!("remote-write: failed to save cursor: {e}");
-                }
-            }
-            Err(msg) => {
-                tracing::warn!("remote-write: {msg}");
-            }
+        if summary.recorded > 0 {
+            tracing::debug!("statsd: recorded {} values", summary.recorded);
+        }
+        if summary.malformed > 0 {
+            tracing::warn!("statsd: {} malformed lines", summary.malformed);
+        }
+        if let Some(error) = summary.error {
+            tracing::warn!("statsd: {} values rejected: {error}", summary.rejected);
         }
     }
 }
@@ -4,12 +4,21 @@
 //! and boots the guest to a serial console with embedded rondo metrics.
 
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex, TryLockError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 use kvm_bindings::kvm_userspace_memory_region;
 use kvm_ioctls::{Kvm, VmFd};
 use linux_loader::loader::KernelLoader;
 use linux_loader::loader::bzimage::BzImage;
+use rondo::export::ExportCursor;
+use rondo::exporter::ExportDriver;
+use rondo::graphite::{GraphiteConfig, GraphiteExporter, PathTemplate};
+use rondo::otlp::{OtlpConfig, OtlpExporter};
+use rondo::remote_write::{RemoteWriteConfig, RemoteWriteExporter};
+use rondo::spool::Spool;
 use vm_memory::{Bytes, GuestAddress, GuestMemory, GuestMemoryMmap, GuestMemoryRegion};
 
 use crate::devices::block::VirtioBlock;
@@ -25,6 +34,9 @@ const CMDLINE_ADDR: u64 = 0x20000;
 /// High memory start / default kernel load address.
 const HIMEM_START: u64 = 0x100000;
 
+/// Extra time the VMM waits for the export thread beyond its flush timeout.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
 /// VMM configuration parsed from CLI arguments.
 pub struct VmmConfig {
     /// Path to the kernel bzImage.
@@ -39,14 +51,30 @@ pub struct VmmConfig {
     pub metrics_store_path: PathBuf,
     /// HTTP API listen port.
     pub api_port: u16,
-    /// Prometheus remote-write endpoint URL (optional).
-    pub remote_write_endpoint: Option<String>,
+    /// Prometheus remote-write endpoint and its settings (optional).
+    pub remote_write: Option<RemoteWriteSink>,
+    /// OTLP/HTTP metrics endpoint URL (optional).
+    pub otlp_endpoint: Option<String>,
+    /// Graphite/carbon plaintext endpoint (optional).
+    pub graphite_endpoint: Option<String>,
+    /// Per-sink size limit of the export spool, in bytes (optional).
+    pub export_spool_bytes: Option<u64>,
+    /// UDP port for the StatsD listener (optional).
+    pub statsd_port: Option<u16>,
     /// Extra labels added to every remote-write time series.
     pub external_labels: Vec<(String, String)>,
     /// Path to the virtio-blk backing file (optional).
     pub disk_path: Option<PathBuf>,
 }
 
+/// A remote-write endpoint and the store tiers pushed to it.
+pub struct RemoteWriteSink {
+    /// Endpoint, authentication, and batching settings.
+    pub config: RemoteWriteConfig,
+    /// Store tiers drained for this endpoint.
+    pub tiers: Vec<usize>,
+}
+
 /// VMM error type.
 #[derive(Debug)]
 pub enum VmmError {
@@ -105,7 +133,11 @@ pub struct Vmm {
     metrics: Arc<Mutex<VmMetrics>>,
     api_port: u16,
     metrics_store_path: PathBuf,
-    remote_write_endpoint: Option<String>,
+    remote_write: Option<RemoteWriteSink>,
+    otlp_endpoint: Option<String>,
+    graphite_endpoint: Option<String>,
+    export_spool_bytes: Option<u64>,
+    statsd_port: Option<u16>,
     external_labels: Vec<(String, String)>,
     block_device: Option<VirtioBlock>,
 }
@@ -224,7 +256,11 @@ impl Vmm {
             metrics: Arc::new(Mutex::new(metrics)),
             api_port: config.api_port,
             metrics_store_path: config.metrics_store_path,
-            remote_write_endpoint: config.remote_write_endpoint,
+            remote_write: config.remote_write,
+            otlp_endpoint: config.otlp_endpoint,
+            graphite_endpoint: config.graphite_endpoint,
+            export_spool_bytes: config.export_spool_bytes,
+            statsd_port: config.statsd_port,
             external_labels: config.external_labels,
             block_device,
         })
@@ -232,15 +268,19 @@ impl Vmm {
 
     /// Starts the VMM: spawns the API server and maintenance threads,
     /// then runs the vCPU loop in the calling thread (blocks until guest
-    /// shuts down).
+    /// shuts down or the VMM is signalled to stop).
+    ///
+    /// On the way out, the export thread gets one last run to push the
+    /// final window, and the metrics store is flushed to disk.
     pub fn run(&mut self) -> Result<(), VmmError> {
         // Spawn HTTP API server
         let api_metrics = self.metrics.clone();
         let api_port = self.api_port;
+        let api_external_labels = self.external_labels.clone();
         std::thread::Builder::new()
             .name("api-server".into())
             .spawn(move || {
-                crate::api::run_api_server(api_metrics, api_port);
+                crate::api::run_api_server(api_metrics, api_port, &api_external_labels);
             })
             .map_err(VmmError::Io)?;
         tracing::info!("API server listening on port {}", self.api_port);
@@ -254,33 +294,145 @@ impl Vmm {
             })
             .map_err(VmmError::Io)?;
 
-        // Spawn remote-write export thread (if configured)
-        if let Some(ref endpoint) = self.remote_write_endpoint {
+        // Spawn export thread (if any sink is configured)
+        let driver = self.export_driver()?;
+        let mut export = None;
+        if !driver.is_empty() {
             let export_metrics = self.metrics.clone();
-            let cursor_path = self.metrics_store_path.join("cursor_prometheus.json");
-            let endpoint = endpoint.clone();
-            let external_labels = self.external_labels.clone();
+            let (shutdown, shutdown_rx) = std::sync::mpsc::channel();
+            let handle = std::thread::Builder::new()
+                .name("export".into())
+                .spawn(move || {
+                    vcpu::export_loop(export_metrics, driver, shutdown_rx);
+                })
+                .map_err(VmmError::Io)?;
+            export = Some((shutdown, handle));
+            for (sink, endpoint) in [
+                (
+                    "remote-write",
+                    self.remote_write.as_ref().map(|s| &s.config.endpoint),
+                ),
+                ("OTLP", self.otlp_endpoint.as_ref()),
+                ("Graphite", self.graphite_endpoint.as_ref()),
+            ] {
+                if let Some(endpoint) = endpoint {
+                    tracing::info!("{sink} export started → {endpoint}");
+                }
+            }
+        }
+
+        // Spawn StatsD listener thread (if configured)
+        if let Some(port) = self.statsd_port {
+            let statsd_metrics = self.metrics.clone();
             std::thread::Builder::new()
-                .name("remote-write".into())
+                .name("statsd".into())
                 .spawn(move || {
-                    vcpu::export_loop(export_metrics, &endpoint, &cursor_path, &external_labels);
+                    vcpu::statsd_loop(statsd_metrics, port);
                 })
                 .map_err(VmmError::Io)?;
-            tracing::info!(
-                "remote-write export thread started → {}",
-                self.remote_write_endpoint.as_ref().unwrap()
-            );
+            tracing::info!("StatsD listener started on UDP port {port}");
         }
 
         // Run vCPU loop in this thread (blocks)
         tracing::info!("starting vCPU");
-        vcpu::run_vcpu_loop(
+        let result = vcpu::run_vcpu_loop(
             &mut self.vcpu_fd,
             &self.vm_fd,
             &self.guest_memory,
             self.metrics.clone(),
             self.block_device.as_mut(),
-        )
+        );
+
+        self.shutdown(export);
+        result
+    }
+
+    /// Stops the export thread after its final export, then flushes the
+    /// metrics store.
+    ///
+    /// Waits at most a little longer than the export flush timeout: a sink
+    /// stuck in a slow push must not keep the VMM from exiting. If the
+    /// store is still locked after that, it is left to the OS to write back.
+    fn shutdown(&self, export: Option<(Sender<()>, JoinHandle<()>)>) {
+        if let Some((shutdown, handle)) = export {
+            let _ = shutdown.send(());
+            let deadline = Instant::now() + vcpu::EXPORT_FLUSH_TIMEOUT + SHUTDOWN_GRACE;
+            while !handle.is_finished() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            if !handle.is_finished() {
+                tracing::warn!("final export did not finish in time, exiting anyway");
+            }
+        }
+
+        let deadline = Instant::now() + SHUTDOWN_GRACE;
+        let m = loop {
+            match self.metrics.try_lock() {
+                Ok(m) => break m,
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(_) => {
+                    tracing::warn!("failed to acquire metrics lock to flush the store");
+                    return;
+                }
+            }
+        };
+        if let Err(e) = m.store().flush() {
+            tracing::warn!("failed to flush metrics store: {e}");
+        }
+    }
+
+    /// Builds the export driver with one sink per configured endpoint.
+    ///
+    /// Each sink resumes from its own cursor file in the metrics store
+    /// directory.
+    fn export_driver(&self) -> Result<ExportDriver, VmmError> {
+        let cursor = |name: &str| {
+            ExportCursor::load_or_new(self.metrics_store_path.join(format!("cursor_{name}.json")))
+        };
+        let external_labels = self.external_labels.clone();
+        let mut driver = ExportDriver::new(0);
+
+        if let Some(ref sink) = self.remote_write {
+            let exporter = RemoteWriteExporter::new(sink.config.clone())
+                .with_external_labels(external_labels.clone());
+            driver.add_sink_with_tiers(
+                Box::new(exporter),
+                cursor("prometheus")?,
+                sink.tiers.iter().copied(),
+            );
+        }
+
+        if let Some(ref endpoint) = self.otlp_endpoint {
+            // External labels become OTLP resource attributes
+            let mut config =
+                OtlpConfig::new(endpoint).with_resource_attribute("service.name", "rondo-vmm");
+            for (key, value) in &external_labels {
+                config = config.with_resource_attribute(key.clone(), value.clone());
+            }
+            driver.add_sink(Box::new(OtlpExporter::new(config)), cursor("otlp")?);
+        }
+
+        if let Some(ref endpoint) = self.graphite_endpoint {
+            // Paths are rondo.<instance>.<name>; other labels become tags
+            let config = GraphiteConfig::new(endpoint)
+                .with_template(PathTemplate::parse("rondo.{instance}.{name}")?);
+            let exporter = GraphiteExporter::new(config).with_external_labels(external_labels);
+            driver.add_sink(Box::new(exporter), cursor("graphite")?);
+        }
+
+        // Each sink spools to <store>/spool/<sink name>
+        if let Some(max_bytes) = self.export_spool_bytes {
+            for name in ["remote_write", "otlp", "graphite"] {
+                if driver.cursor(name).is_some() {
+                    let dir = self.metrics_store_path.join("spool").join(name);
+                    driver.set_spool(name, Spool::open(dir, max_bytes)?);
+                }
+            }
+        }
+
+        Ok(driver)
     }
 
     /// Loads an initramfs file into guest memory above the kernel.
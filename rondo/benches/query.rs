@@ -0,0 +1,137 @@
+//! Microbenchmarks for range queries on a full ring.
+//!
+//! Query cost should scale with the requested range, not with the retention
+//! window: a 1-minute query on a 7-day ring must not visit every slot.
+//!
+//! Run with: `cargo bench -p rondo --bench query`
+
+#![allow(missing_docs, clippy::cast_possible_truncation)]
+
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use rondo::schema::{LabelMatcher, SchemaConfig, TierConfig};
+use rondo::store::Store;
+use std::time::Duration;
+use tempfile::tempdir;
+
+const SECOND_NS: u64 = 1_000_000_000;
+
+/// Seven days of 1s samples.
+const RETENTION_SECS: u64 = 7 * 24 * 3600;
+
+/// Creates a store with a 7-day 1s ring and fills it completely.
+fn setup_full_store() -> (Store, rondo::SeriesHandle, u64, tempfile::TempDir) {
+    let temp_dir = tempdir().unwrap();
+    let store_path = temp_dir.path().join("bench_store");
+
+    let schemas = vec![SchemaConfig {
+        name: "bench".to_string(),
+        label_matcher: LabelMatcher::any(),
+        tiers: vec![TierConfig {
+            interval: Duration::from_secs(1),
+            retention: Duration::from_secs(RETENTION_SECS),
+            consolidation_fn: None,
+        }],
+        max_series: 1,
+    }];
+
+    let mut store = Store::open(&store_path, schemas).unwrap();
+    let handle = store.register("metric", &[]).unwrap();
+
+    let base_time = 1_700_000_000_000_000_000u64;
+    let mut ts = base_time;
+    // Write a bit more than one full lap so the ring has wrapped
+    for i in 0..RETENTION_SECS + 60 {
+        ts = base_time + i * SECOND_NS;
+        store.record(handle, 1.0, ts).unwrap();
+    }
+
+    (store, handle, ts, temp_dir)
+}
+
+fn bench_query_range(c: &mut Criterion) {
+    let (store, handle, newest, _dir) = setup_full_store();
+    let mut group = c.benchmark_group("query/range_7d_ring");
+
+    for (label, range_secs) in [
+        ("1m", 60),
+        ("1h", 3600),
+        ("1d", 24 * 3600),
+        ("7d", RETENTION_SECS),
+    ] {
+        let end = newest + SECOND_NS;
+        let start = end - range_secs * SECOND_NS;
+
+        group.bench_with_input(BenchmarkId::from_parameter(label), &range_secs, |b, _| {
+            b.iter(|| {
+                let count = store
+                    .query(black_box(handle), 0, black_box(start), black_box(end))
+                    .unwrap()
+                    .count();
+                black_box(count)
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_query_old_range(c: &mut Criterion) {
+    let (store, handle, newest, _dir) = setup_full_store();
+
+    // A 1-minute window in the middle of the retained data
+    let end = newest - 3 * 24 * 3600 * SECOND_NS;
+    let start = end - 60 * SECOND_NS;
+
+    c.bench_function("query/1m_mid_window", |b| {
+        b.iter(|| {
+            let count = store
+                .query(black_box(handle), 0, black_box(start), black_box(end))
+                .unwrap()
+                .count();
+            black_box(count)
+        });
+    });
+}
+
+fn bench_read_into(c: &mut Criterion) {
+    let (store, handle, newest, _dir) = setup_full_store();
+    let end = newest + SECOND_NS;
+    let start = end - RETENTION_SECS * SECOND_NS;
+    let mut timestamps = Vec::with_capacity(RETENTION_SECS as usize);
+    let mut values = Vec::with_capacity(RETENTION_SECS as usize);
+
+    c.bench_function("query/read_into_7d", |b| {
+        b.iter(|| {
+            timestamps.clear();
+            values.clear();
+            let count = store
+                .read_into(
+                    black_box(handle),
+                    0,
+                    black_box(start),
+                    black_box(end),
+                    &mut timestamps,
+                    &mut values,
+                )
+                .unwrap();
+            black_box(count)
+        });
+    });
+}
+
+fn bench_latest(c: &mut Criterion) {
+    let (store, handle, _newest, _dir) = setup_full_store();
+
+    c.bench_function("query/latest", |b| {
+        b.iter(|| black_box(store.latest(black_box(handle)).unwrap()));
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_query_range,
+    bench_query_old_range,
+    bench_read_into,
+    bench_latest,
+);
+criterion_main!(benches);
@@ -28,7 +28,7 @@
 //! resumption after restart. Each cursor tracks the last-processed timestamp
 //! per (schema_index, source_tier_index) pair.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -41,6 +41,12 @@ use crate::schema::{ConsolidationFn, SchemaConfig, TierConfig};
 /// Name of the consolidation cursors file in the store directory.
 const CURSORS_FILE: &str = "consolidation_cursors.json";
 
+/// Approximate number of source slots read per consolidation chunk.
+///
+/// Bounds the rows buffered at once when a long backlog is consolidated;
+/// chunks are rounded up to whole destination windows.
+const CHUNK_SLOTS: u64 = 4096;
+
 /// A consolidation cursor that tracks progress for a specific tier pair.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ConsolidationCursor {
@@ -392,7 +398,6 @@ impl ConsolidationEngine {
             consolidation_fn,
             start_timestamp,
             end_timestamp,
-            schema.max_series,
         )?;
 
         // Update cursor to the latest processed timestamp (the actual newest, not the exclusive end)
@@ -439,63 +444,91 @@ impl ConsolidationEngine {
     }
 
     /// Processes consolidation windows for a tier pair.
+    ///
+    /// Reads only the columns of registered series, in chunks of about
+    /// [`CHUNK_SLOTS`] source slots that end on destination window
+    /// boundaries, so each chunk's windows are complete when written.
     #[allow(clippy::too_many_arguments)]
     fn process_consolidation_windows(
         &self,
         source_ring: &RingBuffer,
         dest_ring: &mut RingBuffer,
-        _source_tier: &TierConfig,
+        source_tier: &TierConfig,
         dest_tier: &TierConfig,
         consolidation_fn: ConsolidationFn,
         start_timestamp: u64,
         end_timestamp: u64,
-        max_series: u32,
     ) -> Result<usize> {
         #[allow(clippy::cast_possible_truncation)]
         // Duration nanos fit in u64 for practical intervals
-        let dest_interval_ns = dest_tier.interval.as_nanos() as u64;
+        let (source_interval_ns, dest_interval_ns) = (
+            source_tier.interval.as_nanos() as u64,
+            dest_tier.interval.as_nanos() as u64,
+        );
+        let columns = source_ring.slab().series_count();
         let mut operations = 0;
 
-        // Create all consolidation windows first
-        let mut all_windows: HashMap<u64, ConsolidationWindow> = HashMap::new();
-
-        // Iterate through each series column
-        for series_column in 0..max_series {
-            // Read data from source tier for this series
-            let source_iter = source_ring.read(series_column, start_timestamp, end_timestamp)?;
-
-            // Group data into windows aligned to destination tier intervals
-            for (timestamp, value) in source_iter {
-                // Skip NaN values
-                if value.is_nan() {
-                    continue;
-                }
+        if columns == 0 {
+            return Ok(0);
+        }
 
-                // Determine which destination window this point belongs to
+        let chunk_windows = (CHUNK_SLOTS * source_interval_ns)
+            .div_ceil(dest_interval_ns)
+            .max(1);
+        let chunk_ns = chunk_windows.saturating_mul(dest_interval_ns);
+
+        let mut timestamps = Vec::new();
+        let mut rows = Vec::new();
+        let mut chunk_start = start_timestamp;
+        while chunk_start < end_timestamp {
+            let window_floor = (chunk_start / dest_interval_ns) * dest_interval_ns;
+            let chunk_end = window_floor.saturating_add(chunk_ns).min(end_timestamp);
+
+            // Read every column of each source slot in a single pass over the ring
+            timestamps.clear();
+            rows.clear();
+            source_ring.read_rows_into(
+                columns,
+                chunk_start,
+                chunk_end,
+                &mut timestamps,
+                &mut rows,
+            )?;
+
+            // Group data into windows aligned to destination tier intervals,
+            // ordered by start time so they are written chronologically
+            let mut windows: BTreeMap<u64, ConsolidationWindow> = BTreeMap::new();
+            for (&timestamp, row) in timestamps.iter().zip(rows.chunks(columns as usize)) {
                 let window_start = (timestamp / dest_interval_ns) * dest_interval_ns;
                 let window_end = window_start + dest_interval_ns;
 
-                // Create or get the window
-                let window = all_windows
+                let window = windows
                     .entry(window_start)
                     .or_insert_with(|| ConsolidationWindow::new(window_start, window_end));
 
-                // Add the data point
-                window.add_point(series_column, value);
+                // Add the data points, skipping NaN (unwritten) values
+                for (series_column, &value) in (0..columns).zip(row) {
+                    window.add_point(series_column, value);
+                }
             }
-        }
 
-        // Process each window and write consolidated values
-        for window in all_windows.values() {
-            for series_column in window.series_columns() {
-                if let Some(consolidated_value) =
-                    window.consolidate_series(series_column, consolidation_fn)
-                {
-                    // Write consolidated value to destination tier
-                    dest_ring.write(series_column, consolidated_value, window.start_timestamp)?;
-                    operations += 1;
+            // Process each window and write consolidated values
+            for window in windows.values() {
+                for series_column in window.series_columns() {
+                    if let Some(consolidated_value) =
+                        window.consolidate_series(series_column, consolidation_fn)
+                    {
+                        dest_ring.write(
+                            series_column,
+                            consolidated_value,
+                            window.start_timestamp,
+                        )?;
+                        operations += 1;
+                    }
                 }
             }
+
+            chunk_start = chunk_end;
         }
 
         Ok(operations)
@@ -574,7 +607,7 @@ mod tests {
             #[allow(clippy::cast_possible_truncation)]
             let interval_ns = tier.interval.as_nanos() as u64;
 
-            let slab = Slab::create(
+            let mut slab = Slab::create(
                 slab_path,
                 0x1234567890abcdef,
                 slot_count,
@@ -582,6 +615,8 @@ mod tests {
                 interval_ns,
             )
             .unwrap();
+            // Tests write to every column as if all series were registered
+            slab.set_series_count(schema.max_series);
 
             rings.push(RingBuffer::new(slab));
         }
@@ -743,6 +778,48 @@ mod tests {
         // (Note: exact window boundaries depend on timestamp alignment)
     }
 
+    #[test]
+    fn test_consolidation_in_chunks() {
+        let temp_dir = tempdir().unwrap();
+        let mut schema = create_test_schema();
+        // Room for a backlog spanning several chunks
+        schema.tiers[0].retention = Duration::from_secs(3 * CHUNK_SLOTS);
+        schema.tiers[1].retention = Duration::from_secs(30 * CHUNK_SLOTS);
+        schema.tiers.truncate(2);
+        let mut engine = ConsolidationEngine::new(temp_dir.path(), vec![schema.clone()]).unwrap();
+
+        let mut rings = create_test_rings(&temp_dir, &schema);
+        // Only the first column is registered; the rest are never read
+        rings[0].slab_mut().set_series_count(1);
+        let mut all_rings = vec![rings];
+
+        let base_time = 1_000_000_000_000_000_000u64;
+        let points = (2 * CHUNK_SLOTS).next_multiple_of(10) + 500;
+        for i in 0..points {
+            #[allow(clippy::cast_precision_loss)] // Test values are small
+            all_rings[0][0]
+                .write(0, i as f64, base_time + i * 1_000_000_000)
+                .unwrap();
+        }
+
+        let operations = engine.consolidate(&mut all_rings).unwrap();
+        assert_eq!(operations as u64, points.div_ceil(10));
+
+        // Every 10s window is averaged whole, across chunk boundaries
+        let tier1: Vec<_> = all_rings[0][1]
+            .read(0, base_time, base_time + points * 1_000_000_000)
+            .unwrap()
+            .collect();
+        assert_eq!(tier1.len() as u64, points.div_ceil(10));
+        for (window, &(timestamp, value)) in tier1.iter().enumerate() {
+            let window = window as u64;
+            assert_eq!(timestamp, base_time + window * 10_000_000_000);
+            #[allow(clippy::cast_precision_loss)]
+            let expected = (window * 10) as f64 + 4.5;
+            assert_eq!(value, expected);
+        }
+    }
+
     #[test]
     fn test_consolidation_idempotence() {
         let temp_dir = tempdir().unwrap();
@@ -933,6 +1010,60 @@ mod tests {
         assert!(!series0_data.is_empty() || !series1_data.is_empty());
     }
 
+    #[test]
+    fn test_multiple_series_consolidation_values() {
+        let temp_dir = tempdir().unwrap();
+        let schema = create_test_schema();
+        let mut engine = ConsolidationEngine::new(temp_dir.path(), vec![schema.clone()]).unwrap();
+
+        let rings = create_test_rings(&temp_dir, &schema);
+        let mut all_rings = vec![rings];
+
+        let base_time = 1_000_000_000_000_000_000u64;
+
+        // Two full 10s windows; series 3 only writes every other second
+        for i in 0..20u32 {
+            let timestamp = base_time + u64::from(i) * 1_000_000_000;
+            all_rings[0][0].write(0, f64::from(i), timestamp).unwrap();
+            if i % 2 == 0 {
+                all_rings[0][0].write(3, 100.0, timestamp).unwrap();
+            }
+        }
+        // Start a third window so the first two are followed by newer data
+        all_rings[0][0]
+            .write(0, 0.0, base_time + 20_000_000_000)
+            .unwrap();
+
+        engine.consolidate(&mut all_rings).unwrap();
+
+        let series0: Vec<_> = all_rings[0][1]
+            .read(0, base_time, base_time + 20_000_000_000)
+            .unwrap()
+            .collect();
+        assert_eq!(
+            series0,
+            vec![(base_time, 4.5), (base_time + 10_000_000_000, 14.5)]
+        );
+
+        let series3: Vec<_> = all_rings[0][1]
+            .read(3, base_time, base_time + 20_000_000_000)
+            .unwrap()
+            .collect();
+        assert_eq!(
+            series3,
+            vec![(base_time, 100.0), (base_time + 10_000_000_000, 100.0)]
+        );
+
+        // Columns that were never written stay empty
+        assert_eq!(
+            all_rings[0][1]
+                .read(1, base_time, base_time + 20_000_000_000)
+                .unwrap()
+                .count(),
+            0
+        );
+    }
+
     #[test]
     fn test_three_tier_consolidation() {
         let temp_dir = tempdir().unwrap();
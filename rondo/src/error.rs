@@ -42,10 +42,80 @@ pub enum RondoError {
     #[error("export error: {0}")]
     Export(#[from] ExportError),
 
+    /// Error parsing or evaluating a PromQL expression.
+    #[error("PromQL error: {0}")]
+    Promql(#[from] PromqlError),
+
+    /// Error parsing or rendering InfluxDB line protocol.
+    #[error("line protocol error: {0}")]
+    LineProtocol(#[from] LineProtocolError),
+
+    /// Error in a relabeling rule.
+    #[error("relabel error: {0}")]
+    Relabel(#[from] RelabelError),
+
+    /// Error rendering the text exposition format.
+    #[error("exposition error: {0}")]
+    Exposition(#[from] ExpositionError),
+
     /// Error during remote write operations.
     #[cfg(feature = "prometheus-remote-write")]
     #[error("remote write error: {0}")]
     RemoteWrite(#[from] RemoteWriteError),
+
+    /// Error while serving a remote-read request.
+    #[cfg(feature = "prometheus-remote-write")]
+    #[error("remote read error: {0}")]
+    RemoteRead(#[from] RemoteReadError),
+
+    /// Error during OTLP export operations.
+    #[cfg(feature = "otlp")]
+    #[error("OTLP error: {0}")]
+    Otlp(#[from] OtlpError),
+
+    /// Error during Graphite export operations.
+    #[cfg(feature = "graphite")]
+    #[error("Graphite error: {0}")]
+    Graphite(#[from] GraphiteError),
+
+    /// Error in the StatsD listener.
+    #[cfg(feature = "statsd")]
+    #[error("StatsD error: {0}")]
+    Statsd(#[from] StatsdError),
+}
+
+impl RondoError {
+    /// Returns `false` for export failures that sending the same payload
+    /// again cannot fix, such as a 400 response or an OTLP partial success.
+    ///
+    /// The [`ExportDriver`](crate::exporter::ExportDriver) drops such
+    /// batches instead of retrying or spooling them. Network errors,
+    /// throttling, server errors, and local failures are retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(feature = "prometheus-remote-write")]
+            Self::RemoteWrite(e) => match e {
+                RemoteWriteError::HttpStatus { status, .. } => {
+                    crate::remote_write::is_retryable(*status)
+                }
+                RemoteWriteError::Serialization { .. }
+                | RemoteWriteError::Compression { .. }
+                | RemoteWriteError::Decompression { .. }
+                | RemoteWriteError::Decode { .. } => false,
+                _ => true,
+            },
+            #[cfg(feature = "otlp")]
+            Self::Otlp(e) => match e {
+                OtlpError::HttpStatus { status, .. } => crate::otlp::is_retryable(*status),
+                OtlpError::PartialSuccess { .. }
+                | OtlpError::Serialization { .. }
+                | OtlpError::Decode { .. } => false,
+                _ => true,
+            },
+            Self::Export(ExportError::PermanentlyRejected { .. }) => false,
+            _ => true,
+        }
+    }
 }
 
 /// Errors that can occur when opening or creating a store.
@@ -201,6 +271,13 @@ pub enum QueryError {
         /// The end of the requested range.
         end: u64,
     },
+
+    /// The requested point limit is invalid (must be at least 1).
+    #[error("invalid max_points {max_points}: must be at least 1")]
+    InvalidMaxPoints {
+        /// The requested point limit.
+        max_points: usize,
+    },
 }
 
 /// Errors that can occur during schema validation or processing.
@@ -424,6 +501,145 @@ pub enum ExportError {
         #[source]
         source: serde_json::Error,
     },
+
+    /// An export sink rejected a payload it will never accept, e.g.
+    /// because the data is invalid. The batch is not retried.
+    #[error("sink '{sink}' permanently rejected the export: {reason}")]
+    PermanentlyRejected {
+        /// The sink name.
+        sink: String,
+        /// Why the payload was rejected.
+        reason: String,
+    },
+
+    /// A sink was skipped because the shutdown deadline had passed.
+    #[error("sink '{sink}' was not flushed before the shutdown deadline")]
+    DeadlineExceeded {
+        /// The sink name.
+        sink: String,
+    },
+
+    /// Failed to read or write an export spool.
+    #[error("export spool I/O error at '{}': {source}", path.display())]
+    SpoolIo {
+        /// The spool directory or batch file.
+        path: std::path::PathBuf,
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// A batch is larger than the whole spool.
+    #[error("export batch of {size} bytes exceeds the spool size limit of {max_bytes} bytes")]
+    SpoolFull {
+        /// The batch size in bytes.
+        size: u64,
+        /// The spool size limit in bytes.
+        max_bytes: u64,
+    },
+
+    /// An export sink did not accept a payload.
+    #[error("sink '{sink}' rejected the export: {reason}")]
+    Rejected {
+        /// The sink name.
+        sink: String,
+        /// Why the payload was rejected.
+        reason: String,
+    },
+}
+
+/// Errors that can occur while parsing or evaluating PromQL expressions.
+#[derive(Error, Debug)]
+pub enum PromqlError {
+    /// The expression is not valid PromQL (or uses unsupported syntax).
+    #[error("parse error at position {position}: {message}")]
+    Parse {
+        /// Byte offset in the expression where the error was detected.
+        position: usize,
+        /// Description of the problem.
+        message: String,
+    },
+
+    /// An operator or function was applied to the wrong kind of value.
+    #[error("type error: {message}")]
+    Type {
+        /// Description of the mismatch.
+        message: String,
+    },
+
+    /// The expression is well-formed but cannot be evaluated.
+    #[error("evaluation error: {message}")]
+    Evaluation {
+        /// Description of the problem.
+        message: String,
+    },
+}
+
+/// Errors that can occur while parsing or rendering InfluxDB line protocol.
+#[derive(Error, Debug)]
+pub enum LineProtocolError {
+    /// A line is not valid line protocol.
+    #[error("line {line}: {message}")]
+    Parse {
+        /// 1-based line number in the input.
+        line: usize,
+        /// Description of the problem.
+        message: String,
+    },
+
+    /// Series handle not found in registry.
+    #[error("series not found for schema_index={schema_index} column={column}")]
+    SeriesNotFound {
+        /// The schema index.
+        schema_index: usize,
+        /// The column.
+        column: u32,
+    },
+}
+
+/// Errors in relabeling rules.
+#[derive(Error, Debug)]
+pub enum RelabelError {
+    /// The rule's regular expression does not compile.
+    #[error("invalid relabel regex '{regex}': {source}")]
+    InvalidRegex {
+        /// The regular expression as given.
+        regex: String,
+        /// The underlying regex error.
+        #[source]
+        source: regex::Error,
+    },
+
+    /// A `replace` rule has no target label.
+    #[error("replace rule needs a target_label")]
+    MissingTargetLabel,
+}
+
+/// Errors that can occur while rendering the text exposition format.
+#[derive(Error, Debug)]
+pub enum ExpositionError {
+    /// Two series render as the same sample once their names and label
+    /// keys are sanitized, e.g. `cpu.usage` and `cpu_usage`.
+    #[error("series '{series}' renders as '{sample}', which another series already uses")]
+    DuplicateSeries {
+        /// The colliding series, as registered.
+        series: String,
+        /// The rendered metric name and labels.
+        sample: String,
+    },
+
+    /// Two metrics render under the same family or sample name, e.g. a
+    /// gauge `foo` and a counter `foo_total` in OpenMetrics, where both
+    /// announce `# TYPE foo`.
+    #[error("metrics '{first}' and '{second}' both render as '{name}'")]
+    DuplicateFamily {
+        /// The metric rendered first.
+        first: String,
+        /// The metric that collides with it.
+        second: String,
+        /// The family or sample name both render as.
+        name: String,
+    },
 }
 
 /// Errors that can occur during Prometheus remote-write operations.
@@ -479,7 +695,240 @@ pub enum RemoteWriteError {
         /// The column.
         column: u32,
     },
+
+    /// A received request body is not valid Snappy.
+    #[error("failed to decompress write request: {source}")]
+    Decompression {
+        /// The snappy decompression error.
+        #[source]
+        source: snap::Error,
+    },
+
+    /// A received request body is not a valid `WriteRequest`.
+    #[error("failed to decode write request: {source}")]
+    Decode {
+        /// The protobuf decoding error.
+        #[source]
+        source: prost::DecodeError,
+    },
+
+    /// A token, certificate or key file could not be read.
+    #[error("failed to read credentials from '{}': {source}", path.display())]
+    ReadCredentials {
+        /// The file that could not be read.
+        path: std::path::PathBuf,
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The TLS settings are incomplete or a PEM file is invalid.
+    #[error("invalid TLS configuration: {reason}")]
+    InvalidTls {
+        /// What is wrong.
+        reason: String,
+    },
+}
+
+/// Errors that can occur while serving a Prometheus remote-read request.
+#[cfg(feature = "prometheus-remote-write")]
+#[derive(Error, Debug)]
+pub enum RemoteReadError {
+    /// The request body is not valid Snappy.
+    #[error("failed to decompress request: {source}")]
+    Decompression {
+        /// The snappy decompression error.
+        #[source]
+        source: snap::Error,
+    },
+
+    /// The request body is not a valid `ReadRequest`.
+    #[error("failed to decode read request: {source}")]
+    Decode {
+        /// The protobuf decoding error.
+        #[source]
+        source: prost::DecodeError,
+    },
+
+    /// The client does not accept sample responses.
+    #[error("none of the accepted response types is supported (only SAMPLES is)")]
+    UnsupportedResponseType,
+
+    /// A label matcher has an unknown type or an invalid regular expression.
+    #[error("invalid matcher for label '{name}': {reason}")]
+    InvalidMatcher {
+        /// The label name of the matcher.
+        name: String,
+        /// Why the matcher was rejected.
+        reason: String,
+    },
+
+    /// Failed to serialize the `ReadResponse`.
+    #[error("failed to serialize read response: {source}")]
+    Serialization {
+        /// The protobuf encoding error.
+        #[source]
+        source: prost::EncodeError,
+    },
+
+    /// Failed to compress the response with Snappy.
+    #[error("failed to compress response: {source}")]
+    Compression {
+        /// The snappy compression error.
+        #[source]
+        source: snap::Error,
+    },
+}
+
+/// Errors that can occur during OTLP export operations.
+#[cfg(feature = "otlp")]
+#[derive(Error, Debug)]
+pub enum OtlpError {
+    /// Failed to serialize `ExportMetricsServiceRequest` to protobuf.
+    #[error("failed to serialize export request: {source}")]
+    Serialization {
+        /// The protobuf encoding error.
+        #[source]
+        source: prost::EncodeError,
+    },
+
+    /// Failed to create HTTP client.
+    #[error("failed to create HTTP client: {source}")]
+    ClientCreate {
+        /// The underlying reqwest error.
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// HTTP request failed after retries.
+    #[error("HTTP request failed: {source}")]
+    RequestFailed {
+        /// The underlying reqwest error.
+        #[source]
+        source: reqwest::Error,
+    },
+
+    /// Server returned a non-2xx status (after retries, if retryable).
+    #[error("server returned status {status}: {body}")]
+    HttpStatus {
+        /// The HTTP status code.
+        status: u16,
+        /// The response body text.
+        body: String,
+    },
+
+    /// Server accepted the request but rejected some data points.
+    #[error("server rejected {rejected} data points: {message}")]
+    PartialSuccess {
+        /// Number of rejected data points.
+        rejected: i64,
+        /// The server's explanation.
+        message: String,
+    },
+
+    /// Series handle not found in registry.
+    #[error("series not found for schema_index={schema_index} column={column}")]
+    SeriesNotFound {
+        /// The schema index.
+        schema_index: usize,
+        /// The column.
+        column: u32,
+    },
+
+    /// Request body uses an unsupported `Content-Encoding`.
+    #[error("unsupported content encoding: {encoding}")]
+    UnsupportedEncoding {
+        /// The encoding from the request.
+        encoding: String,
+    },
+
+    /// Failed to decompress a gzip request body.
+    #[error("failed to decompress request: {source}")]
+    Decompression {
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Decompressed request body exceeds the size limit.
+    #[error("request exceeds {limit} bytes")]
+    RequestTooLarge {
+        /// The limit in bytes.
+        limit: u64,
+    },
+
+    /// Failed to decode `ExportMetricsServiceRequest` from protobuf.
+    #[error("failed to decode export request: {source}")]
+    Decode {
+        /// The protobuf decoding error.
+        #[source]
+        source: prost::DecodeError,
+    },
 }
 
 /// Type alias for `Result<T, RondoError>`.
 pub type Result<T> = std::result::Result<T, RondoError>;
+
+/// Errors that can occur during Graphite export operations.
+#[cfg(feature = "graphite")]
+#[derive(Error, Debug)]
+pub enum GraphiteError {
+    /// The path template is malformed.
+    #[error("invalid path template '{template}': {reason}")]
+    InvalidTemplate {
+        /// The template string.
+        template: String,
+        /// Why the template was rejected.
+        reason: String,
+    },
+
+    /// Failed to connect to the carbon receiver.
+    #[error("failed to connect to '{address}': {source}")]
+    Connect {
+        /// The configured address.
+        address: String,
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Failed to write to the carbon receiver.
+    #[error("failed to write to carbon: {source}")]
+    Write {
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Series handle not found in registry.
+    #[error("series not found for schema_index={schema_index} column={column}")]
+    SeriesNotFound {
+        /// The schema index.
+        schema_index: usize,
+        /// The column.
+        column: u32,
+    },
+}
+
+/// Errors that can occur in the StatsD listener.
+#[cfg(feature = "statsd")]
+#[derive(Error, Debug)]
+pub enum StatsdError {
+    /// Failed to bind the UDP socket.
+    #[error("failed to bind StatsD socket on {addr}: {source}")]
+    Bind {
+        /// The requested address.
+        addr: String,
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// Failed to receive from the UDP socket.
+    #[error("failed to receive StatsD packet: {source}")]
+    Receive {
+        /// The underlying I/O error.
+        #[source]
+        source: std::io::Error,
+    },
+}
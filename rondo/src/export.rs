@@ -10,6 +10,18 @@
 //! been exported. Each cursor is identified by a name (e.g., "prometheus")
 //! and tracks the last exported timestamp per series per tier.
 //!
+//! For at-least-once delivery, drain in two phases:
+//! [`Store::drain_pending`](crate::store::Store::drain_pending) reads new
+//! data without touching the cursor, and [`ExportCursor::commit`] advances
+//! it once the push has succeeded. A failed push is retried from the same
+//! position on the next drain.
+//!
+//! Data drained from a consolidated tier is a rollup: each point summarizes
+//! one tier interval with the tier's consolidation function. Exporters mark
+//! such series with a [`Rollup`] such as `avg_5m`, either as a
+//! `__rollup__` label or as a name suffix ([`RollupNaming`]), so rollups of
+//! different functions or intervals never collide with the raw series.
+//!
 //! # Example
 //!
 //! ```rust,no_run
@@ -25,19 +37,31 @@
 //! #     max_series: 10,
 //! # }];
 //! # let store = Store::open("/tmp/export_example", schemas)?;
-//! let cursor = ExportCursor::load_or_new("/tmp/export_example/cursor_prometheus.json")?;
-//! // Use store.drain() to get new data since cursor
+//! let mut cursor = ExportCursor::load_or_new("/tmp/export_example/cursor_prometheus.json")?;
+//!
+//! let pending = store.drain_pending(0, &cursor)?;
+//! // Push pending.exports somewhere; on success:
+//! cursor.commit(&pending);
+//! cursor.save()?;
 //! # Ok(())
 //! # }
 //! ```
 
+#[cfg(any(
+    feature = "prometheus-remote-write",
+    feature = "otlp",
+    feature = "graphite"
+))]
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
 use crate::error::{ExportError, Result};
 use crate::ring::RingBuffer;
+use crate::schema::ConsolidationFn;
 use crate::series::SeriesHandle;
 
 /// A data point exported from the store.
@@ -62,6 +86,102 @@ pub struct SeriesExport {
     pub handle: SeriesHandle,
     /// The exported data points, ordered by timestamp.
     pub points: Vec<(u64, f64)>,
+    /// Tier the points were read from (0 is the highest resolution).
+    pub tier: usize,
+}
+
+/// The consolidation a rollup tier applies, e.g. `avg_5m`.
+///
+/// Returned by [`Store::rollup`](crate::store::Store::rollup) for tiers
+/// with a consolidation function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rollup {
+    /// Function that combined the source points.
+    pub function: ConsolidationFn,
+    /// Interval each point covers.
+    pub interval: Duration,
+}
+
+impl std::fmt::Display for Rollup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let function = match self.function {
+            ConsolidationFn::Average => "avg",
+            ConsolidationFn::Min => "min",
+            ConsolidationFn::Max => "max",
+            ConsolidationFn::Last => "last",
+            ConsolidationFn::Sum => "sum",
+            ConsolidationFn::Count => "count",
+        };
+        let interval = crate::promql::ast::format_duration(self.interval);
+        write!(f, "{function}_{interval}")
+    }
+}
+
+/// How exporters mark series drained from a rollup tier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RollupNaming {
+    /// Adds a `__rollup__` label, e.g. `__rollup__="avg_5m"`.
+    #[default]
+    Label,
+    /// Appends the rollup to the metric name, e.g. `cpu_usage_avg_5m`.
+    Suffix,
+}
+
+/// Label carrying the rollup under [`RollupNaming::Label`].
+pub const ROLLUP_LABEL: &str = "__rollup__";
+
+/// Returns the name and labels a series is exported under.
+///
+/// Raw series (`rollup` is `None`) are returned unchanged.
+#[cfg(any(
+    feature = "prometheus-remote-write",
+    feature = "otlp",
+    feature = "graphite"
+))]
+pub(crate) fn rollup_identity<'a>(
+    name: &'a str,
+    labels: &'a [(String, String)],
+    rollup: Option<Rollup>,
+    naming: RollupNaming,
+) -> (Cow<'a, str>, Cow<'a, [(String, String)]>) {
+    let Some(rollup) = rollup else {
+        return (Cow::Borrowed(name), Cow::Borrowed(labels));
+    };
+    match naming {
+        RollupNaming::Label => {
+            let mut labels = labels.to_vec();
+            labels.retain(|(k, _)| k != ROLLUP_LABEL);
+            labels.push((ROLLUP_LABEL.to_string(), rollup.to_string()));
+            (Cow::Borrowed(name), Cow::Owned(labels))
+        }
+        RollupNaming::Suffix => (
+            Cow::Owned(format!("{name}_{rollup}")),
+            Cow::Borrowed(labels),
+        ),
+    }
+}
+
+/// Data read by [`Store::drain_pending`](crate::store::Store::drain_pending)
+/// that has not been committed to a cursor yet.
+///
+/// Batches from several tiers can be combined by extending `exports`; each
+/// export remembers its tier.
+#[derive(Debug)]
+pub struct PendingExport {
+    /// New data points per series.
+    pub exports: Vec<SeriesExport>,
+}
+
+impl PendingExport {
+    pub(crate) fn new(exports: Vec<SeriesExport>) -> Self {
+        Self { exports }
+    }
+
+    /// Returns `true` if there is no new data.
+    pub fn is_empty(&self) -> bool {
+        self.exports.is_empty()
+    }
 }
 
 /// Persistent cursor tracking export progress.
@@ -133,6 +253,26 @@ impl ExportCursor {
         Ok(())
     }
 
+    /// Advances the cursor past the data of a [`PendingExport`].
+    ///
+    /// Call this once the pending data has been delivered. Positions never
+    /// move backwards, so committing a stale or already committed batch is
+    /// harmless.
+    pub fn commit(&mut self, pending: &PendingExport) {
+        for export in &pending.exports {
+            let Some(&(last_ts, _)) = export.points.last() else {
+                continue;
+            };
+            let (schema_index, column) = (export.handle.schema_index, export.handle.column);
+            if self
+                .get(schema_index, export.tier, column)
+                .is_none_or(|ts| ts < last_ts)
+            {
+                self.update(schema_index, export.tier, column, last_ts);
+            }
+        }
+    }
+
     /// Gets the last exported timestamp for a (schema, tier, column) triple.
     fn get(&self, schema_index: usize, tier_index: usize, series_column: u32) -> Option<u64> {
         let key = Self::make_key(schema_index, tier_index, series_column);
@@ -166,12 +306,34 @@ impl Default for ExportCursor {
 ///
 /// Returns all data points newer than the cursor position for this series.
 /// The cursor is advanced to the newest timestamp found.
+#[cfg(test)]
 pub(crate) fn drain_series(
     ring: &RingBuffer,
     schema_index: usize,
     tier_index: usize,
     series_column: u32,
     cursor: &mut ExportCursor,
+) -> Result<Vec<(u64, f64)>> {
+    let points = pending_series(ring, schema_index, tier_index, series_column, cursor)?;
+
+    // Update cursor to newest timestamp we read
+    if let Some(&(last_ts, _)) = points.last() {
+        cursor.update(schema_index, tier_index, series_column, last_ts);
+    }
+
+    Ok(points)
+}
+
+/// Reads new data from the specified tier of a ring buffer for a given series.
+///
+/// Returns all data points newer than the cursor position for this series,
+/// leaving the cursor unchanged.
+pub(crate) fn pending_series(
+    ring: &RingBuffer,
+    schema_index: usize,
+    tier_index: usize,
+    series_column: u32,
+    cursor: &ExportCursor,
 ) -> Result<Vec<(u64, f64)>> {
     let last_exported = cursor.get(schema_index, tier_index, series_column);
 
@@ -197,26 +359,19 @@ pub(crate) fn drain_series(
 
     // Read data from ring buffer (end is exclusive, so add 1)
     let iter = ring.read(series_column, start, newest_ts + 1)?;
-    let points: Vec<(u64, f64)> = iter.collect();
-
-    // Update cursor to newest timestamp we read
-    if let Some(&(last_ts, _)) = points.last() {
-        cursor.update(schema_index, tier_index, series_column, last_ts);
-    }
-
-    Ok(points)
+    Ok(iter.collect())
 }
 
-/// Drains all new data from a store for all registered series at a specific tier.
+/// Reads all new data for all registered series at a specific tier.
 ///
-/// Returns a vector of `SeriesExport` containing new data points for each series.
-/// The cursor is updated for each series that had new data.
-pub(crate) fn drain_tier(
+/// Returns a vector of `SeriesExport` containing new data points for each
+/// series. The cursor is not modified.
+pub(crate) fn pending_tier(
     rings: &[Vec<RingBuffer>],
     schema_index: usize,
     tier_index: usize,
     handles: &[SeriesHandle],
-    cursor: &mut ExportCursor,
+    cursor: &ExportCursor,
 ) -> Result<Vec<SeriesExport>> {
     let mut exports = Vec::new();
 
@@ -227,10 +382,14 @@ pub(crate) fn drain_tier(
             continue;
         }
 
-        let points = drain_series(ring, schema_index, tier_index, handle.column, cursor)?;
+        let points = pending_series(ring, schema_index, tier_index, handle.column, cursor)?;
 
         if !points.is_empty() {
-            exports.push(SeriesExport { handle, points });
+            exports.push(SeriesExport {
+                handle,
+                points,
+                tier: tier_index,
+            });
         }
     }
 
@@ -254,6 +413,48 @@ mod tests {
         RingBuffer::new(slab)
     }
 
+    #[test]
+    fn test_rollup_display() {
+        let rollup = Rollup {
+            function: ConsolidationFn::Average,
+            interval: Duration::from_secs(300),
+        };
+        assert_eq!(rollup.to_string(), "avg_5m");
+        let hourly = Rollup {
+            function: ConsolidationFn::Max,
+            interval: Duration::from_secs(3600),
+        };
+        assert_eq!(hourly.to_string(), "max_1h");
+    }
+
+    #[cfg(any(
+        feature = "prometheus-remote-write",
+        feature = "otlp",
+        feature = "graphite"
+    ))]
+    #[test]
+    fn test_rollup_identity() {
+        use std::borrow::Cow;
+
+        let rollup = Rollup {
+            function: ConsolidationFn::Average,
+            interval: Duration::from_secs(300),
+        };
+        let labels = vec![("host".to_string(), "a".to_string())];
+        let (name, same) = rollup_identity("cpu", &labels, None, RollupNaming::Suffix);
+        assert!(matches!(
+            (name, same),
+            (Cow::Borrowed("cpu"), Cow::Borrowed(_))
+        ));
+
+        let (name, tagged) = rollup_identity("cpu", &labels, Some(rollup), RollupNaming::Label);
+        assert_eq!(name, "cpu");
+        assert_eq!(tagged[1], (ROLLUP_LABEL.to_string(), "avg_5m".to_string()));
+
+        let (name, _) = rollup_identity("cpu", &labels, Some(rollup), RollupNaming::Suffix);
+        assert_eq!(name, "cpu_avg_5m");
+    }
+
     #[test]
     fn test_export_cursor_new() {
         let cursor = ExportCursor::new();
@@ -0,0 +1,905 @@
+//! Pluggable export sinks and the driver that feeds them.
+//!
+//! An [`Exporter`] turns drained [`SeriesExport`]s into a payload
+//! ([`encode`](Exporter::encode)), delivers it ([`send`](Exporter::send)),
+//! and commits any state it staged once delivery is confirmed
+//! ([`ack`](Exporter::ack)). The remote-write, OTLP and Graphite exporters
+//! implement it behind their features, and applications can add their own.
+//!
+//! [`ExportDriver`] owns one [`ExportCursor`] per sink. Each
+//! [`run_once`](ExportDriver::run_once) reads new data for every sink with
+//! [`Store::drain_pending`], sends the batch, and commits and saves that
+//! sink's cursor only after the sink acknowledges. A sink that is down
+//! keeps its cursor where it was and gets the same data again on the next
+//! run, without holding back the other sinks.
+//!
+//! Each sink drains its own set of tiers: a fleet TSDB can get the 5-minute
+//! rollups while a local collector gets raw data. A sink with several tiers
+//! gets them in one batch, each series marked with its rollup (see
+//! [`Rollup`](crate::export::Rollup)).
+//!
+//! Only draining and encoding read the store: a run can be split into
+//! [`prepare`](ExportDriver::prepare), under whatever lock guards the
+//! store, and [`deliver`](ExportDriver::deliver), which does the slow
+//! network I/O without it.
+//!
+//! Before shutting down, [`flush`](ExportDriver::flush) consolidates the
+//! store and gives every sink one last run within a deadline, so a
+//! short-lived process does not lose its final export window.
+//!
+//! A sink with a [`Spool`] outlives outages longer than the store's
+//! retention: an undelivered batch is written to the spool and the cursor
+//! advances, and spooled batches are replayed oldest-first, ahead of new
+//! data, once the sink accepts payloads again. A batch the sink rejects
+//! permanently (e.g. a 400 response) is dropped rather than retried or
+//! spooled.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::export::ExportCursor;
+//! use rondo::exporter::{ExportDriver, Exporter};
+//! use rondo::store::Store;
+//! # use rondo::export::SeriesExport;
+//!
+//! # struct Stdout;
+//! # impl Exporter for Stdout {
+//! #     fn name(&self) -> &str { "stdout" }
+//! #     fn encode(&mut self, e: &[SeriesExport], _: &Store) -> rondo::Result<Vec<u8>> {
+//! #         Ok(format!("{} series\n", e.len()).into_bytes())
+//! #     }
+//! #     fn send(&mut self, payload: &[u8]) -> rondo::Result<()> {
+//! #         print!("{}", String::from_utf8_lossy(payload));
+//! #         Ok(())
+//! #     }
+//! # }
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let store = Store::open("/tmp/driver_example", vec![])?;
+//! let mut driver = ExportDriver::new(0).with_sink(
+//!     Stdout,
+//!     ExportCursor::load_or_new("/tmp/driver_example/cursor_stdout.json")?,
+//! );
+//!
+//! for report in driver.run_once(&store) {
+//!     if let Some(e) = report.error {
+//!         eprintln!("{}: {e}", report.name);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Instant;
+
+use crate::error::{ExportError, Result};
+use crate::export::{ExportCursor, PendingExport, SeriesExport};
+use crate::spool::Spool;
+use crate::store::Store;
+
+/// A destination for drained series data.
+///
+/// The driver calls [`encode`](Self::encode), then [`send`](Self::send),
+/// then [`ack`](Self::ack) if the send succeeded. Exporters that keep state
+/// across batches (such as delta baselines) should stage it in `encode` and
+/// only commit it in `ack`, so a failed batch can be encoded again.
+pub trait Exporter: Send {
+    /// Short name identifying the sink in reports and logs.
+    fn name(&self) -> &str;
+
+    /// Encodes a batch into a wire payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch cannot be encoded, e.g. because a
+    /// series is missing from the store.
+    fn encode(&mut self, exports: &[SeriesExport], store: &Store) -> Result<Vec<u8>>;
+
+    /// Delivers an encoded payload.
+    ///
+    /// Returning `Ok` acknowledges the payload: the driver advances this
+    /// sink's cursor past the batch. With a [`Spool`], the payload may have
+    /// been encoded in an earlier run, or before a restart.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sink did not accept the payload. The batch
+    /// is drained again on the next run (or spooled), unless the error is
+    /// not [retryable](crate::RondoError::is_retryable): then the batch is
+    /// dropped.
+    fn send(&mut self, payload: &[u8]) -> Result<()>;
+
+    /// Delivers an encoded payload like [`send`](Self::send), giving up
+    /// by `deadline`.
+    ///
+    /// The driver calls this for a final export at shutdown. The built-in
+    /// exporters cap each request's timeout and their retries to the time
+    /// left. The default ignores the deadline and calls `send`.
+    ///
+    /// # Errors
+    ///
+    /// As for [`send`](Self::send); running out of time is reported as
+    /// [`ExportError::DeadlineExceeded`].
+    fn send_before(&mut self, payload: &[u8], deadline: Instant) -> Result<()> {
+        let _ = deadline;
+        self.send(payload)
+    }
+
+    /// Commits state staged by the last [`encode`](Self::encode) after the
+    /// sink accepted the payload. Does nothing by default.
+    fn ack(&mut self) {}
+}
+
+/// Outcome of one driver run for one sink.
+#[derive(Debug)]
+pub struct SinkReport {
+    /// The sink's [`Exporter::name`].
+    pub name: String,
+    /// Series in the batch (0 if there was no new data).
+    pub series: usize,
+    /// Points in the batch.
+    pub points: usize,
+    /// Whether the sink acknowledged the batch.
+    pub delivered: bool,
+    /// Whether the batch was written to the sink's spool instead.
+    pub spooled: bool,
+    /// Spooled batches delivered before the new batch.
+    pub replayed: usize,
+    /// Spooled batches evicted to make room for the new batch.
+    pub evicted: usize,
+    /// Batches (new or spooled) dropped because the sink rejected them
+    /// permanently; see [`RondoError::is_retryable`](crate::RondoError::is_retryable).
+    pub dropped: usize,
+    /// Why the batch (or a spooled batch) was not delivered, or why the
+    /// cursor could not be saved after it was.
+    pub error: Option<crate::RondoError>,
+}
+
+/// A sink and its export progress.
+struct Sink {
+    exporter: Box<dyn Exporter>,
+    cursor: ExportCursor,
+    tiers: Vec<usize>,
+    spool: Option<Spool>,
+}
+
+/// Drains store tiers and fans them out to sinks with independent cursors.
+pub struct ExportDriver {
+    tier: usize,
+    sinks: Vec<Sink>,
+}
+
+impl std::fmt::Debug for ExportDriver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<_> = self.sinks.iter().map(|s| s.exporter.name()).collect();
+        f.debug_struct("ExportDriver")
+            .field("tier", &self.tier)
+            .field("sinks", &names)
+            .finish()
+    }
+}
+
+impl ExportDriver {
+    /// Creates a driver with no sinks, exporting the given tier to sinks
+    /// added without their own tiers.
+    pub fn new(tier: usize) -> Self {
+        Self {
+            tier,
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Adds a sink with its own cursor.
+    #[must_use]
+    pub fn with_sink(mut self, exporter: impl Exporter + 'static, cursor: ExportCursor) -> Self {
+        self.add_sink(Box::new(exporter), cursor);
+        self
+    }
+
+    /// Adds a sink with its own cursor.
+    pub fn add_sink(&mut self, exporter: Box<dyn Exporter>, cursor: ExportCursor) {
+        let tiers = vec![self.tier];
+        self.add_sink_with_tiers(exporter, cursor, tiers);
+    }
+
+    /// Adds a sink that drains the given tiers, in one batch per run.
+    #[must_use]
+    pub fn with_sink_tiers(
+        mut self,
+        exporter: impl Exporter + 'static,
+        cursor: ExportCursor,
+        tiers: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        self.add_sink_with_tiers(Box::new(exporter), cursor, tiers);
+        self
+    }
+
+    /// Adds a sink that drains the given tiers, in one batch per run.
+    pub fn add_sink_with_tiers(
+        &mut self,
+        exporter: Box<dyn Exporter>,
+        cursor: ExportCursor,
+        tiers: impl IntoIterator<Item = usize>,
+    ) {
+        let mut tiers: Vec<usize> = tiers.into_iter().collect();
+        tiers.sort_unstable();
+        tiers.dedup();
+        self.sinks.push(Sink {
+            exporter,
+            cursor,
+            tiers,
+            spool: None,
+        });
+    }
+
+    /// Spools undelivered batches of the first sink with the given name.
+    #[must_use]
+    pub fn with_spool(mut self, name: &str, spool: Spool) -> Self {
+        self.set_spool(name, spool);
+        self
+    }
+
+    /// Spools undelivered batches of the first sink with the given name.
+    ///
+    /// Returns `false` if there is no such sink.
+    pub fn set_spool(&mut self, name: &str, spool: Spool) -> bool {
+        match self.sinks.iter_mut().find(|s| s.exporter.name() == name) {
+            Some(sink) => {
+                sink.spool = Some(spool);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the spool of the first sink with the given name.
+    pub fn spool(&self, name: &str) -> Option<&Spool> {
+        self.sinks
+            .iter()
+            .find(|s| s.exporter.name() == name)
+            .and_then(|s| s.spool.as_ref())
+    }
+
+    /// Returns the tier exported to sinks added without their own tiers.
+    pub fn tier(&self) -> usize {
+        self.tier
+    }
+
+    /// Returns the tiers drained for the first sink with the given name.
+    pub fn tiers(&self, name: &str) -> Option<&[usize]> {
+        self.sinks
+            .iter()
+            .find(|s| s.exporter.name() == name)
+            .map(|s| s.tiers.as_slice())
+    }
+
+    /// Returns the number of sinks.
+    pub fn len(&self) -> usize {
+        self.sinks.len()
+    }
+
+    /// Returns `true` if the driver has no sinks.
+    pub fn is_empty(&self) -> bool {
+        self.sinks.is_empty()
+    }
+
+    /// Returns the cursor of the first sink with the given name.
+    pub fn cursor(&self, name: &str) -> Option<&ExportCursor> {
+        self.sinks
+            .iter()
+            .find(|s| s.exporter.name() == name)
+            .map(|s| &s.cursor)
+    }
+
+    /// Drains new data for every sink and delivers it.
+    ///
+    /// Each sink drains its tiers from its own cursor. The cursor is only
+    /// advanced and saved after the sink acknowledges the batch, or after the
+    /// batch is spooled; on any other failure it stays put so the same data
+    /// is drained again next time. Sinks with no new data are reported with
+    /// `series == 0`.
+    ///
+    /// This is [`prepare`](Self::prepare) followed by
+    /// [`deliver`](Self::deliver); call those separately to keep the store
+    /// locked only while draining.
+    pub fn run_once(&mut self, store: &Store) -> Vec<SinkReport> {
+        let run = self.prepare(store);
+        self.deliver(run)
+    }
+
+    /// Drains and encodes new data for every sink, without sending it.
+    ///
+    /// This is the only step that reads the store. Pass the result to
+    /// [`deliver`](Self::deliver) or [`deliver_before`](Self::deliver_before)
+    /// before preparing again.
+    pub fn prepare(&mut self, store: &Store) -> PreparedRun {
+        PreparedRun {
+            sinks: self
+                .sinks
+                .iter_mut()
+                .map(|sink| sink.prepare(store))
+                .collect(),
+        }
+    }
+
+    /// Sends a [`PreparedRun`], replaying spooled batches first, and
+    /// commits each sink's cursor as in [`run_once`](Self::run_once).
+    pub fn deliver(&mut self, run: PreparedRun) -> Vec<SinkReport> {
+        self.deliver_with(run, None)
+    }
+
+    /// Sends a [`PreparedRun`] like [`deliver`](Self::deliver), giving up
+    /// by `deadline`.
+    ///
+    /// Each send goes through [`Exporter::send_before`], so the built-in
+    /// exporters cap their timeouts and retries to the time left. Batches
+    /// not sent in time are reported with [`ExportError::DeadlineExceeded`]
+    /// and spooled if the sink has a spool; otherwise they are drained
+    /// again on the next run.
+    pub fn deliver_before(&mut self, run: PreparedRun, deadline: Instant) -> Vec<SinkReport> {
+        self.deliver_with(run, Some(deadline))
+    }
+
+    fn deliver_with(&mut self, run: PreparedRun, deadline: Option<Instant>) -> Vec<SinkReport> {
+        self.sinks
+            .iter_mut()
+            .zip(run.sinks)
+            .map(|(sink, prepared)| sink.deliver(prepared, deadline))
+            .collect()
+    }
+
+    /// Runs a final export before shutdown.
+    ///
+    /// Consolidates the store so rollup tiers include the latest complete
+    /// intervals, then prepares every sink's batch and delivers it with
+    /// [`deliver_before`](Self::deliver_before), saving each cursor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if consolidation fails; nothing is exported then.
+    pub fn flush(&mut self, store: &mut Store, deadline: Instant) -> Result<Vec<SinkReport>> {
+        store.consolidate()?;
+        let run = self.prepare(store);
+        Ok(self.deliver_before(run, deadline))
+    }
+}
+
+/// New data drained and encoded for every sink of an [`ExportDriver`],
+/// ready to be sent without access to the store.
+#[derive(Debug)]
+pub struct PreparedRun {
+    sinks: Vec<Prepared>,
+}
+
+impl PreparedRun {
+    /// Returns the number of series drained across all sinks.
+    pub fn series(&self) -> usize {
+        self.sinks.iter().map(|p| p.report.series).sum()
+    }
+}
+
+/// One sink's drained and encoded batch.
+#[derive(Debug)]
+struct Prepared {
+    report: SinkReport,
+    batch: Option<(PendingExport, Vec<u8>)>,
+}
+
+impl SinkReport {
+    /// A report for a sink that delivered nothing.
+    fn empty(name: String) -> Self {
+        Self {
+            name,
+            series: 0,
+            points: 0,
+            delivered: false,
+            spooled: false,
+            replayed: 0,
+            evicted: 0,
+            dropped: 0,
+            error: None,
+        }
+    }
+}
+
+impl Sink {
+    fn prepare(&mut self, store: &Store) -> Prepared {
+        let mut report = SinkReport::empty(self.exporter.name().to_string());
+
+        let mut pending = PendingExport::new(Vec::new());
+        for &tier in &self.tiers {
+            match store.drain_pending(tier, &self.cursor) {
+                Ok(tier_pending) => pending.exports.extend(tier_pending.exports),
+                Err(e) => {
+                    report.error = Some(e);
+                    return Prepared {
+                        report,
+                        batch: None,
+                    };
+                }
+            }
+        }
+        if pending.is_empty() {
+            return Prepared {
+                report,
+                batch: None,
+            };
+        }
+        report.series = pending.exports.len();
+        report.points = pending.exports.iter().map(|e| e.points.len()).sum();
+
+        match self.exporter.encode(&pending.exports, store) {
+            Ok(payload) => Prepared {
+                report,
+                batch: Some((pending, payload)),
+            },
+            Err(e) => {
+                report.error = Some(e);
+                Prepared {
+                    report,
+                    batch: None,
+                }
+            }
+        }
+    }
+
+    fn deliver(&mut self, prepared: Prepared, deadline: Option<Instant>) -> SinkReport {
+        let Prepared { mut report, batch } = prepared;
+
+        if let Some(spool) = self.spool.as_mut()
+            && let Err(e) = replay(spool, self.exporter.as_mut(), deadline, &mut report)
+            && report.error.is_none()
+        {
+            report.error = Some(e);
+        }
+        let Some((pending, payload)) = batch else {
+            return report;
+        };
+
+        // New data goes behind any backlog so batches arrive in order
+        let mut rejected = false;
+        if self.spool.as_ref().is_none_or(Spool::is_empty) {
+            match send(self.exporter.as_mut(), &payload, deadline) {
+                Ok(()) => report.delivered = true,
+                Err(e) => {
+                    rejected = !e.is_retryable();
+                    report.error = Some(e);
+                }
+            }
+        }
+        if rejected {
+            // Sending it again can never succeed: drop it, don't spool it
+            report.dropped += 1;
+        } else if !report.delivered {
+            let Some(spool) = self.spool.as_mut() else {
+                return report;
+            };
+            match spool.push(&payload) {
+                Ok(evicted) => {
+                    report.spooled = true;
+                    report.evicted = evicted;
+                }
+                Err(e) => {
+                    report.error = Some(e);
+                    return report;
+                }
+            }
+        }
+
+        self.exporter.ack();
+        self.cursor.commit(&pending);
+        if let Err(e) = self.cursor.save() {
+            report.error = Some(e);
+        }
+        report
+    }
+}
+
+/// Sends a payload, within `deadline` if there is one.
+fn send(exporter: &mut dyn Exporter, payload: &[u8], deadline: Option<Instant>) -> Result<()> {
+    let Some(deadline) = deadline else {
+        return exporter.send(payload);
+    };
+    if Instant::now() >= deadline {
+        return Err(ExportError::DeadlineExceeded {
+            sink: exporter.name().to_string(),
+        }
+        .into());
+    }
+    exporter.send_before(payload, deadline)
+}
+
+/// Delivers spooled batches oldest-first, stopping at the first failure
+/// that may succeed on retry. Batches the sink rejects permanently are
+/// dropped so they cannot block the rest of the spool.
+fn replay(
+    spool: &mut Spool,
+    exporter: &mut dyn Exporter,
+    deadline: Option<Instant>,
+    report: &mut SinkReport,
+) -> Result<()> {
+    while let Some(payload) = spool.front()? {
+        match send(exporter, &payload, deadline) {
+            Ok(()) => report.replayed += 1,
+            Err(e) if !e.is_retryable() => {
+                report.dropped += 1;
+                report.error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+        spool.pop_front()?;
+    }
+    Ok(())
+}
+
+/// Returns the timeout for the next send attempt: `timeout`, capped at the
+/// time left before `deadline`, or `None` once the deadline has passed.
+#[cfg(any(
+    feature = "prometheus-remote-write",
+    feature = "otlp",
+    feature = "graphite"
+))]
+pub(crate) fn attempt_timeout(
+    timeout: std::time::Duration,
+    deadline: Option<Instant>,
+) -> Option<std::time::Duration> {
+    match deadline {
+        None => Some(timeout),
+        Some(deadline) => deadline
+            .checked_duration_since(Instant::now())
+            .filter(|left| !left.is_zero())
+            .map(|left| left.min(timeout)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{ConsolidationFn, LabelMatcher, SchemaConfig, TierConfig};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    const BASE: u64 = 1_700_000_000_000_000_000;
+    const SEC: u64 = 1_000_000_000;
+
+    fn create_test_store(dir: &std::path::Path) -> Store {
+        let schemas = vec![SchemaConfig {
+            name: "test".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig::new(Duration::from_secs(1), Duration::from_secs(60), None).unwrap(),
+            ],
+            max_series: 10,
+        }];
+        Store::open(dir.join("store"), schemas).unwrap()
+    }
+
+    /// Records the timestamps in delivered payloads; fails while `down` is
+    /// set, and permanently rejects payloads containing `reject`.
+    #[derive(Clone, Default)]
+    struct MockSink {
+        name: &'static str,
+        down: Arc<Mutex<bool>>,
+        reject: Arc<Mutex<Option<u64>>>,
+        delivered: Arc<Mutex<Vec<u64>>>,
+        staged: Vec<u64>,
+        acked: Arc<Mutex<usize>>,
+    }
+
+    impl Exporter for MockSink {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn encode(&mut self, exports: &[SeriesExport], _store: &Store) -> Result<Vec<u8>> {
+            self.staged = exports
+                .iter()
+                .flat_map(|e| e.points.iter().map(|&(ts, _)| ts))
+                .collect();
+            Ok(self.staged.iter().flat_map(|ts| ts.to_le_bytes()).collect())
+        }
+
+        fn send(&mut self, payload: &[u8]) -> Result<()> {
+            if *self.down.lock().unwrap() {
+                return Err(ExportError::Rejected {
+                    sink: self.name.to_string(),
+                    reason: "down".to_string(),
+                }
+                .into());
+            }
+            if let Some(ts) = *self.reject.lock().unwrap()
+                && payload.as_chunks::<8>().0.contains(&ts.to_le_bytes())
+            {
+                return Err(ExportError::PermanentlyRejected {
+                    sink: self.name.to_string(),
+                    reason: "bad point".to_string(),
+                }
+                .into());
+            }
+            let timestamps = payload
+                .as_chunks::<8>()
+                .0
+                .iter()
+                .map(|&b| u64::from_le_bytes(b));
+            self.delivered.lock().unwrap().extend(timestamps);
+            Ok(())
+        }
+
+        fn ack(&mut self) {
+            *self.acked.lock().unwrap() += 1;
+        }
+    }
+
+    #[test]
+    fn test_fans_out_with_independent_cursors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+
+        let healthy = MockSink {
+            name: "healthy",
+            ..MockSink::default()
+        };
+        let flaky = MockSink {
+            name: "flaky",
+            ..MockSink::default()
+        };
+        *flaky.down.lock().unwrap() = true;
+
+        let mut driver = ExportDriver::new(0)
+            .with_sink(healthy.clone(), ExportCursor::new())
+            .with_sink(flaky.clone(), ExportCursor::new());
+
+        store.record(handle, 1.0, BASE).unwrap();
+        let reports = driver.run_once(&store);
+        assert!(reports[0].delivered && reports[0].error.is_none());
+        assert_eq!((reports[0].series, reports[0].points), (1, 1));
+        assert!(!reports[1].delivered && reports[1].error.is_some());
+        assert_eq!(*flaky.acked.lock().unwrap(), 0);
+
+        // The failed sink gets the first point again along with the new one
+        store.record(handle, 2.0, BASE + SEC).unwrap();
+        *flaky.down.lock().unwrap() = false;
+        let reports = driver.run_once(&store);
+        assert!(reports.iter().all(|r| r.delivered));
+        assert_eq!(*healthy.delivered.lock().unwrap(), vec![BASE, BASE + SEC]);
+        assert_eq!(*flaky.delivered.lock().unwrap(), vec![BASE, BASE + SEC]);
+        assert_eq!(*healthy.acked.lock().unwrap(), 2);
+        assert_eq!(*flaky.acked.lock().unwrap(), 1);
+
+        // Nothing new: no sends, no acks
+        let reports = driver.run_once(&store);
+        assert!(reports.iter().all(|r| r.series == 0 && !r.delivered));
+        assert_eq!(*healthy.acked.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_sink_tiers_drain_together() {
+        let dir = tempfile::tempdir().unwrap();
+        let schemas = vec![SchemaConfig {
+            name: "test".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig::new(Duration::from_secs(1), Duration::from_secs(60), None).unwrap(),
+                TierConfig::new(
+                    Duration::from_secs(10),
+                    Duration::from_secs(600),
+                    Some(ConsolidationFn::Average),
+                )
+                .unwrap(),
+            ],
+            max_series: 10,
+        }];
+        let mut store = Store::open(dir.path().join("store"), schemas).unwrap();
+        let handle = store.register("up", &[]).unwrap();
+        for i in 0..25 {
+            store.record(handle, 1.0, BASE + i * SEC).unwrap();
+        }
+        store.consolidate().unwrap();
+
+        let raw = MockSink {
+            name: "raw",
+            ..MockSink::default()
+        };
+        let both = MockSink {
+            name: "both",
+            ..MockSink::default()
+        };
+        let mut driver = ExportDriver::new(0)
+            .with_sink(raw.clone(), ExportCursor::new())
+            .with_sink_tiers(both.clone(), ExportCursor::new(), [1, 0, 1]);
+        assert_eq!(driver.tiers("raw"), Some(&[0][..]));
+        assert_eq!(driver.tiers("both"), Some(&[0, 1][..]));
+
+        let reports = driver.run_once(&store);
+        assert_eq!((reports[0].series, reports[0].points), (1, 25));
+        // The same series once per tier, in one batch
+        assert_eq!(reports[1].series, 2);
+        assert!(reports[1].points > 25);
+        assert_eq!(*both.acked.lock().unwrap(), 1);
+
+        // Both tiers were committed
+        let reports = driver.run_once(&store);
+        assert!(reports.iter().all(|r| r.series == 0));
+    }
+
+    #[test]
+    fn test_spools_while_down_and_replays_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+
+        let sink = MockSink {
+            name: "mock",
+            ..MockSink::default()
+        };
+        *sink.down.lock().unwrap() = true;
+        let spool = Spool::open(dir.path().join("spool"), 1024)
+            .unwrap()
+            .with_max_batches(2);
+        let mut driver = ExportDriver::new(0)
+            .with_sink(sink.clone(), ExportCursor::new())
+            .with_spool("mock", spool);
+        assert!(!driver.set_spool("other", Spool::open(dir.path().join("x"), 1).unwrap()));
+
+        for i in 0..3 {
+            store.record(handle, 1.0, BASE + i * SEC).unwrap();
+            let report = driver.run_once(&store).remove(0);
+            assert!(report.spooled && !report.delivered && report.error.is_some());
+            // The third batch pushes out the first
+            assert_eq!(report.evicted, usize::from(i == 2));
+        }
+        assert_eq!(driver.spool("mock").unwrap().len(), 2);
+        // Spooled data counts as exported
+        assert_eq!(*sink.acked.lock().unwrap(), 3);
+
+        *sink.down.lock().unwrap() = false;
+        store.record(handle, 1.0, BASE + 3 * SEC).unwrap();
+        let report = driver.run_once(&store).remove(0);
+        assert!(report.delivered && !report.spooled && report.error.is_none());
+        assert_eq!(report.replayed, 2);
+        assert_eq!(
+            *sink.delivered.lock().unwrap(),
+            vec![BASE + SEC, BASE + 2 * SEC, BASE + 3 * SEC]
+        );
+        assert!(driver.spool("mock").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_flush_exports_final_window_before_deadline() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+        store.record(handle, 1.0, BASE).unwrap();
+
+        let cursor_path = dir.path().join("cursor_mock.json");
+        let sink = MockSink {
+            name: "mock",
+            ..MockSink::default()
+        };
+        let mut driver = ExportDriver::new(0).with_sink(
+            sink.clone(),
+            ExportCursor::load_or_new(&cursor_path).unwrap(),
+        );
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let reports = driver.flush(&mut store, deadline).unwrap();
+        assert!(reports[0].delivered && reports[0].error.is_none());
+        assert_eq!(*sink.delivered.lock().unwrap(), vec![BASE]);
+        assert!(cursor_path.exists());
+
+        // Past the deadline, sinks are skipped and keep their cursor
+        store.record(handle, 1.0, BASE + SEC).unwrap();
+        let reports = driver.flush(&mut store, std::time::Instant::now()).unwrap();
+        assert!(!reports[0].delivered);
+        assert!(matches!(
+            reports[0].error,
+            Some(crate::RondoError::Export(
+                ExportError::DeadlineExceeded { .. }
+            ))
+        ));
+        assert_eq!(*sink.delivered.lock().unwrap(), vec![BASE]);
+    }
+
+    #[test]
+    fn test_delivers_prepared_run_without_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+        store.record(handle, 1.0, BASE).unwrap();
+
+        let sink = MockSink {
+            name: "mock",
+            ..MockSink::default()
+        };
+        let mut driver = ExportDriver::new(0).with_sink(
+            sink.clone(),
+            ExportCursor::load_or_new(dir.path().join("cursor_mock.json")).unwrap(),
+        );
+
+        let run = driver.prepare(&store);
+        assert_eq!(run.series(), 1);
+        // Data recorded after prepare goes in the next run
+        store.record(handle, 1.0, BASE + SEC).unwrap();
+        drop(store);
+
+        let reports = driver.deliver(run);
+        assert!(reports[0].delivered && reports[0].error.is_none());
+        assert_eq!(*sink.acked.lock().unwrap(), 1);
+        assert_eq!(*sink.delivered.lock().unwrap(), vec![BASE]);
+    }
+
+    #[test]
+    fn test_drops_permanently_rejected_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+
+        let sink = MockSink {
+            name: "mock",
+            ..MockSink::default()
+        };
+        *sink.down.lock().unwrap() = true;
+        let spool = Spool::open(dir.path().join("spool"), 1024).unwrap();
+        let mut driver = ExportDriver::new(0)
+            .with_sink(sink.clone(), ExportCursor::new())
+            .with_spool("mock", spool);
+        for i in 0..2 {
+            store.record(handle, 1.0, BASE + i * SEC).unwrap();
+            assert!(driver.run_once(&store)[0].spooled);
+        }
+
+        // The first spooled batch is rejected for good: it is dropped and
+        // does not hold back the one behind it
+        *sink.down.lock().unwrap() = false;
+        *sink.reject.lock().unwrap() = Some(BASE);
+        store.record(handle, 1.0, BASE + 2 * SEC).unwrap();
+        let report = driver.run_once(&store).remove(0);
+        assert_eq!((report.dropped, report.replayed), (1, 1));
+        assert!(report.delivered);
+        assert_eq!(
+            *sink.delivered.lock().unwrap(),
+            vec![BASE + SEC, BASE + 2 * SEC]
+        );
+
+        // A new batch that is rejected for good is not spooled
+        *sink.reject.lock().unwrap() = Some(BASE + 3 * SEC);
+        store.record(handle, 1.0, BASE + 3 * SEC).unwrap();
+        let report = driver.run_once(&store).remove(0);
+        assert!(!report.delivered && !report.spooled && report.dropped == 1);
+        assert!(!report.error.unwrap().is_retryable());
+        assert!(driver.spool("mock").unwrap().is_empty());
+        assert_eq!(driver.run_once(&store)[0].series, 0);
+    }
+
+    #[test]
+    fn test_saves_cursor_only_after_ack() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+        store.record(handle, 1.0, BASE).unwrap();
+
+        let cursor_path = dir.path().join("cursor_mock.json");
+        let sink = MockSink {
+            name: "mock",
+            ..MockSink::default()
+        };
+        *sink.down.lock().unwrap() = true;
+        let mut driver = ExportDriver::new(0).with_sink(
+            sink.clone(),
+            ExportCursor::load_or_new(&cursor_path).unwrap(),
+        );
+
+        driver.run_once(&store);
+        assert!(!cursor_path.exists());
+
+        *sink.down.lock().unwrap() = false;
+        driver.run_once(&store);
+        assert!(cursor_path.exists());
+
+        // A fresh driver resumes from the saved cursor
+        let mut resumed = ExportCursor::load_or_new(&cursor_path).unwrap();
+        assert!(store.drain(0, &mut resumed).unwrap().is_empty());
+        assert!(driver.cursor("mock").is_some());
+        assert!(driver.cursor("other").is_none());
+    }
+}
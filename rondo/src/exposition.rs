@@ -0,0 +1,515 @@
+//! Prometheus text exposition of the latest value of every series.
+//!
+//! For environments that scrape rather than receive remote-write, this
+//! module renders a store as a `/metrics` page: one sample per registered
+//! series carrying its most recent value and timestamp, grouped into metric
+//! families with `# HELP` and `# TYPE` lines.
+//!
+//! Two formats are supported:
+//!
+//! - [`ExpositionFormat::Prometheus`] — the classic text format (0.0.4),
+//!   with millisecond timestamps
+//! - [`ExpositionFormat::OpenMetrics`] — OpenMetrics 1.0, with second
+//!   timestamps, `_total` counter samples and a trailing `# EOF`
+//!
+//! rondo does not record metric types or help text, so by default names
+//! ending in `_total` are exposed as counters and everything else as gauges,
+//! with a generic help string. Both can be overridden per metric name.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::exposition::{ExpositionFormat, MetricType, TextEncoder};
+//! use rondo::store::Store;
+//!
+//! # let store = Store::open("./data", vec![])?;
+//! let encoder = TextEncoder::new(ExpositionFormat::Prometheus)
+//!     .with_help("vmm_rss_bytes", "Resident set size of the VMM process")
+//!     .with_type("vcpu_exit_duration_ns", MetricType::Gauge);
+//!
+//! let body = encoder.encode(&store)?;
+//! println!("Content-Type: {}\n\n{body}", encoder.format().content_type());
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::error::{ExpositionError, Result};
+use crate::store::Store;
+
+/// Series of one metric family: sorted labels -> latest `(timestamp, value)`.
+type Family = BTreeMap<Vec<(String, String)>, (u64, f64)>;
+
+/// Text format to render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpositionFormat {
+    /// Prometheus text format, version 0.0.4.
+    #[default]
+    Prometheus,
+    /// OpenMetrics text format, version 1.0.0.
+    OpenMetrics,
+}
+
+impl ExpositionFormat {
+    /// Returns the HTTP `Content-Type` for this format.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Prometheus => "text/plain; version=0.0.4; charset=utf-8",
+            Self::OpenMetrics => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        }
+    }
+
+    /// Picks a format from an HTTP `Accept` header, preferring OpenMetrics
+    /// when the client asks for it.
+    pub fn from_accept(accept: &str) -> Self {
+        if accept.contains("application/openmetrics-text") {
+            Self::OpenMetrics
+        } else {
+            Self::Prometheus
+        }
+    }
+}
+
+/// Metric type announced in the `# TYPE` line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+    /// Monotonically increasing value.
+    Counter,
+    /// Value that can go up and down.
+    Gauge,
+    /// Unknown type.
+    Untyped,
+}
+
+impl MetricType {
+    /// Infers a type from a metric name: `_total` suffix means counter.
+    pub fn infer(name: &str) -> Self {
+        if name.ends_with("_total") {
+            Self::Counter
+        } else {
+            Self::Gauge
+        }
+    }
+
+    fn as_str(self, format: ExpositionFormat) -> &'static str {
+        match (self, format) {
+            (Self::Counter, _) => "counter",
+            (Self::Gauge, _) => "gauge",
+            (Self::Untyped, ExpositionFormat::Prometheus) => "untyped",
+            (Self::Untyped, ExpositionFormat::OpenMetrics) => "unknown",
+        }
+    }
+}
+
+/// Renders the latest sample of every series in a store.
+#[derive(Debug, Clone, Default)]
+pub struct TextEncoder {
+    format: ExpositionFormat,
+    help: HashMap<String, String>,
+    types: HashMap<String, MetricType>,
+    timestamps: bool,
+}
+
+impl TextEncoder {
+    /// Creates an encoder for the given format, with timestamps enabled.
+    pub fn new(format: ExpositionFormat) -> Self {
+        Self {
+            format,
+            timestamps: true,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the `# HELP` text for a metric name.
+    #[must_use]
+    pub fn with_help(mut self, name: impl Into<String>, help: impl Into<String>) -> Self {
+        self.help.insert(name.into(), help.into());
+        self
+    }
+
+    /// Overrides the inferred `# TYPE` of a metric name.
+    #[must_use]
+    pub fn with_type(mut self, name: impl Into<String>, metric_type: MetricType) -> Self {
+        self.types.insert(name.into(), metric_type);
+        self
+    }
+
+    /// Sets whether samples carry their recorded timestamp.
+    ///
+    /// Without timestamps the scraper stamps samples with the scrape time.
+    #[must_use]
+    pub fn with_timestamps(mut self, timestamps: bool) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Returns the output format.
+    pub fn format(&self) -> ExpositionFormat {
+        self.format
+    }
+
+    /// Renders the store.
+    ///
+    /// Series without any recorded sample are omitted. Names and label keys
+    /// that are not valid Prometheus identifiers (e.g. `cpu.usage`) have
+    /// the offending characters replaced with `_`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading the latest sample of a series fails, or
+    /// [`ExpositionError::DuplicateSeries`] if two series render the same
+    /// once sanitized (e.g. `cpu.usage` and `cpu_usage`, or a series with
+    /// both `a.b` and `a_b` labels), or [`ExpositionError::DuplicateFamily`]
+    /// if two metrics share a family or sample name (in OpenMetrics, a gauge
+    /// `foo` and a counter `foo_total`).
+    pub fn encode(&self, store: &Store) -> Result<String> {
+        let mut families: BTreeMap<String, Family> = BTreeMap::new();
+
+        for handle in store.handles() {
+            let Some((name, labels)) = store.series_info(&handle) else {
+                continue;
+            };
+            let Some(sample) = store.latest(handle)? else {
+                continue;
+            };
+
+            let mut sanitized: Vec<(String, String)> = labels
+                .iter()
+                .map(|(k, v)| (sanitize_name(k, false), v.clone()))
+                .collect();
+            sanitized.sort();
+            let family = sanitize_name(name, true);
+
+            let duplicate_key = sanitized.windows(2).any(|w| w[0].0 == w[1].0);
+            let entry = families.entry(family.clone()).or_default().entry(sanitized);
+            match entry {
+                Entry::Vacant(entry) if !duplicate_key => {
+                    entry.insert(sample);
+                }
+                entry => {
+                    return Err(ExpositionError::DuplicateSeries {
+                        series: describe(name, labels),
+                        sample: describe(&family, entry.key()),
+                    }
+                    .into());
+                }
+            }
+        }
+
+        let mut rendered: HashMap<String, &str> = HashMap::new();
+        for name in families.keys() {
+            let (_, family, sample_name) = self.rendered_names(name);
+            for rendered_name in [family.to_string(), sample_name] {
+                match rendered.insert(rendered_name.clone(), name) {
+                    Some(first) if first != name => {
+                        return Err(ExpositionError::DuplicateFamily {
+                            first: first.to_string(),
+                            second: name.clone(),
+                            name: rendered_name,
+                        }
+                        .into());
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut out = String::new();
+        for (name, series) in &families {
+            self.encode_family(&mut out, name, series);
+        }
+        if self.format == ExpositionFormat::OpenMetrics {
+            out.push_str("# EOF\n");
+        }
+        Ok(out)
+    }
+
+    /// Returns a metric's type and the family and sample names it renders as.
+    fn rendered_names<'a>(&self, name: &'a str) -> (MetricType, &'a str, String) {
+        let metric_type = self
+            .types
+            .get(name)
+            .copied()
+            .unwrap_or_else(|| MetricType::infer(name));
+
+        // OpenMetrics names counter families without the `_total` suffix
+        // and requires it on the sample
+        if self.format == ExpositionFormat::OpenMetrics && metric_type == MetricType::Counter {
+            let family = name.strip_suffix("_total").unwrap_or(name);
+            (metric_type, family, format!("{family}_total"))
+        } else {
+            (metric_type, name, name.to_string())
+        }
+    }
+
+    fn encode_family(&self, out: &mut String, name: &str, series: &Family) {
+        let (metric_type, family, sample_name) = self.rendered_names(name);
+
+        let help = self
+            .help
+            .get(name)
+            .map_or_else(|| format!("rondo series {name}"), Clone::clone);
+        let _ = writeln!(out, "# HELP {family} {}", escape_help(&help));
+        let _ = writeln!(out, "# TYPE {family} {}", metric_type.as_str(self.format));
+
+        for (labels, &(timestamp_ns, value)) in series {
+            out.push_str(&sample_name);
+            if !labels.is_empty() {
+                out.push('{');
+                for (i, (key, val)) in labels.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    let _ = write!(out, "{key}=\"{}\"", escape_label_value(val));
+                }
+                out.push('}');
+            }
+            let _ = write!(out, " {}", format_value(value));
+
+            if self.timestamps {
+                match self.format {
+                    ExpositionFormat::Prometheus => {
+                        let _ = write!(out, " {}", timestamp_ns / 1_000_000);
+                    }
+                    ExpositionFormat::OpenMetrics => {
+                        let _ = write!(
+                            out,
+                            " {}.{:03}",
+                            timestamp_ns / 1_000_000_000,
+                            timestamp_ns / 1_000_000 % 1000
+                        );
+                    }
+                }
+            }
+            out.push('\n');
+        }
+    }
+}
+
+/// Replaces characters that are invalid in a metric name (or label name if
+/// `metric` is false) with `_`.
+pub(crate) fn sanitize_name(name: &str, metric: bool) -> String {
+    let mut out: String = name
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let valid = c.is_ascii_alphabetic()
+                || c == '_'
+                || (metric && c == ':')
+                || (i > 0 && c.is_ascii_digit());
+            if valid { c } else { '_' }
+        })
+        .collect();
+    if out.is_empty() {
+        out.push('_');
+    }
+    out
+}
+
+/// Formats a series as `name{key="value",...}` for error messages.
+fn describe(name: &str, labels: &[(String, String)]) -> String {
+    if labels.is_empty() {
+        return name.to_string();
+    }
+    let labels: Vec<String> = labels.iter().map(|(k, v)| format!("{k}={v:?}")).collect();
+    format!("{name}{{{}}}", labels.join(","))
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{LabelMatcher, SchemaConfig, TierConfig};
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    const TS: u64 = 1_700_000_000_123_000_000;
+
+    fn test_store() -> (TempDir, Store) {
+        let dir = TempDir::new().unwrap();
+        let schemas = vec![SchemaConfig {
+            name: "all".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![TierConfig {
+                interval: Duration::from_secs(1),
+                retention: Duration::from_secs(60),
+                consolidation_fn: None,
+            }],
+            max_series: 10,
+        }];
+        let mut store = Store::open(dir.path().join("store"), schemas).unwrap();
+
+        let io = store
+            .register(
+                "vcpu_exits_total",
+                &[("reason".to_string(), "io".to_string())],
+            )
+            .unwrap();
+        let hlt = store
+            .register(
+                "vcpu_exits_total",
+                &[("reason".to_string(), "say \"hi\"".to_string())],
+            )
+            .unwrap();
+        let cpu = store.register("cpu.usage", &[]).unwrap();
+        store.register("never_recorded", &[]).unwrap();
+
+        store.record(io, 1.0, TS - 1_000_000_000).unwrap();
+        store.record(io, 7.0, TS).unwrap();
+        store.record(hlt, 3.0, TS).unwrap();
+        store.record(cpu, 0.5, TS).unwrap();
+
+        (dir, store)
+    }
+
+    #[test]
+    fn test_prometheus_format() {
+        let (_dir, store) = test_store();
+        let body = TextEncoder::new(ExpositionFormat::Prometheus)
+            .with_help("vcpu_exits_total", "vCPU exits\nby reason")
+            .encode(&store)
+            .unwrap();
+
+        assert_eq!(
+            body,
+            "# HELP cpu_usage rondo series cpu_usage\n\
+             # TYPE cpu_usage gauge\n\
+             cpu_usage 0.5 1700000000123\n\
+             # HELP vcpu_exits_total vCPU exits\\nby reason\n\
+             # TYPE vcpu_exits_total counter\n\
+             vcpu_exits_total{reason=\"io\"} 7 1700000000123\n\
+             vcpu_exits_total{reason=\"say \\\"hi\\\"\"} 3 1700000000123\n"
+        );
+    }
+
+    #[test]
+    fn test_openmetrics_format() {
+        let (_dir, store) = test_store();
+        let body = TextEncoder::new(ExpositionFormat::OpenMetrics)
+            .with_type("cpu_usage", MetricType::Untyped)
+            .encode(&store)
+            .unwrap();
+
+        assert!(body.contains("# TYPE cpu_usage unknown\ncpu_usage 0.5 1700000000.123\n"));
+        assert!(body.contains("# TYPE vcpu_exits counter\n"));
+        assert!(body.contains("vcpu_exits_total{reason=\"io\"} 7 1700000000.123\n"));
+        assert!(body.ends_with("# EOF\n"));
+    }
+
+    #[test]
+    fn test_without_timestamps() {
+        let (_dir, store) = test_store();
+        let body = TextEncoder::new(ExpositionFormat::Prometheus)
+            .with_timestamps(false)
+            .encode(&store)
+            .unwrap();
+        assert!(body.contains("\ncpu_usage 0.5\n"));
+    }
+
+    #[test]
+    fn test_rejects_series_colliding_after_sanitizing() {
+        let (_dir, mut store) = test_store();
+        let encoder = TextEncoder::new(ExpositionFormat::Prometheus);
+
+        let cpu = store.register("cpu_usage", &[]).unwrap();
+        store.record(cpu, 0.7, TS).unwrap();
+        let err = encoder.encode(&store).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::RondoError::Exposition(ExpositionError::DuplicateSeries { ref sample, .. })
+                if sample == "cpu_usage"
+        ));
+
+        // Label keys that collide within one series are rejected too
+        let (_dir, mut store) = test_store();
+        let labels = [
+            ("a.b".to_string(), "1".to_string()),
+            ("a_b".to_string(), "2".to_string()),
+        ];
+        let handle = store.register("clash", &labels).unwrap();
+        store.record(handle, 1.0, TS).unwrap();
+        assert!(encoder.encode(&store).is_err());
+    }
+
+    #[test]
+    fn test_rejects_families_colliding_in_openmetrics() {
+        let (_dir, mut store) = test_store();
+        let requests = store.register("requests", &[]).unwrap();
+        store.record(requests, 1.0, TS).unwrap();
+        let total = store.register("requests_total", &[]).unwrap();
+        store.record(total, 5.0, TS).unwrap();
+
+        // Fine in the classic format, where the names stay distinct
+        let body = TextEncoder::new(ExpositionFormat::Prometheus)
+            .encode(&store)
+            .unwrap();
+        assert!(body.contains("# TYPE requests gauge\n"));
+        assert!(body.contains("# TYPE requests_total counter\n"));
+
+        let err = TextEncoder::new(ExpositionFormat::OpenMetrics)
+            .encode(&store)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::RondoError::Exposition(ExpositionError::DuplicateFamily { ref name, .. })
+                if name == "requests"
+        ));
+
+        // A counter typed without the suffix collides on its sample name
+        let (_dir, mut store) = test_store();
+        let requests = store.register("requests", &[]).unwrap();
+        store.record(requests, 1.0, TS).unwrap();
+        let total = store.register("requests_total", &[]).unwrap();
+        store.record(total, 5.0, TS).unwrap();
+        let err = TextEncoder::new(ExpositionFormat::OpenMetrics)
+            .with_type("requests", MetricType::Counter)
+            .with_type("requests_total", MetricType::Gauge)
+            .encode(&store)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            crate::RondoError::Exposition(ExpositionError::DuplicateFamily { ref name, .. })
+                if name == "requests_total"
+        ));
+    }
+
+    #[test]
+    fn test_sanitize_and_negotiate() {
+        assert_eq!(sanitize_name("cpu.usage", true), "cpu_usage");
+        assert_eq!(sanitize_name("9lives", true), "_lives");
+        assert_eq!(sanitize_name("job:rate", true), "job:rate");
+        assert_eq!(sanitize_name("job:rate", false), "job_rate");
+        assert_eq!(
+            ExpositionFormat::from_accept("application/openmetrics-text;version=1.0.0,*/*;q=0.1"),
+            ExpositionFormat::OpenMetrics
+        );
+        assert_eq!(
+            ExpositionFormat::from_accept("*/*"),
+            ExpositionFormat::Prometheus
+        );
+    }
+}
@@ -0,0 +1,786 @@
+//! Graphite/carbon exporter for rondo data.
+//!
+//! Renders drain output as Graphite metric paths and pushes them to a carbon
+//! receiver over TCP, either as the plaintext protocol (`<path> <value>
+//! <timestamp>` lines, usually port 2003) or the pickle protocol
+//! (length-prefixed pickled lists, usually port 2004).
+//!
+//! Graphite has no labels, so each series is turned into a dotted path by a
+//! [`PathTemplate`] such as `vmm.{instance}.{name}`, where `{name}` is the
+//! series name and any other placeholder is a label value. Labels the
+//! template does not use are appended as Graphite 1.1 tags
+//! (`path;key=value`) unless disabled with
+//! [`GraphiteConfig::with_tags`]. External labels take part in both.
+//!
+//! This module is only available when the `graphite` feature is enabled.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::store::Store;
+//! use rondo::export::ExportCursor;
+//! use rondo::graphite::{push, GraphiteConfig, PathTemplate};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let store = Store::open("/tmp/graphite_example", vec![])?;
+//! let config = GraphiteConfig::new("carbon.example.com:2003")
+//!     .with_template(PathTemplate::parse("vmm.{instance}.{name}")?);
+//! let mut cursor = ExportCursor::load_or_new("/tmp/cursor_graphite.json")?;
+//!
+//! let pending = store.drain_pending(0, &cursor)?;
+//! let external_labels = vec![("instance".to_string(), "vmm_1".to_string())];
+//! push(&config, &pending.exports, &store, &external_labels)?;
+//! cursor.commit(&pending);
+//! cursor.save()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use crate::error::{ExportError, GraphiteError, Result};
+use crate::export::{RollupNaming, SeriesExport, rollup_identity};
+use crate::exporter::{Exporter, attempt_timeout};
+use crate::relabel::{RelabelConfig, relabel_series};
+use crate::store::Store;
+
+/// Value rendered for template labels a series does not have.
+const MISSING_LABEL: &str = "unknown";
+
+/// Carbon wire protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphiteProtocol {
+    /// Newline-separated `<path> <value> <timestamp>` lines.
+    #[default]
+    Plaintext,
+    /// Length-prefixed pickled lists of `(path, (timestamp, value))`.
+    Pickle,
+}
+
+/// One piece of a [`PathTemplate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Name,
+    Label(String),
+}
+
+/// Template that maps a series name and labels to a Graphite path.
+///
+/// Placeholders are written in braces: `{name}` is the series name, any
+/// other placeholder is the value of the label with that name. Dots in
+/// label values are replaced with `_` so they do not add path levels;
+/// dots in the series name are kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    segments: Vec<Segment>,
+}
+
+impl Default for PathTemplate {
+    fn default() -> Self {
+        Self {
+            segments: vec![Segment::Name],
+        }
+    }
+}
+
+impl PathTemplate {
+    /// Parses a template such as `rondo.{host}.{name}`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GraphiteError::InvalidTemplate`] for unbalanced braces or
+    /// empty placeholders.
+    pub fn parse(template: &str) -> Result<Self> {
+        let invalid = |reason: &str| GraphiteError::InvalidTemplate {
+            template: template.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while !rest.is_empty() {
+            match rest.find(['{', '}']) {
+                Some(i) if rest[i..].starts_with('}') => {
+                    return Err(invalid("unmatched '}'").into());
+                }
+                Some(i) => {
+                    if i > 0 {
+                        segments.push(Segment::Literal(rest[..i].to_string()));
+                    }
+                    let end = rest[i..].find('}').ok_or_else(|| invalid("unclosed '{'"))?;
+                    let placeholder = rest[i + 1..i + end].trim();
+                    if placeholder.is_empty() || placeholder.contains('{') {
+                        return Err(invalid("empty or nested placeholder").into());
+                    }
+                    segments.push(if placeholder == "name" {
+                        Segment::Name
+                    } else {
+                        Segment::Label(placeholder.to_string())
+                    });
+                    rest = &rest[i + end + 1..];
+                }
+                None => {
+                    segments.push(Segment::Literal(rest.to_string()));
+                    rest = "";
+                }
+            }
+        }
+        if segments.is_empty() {
+            return Err(invalid("template is empty").into());
+        }
+
+        Ok(Self { segments })
+    }
+
+    /// Renders the path for a series, returning it and the labels the
+    /// template did not use.
+    fn render<'a>(
+        &self,
+        name: &str,
+        labels: &[(&'a str, &'a str)],
+    ) -> (String, Vec<(&'a str, &'a str)>) {
+        let mut path = String::new();
+        let mut used = Vec::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => path.push_str(text),
+                Segment::Name => path.push_str(&sanitize(name, true)),
+                Segment::Label(label) => {
+                    let value = labels
+                        .iter()
+                        .find(|(k, _)| k == label)
+                        .map_or(MISSING_LABEL, |(_, v)| v);
+                    path.push_str(&sanitize(value, false));
+                    used.push(label.as_str());
+                }
+            }
+        }
+        let unused = labels
+            .iter()
+            .filter(|(k, _)| !used.contains(k))
+            .copied()
+            .collect();
+        (path, unused)
+    }
+}
+
+/// Configuration for a Graphite/carbon endpoint.
+#[derive(Debug, Clone)]
+pub struct GraphiteConfig {
+    /// Carbon receiver address (e.g., `localhost:2003`).
+    pub address: String,
+    /// Wire protocol.
+    pub protocol: GraphiteProtocol,
+    /// Template mapping series to paths.
+    pub template: PathTemplate,
+    /// Whether labels not used by the template are sent as tags.
+    pub tags: bool,
+    /// Maximum number of datapoints per pickle message (0 is treated as 1).
+    pub batch_size: usize,
+    /// Connect and write timeout.
+    pub timeout: Duration,
+    /// Maximum number of retry attempts on failure.
+    pub max_retries: u32,
+    /// Initial backoff duration between retries (doubles each attempt).
+    pub retry_backoff: Duration,
+    /// Relabeling rules applied to each series after external labels are
+    /// added, before the template; series they drop are not sent.
+    pub write_relabel_configs: Vec<RelabelConfig>,
+    /// How series drained from rollup tiers are marked. The
+    /// `__rollup__` label can be used in the template.
+    pub rollup_naming: RollupNaming,
+}
+
+impl GraphiteConfig {
+    /// Creates a new config with sensible defaults.
+    ///
+    /// Defaults: plaintext protocol, `{name}` template with tags, 500
+    /// datapoints per pickle message, 10s timeout, 3 retries, 100ms initial
+    /// backoff.
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            protocol: GraphiteProtocol::Plaintext,
+            template: PathTemplate::default(),
+            tags: true,
+            batch_size: 500,
+            timeout: Duration::from_secs(10),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(100),
+            write_relabel_configs: Vec::new(),
+            rollup_naming: RollupNaming::Label,
+        }
+    }
+
+    /// Sets the wire protocol.
+    #[must_use]
+    pub fn with_protocol(mut self, protocol: GraphiteProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the path template.
+    #[must_use]
+    pub fn with_template(mut self, template: PathTemplate) -> Self {
+        self.template = template;
+        self
+    }
+
+    /// Sets whether labels not used by the template are sent as tags.
+    #[must_use]
+    pub fn with_tags(mut self, tags: bool) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Sets the maximum number of datapoints per pickle message.
+    #[must_use]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Sets the connect and write timeout.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum number of retries.
+    #[must_use]
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Adds a relabeling rule, applied after the ones already added.
+    #[must_use]
+    pub fn with_write_relabel_config(mut self, rule: RelabelConfig) -> Self {
+        self.write_relabel_configs.push(rule);
+        self
+    }
+
+    /// Sets how series drained from rollup tiers are marked.
+    #[must_use]
+    pub fn with_rollup_naming(mut self, rollup_naming: RollupNaming) -> Self {
+        self.rollup_naming = rollup_naming;
+        self
+    }
+}
+
+/// Graphite sink for an [`ExportDriver`](crate::exporter::ExportDriver).
+#[derive(Debug, Clone)]
+pub struct GraphiteExporter {
+    config: GraphiteConfig,
+    external_labels: Vec<(String, String)>,
+}
+
+impl GraphiteExporter {
+    /// Creates an exporter for the given carbon configuration.
+    pub fn new(config: GraphiteConfig) -> Self {
+        Self {
+            config,
+            external_labels: Vec::new(),
+        }
+    }
+
+    /// Sets labels merged into every series before rendering its path.
+    #[must_use]
+    pub fn with_external_labels(mut self, external_labels: Vec<(String, String)>) -> Self {
+        self.external_labels = external_labels;
+        self
+    }
+}
+
+impl Exporter for GraphiteExporter {
+    fn name(&self) -> &str {
+        "graphite"
+    }
+
+    fn encode(&mut self, exports: &[SeriesExport], store: &Store) -> Result<Vec<u8>> {
+        encode(&self.config, exports, store, &self.external_labels)
+    }
+
+    fn send(&mut self, payload: &[u8]) -> Result<()> {
+        if payload.is_empty() {
+            return Ok(());
+        }
+        send_with_retry(&self.config, payload, None)
+    }
+
+    fn send_before(&mut self, payload: &[u8], deadline: Instant) -> Result<()> {
+        if payload.is_empty() {
+            return Ok(());
+        }
+        send_with_retry(&self.config, payload, Some(deadline))
+    }
+}
+
+/// Pushes series exports to a carbon receiver.
+///
+/// Encodes the exports with [`encode`] and writes them over a fresh TCP
+/// connection, retrying with exponential backoff on failure.
+///
+/// `external_labels` are merged into every series' labels before the path
+/// is rendered.
+///
+/// # Errors
+///
+/// Returns [`GraphiteError`] if a series cannot be found in the store, or
+/// if connecting or writing fails after all retries.
+pub fn push(
+    config: &GraphiteConfig,
+    exports: &[SeriesExport],
+    store: &Store,
+    external_labels: &[(String, String)],
+) -> Result<usize> {
+    if exports.is_empty() {
+        return Ok(0);
+    }
+
+    let payload = encode(config, exports, store, external_labels)?;
+    if !payload.is_empty() {
+        send_with_retry(config, &payload, None)?;
+    }
+
+    Ok(exports.len())
+}
+
+/// Encodes series exports in the configured carbon protocol.
+///
+/// NaN and infinite values are skipped. This is useful for testing or
+/// custom transport implementations.
+///
+/// # Errors
+///
+/// Returns [`GraphiteError::SeriesNotFound`] if a series handle cannot be
+/// found in the store.
+pub fn encode(
+    config: &GraphiteConfig,
+    exports: &[SeriesExport],
+    store: &Store,
+    external_labels: &[(String, String)],
+) -> Result<Vec<u8>> {
+    let datapoints = build_datapoints(config, exports, store, external_labels)?;
+
+    Ok(match config.protocol {
+        GraphiteProtocol::Plaintext => {
+            let mut out = String::new();
+            for (path, timestamp, value) in &datapoints {
+                out.push_str(&format!("{path} {value} {timestamp}\n"));
+            }
+            out.into_bytes()
+        }
+        GraphiteProtocol::Pickle => {
+            let mut out = Vec::new();
+            for batch in datapoints.chunks(config.batch_size.max(1)) {
+                let message = pickle_batch(batch);
+                #[allow(clippy::cast_possible_truncation)]
+                // batch_size keeps messages far below 4 GiB
+                out.extend_from_slice(&(message.len() as u32).to_be_bytes());
+                out.extend_from_slice(&message);
+            }
+            out
+        }
+    })
+}
+
+/// Renders every finite point as `(path, timestamp_secs, value)`.
+fn build_datapoints(
+    config: &GraphiteConfig,
+    exports: &[SeriesExport],
+    store: &Store,
+    external_labels: &[(String, String)],
+) -> Result<Vec<(String, i64, f64)>> {
+    let mut datapoints = Vec::new();
+
+    for export in exports {
+        let (name, series_labels) =
+            store
+                .series_info(&export.handle)
+                .ok_or(GraphiteError::SeriesNotFound {
+                    schema_index: export.handle.schema_index,
+                    column: export.handle.column,
+                })?;
+
+        let rollup = store.rollup(export.handle.schema_index, export.tier);
+        let (name, series_labels) =
+            rollup_identity(name, series_labels, rollup, config.rollup_naming);
+
+        // Series labels win over external labels of the same name
+        let mut name = name.into_owned();
+        let mut labels = series_labels.into_owned();
+        for (key, value) in external_labels {
+            if !labels.iter().any(|(k, _)| k == key) {
+                labels.push((key.clone(), value.clone()));
+            }
+        }
+        if !config.write_relabel_configs.is_empty() {
+            let Some(relabeled) = relabel_series(&name, &labels, &config.write_relabel_configs)
+            else {
+                continue;
+            };
+            (name, labels) = relabeled;
+        }
+        labels.sort_unstable();
+        let labels: Vec<(&str, &str)> = labels
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+
+        let (mut path, unused) = config.template.render(&name, &labels);
+        if config.tags {
+            for (key, value) in unused {
+                path.push(';');
+                path.push_str(&sanitize_tag(key));
+                path.push('=');
+                path.push_str(&sanitize_tag(value));
+            }
+        }
+
+        for &(timestamp_ns, value) in &export.points {
+            if value.is_finite() {
+                #[allow(clippy::cast_possible_wrap)] // Epoch seconds fit in i64
+                let timestamp = (timestamp_ns / 1_000_000_000) as i64;
+                datapoints.push((path.clone(), timestamp, value));
+            }
+        }
+    }
+
+    Ok(datapoints)
+}
+
+/// Pickles a list of `(path, (timestamp, value))` tuples (protocol 2).
+fn pickle_batch(datapoints: &[(String, i64, f64)]) -> Vec<u8> {
+    // Opcodes from CPython's pickle module
+    const PROTO: u8 = 0x80;
+    const EMPTY_LIST: u8 = b']';
+    const MARK: u8 = b'(';
+    const BINUNICODE: u8 = b'X';
+    const BININT: u8 = b'J';
+    const BINFLOAT: u8 = b'G';
+    const TUPLE2: u8 = 0x86;
+    const APPENDS: u8 = b'e';
+    const STOP: u8 = b'.';
+
+    let mut out = vec![PROTO, 2, EMPTY_LIST, MARK];
+    for (path, timestamp, value) in datapoints {
+        out.push(BINUNICODE);
+        #[allow(clippy::cast_possible_truncation)] // Paths are far below 4 GiB
+        out.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        out.extend_from_slice(path.as_bytes());
+
+        match i32::try_from(*timestamp) {
+            Ok(ts) => {
+                out.push(BININT);
+                out.extend_from_slice(&ts.to_le_bytes());
+            }
+            Err(_) => {
+                #[allow(clippy::cast_precision_loss)] // Seconds stay exact far beyond 2038
+                let ts = *timestamp as f64;
+                out.push(BINFLOAT);
+                out.extend_from_slice(&ts.to_be_bytes());
+            }
+        }
+
+        out.push(BINFLOAT);
+        out.extend_from_slice(&value.to_be_bytes());
+        out.push(TUPLE2);
+        out.push(TUPLE2);
+    }
+    out.extend_from_slice(&[APPENDS, STOP]);
+    out
+}
+
+/// Replaces characters that are not safe in a path component with `_`.
+///
+/// Dots are kept for series names, which may already be hierarchical.
+fn sanitize(s: &str, keep_dots: bool) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ':') || (keep_dots && c == '.')
+            {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Replaces characters that Graphite does not allow in tags with `_`.
+fn sanitize_tag(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if matches!(c, ';' | '=' | '!' | '^' | '~') || c.is_whitespace() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Writes the payload over a new connection, retrying with backoff.
+///
+/// With a `deadline`, each attempt's timeout is capped to the time left and
+/// no retry is started once it has passed.
+fn send_with_retry(
+    config: &GraphiteConfig,
+    payload: &[u8],
+    deadline: Option<Instant>,
+) -> Result<()> {
+    let mut last_error = None;
+    let mut backoff = config.retry_backoff;
+
+    for attempt in 0..=config.max_retries {
+        let Some(timeout) = attempt_timeout(config.timeout, deadline) else {
+            break;
+        };
+        match send(config, payload, timeout) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+
+        if attempt < config.max_retries {
+            if deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline) {
+                break;
+            }
+            std::thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+
+    match last_error {
+        Some(e) => Err(e.into()),
+        None => Err(ExportError::DeadlineExceeded {
+            sink: "graphite".to_string(),
+        }
+        .into()),
+    }
+}
+
+/// Connects to the carbon receiver and writes the payload.
+fn send(
+    config: &GraphiteConfig,
+    payload: &[u8],
+    timeout: Duration,
+) -> std::result::Result<(), GraphiteError> {
+    let connect_error = |source| GraphiteError::Connect {
+        address: config.address.clone(),
+        source,
+    };
+
+    let addrs = config.address.to_socket_addrs().map_err(connect_error)?;
+    let mut last_error = std::io::Error::new(std::io::ErrorKind::NotFound, "no addresses resolved");
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(mut stream) => {
+                let write_error = |source| GraphiteError::Write { source };
+                stream
+                    .set_write_timeout(Some(timeout))
+                    .map_err(write_error)?;
+                stream.write_all(payload).map_err(write_error)?;
+                stream.flush().map_err(write_error)?;
+                return Ok(());
+            }
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(connect_error(last_error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{LabelMatcher, SchemaConfig, TierConfig};
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    const BASE: u64 = 1_700_000_000_000_000_000;
+
+    fn create_test_store(dir: &std::path::Path) -> Store {
+        let schemas = vec![SchemaConfig {
+            name: "test".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig::new(Duration::from_secs(1), Duration::from_secs(60), None).unwrap(),
+            ],
+            max_series: 10,
+        }];
+        Store::open(dir.join("store"), schemas).unwrap()
+    }
+
+    fn label(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_path_template() {
+        let template = PathTemplate::parse("vmm.{instance}.{name}.by-{reason}").unwrap();
+        let labels = [("instance", "vm.1"), ("reason", "io"), ("zone", "a b")];
+        let (path, unused) = template.render("vcpu.exits", &labels);
+        assert_eq!(path, "vmm.vm_1.vcpu.exits.by-io");
+        assert_eq!(unused, vec![("zone", "a b")]);
+
+        let (path, _) = template.render("up", &[]);
+        assert_eq!(path, "vmm.unknown.up.by-unknown");
+
+        for bad in ["", "a.{", "a.}", "a.{}", "{a{b}}"] {
+            assert!(PathTemplate::parse(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_encode_plaintext() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store
+            .register("cpu", &[label("host", "web1"), label("core", "0")])
+            .unwrap();
+
+        let exports = vec![SeriesExport {
+            handle,
+            points: vec![
+                (BASE, 1.5),
+                (BASE + 1_000_000_000, f64::NAN),
+                (BASE + 2_000_000_000, 2.0),
+            ],
+            tier: 0,
+        }];
+        let config = GraphiteConfig::new("localhost:2003")
+            .with_template(PathTemplate::parse("servers.{host}.{name}").unwrap());
+        let external = vec![label("instance", "vmm_1"), label("host", "ignored")];
+
+        let body = encode(&config, &exports, &store, &external).unwrap();
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "servers.web1.cpu;core=0;instance=vmm_1 1.5 1700000000\n\
+             servers.web1.cpu;core=0;instance=vmm_1 2 1700000002\n"
+        );
+
+        let config = config.with_tags(false);
+        let body = encode(&config, &exports, &store, &[]).unwrap();
+        assert!(
+            String::from_utf8(body)
+                .unwrap()
+                .starts_with("servers.web1.cpu 1.5 ")
+        );
+    }
+
+    #[test]
+    fn test_write_relabel_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let debug = store.register("debug_ticks", &[]).unwrap();
+        let cpu = store.register("cpu", &[label("pid", "42")]).unwrap();
+        let exports: Vec<_> = [debug, cpu]
+            .into_iter()
+            .map(|handle| SeriesExport {
+                handle,
+                points: vec![(BASE, 1.0)],
+                tier: 0,
+            })
+            .collect();
+
+        let config = GraphiteConfig::new("localhost:2003")
+            .with_template(PathTemplate::parse("vmm.{vm}.{name}").unwrap())
+            .with_write_relabel_config(RelabelConfig::drop(["__name__"], "debug_.*").unwrap())
+            .with_write_relabel_config(RelabelConfig::labelmap("instance", "vm").unwrap())
+            .with_write_relabel_config(RelabelConfig::labeldrop("instance|pid").unwrap());
+        let external = vec![label("instance", "vmm_1")];
+
+        let body = encode(&config, &exports, &store, &external).unwrap();
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "vmm.vmm_1.cpu 1 1700000000\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_pickle() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+
+        let exports = vec![SeriesExport {
+            handle,
+            points: vec![(BASE, 1.5), (BASE + 1_000_000_000, 1.0)],
+            tier: 0,
+        }];
+        let config = GraphiteConfig::new("localhost:2004")
+            .with_protocol(GraphiteProtocol::Pickle)
+            .with_batch_size(1);
+        let body = encode(&config, &exports, &store, &[]).unwrap();
+
+        let mut expected = Vec::new();
+        for (ts, value) in [(1_700_000_000i32, 1.5f64), (1_700_000_001, 1.0)] {
+            let mut message = vec![0x80, 2, b']', b'(', b'X', 2, 0, 0, 0, b'u', b'p', b'J'];
+            message.extend_from_slice(&ts.to_le_bytes());
+            message.push(b'G');
+            message.extend_from_slice(&value.to_be_bytes());
+            message.extend_from_slice(&[0x86, 0x86, b'e', b'.']);
+            expected.extend_from_slice(&u32::try_from(message.len()).unwrap().to_be_bytes());
+            expected.extend_from_slice(&message);
+        }
+        assert_eq!(body, expected);
+
+        // The field is public, so a zero set directly must not panic
+        let mut config = config;
+        config.batch_size = 0;
+        assert_eq!(encode(&config, &exports, &store, &[]).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_push_to_tcp_stub() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("load", &[label("host", "a")]).unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = String::new();
+            stream.read_to_string(&mut received).unwrap();
+            received
+        });
+
+        let exports = vec![SeriesExport {
+            handle,
+            points: vec![(BASE, 0.25)],
+            tier: 0,
+        }];
+        let config = GraphiteConfig::new(addr.to_string());
+        assert_eq!(push(&config, &exports, &store, &[]).unwrap(), 1);
+        assert_eq!(server.join().unwrap(), "load;host=a 0.25 1700000000\n");
+    }
+
+    #[test]
+    fn test_push_connection_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("load", &[]).unwrap();
+
+        // Bind then drop to get a port nothing listens on
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let exports = vec![SeriesExport {
+            handle,
+            points: vec![(BASE, 1.0)],
+            tier: 0,
+        }];
+        let config = GraphiteConfig::new(addr.to_string()).with_max_retries(0);
+        assert!(matches!(
+            push(&config, &exports, &store, &[]),
+            Err(crate::RondoError::Graphite(GraphiteError::Connect { .. }))
+        ));
+    }
+}
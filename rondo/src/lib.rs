@@ -73,23 +73,49 @@
 //! - [`ring`] — Ring buffer implementation over memory-mapped slabs
 //! - [`slab`] — Raw memory-mapped file format
 //! - [`query`] — Query result types and tier selection
+//! - [`promql`] — PromQL parser and evaluator
+//! - [`exporter`] — Pluggable export sinks and the cursor-owning export driver
+//! - [`spool`] — Bounded on-disk spool for batches a sink could not deliver
+//! - [`exposition`] — Prometheus text format rendering of latest values
+//! - [`relabel`] — Prometheus-style relabeling rules applied by exporters
+//! - [`line_protocol`] — InfluxDB line protocol parsing and rendering
 //! - [`error`] — Error types
 
 pub mod consolidate;
 pub mod error;
 pub mod export;
+pub mod exporter;
+pub mod exposition;
+#[cfg(feature = "graphite")]
+pub mod graphite;
+pub mod line_protocol;
+#[cfg(feature = "otlp")]
+pub mod otlp;
+#[cfg(feature = "otlp")]
+pub mod otlp_receiver;
+#[cfg(feature = "prometheus-api")]
+pub mod prometheus_api;
+pub mod promql;
 pub mod query;
+pub mod relabel;
+#[cfg(feature = "prometheus-remote-write")]
+pub mod remote_read;
 #[cfg(feature = "prometheus-remote-write")]
 pub mod remote_write;
+#[cfg(feature = "prometheus-remote-write")]
+pub mod remote_write_receiver;
 pub mod ring;
 pub mod schema;
 pub mod series;
 pub mod slab;
+pub mod spool;
+#[cfg(feature = "statsd")]
+pub mod statsd;
 pub mod store;
 
 // Re-export primary API types at crate root for convenience.
 pub use error::{Result, RondoError};
-pub use query::QueryResult;
+pub use query::{QueryOptions, QueryResult, QuerySegment};
 pub use schema::{ConsolidationFn, LabelMatcher, SchemaConfig, TierConfig};
-pub use series::SeriesHandle;
+pub use series::{MetricKind, MetricMetadata, SeriesHandle};
 pub use store::{Store, TierInfo};
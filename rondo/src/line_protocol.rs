@@ -0,0 +1,653 @@
+//! InfluxDB line protocol parsing and rendering.
+//!
+//! Line protocol is the text format spoken by InfluxDB, Telegraf and a lot
+//! of host tooling:
+//!
+//! ```text
+//! cpu,host=web1,core=0 usage_user=12.5,usage_system=3i 1700000000000000000
+//! ```
+//!
+//! Each line is a *measurement* with optional *tags*, one or more *fields*
+//! and an optional timestamp. rondo series hold a single value, so every
+//! field becomes its own series named `<measurement>_<field>` with the tags
+//! as labels (`cpu_usage_user{core="0",host="web1"}` above). A field named
+//! [`VALUE_FIELD`] maps to a series named after the bare measurement.
+//!
+//! - [`parse`] / [`parse_with_precision`] turn text into [`Point`]s
+//! - [`write()`] records points into a store, auto-registering series
+//! - [`encode`] renders drained [`SeriesExport`] batches back as line
+//!   protocol, one [`VALUE_FIELD`] field per series, so its output imports
+//!   back into the same series
+//!
+//! Integer, unsigned and boolean fields are stored as `f64` (booleans as
+//! `1`/`0`). String fields cannot be stored and are rejected by [`write()`].
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::line_protocol;
+//! use rondo::store::Store;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let mut store = Store::open("./data", vec![])?;
+//! let points = line_protocol::parse("mem,host=web1 used=512i,free=256i 1700000000000000000")?;
+//! let summary = line_protocol::write(&mut store, &points, 1_700_000_000_000_000_000);
+//! assert_eq!(summary.accepted, 2);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::error::{LineProtocolError, Result};
+use crate::export::SeriesExport;
+use crate::series::SeriesHandle;
+use crate::store::Store;
+
+/// Field name used by [`encode`] for the value of a rondo series.
+///
+/// [`write()`] maps it back to a series named after the measurement alone.
+pub const VALUE_FIELD: &str = "value";
+
+/// Unit of the timestamps in line protocol input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// Nanoseconds (the line protocol default).
+    #[default]
+    Nanoseconds,
+    /// Microseconds.
+    Microseconds,
+    /// Milliseconds.
+    Milliseconds,
+    /// Seconds.
+    Seconds,
+}
+
+impl Precision {
+    /// Parses an InfluxDB precision name (`ns`, `us`, `ms` or `s`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ns" | "n" => Some(Self::Nanoseconds),
+            "us" | "u" | "µs" => Some(Self::Microseconds),
+            "ms" => Some(Self::Milliseconds),
+            "s" => Some(Self::Seconds),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of nanoseconds per unit.
+    pub fn nanos(self) -> u64 {
+        match self {
+            Self::Nanoseconds => 1,
+            Self::Microseconds => 1_000,
+            Self::Milliseconds => 1_000_000,
+            Self::Seconds => 1_000_000_000,
+        }
+    }
+}
+
+/// A typed field value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// A float (`1.5`, or an unsuffixed number).
+    Float(f64),
+    /// A signed integer (`3i`).
+    Integer(i64),
+    /// An unsigned integer (`3u`).
+    UInteger(u64),
+    /// A boolean (`t`, `true`, `F`, ...).
+    Boolean(bool),
+    /// A quoted string.
+    String(String),
+}
+
+impl FieldValue {
+    /// Returns the value as a number, or `None` for strings.
+    #[allow(clippy::cast_precision_loss)] // rondo stores every value as f64
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Float(v) => Some(*v),
+            Self::Integer(v) => Some(*v as f64),
+            Self::UInteger(v) => Some(*v as f64),
+            Self::Boolean(v) => Some(if *v { 1.0 } else { 0.0 }),
+            Self::String(_) => None,
+        }
+    }
+}
+
+/// One line of line protocol.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    /// The measurement name.
+    pub measurement: String,
+    /// Tags, in input order.
+    pub tags: Vec<(String, String)>,
+    /// Fields, in input order.
+    pub fields: Vec<(String, FieldValue)>,
+    /// Timestamp in nanoseconds, if the line had one.
+    pub timestamp_ns: Option<u64>,
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_escaped(f, &self.measurement, &[',', ' '])?;
+        for (key, value) in &self.tags {
+            f.write_str(",")?;
+            write_escaped(f, key, &[',', '=', ' '])?;
+            f.write_str("=")?;
+            write_escaped(f, value, &[',', '=', ' '])?;
+        }
+        for (i, (key, value)) in self.fields.iter().enumerate() {
+            f.write_str(if i == 0 { " " } else { "," })?;
+            write_escaped(f, key, &[',', '=', ' '])?;
+            match value {
+                FieldValue::Float(v) => write!(f, "={v}")?,
+                FieldValue::Integer(v) => write!(f, "={v}i")?,
+                FieldValue::UInteger(v) => write!(f, "={v}u")?,
+                FieldValue::Boolean(v) => write!(f, "={v}")?,
+                FieldValue::String(v) => {
+                    f.write_str("=\"")?;
+                    write_escaped(f, v, &['"', '\\'])?;
+                    f.write_str("\"")?;
+                }
+            }
+        }
+        if let Some(ts) = self.timestamp_ns {
+            write!(f, " {ts}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of writing points into a store.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngestSummary {
+    /// Field values recorded.
+    pub accepted: u64,
+    /// Field values dropped.
+    pub rejected: u64,
+    /// Reason for the first rejection, if any.
+    pub error: Option<String>,
+}
+
+impl IngestSummary {
+    fn reject(&mut self, reason: String) {
+        self.rejected += 1;
+        self.error.get_or_insert(reason);
+    }
+}
+
+/// Parses line protocol with nanosecond timestamps.
+///
+/// Blank lines and `#` comments are skipped.
+///
+/// # Errors
+///
+/// Returns [`LineProtocolError::Parse`] with the line number of the first
+/// malformed line.
+pub fn parse(input: &str) -> Result<Vec<Point>> {
+    parse_with_precision(input, Precision::Nanoseconds)
+}
+
+/// Parses line protocol whose timestamps are in the given unit.
+///
+/// # Errors
+///
+/// Returns [`LineProtocolError::Parse`] with the line number of the first
+/// malformed line.
+pub fn parse_with_precision(input: &str, precision: Precision) -> Result<Vec<Point>> {
+    let mut points = Vec::new();
+    for (i, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let point = parse_line(line, precision).map_err(|message| LineProtocolError::Parse {
+            line: i + 1,
+            message,
+        })?;
+        points.push(point);
+    }
+    Ok(points)
+}
+
+/// Records points into a store as `<measurement>_<field>` series.
+///
+/// A [`VALUE_FIELD`] field is recorded as `<measurement>`, the inverse of
+/// [`encode`].
+/// Series are registered on first sight, with the tags as labels; the
+/// store's schemas decide where they go. Each series is registered once
+/// per call, however many points it has. Points without a timestamp are
+/// recorded at `default_timestamp_ns`. Values are written in timestamp
+/// order, since all series of a schema share one ring write cursor.
+pub fn write(store: &mut Store, points: &[Point], default_timestamp_ns: u64) -> IngestSummary {
+    let mut summary = IngestSummary::default();
+
+    let mut handles: HashMap<(String, Vec<(String, String)>), SeriesHandle> = HashMap::new();
+    let mut samples = Vec::new();
+    for point in points {
+        let mut labels = point.tags.clone();
+        labels.sort();
+        let timestamp_ns = point.timestamp_ns.unwrap_or(default_timestamp_ns);
+
+        for (field, value) in &point.fields {
+            let name = if field == VALUE_FIELD {
+                point.measurement.clone()
+            } else {
+                format!("{}_{field}", point.measurement)
+            };
+            let Some(value) = value.as_f64() else {
+                summary.reject(format!("string field '{name}' is not supported"));
+                continue;
+            };
+            let key = (name, labels.clone());
+            let handle = if let Some(&handle) = handles.get(&key) {
+                handle
+            } else {
+                match store.register(&key.0, &key.1) {
+                    Ok(handle) => {
+                        handles.insert(key.clone(), handle);
+                        handle
+                    }
+                    Err(e) => {
+                        summary.reject(format!("failed to register '{}': {e}", key.0));
+                        continue;
+                    }
+                }
+            };
+            samples.push((timestamp_ns, handle, value, key.0));
+        }
+    }
+
+    samples.sort_by_key(|&(timestamp_ns, ..)| timestamp_ns);
+    for (timestamp_ns, handle, value, name) in samples {
+        match store.record(handle, value, timestamp_ns) {
+            Ok(()) => summary.accepted += 1,
+            Err(e) => summary.reject(format!("failed to record '{name}': {e}")),
+        }
+    }
+
+    summary
+}
+
+/// Renders series exports as line protocol.
+///
+/// Each point becomes a line with the series name as measurement, its
+/// labels as tags and a single [`VALUE_FIELD`] field. NaN and infinite
+/// values are skipped, as line protocol cannot represent them.
+///
+/// # Errors
+///
+/// Returns [`LineProtocolError::SeriesNotFound`] if an export refers to a
+/// series the store does not know.
+pub fn encode(exports: &[SeriesExport], store: &Store) -> Result<String> {
+    let mut out = String::new();
+    for export in exports {
+        let (name, labels) =
+            store
+                .series_info(&export.handle)
+                .ok_or(LineProtocolError::SeriesNotFound {
+                    schema_index: export.handle.schema_index,
+                    column: export.handle.column,
+                })?;
+        let mut point = Point {
+            measurement: name.to_string(),
+            tags: labels.to_vec(),
+            fields: Vec::new(),
+            timestamp_ns: None,
+        };
+        point.tags.sort();
+
+        for &(timestamp_ns, value) in &export.points {
+            if !value.is_finite() {
+                continue;
+            }
+            point.fields = vec![(VALUE_FIELD.to_string(), FieldValue::Float(value))];
+            point.timestamp_ns = Some(timestamp_ns);
+            out.push_str(&point.to_string());
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+/// Parses a single non-empty line.
+fn parse_line(line: &str, precision: Precision) -> std::result::Result<Point, String> {
+    let mut cursor = Cursor { line, pos: 0 };
+
+    let measurement = cursor.token(&[',', ' '], &[',', ' ']);
+    if measurement.is_empty() {
+        return Err("missing measurement".to_string());
+    }
+
+    let mut tags = Vec::new();
+    while cursor.eat(',') {
+        let key = cursor.token(&['=', ',', ' '], &[',', '=', ' ']);
+        if key.is_empty() || !cursor.eat('=') {
+            return Err(format!("invalid tag in measurement '{measurement}'"));
+        }
+        let value = cursor.token(&[',', ' '], &[',', '=', ' ']);
+        if value.is_empty() {
+            return Err(format!("tag '{key}' has no value"));
+        }
+        tags.push((key, value));
+    }
+
+    if !cursor.eat(' ') {
+        return Err(format!("measurement '{measurement}' has no fields"));
+    }
+    cursor.skip_spaces();
+
+    let mut fields = Vec::new();
+    loop {
+        let key = cursor.token(&['=', ',', ' '], &[',', '=', ' ']);
+        if key.is_empty() || !cursor.eat('=') {
+            return Err(format!("invalid field in measurement '{measurement}'"));
+        }
+        let value = if cursor.eat('"') {
+            FieldValue::String(cursor.string()?)
+        } else {
+            parse_field_value(&cursor.token(&[',', ' '], &[]))
+                .ok_or_else(|| format!("invalid value for field '{key}'"))?
+        };
+        fields.push((key, value));
+        if !cursor.eat(',') {
+            break;
+        }
+    }
+
+    cursor.skip_spaces();
+    let rest = cursor.rest().trim_end();
+    let timestamp_ns = if rest.is_empty() {
+        None
+    } else {
+        let ts: u64 = rest
+            .parse()
+            .map_err(|_| format!("invalid timestamp '{rest}'"))?;
+        Some(
+            ts.checked_mul(precision.nanos())
+                .ok_or_else(|| format!("timestamp '{rest}' is out of range"))?,
+        )
+    };
+
+    Ok(Point {
+        measurement,
+        tags,
+        fields,
+        timestamp_ns,
+    })
+}
+
+/// Parses an unquoted field value.
+fn parse_field_value(raw: &str) -> Option<FieldValue> {
+    match raw {
+        "t" | "T" | "true" | "True" | "TRUE" => return Some(FieldValue::Boolean(true)),
+        "f" | "F" | "false" | "False" | "FALSE" => return Some(FieldValue::Boolean(false)),
+        _ => {}
+    }
+    if let Some(int) = raw.strip_suffix('i') {
+        return int.parse().ok().map(FieldValue::Integer);
+    }
+    if let Some(uint) = raw.strip_suffix('u') {
+        return uint.parse().ok().map(FieldValue::UInteger);
+    }
+    // Rust also accepts "inf" and "NaN", which line protocol does not
+    raw.parse::<f64>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .map(FieldValue::Float)
+}
+
+/// Writes `s`, backslash-escaping the given characters.
+fn write_escaped(f: &mut fmt::Formatter<'_>, s: &str, special: &[char]) -> fmt::Result {
+    for c in s.chars() {
+        if special.contains(&c) {
+            f.write_str("\\")?;
+        }
+        write!(f, "{c}")?;
+    }
+    Ok(())
+}
+
+/// Position within a line being parsed.
+struct Cursor<'a> {
+    line: &'a str,
+    pos: usize,
+}
+
+impl Cursor<'_> {
+    fn rest(&self) -> &str {
+        &self.line[self.pos..]
+    }
+
+    /// Consumes `c` if it is next.
+    fn eat(&mut self, c: char) -> bool {
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.eat(' ') {}
+    }
+
+    /// Reads up to the first unescaped `stop` character, unescaping
+    /// `escapable` characters preceded by a backslash.
+    fn token(&mut self, stops: &[char], escapable: &[char]) -> String {
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices().peekable();
+        let start = self.pos;
+        while let Some(&(i, c)) = chars.peek() {
+            if stops.contains(&c) {
+                self.pos = start + i;
+                return out;
+            }
+            chars.next();
+            if c == '\\'
+                && let Some(&(_, next)) = chars.peek()
+                && escapable.contains(&next)
+            {
+                out.push(next);
+                chars.next();
+                continue;
+            }
+            out.push(c);
+        }
+        self.pos = self.line.len();
+        out
+    }
+
+    /// Reads the rest of a quoted string after its opening quote.
+    fn string(&mut self) -> std::result::Result<String, String> {
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+        let start = self.pos;
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos = start + i + 1;
+                    return Ok(out);
+                }
+                '\\' => match chars.next() {
+                    Some((_, next @ ('"' | '\\'))) => out.push(next),
+                    Some((_, next)) => {
+                        out.push('\\');
+                        out.push(next);
+                    }
+                    None => break,
+                },
+                _ => out.push(c),
+            }
+        }
+        Err("unterminated string field".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{LabelMatcher, SchemaConfig, TierConfig};
+    use std::time::Duration;
+
+    const BASE: u64 = 1_700_000_000_000_000_000;
+
+    fn create_test_store(dir: &std::path::Path) -> Store {
+        let schemas = vec![SchemaConfig {
+            name: "test".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig::new(Duration::from_secs(1), Duration::from_secs(600), None).unwrap(),
+            ],
+            max_series: 10,
+        }];
+        Store::open(dir.join("store"), schemas).unwrap()
+    }
+
+    fn tag(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_parse_types_and_escapes() {
+        let input = "# comment\n\
+            \n\
+            cpu\\ load,host=web\\,1,region=us\\=east usage=12.5,cores=4i,ok=t,n=7u,msg=\"say \\\"hi\\\", ok\" 1700000000\n\
+            mem free=1e3\n";
+        let points = parse_with_precision(input, Precision::Seconds).unwrap();
+        assert_eq!(points.len(), 2);
+
+        let cpu = &points[0];
+        assert_eq!(cpu.measurement, "cpu load");
+        assert_eq!(
+            cpu.tags,
+            vec![tag("host", "web,1"), tag("region", "us=east")]
+        );
+        assert_eq!(
+            cpu.fields,
+            vec![
+                ("usage".to_string(), FieldValue::Float(12.5)),
+                ("cores".to_string(), FieldValue::Integer(4)),
+                ("ok".to_string(), FieldValue::Boolean(true)),
+                ("n".to_string(), FieldValue::UInteger(7)),
+                (
+                    "msg".to_string(),
+                    FieldValue::String("say \"hi\", ok".to_string())
+                ),
+            ]
+        );
+        assert_eq!(cpu.timestamp_ns, Some(BASE));
+
+        assert_eq!(
+            points[1].fields,
+            vec![("free".to_string(), FieldValue::Float(1000.0))]
+        );
+        assert_eq!(points[1].timestamp_ns, None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases = [
+            "cpu",
+            "cpu,host usage=1",
+            "cpu usage",
+            "cpu usage=abc",
+            "cpu usage=inf",
+            "cpu usage=1 notatime",
+            "cpu msg=\"open",
+        ];
+        for input in cases {
+            let err = parse(input).unwrap_err();
+            assert!(
+                matches!(
+                    err,
+                    crate::RondoError::LineProtocol(LineProtocolError::Parse { line: 1, .. })
+                ),
+                "{input}: {err}"
+            );
+        }
+
+        let err = parse("ok value=1\nbad").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let input = "cpu\\ load,host=web\\,1 usage=12.5,cores=4i,ok=true,msg=\"a \\\"b\\\"\" 42";
+        let points = parse(input).unwrap();
+        assert_eq!(points[0].to_string(), input);
+        assert_eq!(parse(&points[0].to_string()).unwrap(), points);
+    }
+
+    #[test]
+    fn test_write_and_encode() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+
+        let input = format!(
+            "disk,path=/,dev=sda used=10i,label=\"root\" {}\n\
+             disk,dev=sda,path=/ used=20i {BASE}\n\
+             up value=t\n",
+            BASE + 1_000_000_000
+        );
+        let points = parse(&input).unwrap();
+        let summary = write(&mut store, &points, BASE + 2_000_000_000);
+        assert_eq!(summary.accepted, 3);
+        assert_eq!(summary.rejected, 1);
+        assert!(summary.error.unwrap().contains("disk_label"));
+
+        // Tag order does not matter; both lines hit the same series
+        let handles = store.handles();
+        assert_eq!(handles.len(), 2);
+        let disk = handles
+            .iter()
+            .copied()
+            .find(|h| store.series_info(h).unwrap().0 == "disk_used")
+            .unwrap();
+        let points: Vec<_> = store.query(disk, 0, 0, u64::MAX).unwrap().collect();
+        assert_eq!(points, vec![(BASE, 20.0), (BASE + 1_000_000_000, 10.0)]);
+
+        let exports = vec![SeriesExport {
+            handle: disk,
+            points: vec![(BASE, 20.0), (BASE + 1, f64::NAN)],
+            tier: 0,
+        }];
+        assert_eq!(
+            encode(&exports, &store).unwrap(),
+            format!("disk_used,dev=sda,path=/ value=20 {BASE}\n")
+        );
+    }
+
+    #[test]
+    fn test_encode_imports_back_into_same_series() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let labels = [("host".to_string(), "web1".to_string())];
+        let cpu = store.register("cpu_usage_user", &labels).unwrap();
+        let exports = vec![SeriesExport {
+            handle: cpu,
+            points: vec![(BASE, 12.5), (BASE + 1_000_000_000, 13.0)],
+            tier: 0,
+        }];
+        let encoded = encode(&exports, &store).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut imported = create_test_store(dir.path());
+        let summary = write(&mut imported, &parse(&encoded).unwrap(), BASE);
+        assert_eq!(summary.accepted, 2);
+
+        let handles = imported.handles();
+        assert_eq!(handles.len(), 1);
+        let (name, imported_labels) = imported.series_info(&handles[0]).unwrap();
+        assert_eq!(name, "cpu_usage_user");
+        assert_eq!(imported_labels, labels);
+        let points: Vec<_> = imported
+            .query(handles[0], 0, 0, u64::MAX)
+            .unwrap()
+            .collect();
+        assert_eq!(points, exports[0].points);
+    }
+}
@@ -0,0 +1,1516 @@
+//! OTLP metrics exporter for pushing rondo data to OpenTelemetry pipelines.
+//!
+//! Converts drain output into an OTLP `ExportMetricsServiceRequest` and
+//! sends it over OTLP/HTTP with protobuf encoding (e.g. to a collector at
+//! `http://localhost:4318/v1/metrics`).
+//!
+//! rondo stores plain numeric series, so metric kinds are derived from
+//! Prometheus naming conventions:
+//!
+//! - `<base>_bucket{le="..."}` series, together with `<base>_sum` and
+//!   `<base>_count`, become an explicit-bucket **histogram** named `<base>`
+//! - names ending in `_total` become monotonic **sums** (with the suffix
+//!   dropped, as OTLP-to-Prometheus conversion adds it back)
+//! - everything else is a **gauge**
+//!
+//! [`OtlpExporter::with_kind`] overrides the gauge/sum decision per name.
+//! Stored counter and bucket values are taken to be cumulative; with
+//! [`Temporality::Delta`] the exporter converts them to per-interval deltas,
+//! using the first point of each series as the baseline.
+//!
+//! This module is only available when the `otlp` feature is enabled.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::store::Store;
+//! use rondo::export::ExportCursor;
+//! use rondo::otlp::{OtlpConfig, OtlpExporter, Temporality};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let store = Store::open("/tmp/otlp_example", vec![])?;
+//! let config = OtlpConfig::new("http://localhost:4318/v1/metrics")
+//!     .with_resource_attribute("service.name", "vmm")
+//!     .with_temporality(Temporality::Delta);
+//! let mut exporter = OtlpExporter::new(config);
+//! let mut cursor = ExportCursor::load_or_new("/tmp/cursor_otlp.json")?;
+//!
+//! let pending = store.drain_pending(0, &cursor)?;
+//! exporter.push(&pending.exports, &store)?;
+//! cursor.commit(&pending);
+//! cursor.save()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use prost::Message;
+
+use crate::error::{ExportError, OtlpError, Result};
+use crate::export::{RollupNaming, SeriesExport, rollup_identity};
+use crate::exporter::{Exporter, attempt_timeout};
+use crate::relabel::{RelabelConfig, relabel_series};
+use crate::store::Store;
+
+/// OTLP protobuf types.
+///
+/// Hand-written subset of `opentelemetry/proto/collector/metrics/v1`,
+/// `metrics/v1`, `common/v1` and `resource/v1`, covering gauges, sums and
+/// explicit-bucket histograms. Unknown fields are skipped when decoding.
+pub mod proto {
+    /// Request body of the OTLP metrics export service.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportMetricsServiceRequest {
+        /// Metrics grouped by the resource that produced them.
+        #[prost(message, repeated, tag = "1")]
+        pub resource_metrics: Vec<ResourceMetrics>,
+    }
+
+    /// Response body of the OTLP metrics export service.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportMetricsServiceResponse {
+        /// Set when the receiver rejected part of the request.
+        #[prost(message, optional, tag = "1")]
+        pub partial_success: Option<ExportMetricsPartialSuccess>,
+    }
+
+    /// Details of a partially rejected export.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ExportMetricsPartialSuccess {
+        /// Number of rejected data points.
+        #[prost(int64, tag = "1")]
+        pub rejected_data_points: i64,
+        /// Human-readable reason for the rejection.
+        #[prost(string, tag = "2")]
+        pub error_message: String,
+    }
+
+    /// Metrics from a single resource.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ResourceMetrics {
+        /// The resource the metrics describe.
+        #[prost(message, optional, tag = "1")]
+        pub resource: Option<Resource>,
+        /// Metrics grouped by instrumentation scope.
+        #[prost(message, repeated, tag = "2")]
+        pub scope_metrics: Vec<ScopeMetrics>,
+        /// Schema URL of the resource.
+        #[prost(string, tag = "3")]
+        pub schema_url: String,
+    }
+
+    /// An entity producing telemetry, identified by its attributes.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Resource {
+        /// Resource attributes (e.g. `service.name`).
+        #[prost(message, repeated, tag = "1")]
+        pub attributes: Vec<KeyValue>,
+        /// Number of attributes dropped by the producer.
+        #[prost(uint32, tag = "2")]
+        pub dropped_attributes_count: u32,
+    }
+
+    /// Metrics from a single instrumentation scope.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ScopeMetrics {
+        /// The instrumentation scope.
+        #[prost(message, optional, tag = "1")]
+        pub scope: Option<InstrumentationScope>,
+        /// The metrics.
+        #[prost(message, repeated, tag = "2")]
+        pub metrics: Vec<Metric>,
+        /// Schema URL of the scope.
+        #[prost(string, tag = "3")]
+        pub schema_url: String,
+    }
+
+    /// The library that produced the metrics.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct InstrumentationScope {
+        /// Scope name.
+        #[prost(string, tag = "1")]
+        pub name: String,
+        /// Scope version.
+        #[prost(string, tag = "2")]
+        pub version: String,
+        /// Scope attributes.
+        #[prost(message, repeated, tag = "3")]
+        pub attributes: Vec<KeyValue>,
+        /// Number of attributes dropped by the producer.
+        #[prost(uint32, tag = "4")]
+        pub dropped_attributes_count: u32,
+    }
+
+    /// A named metric with its data points.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Metric {
+        /// Metric name.
+        #[prost(string, tag = "1")]
+        pub name: String,
+        /// Metric description.
+        #[prost(string, tag = "2")]
+        pub description: String,
+        /// Unit in UCUM notation.
+        #[prost(string, tag = "3")]
+        pub unit: String,
+        /// The data, by metric kind.
+        #[prost(oneof = "metric::Data", tags = "5, 7, 9")]
+        pub data: Option<metric::Data>,
+    }
+
+    /// Nested types of [`Metric`].
+    pub mod metric {
+        /// Metric data by kind.
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Data {
+            /// Instantaneous values.
+            #[prost(message, tag = "5")]
+            Gauge(super::Gauge),
+            /// Sums over time.
+            #[prost(message, tag = "7")]
+            Sum(super::Sum),
+            /// Explicit-bucket histograms.
+            #[prost(message, tag = "9")]
+            Histogram(super::Histogram),
+        }
+    }
+
+    /// Gauge data.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Gauge {
+        /// The data points.
+        #[prost(message, repeated, tag = "1")]
+        pub data_points: Vec<NumberDataPoint>,
+    }
+
+    /// Sum data.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Sum {
+        /// The data points.
+        #[prost(message, repeated, tag = "1")]
+        pub data_points: Vec<NumberDataPoint>,
+        /// Whether values are deltas or cumulative totals.
+        #[prost(enumeration = "AggregationTemporality", tag = "2")]
+        pub aggregation_temporality: i32,
+        /// Whether the sum only ever increases.
+        #[prost(bool, tag = "3")]
+        pub is_monotonic: bool,
+    }
+
+    /// Histogram data.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Histogram {
+        /// The data points.
+        #[prost(message, repeated, tag = "1")]
+        pub data_points: Vec<HistogramDataPoint>,
+        /// Whether counts are deltas or cumulative totals.
+        #[prost(enumeration = "AggregationTemporality", tag = "2")]
+        pub aggregation_temporality: i32,
+    }
+
+    /// Aggregation temporality of sums and histograms.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum AggregationTemporality {
+        /// Not set; invalid for export.
+        Unspecified = 0,
+        /// Each point covers the interval since the previous point.
+        Delta = 1,
+        /// Each point covers the interval since a fixed start time.
+        Cumulative = 2,
+    }
+
+    /// A single numeric value.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NumberDataPoint {
+        /// Attributes identifying the stream.
+        #[prost(message, repeated, tag = "7")]
+        pub attributes: Vec<KeyValue>,
+        /// Start of the aggregation interval, in nanoseconds since epoch.
+        #[prost(fixed64, tag = "2")]
+        pub start_time_unix_nano: u64,
+        /// Time of the observation, in nanoseconds since epoch.
+        #[prost(fixed64, tag = "3")]
+        pub time_unix_nano: u64,
+        /// The value.
+        #[prost(oneof = "number_data_point::Value", tags = "4, 6")]
+        pub value: Option<number_data_point::Value>,
+        /// Data point flags.
+        #[prost(uint32, tag = "8")]
+        pub flags: u32,
+    }
+
+    /// Nested types of [`NumberDataPoint`].
+    pub mod number_data_point {
+        /// A floating-point or integer value.
+        #[derive(Clone, Copy, PartialEq, prost::Oneof)]
+        pub enum Value {
+            /// Floating-point value.
+            #[prost(double, tag = "4")]
+            AsDouble(f64),
+            /// Integer value.
+            #[prost(sfixed64, tag = "6")]
+            AsInt(i64),
+        }
+    }
+
+    /// A single explicit-bucket histogram observation.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HistogramDataPoint {
+        /// Attributes identifying the stream.
+        #[prost(message, repeated, tag = "9")]
+        pub attributes: Vec<KeyValue>,
+        /// Start of the aggregation interval, in nanoseconds since epoch.
+        #[prost(fixed64, tag = "2")]
+        pub start_time_unix_nano: u64,
+        /// Time of the observation, in nanoseconds since epoch.
+        #[prost(fixed64, tag = "3")]
+        pub time_unix_nano: u64,
+        /// Total number of observations.
+        #[prost(fixed64, tag = "4")]
+        pub count: u64,
+        /// Sum of all observations, if known.
+        #[prost(double, optional, tag = "5")]
+        pub sum: Option<f64>,
+        /// Per-bucket (non-cumulative) counts; one more than `explicit_bounds`.
+        #[prost(fixed64, repeated, tag = "6")]
+        pub bucket_counts: Vec<u64>,
+        /// Upper bounds of all but the last (overflow) bucket.
+        #[prost(double, repeated, tag = "7")]
+        pub explicit_bounds: Vec<f64>,
+        /// Data point flags.
+        #[prost(uint32, tag = "10")]
+        pub flags: u32,
+        /// Smallest observation, if known.
+        #[prost(double, optional, tag = "11")]
+        pub min: Option<f64>,
+        /// Largest observation, if known.
+        #[prost(double, optional, tag = "12")]
+        pub max: Option<f64>,
+    }
+
+    /// An attribute.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct KeyValue {
+        /// Attribute key.
+        #[prost(string, tag = "1")]
+        pub key: String,
+        /// Attribute value.
+        #[prost(message, optional, tag = "2")]
+        pub value: Option<AnyValue>,
+    }
+
+    /// A scalar attribute value.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AnyValue {
+        /// The value, by type.
+        #[prost(oneof = "any_value::Value", tags = "1, 2, 3, 4, 7")]
+        pub value: Option<any_value::Value>,
+    }
+
+    /// Nested types of [`AnyValue`].
+    pub mod any_value {
+        /// Scalar value types (arrays and key-value lists are not supported).
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Value {
+            /// String value.
+            #[prost(string, tag = "1")]
+            StringValue(String),
+            /// Boolean value.
+            #[prost(bool, tag = "2")]
+            BoolValue(bool),
+            /// Integer value.
+            #[prost(int64, tag = "3")]
+            IntValue(i64),
+            /// Floating-point value.
+            #[prost(double, tag = "4")]
+            DoubleValue(f64),
+            /// Raw bytes.
+            #[prost(bytes, tag = "7")]
+            BytesValue(Vec<u8>),
+        }
+    }
+
+    impl KeyValue {
+        /// Creates a string-valued attribute.
+        pub fn string(key: impl Into<String>, value: impl Into<String>) -> Self {
+            Self {
+                key: key.into(),
+                value: Some(AnyValue {
+                    value: Some(any_value::Value::StringValue(value.into())),
+                }),
+            }
+        }
+    }
+}
+
+/// Aggregation temporality of exported sums and histograms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Temporality {
+    /// Each point carries the running total since the series started.
+    #[default]
+    Cumulative,
+    /// Each point carries the change since the previous point.
+    Delta,
+}
+
+impl Temporality {
+    fn to_proto(self) -> i32 {
+        match self {
+            Self::Cumulative => proto::AggregationTemporality::Cumulative as i32,
+            Self::Delta => proto::AggregationTemporality::Delta as i32,
+        }
+    }
+}
+
+/// How a non-histogram series is exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    /// Exported as an OTLP gauge.
+    Gauge,
+    /// Exported as a monotonic OTLP sum.
+    Counter,
+}
+
+/// Configuration for an OTLP/HTTP metrics endpoint.
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// Endpoint URL (e.g., `http://localhost:4318/v1/metrics`).
+    pub endpoint: String,
+    /// HTTP timeout for export requests.
+    pub timeout: Duration,
+    /// Maximum number of retry attempts on retryable failures.
+    pub max_retries: u32,
+    /// Initial backoff duration between retries (doubles each attempt).
+    pub retry_backoff: Duration,
+    /// Optional HTTP headers (e.g., for authentication).
+    pub headers: Vec<(String, String)>,
+    /// Attributes of the exported resource.
+    pub resource_attributes: Vec<(String, String)>,
+    /// Temporality of sums and histograms.
+    pub temporality: Temporality,
+    /// Relabeling rules applied to each series' name and labels (not the
+    /// resource attributes); series they drop are not sent.
+    pub write_relabel_configs: Vec<RelabelConfig>,
+    /// How series drained from rollup tiers are marked.
+    pub rollup_naming: RollupNaming,
+}
+
+impl OtlpConfig {
+    /// Creates a new config with sensible defaults.
+    ///
+    /// Defaults: 30s timeout, 3 retries, 100ms initial backoff, cumulative
+    /// temporality, and `service.name=rondo`.
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(100),
+            headers: Vec::new(),
+            resource_attributes: vec![("service.name".to_string(), "rondo".to_string())],
+            temporality: Temporality::Cumulative,
+            write_relabel_configs: Vec::new(),
+            rollup_naming: RollupNaming::Label,
+        }
+    }
+
+    /// Adds an HTTP header (e.g., for authentication tokens).
+    #[must_use]
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets a resource attribute, replacing any previous value for the key.
+    #[must_use]
+    pub fn with_resource_attribute(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        let key = key.into();
+        self.resource_attributes.retain(|(k, _)| *k != key);
+        self.resource_attributes.push((key, value.into()));
+        self
+    }
+
+    /// Sets the temporality of sums and histograms.
+    #[must_use]
+    pub fn with_temporality(mut self, temporality: Temporality) -> Self {
+        self.temporality = temporality;
+        self
+    }
+
+    /// Sets the HTTP timeout.
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the maximum number of retries.
+    #[must_use]
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Adds a relabeling rule, applied after the ones already added.
+    #[must_use]
+    pub fn with_write_relabel_config(mut self, rule: RelabelConfig) -> Self {
+        self.write_relabel_configs.push(rule);
+        self
+    }
+
+    /// Sets how series drained from rollup tiers are marked.
+    #[must_use]
+    pub fn with_rollup_naming(mut self, rollup_naming: RollupNaming) -> Self {
+        self.rollup_naming = rollup_naming;
+        self
+    }
+}
+
+/// Running totals of one cumulative stream (counter or histogram).
+#[derive(Debug, Clone)]
+struct StreamState {
+    /// Start time reported for cumulative points.
+    start_ns: u64,
+    /// Timestamp of the last point seen.
+    last_ns: u64,
+    /// Last cumulative values.
+    last: Vec<f64>,
+}
+
+/// Converts drain output to OTLP and pushes it to an endpoint.
+///
+/// The exporter keeps per-stream start times and last values across
+/// batches, so one exporter should be reused for the lifetime of an export
+/// cursor.
+#[derive(Debug)]
+pub struct OtlpExporter {
+    config: OtlpConfig,
+    kinds: HashMap<String, MetricKind>,
+    state: HashMap<String, StreamState>,
+    /// State staged by [`Exporter::encode`] until the batch is acknowledged.
+    staged: Option<HashMap<String, StreamState>>,
+}
+
+impl OtlpExporter {
+    /// Creates an exporter with the given endpoint configuration.
+    pub fn new(config: OtlpConfig) -> Self {
+        Self {
+            config,
+            kinds: HashMap::new(),
+            state: HashMap::new(),
+            staged: None,
+        }
+    }
+
+    /// Overrides the inferred kind of a metric name.
+    #[must_use]
+    pub fn with_kind(mut self, name: impl Into<String>, kind: MetricKind) -> Self {
+        self.kinds.insert(name.into(), kind);
+        self
+    }
+
+    /// Returns the endpoint configuration.
+    pub fn config(&self) -> &OtlpConfig {
+        &self.config
+    }
+
+    /// Pushes series exports to the OTLP endpoint.
+    ///
+    /// Stream state (delta baselines, cumulative start times) only advances
+    /// when the receiver accepts the request, so a failed batch can be
+    /// re-drained and pushed again.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OtlpError`] if a series cannot be found in the store,
+    /// serialization fails, the receiver rejects the request (after retries
+    /// for retryable statuses), or it reports a partial success.
+    pub fn push(&mut self, exports: &[SeriesExport], store: &Store) -> Result<usize> {
+        if exports.is_empty() {
+            return Ok(0);
+        }
+
+        let mut state = self.state.clone();
+        let request = self.build_request(exports, store, &mut state)?;
+        let body = serialize_request(&request)?;
+        send_with_retry(&self.config, &body, None)?;
+
+        self.state = state;
+        Ok(exports.len())
+    }
+
+    /// Encodes series exports as an OTLP protobuf payload and commits the
+    /// stream state at once.
+    ///
+    /// Returns the uncompressed protobuf bytes, for testing or custom
+    /// transports. Stream state advances as if the payload was delivered;
+    /// use [`Exporter::encode`] and [`Exporter::ack`] to advance it only
+    /// once the payload is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a series handle cannot be found in the store or
+    /// serialization fails.
+    pub fn encode_committed(&mut self, exports: &[SeriesExport], store: &Store) -> Result<Vec<u8>> {
+        let mut state = self.state.clone();
+        let request = self.build_request(exports, store, &mut state)?;
+        let body = serialize_request(&request)?;
+        self.state = state;
+        Ok(body)
+    }
+
+    /// Converts `SeriesExport` data to an `ExportMetricsServiceRequest`.
+    fn build_request(
+        &self,
+        exports: &[SeriesExport],
+        store: &Store,
+        state: &mut HashMap<String, StreamState>,
+    ) -> Result<proto::ExportMetricsServiceRequest> {
+        let rules = &self.config.write_relabel_configs;
+        let histogram_bases = histogram_bases(store, rules);
+        // Keyed by name within each kind: a gauge `requests` and a counter
+        // `requests_total` both export as "requests" and stay two metrics
+        let mut gauges: BTreeMap<String, Vec<proto::NumberDataPoint>> = BTreeMap::new();
+        let mut sums: BTreeMap<String, Vec<proto::NumberDataPoint>> = BTreeMap::new();
+        let mut histograms: BTreeMap<(String, Vec<(String, String)>), HistogramSeries> =
+            BTreeMap::new();
+
+        for export in exports {
+            let (name, labels) =
+                store
+                    .series_info(&export.handle)
+                    .ok_or_else(|| OtlpError::SeriesNotFound {
+                        schema_index: export.handle.schema_index,
+                        column: export.handle.column,
+                    })?;
+            let rollup = store.rollup(export.handle.schema_index, export.tier);
+            let (name, labels) = rollup_identity(name, labels, rollup, self.config.rollup_naming);
+            let relabeled;
+            let (name, labels) = if rules.is_empty() {
+                (name.as_ref(), labels.as_ref())
+            } else {
+                let Some(series) = relabel_series(&name, &labels, rules) else {
+                    continue;
+                };
+                relabeled = series;
+                (relabeled.0.as_str(), relabeled.1.as_slice())
+            };
+
+            if let Some((base, part)) = histogram_part(name, labels, &histogram_bases) {
+                let mut group_labels: Vec<(String, String)> =
+                    labels.iter().filter(|(k, _)| k != "le").cloned().collect();
+                group_labels.sort();
+                let series = histograms.entry((base, group_labels)).or_default();
+                series.add(part, labels, &export.points);
+                continue;
+            }
+
+            let kind = self
+                .kinds
+                .get(name)
+                .copied()
+                .unwrap_or_else(|| infer_kind(name));
+            let attributes = attributes(labels);
+
+            match kind {
+                MetricKind::Gauge => {
+                    let points = export
+                        .points
+                        .iter()
+                        .map(|&(ts, value)| proto::NumberDataPoint {
+                            attributes: attributes.clone(),
+                            start_time_unix_nano: 0,
+                            time_unix_nano: ts,
+                            value: Some(proto::number_data_point::Value::AsDouble(value)),
+                            flags: 0,
+                        });
+                    gauges.entry(name.to_string()).or_default().extend(points);
+                }
+                MetricKind::Counter => {
+                    let key = stream_key(name, labels);
+                    let mut points = Vec::with_capacity(export.points.len());
+                    for &(ts, value) in &export.points {
+                        let Some((start, values)) =
+                            advance(state, &key, ts, &[value], 1, self.config.temporality)
+                        else {
+                            continue;
+                        };
+                        points.push(proto::NumberDataPoint {
+                            attributes: attributes.clone(),
+                            start_time_unix_nano: start,
+                            time_unix_nano: ts,
+                            value: Some(proto::number_data_point::Value::AsDouble(values[0])),
+                            flags: 0,
+                        });
+                    }
+                    let family = name.strip_suffix("_total").unwrap_or(name);
+                    sums.entry(family.to_string()).or_default().extend(points);
+                }
+            }
+        }
+
+        let mut histogram_points: BTreeMap<String, Vec<proto::HistogramDataPoint>> =
+            BTreeMap::new();
+        for ((base, labels), series) in &histograms {
+            let points = series.data_points(
+                &base_key(base, labels),
+                labels,
+                state,
+                self.config.temporality,
+            );
+            histogram_points
+                .entry(base.clone())
+                .or_default()
+                .extend(points);
+        }
+
+        // Drop metrics that ended up without points (e.g. delta baselines)
+        let temporality = self.config.temporality;
+        let mut metrics: Vec<proto::Metric> = gauges
+            .into_iter()
+            .filter(|(_, points)| !points.is_empty())
+            .map(|(name, points)| gauge_metric(name, points))
+            .chain(
+                sums.into_iter()
+                    .filter(|(_, points)| !points.is_empty())
+                    .map(|(name, points)| sum_metric(name, points, temporality)),
+            )
+            .chain(
+                histogram_points
+                    .into_iter()
+                    .filter(|(_, points)| !points.is_empty())
+                    .map(|(name, points)| histogram_metric(name, points, temporality)),
+            )
+            .collect();
+        metrics.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(proto::ExportMetricsServiceRequest {
+            resource_metrics: vec![proto::ResourceMetrics {
+                resource: Some(proto::Resource {
+                    attributes: attributes(&self.config.resource_attributes),
+                    dropped_attributes_count: 0,
+                }),
+                scope_metrics: vec![proto::ScopeMetrics {
+                    scope: Some(proto::InstrumentationScope {
+                        name: "rondo".to_string(),
+                        version: env!("CARGO_PKG_VERSION").to_string(),
+                        ..Default::default()
+                    }),
+                    metrics,
+                    schema_url: String::new(),
+                }],
+                schema_url: String::new(),
+            }],
+        })
+    }
+}
+
+/// Which part of a Prometheus-style histogram a series holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistogramPart {
+    Bucket,
+    Sum,
+    Count,
+}
+
+/// Samples of one histogram stream, collected from its component series.
+#[derive(Debug, Default)]
+struct HistogramSeries {
+    /// Timestamp -> (upper bound, cumulative count) per bucket.
+    buckets: BTreeMap<u64, Vec<(f64, f64)>>,
+    sums: HashMap<u64, f64>,
+    counts: HashMap<u64, f64>,
+}
+
+impl HistogramSeries {
+    fn add(&mut self, part: HistogramPart, labels: &[(String, String)], points: &[(u64, f64)]) {
+        match part {
+            HistogramPart::Bucket => {
+                let Some(le) = labels
+                    .iter()
+                    .find(|(k, _)| k == "le")
+                    .and_then(|(_, v)| v.parse::<f64>().ok())
+                else {
+                    return;
+                };
+                for &(ts, value) in points {
+                    self.buckets.entry(ts).or_default().push((le, value));
+                }
+            }
+            HistogramPart::Sum => self.sums.extend(points.iter().copied()),
+            HistogramPart::Count => self.counts.extend(points.iter().copied()),
+        }
+    }
+
+    /// Builds one data point per timestamp that has buckets and a total.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Counts are clamped non-negative
+    fn data_points(
+        &self,
+        key: &str,
+        labels: &[(String, String)],
+        state: &mut HashMap<String, StreamState>,
+        temporality: Temporality,
+    ) -> Vec<proto::HistogramDataPoint> {
+        let attributes = attributes(labels);
+        let mut out = Vec::new();
+
+        for (&ts, buckets) in &self.buckets {
+            let mut buckets = buckets.clone();
+            buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let bounds: Vec<f64> = buckets
+                .iter()
+                .map(|&(le, _)| le)
+                .filter(|le| le.is_finite())
+                .collect();
+            let inf_bucket = buckets
+                .iter()
+                .find(|(le, _)| le.is_infinite())
+                .map(|&(_, v)| v);
+            let Some(total) = self.counts.get(&ts).copied().or(inf_bucket) else {
+                continue;
+            };
+
+            // [cumulative finite buckets..., total, sum]
+            let mut values: Vec<f64> = buckets
+                .iter()
+                .filter(|(le, _)| le.is_finite())
+                .map(|&(_, v)| v)
+                .collect();
+            values.push(total);
+            values.push(self.sums.get(&ts).copied().unwrap_or(f64::NAN));
+
+            let monotonic = bounds.len() + 1;
+            let Some((start, values)) = advance(state, key, ts, &values, monotonic, temporality)
+            else {
+                continue;
+            };
+
+            let count = |v: f64| v.max(0.0).round() as u64;
+            let mut bucket_counts = Vec::with_capacity(monotonic);
+            let mut previous = 0.0;
+            for &cumulative in &values[..monotonic] {
+                bucket_counts.push(count(cumulative - previous));
+                previous = cumulative;
+            }
+            let sum = values[monotonic];
+
+            out.push(proto::HistogramDataPoint {
+                attributes: attributes.clone(),
+                start_time_unix_nano: start,
+                time_unix_nano: ts,
+                count: count(values[monotonic - 1]),
+                sum: (!sum.is_nan()).then_some(sum),
+                bucket_counts,
+                explicit_bounds: bounds,
+                flags: 0,
+                min: None,
+                max: None,
+            });
+        }
+
+        out
+    }
+}
+
+/// Feeds one cumulative observation into its stream state.
+///
+/// The first `monotonic` values must never decrease; a decrease is a reset.
+/// Returns the start time and the values to report, or `None` if the point
+/// is a delta baseline or was already seen.
+fn advance(
+    state: &mut HashMap<String, StreamState>,
+    key: &str,
+    ts: u64,
+    values: &[f64],
+    monotonic: usize,
+    temporality: Temporality,
+) -> Option<(u64, Vec<f64>)> {
+    let Some(stream) = state.get_mut(key) else {
+        state.insert(
+            key.to_string(),
+            StreamState {
+                start_ns: ts,
+                last_ns: ts,
+                last: values.to_vec(),
+            },
+        );
+        return match temporality {
+            Temporality::Cumulative => Some((ts, values.to_vec())),
+            Temporality::Delta => None,
+        };
+    };
+
+    if ts <= stream.last_ns {
+        return None;
+    }
+
+    let reset = stream.last.len() != values.len()
+        || values[..monotonic]
+            .iter()
+            .zip(&stream.last)
+            .any(|(new, old)| new < old);
+
+    let result = match temporality {
+        Temporality::Cumulative => {
+            if reset {
+                stream.start_ns = ts;
+            }
+            (stream.start_ns, values.to_vec())
+        }
+        Temporality::Delta if reset => (stream.last_ns, values.to_vec()),
+        Temporality::Delta => (
+            stream.last_ns,
+            values
+                .iter()
+                .zip(&stream.last)
+                .map(|(new, old)| new - old)
+                .collect(),
+        ),
+    };
+
+    stream.last_ns = ts;
+    stream.last = values.to_vec();
+    Some(result)
+}
+
+/// Infers the kind of a non-histogram metric from its name.
+fn infer_kind(name: &str) -> MetricKind {
+    if name.ends_with("_total") {
+        MetricKind::Counter
+    } else {
+        MetricKind::Gauge
+    }
+}
+
+/// Returns the base names of all histograms registered in the store, i.e.
+/// names `<base>` for which a `<base>_bucket` series with an `le` label
+/// exists after relabeling.
+fn histogram_bases(store: &Store, rules: &[RelabelConfig]) -> HashSet<String> {
+    let base = |name: &str, labels: &[(String, String)]| {
+        if !labels.iter().any(|(k, _)| k == "le") {
+            return None;
+        }
+        name.strip_suffix("_bucket").map(str::to_string)
+    };
+    store
+        .handles()
+        .iter()
+        .filter_map(|h| store.series_info(h))
+        .filter_map(|(name, labels)| {
+            if rules.is_empty() {
+                return base(name, labels);
+            }
+            let (name, labels) = relabel_series(name, labels, rules)?;
+            base(&name, &labels)
+        })
+        .collect()
+}
+
+/// Classifies a series as part of a histogram, returning its base name.
+fn histogram_part(
+    name: &str,
+    labels: &[(String, String)],
+    bases: &HashSet<String>,
+) -> Option<(String, HistogramPart)> {
+    let (base, part) = if let Some(base) = name.strip_suffix("_bucket") {
+        if !labels.iter().any(|(k, _)| k == "le") {
+            return None;
+        }
+        (base, HistogramPart::Bucket)
+    } else if let Some(base) = name.strip_suffix("_sum") {
+        (base, HistogramPart::Sum)
+    } else {
+        (name.strip_suffix("_count")?, HistogramPart::Count)
+    };
+
+    bases.contains(base).then(|| (base.to_string(), part))
+}
+
+/// Converts label pairs to OTLP string attributes.
+fn attributes(labels: &[(String, String)]) -> Vec<proto::KeyValue> {
+    labels
+        .iter()
+        .map(|(k, v)| proto::KeyValue::string(k.clone(), v.clone()))
+        .collect()
+}
+
+/// Identifies a counter stream by name and sorted labels.
+fn stream_key(name: &str, labels: &[(String, String)]) -> String {
+    let mut labels = labels.to_vec();
+    labels.sort();
+    base_key(name, &labels)
+}
+
+/// Identifies a stream by name and already sorted labels.
+fn base_key(name: &str, labels: &[(String, String)]) -> String {
+    let mut key = name.to_string();
+    for (k, v) in labels {
+        key.push('\0');
+        key.push_str(k);
+        key.push('=');
+        key.push_str(v);
+    }
+    key
+}
+
+fn gauge_metric(name: String, data_points: Vec<proto::NumberDataPoint>) -> proto::Metric {
+    proto::Metric {
+        name,
+        data: Some(proto::metric::Data::Gauge(proto::Gauge { data_points })),
+        ..Default::default()
+    }
+}
+
+fn sum_metric(
+    name: String,
+    data_points: Vec<proto::NumberDataPoint>,
+    temporality: Temporality,
+) -> proto::Metric {
+    proto::Metric {
+        name,
+        data: Some(proto::metric::Data::Sum(proto::Sum {
+            data_points,
+            aggregation_temporality: temporality.to_proto(),
+            is_monotonic: true,
+        })),
+        ..Default::default()
+    }
+}
+
+fn histogram_metric(
+    name: String,
+    data_points: Vec<proto::HistogramDataPoint>,
+    temporality: Temporality,
+) -> proto::Metric {
+    proto::Metric {
+        name,
+        data: Some(proto::metric::Data::Histogram(proto::Histogram {
+            data_points,
+            aggregation_temporality: temporality.to_proto(),
+        })),
+        ..Default::default()
+    }
+}
+
+/// Serializes an `ExportMetricsServiceRequest` to protobuf bytes.
+fn serialize_request(request: &proto::ExportMetricsServiceRequest) -> Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(request.encoded_len());
+    request
+        .encode(&mut buf)
+        .map_err(|e| OtlpError::Serialization { source: e })?;
+    Ok(buf)
+}
+
+/// Sink for an [`ExportDriver`](crate::exporter::ExportDriver).
+///
+/// Unlike [`OtlpExporter::encode_committed`], stream state only advances
+/// in [`ack`](Exporter::ack), so a rejected batch is re-encoded against the
+/// same baselines.
+impl Exporter for OtlpExporter {
+    fn name(&self) -> &str {
+        "otlp"
+    }
+
+    fn encode(&mut self, exports: &[SeriesExport], store: &Store) -> Result<Vec<u8>> {
+        let mut state = self.state.clone();
+        let request = self.build_request(exports, store, &mut state)?;
+        let body = serialize_request(&request)?;
+        self.staged = Some(state);
+        Ok(body)
+    }
+
+    fn send(&mut self, payload: &[u8]) -> Result<()> {
+        send_with_retry(&self.config, payload, None)
+    }
+
+    fn send_before(&mut self, payload: &[u8], deadline: Instant) -> Result<()> {
+        send_with_retry(&self.config, payload, Some(deadline))
+    }
+
+    fn ack(&mut self) {
+        if let Some(state) = self.staged.take() {
+            self.state = state;
+        }
+    }
+}
+
+/// Returns whether an HTTP status may succeed on retry, per the OTLP spec.
+pub(crate) fn is_retryable(status: u16) -> bool {
+    matches!(status, 429 | 502 | 503 | 504)
+}
+
+/// Sends the protobuf payload with exponential backoff retry.
+///
+/// With a `deadline`, each request's timeout is capped to the time left and
+/// no retry is started once it has passed.
+fn send_with_retry(config: &OtlpConfig, body: &[u8], deadline: Option<Instant>) -> Result<()> {
+    let client = reqwest::blocking::Client::builder()
+        .build()
+        .map_err(|e| OtlpError::ClientCreate { source: e })?;
+
+    let mut last_error = None;
+    let mut backoff = config.retry_backoff;
+
+    for attempt in 0..=config.max_retries {
+        let Some(timeout) = attempt_timeout(config.timeout, deadline) else {
+            break;
+        };
+        let mut request = client
+            .post(&config.endpoint)
+            .timeout(timeout)
+            .header("Content-Type", "application/x-protobuf");
+
+        for (name, value) in &config.headers {
+            request = request.header(name, value);
+        }
+
+        match request.body(body.to_vec()).send() {
+            Ok(resp) if resp.status().is_success() => {
+                let bytes = resp.bytes().unwrap_or_default();
+                let partial = proto::ExportMetricsServiceResponse::decode(bytes.as_ref())
+                    .ok()
+                    .and_then(|r| r.partial_success)
+                    .filter(|p| p.rejected_data_points > 0);
+                return match partial {
+                    Some(p) => Err(OtlpError::PartialSuccess {
+                        rejected: p.rejected_data_points,
+                        message: p.error_message,
+                    }
+                    .into()),
+                    None => Ok(()),
+                };
+            }
+            Ok(resp) => {
+                let status = resp.status().as_u16();
+                let body = resp.text().unwrap_or_default();
+                let error = OtlpError::HttpStatus { status, body };
+                if !is_retryable(status) {
+                    return Err(error.into());
+                }
+                last_error = Some(error);
+            }
+            Err(e) => {
+                last_error = Some(OtlpError::RequestFailed { source: e });
+            }
+        }
+
+        if attempt < config.max_retries {
+            if deadline.is_some_and(|deadline| Instant::now() + backoff >= deadline) {
+                break;
+            }
+            std::thread::sleep(backoff);
+            backoff *= 2;
+        }
+    }
+
+    match last_error {
+        Some(e) => Err(e.into()),
+        None => Err(ExportError::DeadlineExceeded {
+            sink: "otlp".to_string(),
+        }
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{LabelMatcher, SchemaConfig, TierConfig};
+    use crate::series::SeriesHandle;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    const S: u64 = 1_000_000_000;
+    const BASE: u64 = 1_700_000_000 * S;
+
+    fn create_test_store(dir: &std::path::Path) -> Store {
+        let schemas = vec![SchemaConfig {
+            name: "test".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig::new(Duration::from_secs(1), Duration::from_secs(60), None).unwrap(),
+            ],
+            max_series: 20,
+        }];
+        Store::open(dir.join("store"), schemas).unwrap()
+    }
+
+    fn label(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    fn export(handle: SeriesHandle, points: &[(u64, f64)]) -> SeriesExport {
+        SeriesExport {
+            handle,
+            points: points.to_vec(),
+            tier: 0,
+        }
+    }
+
+    fn decode(body: &[u8]) -> Vec<proto::Metric> {
+        let request = proto::ExportMetricsServiceRequest::decode(body).unwrap();
+        request.resource_metrics[0].scope_metrics[0].metrics.clone()
+    }
+
+    fn value(point: &proto::NumberDataPoint) -> f64 {
+        match point.value {
+            Some(proto::number_data_point::Value::AsDouble(v)) => v,
+            other => panic!("expected double, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_gauges_and_cumulative_sums() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let rss = store.register("vmm_rss_bytes", &[]).unwrap();
+        let exits = store
+            .register("vcpu_exits_total", &[label("reason", "io")])
+            .unwrap();
+
+        let mut exporter = OtlpExporter::new(OtlpConfig::new("http://unused"));
+        let body = exporter
+            .encode_committed(
+                &[
+                    export(rss, &[(BASE, 100.0), (BASE + S, 200.0)]),
+                    export(exits, &[(BASE, 5.0), (BASE + S, 7.0)]),
+                ],
+                &store,
+            )
+            .unwrap();
+
+        let request = proto::ExportMetricsServiceRequest::decode(body.as_slice()).unwrap();
+        let resource = request.resource_metrics[0].resource.as_ref().unwrap();
+        assert_eq!(
+            resource.attributes,
+            vec![proto::KeyValue::string("service.name", "rondo")]
+        );
+
+        let metrics = decode(&body);
+        assert_eq!(metrics.len(), 2);
+
+        // Sorted by name: the counter family drops `_total`
+        assert_eq!(metrics[0].name, "vcpu_exits");
+        let Some(proto::metric::Data::Sum(sum)) = &metrics[0].data else {
+            panic!("expected sum");
+        };
+        assert!(sum.is_monotonic);
+        assert_eq!(
+            sum.aggregation_temporality,
+            proto::AggregationTemporality::Cumulative as i32
+        );
+        assert_eq!(sum.data_points.len(), 2);
+        assert_eq!(sum.data_points[1].start_time_unix_nano, BASE);
+        assert_eq!(value(&sum.data_points[1]), 7.0);
+        assert_eq!(
+            sum.data_points[0].attributes,
+            vec![proto::KeyValue::string("reason", "io")]
+        );
+
+        assert_eq!(metrics[1].name, "vmm_rss_bytes");
+        let Some(proto::metric::Data::Gauge(gauge)) = &metrics[1].data else {
+            panic!("expected gauge");
+        };
+        assert_eq!(gauge.data_points.len(), 2);
+        assert_eq!(gauge.data_points[0].time_unix_nano, BASE);
+
+        // Cumulative start time persists across batches and moves on reset
+        let body = exporter
+            .encode_committed(
+                &[export(exits, &[(BASE + 2 * S, 9.0), (BASE + 3 * S, 1.0)])],
+                &store,
+            )
+            .unwrap();
+        let Some(proto::metric::Data::Sum(sum)) = &decode(&body)[0].data else {
+            panic!("expected sum");
+        };
+        assert_eq!(sum.data_points[0].start_time_unix_nano, BASE);
+        assert_eq!(sum.data_points[1].start_time_unix_nano, BASE + 3 * S);
+    }
+
+    #[test]
+    fn test_write_relabel_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let debug = store.register("debug_ticks", &[]).unwrap();
+        let rss = store
+            .register("rss_bytes", &[label("pid", "42"), label("vm", "a")])
+            .unwrap();
+
+        let config = OtlpConfig::new("http://unused")
+            .with_write_relabel_config(RelabelConfig::drop(["__name__"], "debug_.*").unwrap())
+            .with_write_relabel_config(
+                RelabelConfig::replace(["__name__"], "(.*)", "__name__", "vmm_$1").unwrap(),
+            )
+            .with_write_relabel_config(RelabelConfig::labeldrop("pid").unwrap());
+        let mut exporter = OtlpExporter::new(config);
+        let body = exporter
+            .encode_committed(
+                &[export(debug, &[(BASE, 1.0)]), export(rss, &[(BASE, 2.0)])],
+                &store,
+            )
+            .unwrap();
+
+        let metrics = decode(&body);
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "vmm_rss_bytes");
+        let Some(proto::metric::Data::Gauge(gauge)) = &metrics[0].data else {
+            panic!("expected gauge");
+        };
+        assert_eq!(
+            gauge.data_points[0].attributes,
+            vec![proto::KeyValue::string("vm", "a")]
+        );
+    }
+
+    #[test]
+    fn test_delta_sums_and_kind_override() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let exits = store.register("vcpu_exits_total", &[]).unwrap();
+        let bytes = store.register("blk_bytes", &[]).unwrap();
+
+        let config = OtlpConfig::new("http://unused").with_temporality(Temporality::Delta);
+        let mut exporter = OtlpExporter::new(config).with_kind("blk_bytes", MetricKind::Counter);
+        let body = exporter
+            .encode_committed(
+                &[
+                    export(exits, &[(BASE, 5.0), (BASE + S, 7.0), (BASE + 2 * S, 10.0)]),
+                    export(bytes, &[(BASE, 1.0), (BASE + S, 4.0)]),
+                ],
+                &store,
+            )
+            .unwrap();
+
+        let metrics = decode(&body);
+        assert_eq!(metrics[0].name, "blk_bytes");
+        let Some(proto::metric::Data::Sum(sum)) = &metrics[1].data else {
+            panic!("expected sum");
+        };
+        assert_eq!(
+            sum.aggregation_temporality,
+            proto::AggregationTemporality::Delta as i32
+        );
+        // The first point is the baseline
+        assert_eq!(sum.data_points.len(), 2);
+        assert_eq!(value(&sum.data_points[0]), 2.0);
+        assert_eq!(sum.data_points[0].start_time_unix_nano, BASE);
+        assert_eq!(value(&sum.data_points[1]), 3.0);
+        assert_eq!(sum.data_points[1].start_time_unix_nano, BASE + S);
+    }
+
+    #[test]
+    fn test_histograms() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let mut exports = Vec::new();
+        for (le, first, second) in [("0.1", 2.0, 3.0), ("0.5", 5.0, 9.0), ("+Inf", 6.0, 10.0)] {
+            let handle = store
+                .register("latency_bucket", &[label("le", le), label("op", "read")])
+                .unwrap();
+            exports.push(export(handle, &[(BASE, first), (BASE + S, second)]));
+        }
+        let sum = store
+            .register("latency_sum", &[label("op", "read")])
+            .unwrap();
+        exports.push(export(sum, &[(BASE, 1.5), (BASE + S, 2.5)]));
+        // Not a histogram: no `latency2_bucket` exists
+        let plain = store.register("latency2_sum", &[]).unwrap();
+        exports.push(export(plain, &[(BASE, 1.0)]));
+
+        let config = OtlpConfig::new("http://unused").with_temporality(Temporality::Delta);
+        let mut exporter = OtlpExporter::new(config);
+        let metrics = decode(&exporter.encode_committed(&exports, &store).unwrap());
+
+        assert_eq!(metrics.len(), 2);
+        assert_eq!(metrics[0].name, "latency");
+        let Some(proto::metric::Data::Histogram(histogram)) = &metrics[0].data else {
+            panic!("expected histogram");
+        };
+        assert_eq!(histogram.data_points.len(), 1);
+        let point = &histogram.data_points[0];
+        assert_eq!(
+            point.attributes,
+            vec![proto::KeyValue::string("op", "read")]
+        );
+        assert_eq!(point.explicit_bounds, vec![0.1, 0.5]);
+        // Deltas of [2, 5, 6] -> [3, 9, 10] are [1, 4, 4] cumulative
+        assert_eq!(point.bucket_counts, vec![1, 3, 0]);
+        assert_eq!(point.count, 4);
+        assert_eq!(point.sum, Some(1.0));
+        assert_eq!(point.start_time_unix_nano, BASE);
+
+        assert_eq!(metrics[1].name, "latency2_sum");
+    }
+
+    #[test]
+    fn test_same_name_different_kinds() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let gauge = store.register("requests", &[]).unwrap();
+        let counter = store.register("requests_total", &[]).unwrap();
+        let bucket = store
+            .register("requests_bucket", &[label("le", "+Inf")])
+            .unwrap();
+        let exports = vec![
+            export(gauge, &[(BASE, 1.0)]),
+            export(counter, &[(BASE, 2.0)]),
+            export(bucket, &[(BASE, 3.0)]),
+        ];
+
+        let mut exporter = OtlpExporter::new(OtlpConfig::new("http://unused"));
+        let metrics = decode(&exporter.encode_committed(&exports, &store).unwrap());
+
+        // All three export as "requests", each as its own metric
+        assert!(metrics.iter().all(|m| m.name == "requests"));
+        assert!(matches!(
+            metrics.iter().map(|m| m.data.as_ref()).collect::<Vec<_>>()[..],
+            [
+                Some(proto::metric::Data::Gauge(_)),
+                Some(proto::metric::Data::Sum(_)),
+                Some(proto::metric::Data::Histogram(_)),
+            ]
+        ));
+    }
+
+    /// Accepts one OTLP/HTTP request and replies with `status` and `body`.
+    fn stub_receiver(status: u16, body: Vec<u8>) -> (String, std::thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/v1/metrics", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse().unwrap();
+                }
+            }
+            let mut request = vec![0u8; content_length];
+            reader.read_exact(&mut request).unwrap();
+
+            let mut stream = &stream;
+            write!(
+                stream,
+                "HTTP/1.1 {status} X\r\nContent-Type: application/x-protobuf\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+            request
+        });
+
+        (endpoint, handle)
+    }
+
+    #[test]
+    fn test_push_to_stub_receiver() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let rss = store.register("vmm_rss_bytes", &[]).unwrap();
+
+        let response = proto::ExportMetricsServiceResponse::default().encode_to_vec();
+        let (endpoint, receiver) = stub_receiver(200, response);
+        let config = OtlpConfig::new(endpoint).with_resource_attribute("service.name", "vmm");
+        let mut exporter = OtlpExporter::new(config);
+
+        let count = exporter
+            .push(&[export(rss, &[(BASE, 42.0)])], &store)
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let request =
+            proto::ExportMetricsServiceRequest::decode(receiver.join().unwrap().as_slice())
+                .unwrap();
+        let resource = request.resource_metrics[0].resource.as_ref().unwrap();
+        assert_eq!(
+            resource.attributes,
+            vec![proto::KeyValue::string("service.name", "vmm")]
+        );
+        assert_eq!(
+            request.resource_metrics[0].scope_metrics[0].metrics[0].name,
+            "vmm_rss_bytes"
+        );
+    }
+
+    #[test]
+    fn test_push_rejections_keep_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let exits = store.register("vcpu_exits_total", &[]).unwrap();
+
+        // 400 is not retryable, so exactly one request is made
+        let (endpoint, receiver) = stub_receiver(400, b"bad".to_vec());
+        let mut exporter = OtlpExporter::new(OtlpConfig::new(endpoint));
+        let result = exporter.push(&[export(exits, &[(BASE, 1.0)])], &store);
+        assert!(matches!(
+            result,
+            Err(crate::RondoError::Otlp(OtlpError::HttpStatus {
+                status: 400,
+                ..
+            }))
+        ));
+        receiver.join().unwrap();
+        assert!(exporter.state.is_empty());
+
+        let partial = proto::ExportMetricsServiceResponse {
+            partial_success: Some(proto::ExportMetricsPartialSuccess {
+                rejected_data_points: 1,
+                error_message: "out of order".to_string(),
+            }),
+        };
+        let (endpoint, receiver) = stub_receiver(200, partial.encode_to_vec());
+        exporter.config.endpoint = endpoint;
+        let result = exporter.push(&[export(exits, &[(BASE, 1.0)])], &store);
+        assert!(matches!(
+            result,
+            Err(crate::RondoError::Otlp(OtlpError::PartialSuccess {
+                rejected: 1,
+                ..
+            }))
+        ));
+        receiver.join().unwrap();
+    }
+
+    #[test]
+    fn test_exporter_trait_commits_state_on_ack() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let exits = store.register("vcpu_exits_total", &[]).unwrap();
+
+        let mut exporter = OtlpExporter::new(OtlpConfig::new("http://unused"));
+        let batch = [export(exits, &[(BASE, 1.0)])];
+        Exporter::encode(&mut exporter, &batch, &store).unwrap();
+        assert!(exporter.state.is_empty());
+
+        Exporter::encode(&mut exporter, &batch, &store).unwrap();
+        exporter.ack();
+        assert_eq!(exporter.state.len(), 1);
+        assert!(exporter.staged.is_none());
+    }
+
+    #[test]
+    fn test_push_empty_exports() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_test_store(dir.path());
+        let mut exporter = OtlpExporter::new(OtlpConfig::new("http://localhost:9999/v1/metrics"));
+        assert_eq!(exporter.push(&[], &store).unwrap(), 0);
+    }
+}
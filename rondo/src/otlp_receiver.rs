@@ -0,0 +1,836 @@
+//! OTLP/HTTP metrics receiver for ingesting OpenTelemetry data into a store.
+//!
+//! The counterpart of [`otlp`](crate::otlp): decodes an
+//! `ExportMetricsServiceRequest`, registers a rondo series for every
+//! distinct metric stream and records its data points.
+//!
+//! Streams map to series the way Prometheus ingests OTLP:
+//!
+//! - metric names and attribute keys have characters that are invalid in
+//!   Prometheus names replaced with `_` (`http.server.duration` becomes
+//!   `http_server_duration`)
+//! - data point attributes become labels, together with an allowlist of
+//!   resource attributes (`service.name` and `service.instance.id` by
+//!   default) and any static labels from the config
+//! - gauges are recorded as-is; monotonic sums get a `_total` suffix
+//! - histograms become `<name>_bucket{le="..."}`, `<name>_sum` and
+//!   `<name>_count` series with cumulative counts
+//!
+//! Delta sums and histograms are accumulated into cumulative values,
+//! continuing from the latest stored value after a restart. Exponential
+//! histograms and summaries are rejected.
+//!
+//! Series are created with [`Store::register`], so the schema for each
+//! stream is chosen by the schemas' [`LabelMatcher`]s; a static label such as
+//! `source="otlp"` lets OTLP data be routed to its own schema. Because every
+//! new label combination allocates a column, the receiver caps the number
+//! of series it registers, overall and per metric name. Points that would
+//! exceed a limit are dropped and reported back as a partial success.
+//!
+//! This module is only available when the `otlp` feature is enabled.
+//!
+//! [`LabelMatcher`]: crate::schema::LabelMatcher
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::otlp_receiver::{OtlpReceiver, OtlpReceiverConfig};
+//! use rondo::store::Store;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let mut store = Store::open("./data", vec![])?;
+//! # let body: Vec<u8> = Vec::new();
+//! let config = OtlpReceiverConfig::new()
+//!     .with_label("source", "otlp")
+//!     .with_max_series(500);
+//! let mut receiver = OtlpReceiver::new(config);
+//!
+//! // For each `POST /v1/metrics`:
+//! let response = receiver.handle(&mut store, &body, Some("gzip"))?;
+//! // Reply 200 with Content-Type: application/x-protobuf and `response`
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+
+use prost::Message;
+
+use crate::error::{OtlpError, Result};
+use crate::exposition::sanitize_name;
+use crate::otlp::proto::{self, any_value, metric, number_data_point};
+use crate::series::SeriesHandle;
+use crate::store::Store;
+
+/// `Content-Type` of OTLP/HTTP protobuf requests and responses.
+pub const CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Largest decompressed request body accepted.
+pub const MAX_DECOMPRESSED_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Data point flag marking a point without a recorded value.
+const FLAG_NO_RECORDED_VALUE: u32 = 1;
+
+/// Configuration for an [`OtlpReceiver`].
+#[derive(Debug, Clone)]
+pub struct OtlpReceiverConfig {
+    /// Maximum number of series the receiver registers in total.
+    pub max_series: u32,
+    /// Maximum number of series the receiver registers per metric name.
+    pub max_series_per_metric: u32,
+    /// Resource attributes promoted to labels.
+    pub resource_attributes: Vec<String>,
+    /// Labels added to every series (e.g. for schema routing).
+    pub labels: Vec<(String, String)>,
+}
+
+impl Default for OtlpReceiverConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OtlpReceiverConfig {
+    /// Creates a config with sensible defaults.
+    ///
+    /// Defaults: at most 1000 series in total and 100 per metric name,
+    /// promoting the `service.name` and `service.instance.id` resource
+    /// attributes.
+    pub fn new() -> Self {
+        Self {
+            max_series: 1000,
+            max_series_per_metric: 100,
+            resource_attributes: vec![
+                "service.name".to_string(),
+                "service.instance.id".to_string(),
+            ],
+            labels: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of series registered in total.
+    #[must_use]
+    pub fn with_max_series(mut self, max_series: u32) -> Self {
+        self.max_series = max_series;
+        self
+    }
+
+    /// Sets the maximum number of series registered per metric name.
+    #[must_use]
+    pub fn with_max_series_per_metric(mut self, max_series: u32) -> Self {
+        self.max_series_per_metric = max_series;
+        self
+    }
+
+    /// Sets the resource attributes promoted to labels.
+    #[must_use]
+    pub fn with_resource_attributes<I, S>(mut self, attributes: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.resource_attributes = attributes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Adds a label to every series.
+    ///
+    /// Static labels take precedence over attributes with the same name.
+    #[must_use]
+    pub fn with_label(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Outcome of ingesting one request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngestSummary {
+    /// Data points recorded.
+    pub accepted: u64,
+    /// Data points (or whole metrics of unsupported types) dropped.
+    pub rejected: u64,
+    /// Reason for the first rejection, if any.
+    pub error: Option<String>,
+}
+
+impl IngestSummary {
+    fn reject(&mut self, reason: String) {
+        self.rejected += 1;
+        self.error.get_or_insert(reason);
+    }
+}
+
+/// Ingests OTLP metrics into a store.
+///
+/// The receiver remembers the series it registered (for the cardinality
+/// limits) and the running totals of delta streams, so one receiver should
+/// be kept for the lifetime of the store.
+#[derive(Debug)]
+pub struct OtlpReceiver {
+    config: OtlpReceiverConfig,
+    series: HashMap<String, SeriesHandle>,
+    per_metric: HashMap<String, u32>,
+    totals: HashMap<SeriesHandle, f64>,
+}
+
+impl OtlpReceiver {
+    /// Creates a receiver with the given limits and label mapping.
+    pub fn new(config: OtlpReceiverConfig) -> Self {
+        Self {
+            config,
+            series: HashMap::new(),
+            per_metric: HashMap::new(),
+            totals: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of series registered by this receiver.
+    pub fn series_count(&self) -> usize {
+        self.series.len()
+    }
+
+    /// Handles an OTLP/HTTP protobuf request body.
+    ///
+    /// `content_encoding` is the request's `Content-Encoding` header;
+    /// `gzip` and `identity` are supported. Returns the encoded
+    /// `ExportMetricsServiceResponse`, which carries a partial success when
+    /// points were dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OtlpError`] if the encoding is unsupported, the body cannot
+    /// be decompressed or decoded, or it exceeds
+    /// [`MAX_DECOMPRESSED_BYTES`]. Per-point failures are not errors; they
+    /// are reported in the response.
+    pub fn handle(
+        &mut self,
+        store: &mut Store,
+        body: &[u8],
+        content_encoding: Option<&str>,
+    ) -> Result<Vec<u8>> {
+        let request = decode_request(body, content_encoding)?;
+        let summary = self.ingest(store, &request);
+
+        let response = proto::ExportMetricsServiceResponse {
+            partial_success: (summary.rejected > 0).then(|| proto::ExportMetricsPartialSuccess {
+                rejected_data_points: i64::try_from(summary.rejected).unwrap_or(i64::MAX),
+                error_message: summary.error.unwrap_or_default(),
+            }),
+        };
+        Ok(response.encode_to_vec())
+    }
+
+    /// Records every data point of a decoded request.
+    pub fn ingest(
+        &mut self,
+        store: &mut Store,
+        request: &proto::ExportMetricsServiceRequest,
+    ) -> IngestSummary {
+        let mut summary = IngestSummary::default();
+
+        for resource_metrics in &request.resource_metrics {
+            let mut base_labels = BTreeMap::new();
+            if let Some(resource) = &resource_metrics.resource {
+                for kv in &resource.attributes {
+                    if self.config.resource_attributes.contains(&kv.key) {
+                        insert_attribute(&mut base_labels, kv);
+                    }
+                }
+            }
+
+            for scope_metrics in &resource_metrics.scope_metrics {
+                for metric in &scope_metrics.metrics {
+                    self.ingest_metric(store, metric, &base_labels, &mut summary);
+                }
+            }
+        }
+
+        summary
+    }
+
+    fn ingest_metric(
+        &mut self,
+        store: &mut Store,
+        metric: &proto::Metric,
+        base_labels: &BTreeMap<String, String>,
+        summary: &mut IngestSummary,
+    ) {
+        let name = sanitize_name(&metric.name, true);
+
+        match &metric.data {
+            Some(metric::Data::Gauge(gauge)) => {
+                for point in &gauge.data_points {
+                    self.ingest_number(store, &name, point, false, base_labels, summary);
+                }
+            }
+            Some(metric::Data::Sum(sum)) => {
+                let name = if sum.is_monotonic && !name.ends_with("_total") {
+                    format!("{name}_total")
+                } else {
+                    name
+                };
+                let delta =
+                    sum.aggregation_temporality == proto::AggregationTemporality::Delta as i32;
+                for point in &sum.data_points {
+                    self.ingest_number(store, &name, point, delta, base_labels, summary);
+                }
+            }
+            Some(metric::Data::Histogram(histogram)) => {
+                let delta = histogram.aggregation_temporality
+                    == proto::AggregationTemporality::Delta as i32;
+                for point in &histogram.data_points {
+                    self.ingest_histogram(store, &name, point, delta, base_labels, summary);
+                }
+            }
+            None => summary.reject(format!("unsupported metric type for '{}'", metric.name)),
+        }
+    }
+
+    fn ingest_number(
+        &mut self,
+        store: &mut Store,
+        name: &str,
+        point: &proto::NumberDataPoint,
+        delta: bool,
+        base_labels: &BTreeMap<String, String>,
+        summary: &mut IngestSummary,
+    ) {
+        if point.flags & FLAG_NO_RECORDED_VALUE != 0 {
+            return;
+        }
+        #[allow(clippy::cast_precision_loss)] // Integer points are stored as f64
+        let value = match point.value {
+            Some(number_data_point::Value::AsDouble(v)) => v,
+            Some(number_data_point::Value::AsInt(v)) => v as f64,
+            None => {
+                summary.reject(format!("data point of '{name}' has no value"));
+                return;
+            }
+        };
+
+        let labels = self.labels(base_labels, &point.attributes);
+        let result = self.record(store, name, &labels, value, point.time_unix_nano, delta);
+        match result {
+            Ok(()) => summary.accepted += 1,
+            Err(reason) => summary.reject(reason),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)] // Counts are stored as f64
+    fn ingest_histogram(
+        &mut self,
+        store: &mut Store,
+        name: &str,
+        point: &proto::HistogramDataPoint,
+        delta: bool,
+        base_labels: &BTreeMap<String, String>,
+        summary: &mut IngestSummary,
+    ) {
+        if point.flags & FLAG_NO_RECORDED_VALUE != 0 {
+            return;
+        }
+        if !point.bucket_counts.is_empty()
+            && point.bucket_counts.len() != point.explicit_bounds.len() + 1
+        {
+            summary.reject(format!(
+                "histogram '{name}' has mismatched buckets and bounds"
+            ));
+            return;
+        }
+
+        let labels = self.labels(base_labels, &point.attributes);
+        let ts = point.time_unix_nano;
+        let mut samples = Vec::with_capacity(point.bucket_counts.len() + 2);
+
+        let mut cumulative = 0u64;
+        for (i, &count) in point.bucket_counts.iter().enumerate() {
+            cumulative = cumulative.saturating_add(count);
+            let le = point
+                .explicit_bounds
+                .get(i)
+                .map_or_else(|| "+Inf".to_string(), |b| b.to_string());
+            let mut bucket_labels = labels.clone();
+            bucket_labels.push(("le".to_string(), le));
+            bucket_labels.sort();
+            samples.push((format!("{name}_bucket"), bucket_labels, cumulative as f64));
+        }
+        samples.push((format!("{name}_count"), labels.clone(), point.count as f64));
+        if let Some(sum) = point.sum {
+            samples.push((format!("{name}_sum"), labels, sum));
+        }
+
+        let mut failure = None;
+        for (series, labels, value) in samples {
+            if let Err(reason) = self.record(store, &series, &labels, value, ts, delta) {
+                failure.get_or_insert(reason);
+            }
+        }
+        match failure {
+            None => summary.accepted += 1,
+            Some(reason) => summary.reject(reason),
+        }
+    }
+
+    /// Builds the sorted label set of a data point.
+    fn labels(
+        &self,
+        base_labels: &BTreeMap<String, String>,
+        attributes: &[proto::KeyValue],
+    ) -> Vec<(String, String)> {
+        let mut labels = base_labels.clone();
+        for kv in attributes {
+            insert_attribute(&mut labels, kv);
+        }
+        for (name, value) in &self.config.labels {
+            labels.insert(name.clone(), value.clone());
+        }
+        labels.into_iter().collect()
+    }
+
+    /// Records one sample, registering its series on first use.
+    fn record(
+        &mut self,
+        store: &mut Store,
+        name: &str,
+        labels: &[(String, String)],
+        value: f64,
+        timestamp_ns: u64,
+        delta: bool,
+    ) -> std::result::Result<(), String> {
+        if timestamp_ns == 0 {
+            return Err(format!("data point of '{name}' has no timestamp"));
+        }
+        let handle = self.resolve(store, name, labels)?;
+
+        let value = if delta {
+            let total = match self.totals.get(&handle) {
+                Some(&total) => total,
+                // Continue from the stored total after a restart
+                None => store.latest(handle).ok().flatten().map_or(0.0, |(_, v)| v),
+            };
+            let total = total + value;
+            self.totals.insert(handle, total);
+            total
+        } else {
+            value
+        };
+
+        store
+            .record(handle, value, timestamp_ns)
+            .map_err(|e| format!("failed to record '{name}': {e}"))
+    }
+
+    /// Returns the handle of a series, registering it within the limits.
+    fn resolve(
+        &mut self,
+        store: &mut Store,
+        name: &str,
+        labels: &[(String, String)],
+    ) -> std::result::Result<SeriesHandle, String> {
+        let key = series_key(name, labels);
+        if let Some(&handle) = self.series.get(&key) {
+            return Ok(handle);
+        }
+
+        if self.series.len() >= self.config.max_series as usize {
+            return Err(format!(
+                "series limit of {} reached, dropping '{name}'",
+                self.config.max_series
+            ));
+        }
+        let per_metric = self.per_metric.get(name).copied().unwrap_or(0);
+        if per_metric >= self.config.max_series_per_metric {
+            return Err(format!(
+                "limit of {} series per metric reached for '{name}'",
+                self.config.max_series_per_metric
+            ));
+        }
+
+        let handle = store
+            .register(name, labels)
+            .map_err(|e| format!("failed to register '{name}': {e}"))?;
+        self.series.insert(key, handle);
+        self.per_metric.insert(name.to_string(), per_metric + 1);
+        Ok(handle)
+    }
+}
+
+/// Decompresses and decodes an OTLP/HTTP request body.
+///
+/// # Errors
+///
+/// Returns [`OtlpError`] if the encoding is unsupported, decompression
+/// fails or exceeds [`MAX_DECOMPRESSED_BYTES`], or the protobuf is invalid.
+pub fn decode_request(
+    body: &[u8],
+    content_encoding: Option<&str>,
+) -> Result<proto::ExportMetricsServiceRequest> {
+    let decompressed;
+    let bytes = match content_encoding.map(str::trim) {
+        None | Some("" | "identity") => body,
+        Some(encoding) if encoding.eq_ignore_ascii_case("gzip") => {
+            let mut buf = Vec::new();
+            flate2::read::GzDecoder::new(body)
+                .take(MAX_DECOMPRESSED_BYTES + 1)
+                .read_to_end(&mut buf)
+                .map_err(|e| OtlpError::Decompression { source: e })?;
+            decompressed = buf;
+            &decompressed
+        }
+        Some(encoding) => {
+            return Err(OtlpError::UnsupportedEncoding {
+                encoding: encoding.to_string(),
+            }
+            .into());
+        }
+    };
+
+    if bytes.len() as u64 > MAX_DECOMPRESSED_BYTES {
+        return Err(OtlpError::RequestTooLarge {
+            limit: MAX_DECOMPRESSED_BYTES,
+        }
+        .into());
+    }
+
+    proto::ExportMetricsServiceRequest::decode(bytes)
+        .map_err(|e| OtlpError::Decode { source: e }.into())
+}
+
+/// Inserts an attribute as a label, sanitizing its key.
+fn insert_attribute(labels: &mut BTreeMap<String, String>, kv: &proto::KeyValue) {
+    let Some(value) = kv.value.as_ref().and_then(|v| v.value.as_ref()) else {
+        return;
+    };
+    let value = match value {
+        any_value::Value::StringValue(s) => s.clone(),
+        any_value::Value::BoolValue(b) => b.to_string(),
+        any_value::Value::IntValue(i) => i.to_string(),
+        any_value::Value::DoubleValue(d) => d.to_string(),
+        any_value::Value::BytesValue(bytes) => bytes.iter().map(|b| format!("{b:02x}")).collect(),
+    };
+    labels.insert(sanitize_name(&kv.key, false), value);
+}
+
+/// Identifies a series by name and sorted labels.
+fn series_key(name: &str, labels: &[(String, String)]) -> String {
+    let mut key = name.to_string();
+    for (k, v) in labels {
+        key.push('\0');
+        key.push_str(k);
+        key.push('=');
+        key.push_str(v);
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{LabelMatcher, SchemaConfig, TierConfig};
+    use std::io::Write;
+    use std::time::Duration;
+
+    const S: u64 = 1_000_000_000;
+    const BASE: u64 = 1_700_000_000 * S;
+
+    /// A store with an `otlp` schema (selected by `source="otlp"`) ahead of
+    /// a catch-all schema.
+    fn create_test_store(dir: &std::path::Path) -> Store {
+        let tiers =
+            vec![TierConfig::new(Duration::from_secs(1), Duration::from_secs(600), None).unwrap()];
+        let schemas = vec![
+            SchemaConfig {
+                name: "otlp".to_string(),
+                label_matcher: LabelMatcher::new([("source", "otlp")]),
+                tiers: tiers.clone(),
+                max_series: 20,
+            },
+            SchemaConfig {
+                name: "default".to_string(),
+                label_matcher: LabelMatcher::any(),
+                tiers,
+                max_series: 20,
+            },
+        ];
+        Store::open(dir.join("store"), schemas).unwrap()
+    }
+
+    fn number_point(
+        attributes: Vec<proto::KeyValue>,
+        ts: u64,
+        value: f64,
+    ) -> proto::NumberDataPoint {
+        proto::NumberDataPoint {
+            attributes,
+            start_time_unix_nano: 0,
+            time_unix_nano: ts,
+            value: Some(number_data_point::Value::AsDouble(value)),
+            flags: 0,
+        }
+    }
+
+    fn request(
+        resource: Vec<proto::KeyValue>,
+        metrics: Vec<proto::Metric>,
+    ) -> proto::ExportMetricsServiceRequest {
+        proto::ExportMetricsServiceRequest {
+            resource_metrics: vec![proto::ResourceMetrics {
+                resource: Some(proto::Resource {
+                    attributes: resource,
+                    dropped_attributes_count: 0,
+                }),
+                scope_metrics: vec![proto::ScopeMetrics {
+                    scope: None,
+                    metrics,
+                    schema_url: String::new(),
+                }],
+                schema_url: String::new(),
+            }],
+        }
+    }
+
+    fn gauge(name: &str, data_points: Vec<proto::NumberDataPoint>) -> proto::Metric {
+        proto::Metric {
+            name: name.to_string(),
+            data: Some(metric::Data::Gauge(proto::Gauge { data_points })),
+            ..Default::default()
+        }
+    }
+
+    fn sum(
+        name: &str,
+        temporality: proto::AggregationTemporality,
+        data_points: Vec<proto::NumberDataPoint>,
+    ) -> proto::Metric {
+        proto::Metric {
+            name: name.to_string(),
+            data: Some(metric::Data::Sum(proto::Sum {
+                data_points,
+                aggregation_temporality: temporality as i32,
+                is_monotonic: true,
+            })),
+            ..Default::default()
+        }
+    }
+
+    fn find(store: &Store, name: &str) -> Vec<(SeriesHandle, Vec<(String, String)>)> {
+        store
+            .handles()
+            .into_iter()
+            .filter_map(|h| {
+                let (n, labels) = store.series_info(&h)?;
+                (n == name).then(|| (h, labels.to_vec()))
+            })
+            .collect()
+    }
+
+    fn label(key: &str, value: &str) -> (String, String) {
+        (key.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_ingest_maps_attributes_and_routes_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let mut receiver =
+            OtlpReceiver::new(OtlpReceiverConfig::new().with_label("source", "otlp"));
+
+        let req = request(
+            vec![
+                proto::KeyValue::string("service.name", "agent"),
+                proto::KeyValue::string("process.pid", "1234"),
+            ],
+            vec![gauge(
+                "system.memory.usage",
+                vec![number_point(
+                    vec![proto::KeyValue::string("state", "used")],
+                    BASE,
+                    512.0,
+                )],
+            )],
+        );
+        let summary = receiver.ingest(&mut store, &req);
+        assert_eq!(summary.accepted, 1);
+        assert_eq!(summary.rejected, 0);
+
+        let series = find(&store, "system_memory_usage");
+        assert_eq!(series.len(), 1);
+        let (handle, labels) = &series[0];
+        // process.pid is not promoted
+        assert_eq!(
+            labels,
+            &vec![
+                label("service_name", "agent"),
+                label("source", "otlp"),
+                label("state", "used"),
+            ]
+        );
+        assert_eq!(handle.schema_index, 0);
+        assert_eq!(store.latest(*handle).unwrap(), Some((BASE, 512.0)));
+    }
+
+    #[test]
+    fn test_ingest_sums() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let mut receiver = OtlpReceiver::new(OtlpReceiverConfig::new());
+
+        let req = request(
+            vec![],
+            vec![
+                sum(
+                    "requests",
+                    proto::AggregationTemporality::Delta,
+                    vec![
+                        number_point(vec![], BASE, 3.0),
+                        number_point(vec![], BASE + S, 2.0),
+                    ],
+                ),
+                sum(
+                    "bytes_total",
+                    proto::AggregationTemporality::Cumulative,
+                    vec![number_point(vec![], BASE + S, 100.0)],
+                ),
+            ],
+        );
+        let summary = receiver.ingest(&mut store, &req);
+        assert_eq!(summary.accepted, 3);
+
+        let (requests, _) = find(&store, "requests_total")[0].clone();
+        assert_eq!(store.latest(requests).unwrap(), Some((BASE + S, 5.0)));
+        let (bytes, _) = find(&store, "bytes_total")[0].clone();
+        assert_eq!(store.latest(bytes).unwrap(), Some((BASE + S, 100.0)));
+
+        // A new receiver continues the delta total from the store
+        let mut receiver = OtlpReceiver::new(OtlpReceiverConfig::new());
+        let req = request(
+            vec![],
+            vec![sum(
+                "requests",
+                proto::AggregationTemporality::Delta,
+                vec![number_point(vec![], BASE + 2 * S, 1.0)],
+            )],
+        );
+        receiver.ingest(&mut store, &req);
+        assert_eq!(store.latest(requests).unwrap(), Some((BASE + 2 * S, 6.0)));
+    }
+
+    #[test]
+    fn test_ingest_histogram() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let mut receiver = OtlpReceiver::new(OtlpReceiverConfig::new());
+
+        let histogram = proto::Metric {
+            name: "latency".to_string(),
+            data: Some(metric::Data::Histogram(proto::Histogram {
+                data_points: vec![proto::HistogramDataPoint {
+                    time_unix_nano: BASE,
+                    count: 6,
+                    sum: Some(1.5),
+                    bucket_counts: vec![2, 3, 1],
+                    explicit_bounds: vec![0.1, 0.5],
+                    ..Default::default()
+                }],
+                aggregation_temporality: proto::AggregationTemporality::Cumulative as i32,
+            })),
+            ..Default::default()
+        };
+        let summary = receiver.ingest(&mut store, &request(vec![], vec![histogram]));
+        assert_eq!(summary.accepted, 1);
+
+        let mut buckets: Vec<(String, f64)> = find(&store, "latency_bucket")
+            .into_iter()
+            .map(|(h, labels)| (labels[0].1.clone(), store.latest(h).unwrap().unwrap().1))
+            .collect();
+        buckets.sort_by(|a, b| a.1.total_cmp(&b.1));
+        assert_eq!(
+            buckets,
+            vec![
+                ("0.1".to_string(), 2.0),
+                ("0.5".to_string(), 5.0),
+                ("+Inf".to_string(), 6.0)
+            ]
+        );
+        let (count, _) = find(&store, "latency_count")[0].clone();
+        assert_eq!(store.latest(count).unwrap(), Some((BASE, 6.0)));
+        let (sum, _) = find(&store, "latency_sum")[0].clone();
+        assert_eq!(store.latest(sum).unwrap(), Some((BASE, 1.5)));
+    }
+
+    #[test]
+    fn test_cardinality_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let config = OtlpReceiverConfig::new()
+            .with_max_series(3)
+            .with_max_series_per_metric(2);
+        let mut receiver = OtlpReceiver::new(config);
+
+        let points = |n: u32| {
+            (0..n)
+                .map(|i| {
+                    number_point(
+                        vec![proto::KeyValue::string("id", i.to_string())],
+                        BASE,
+                        f64::from(i),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let req = request(vec![], vec![gauge("a", points(3)), gauge("b", points(3))]);
+        let summary = receiver.ingest(&mut store, &req);
+
+        // a: 2 per metric; b: 1 more before the total limit of 3
+        assert_eq!(summary.accepted, 3);
+        assert_eq!(summary.rejected, 3);
+        assert!(summary.error.unwrap().contains("per metric"));
+        assert_eq!(receiver.series_count(), 3);
+
+        // Known series keep being accepted at the limit
+        let req = request(vec![], vec![gauge("a", points(1))]);
+        assert_eq!(receiver.ingest(&mut store, &req).accepted, 1);
+    }
+
+    #[test]
+    fn test_handle_gzip_and_partial_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let mut receiver = OtlpReceiver::new(OtlpReceiverConfig::new());
+
+        let unsupported = proto::Metric {
+            name: "summary".to_string(),
+            ..Default::default()
+        };
+        let req = request(
+            vec![],
+            vec![
+                gauge("up", vec![number_point(vec![], BASE, 1.0)]),
+                unsupported,
+            ],
+        );
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(&req.encode_to_vec()).unwrap();
+        let body = encoder.finish().unwrap();
+
+        let response = receiver.handle(&mut store, &body, Some("gzip")).unwrap();
+        let response = proto::ExportMetricsServiceResponse::decode(response.as_slice()).unwrap();
+        let partial = response.partial_success.unwrap();
+        assert_eq!(partial.rejected_data_points, 1);
+        assert!(partial.error_message.contains("unsupported"));
+        assert_eq!(find(&store, "up").len(), 1);
+
+        assert!(matches!(
+            receiver.handle(&mut store, &body, Some("br")),
+            Err(crate::RondoError::Otlp(
+                OtlpError::UnsupportedEncoding { .. }
+            ))
+        ));
+        assert!(matches!(
+            receiver.handle(&mut store, b"\xff\xff", None),
+            Err(crate::RondoError::Otlp(OtlpError::Decode { .. }))
+        ));
+    }
+}
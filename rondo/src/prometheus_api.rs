@@ -0,0 +1,721 @@
+//! Prometheus HTTP query API over a [`Store`].
+//!
+//! Implements the read endpoints Grafana's Prometheus data source uses, so a
+//! dashboard can query a rondo store directly instead of a separate
+//! Prometheus server:
+//!
+//! - `/api/v1/query` — instant PromQL query
+//! - `/api/v1/query_range` — range PromQL query
+//! - `/api/v1/series` — series matching `match[]` selectors
+//! - `/api/v1/labels` — label names
+//! - `/api/v1/label/<name>/values` — values of one label
+//! - `/api/v1/status/buildinfo` and `/api/v1/metadata` — minimal answers for
+//!   Grafana's data source health checks and autocompletion
+//!
+//! The module is transport-agnostic: [`handle`] maps a request path and its
+//! decoded parameters (query string and, for `POST`, the form body) to a
+//! status code and JSON body in the Prometheus response envelope. The
+//! embedding application owns the HTTP server and store locking.
+//!
+//! Timestamps are accepted as Unix seconds (`1700000000.5`) or RFC 3339
+//! (`2023-11-14T22:13:20Z`), and durations as seconds or PromQL durations
+//! (`15s`, `1m`), as in Prometheus.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use rondo::prometheus_api::{handle, parse_form};
+//! use rondo::store::Store;
+//!
+//! # let store = Store::open("./data", vec![])?;
+//! # let now_ns = 1_640_000_000_000_000_000u64;
+//! let params = parse_form("query=sum%20by%20(reason)%20(vcpu_exits_total)");
+//! let response = handle(&store, "/api/v1/query", &params, now_ns);
+//! assert_eq!(response.status, 200);
+//! println!("{}", response.body);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+
+use serde_json::{Value as Json, json};
+
+use crate::error::{PromqlError, RondoError};
+use crate::promql::{self, Engine, Labels, Value, ast::Expr};
+use crate::query::QueryOptions;
+use crate::store::Store;
+
+/// Path prefix under which all endpoints are served.
+pub const API_PREFIX: &str = "/api/v1/";
+
+/// A JSON response produced by [`handle`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiResponse {
+    /// HTTP status code.
+    pub status: u16,
+    /// JSON response body.
+    pub body: String,
+}
+
+/// A failed request, rendered as a Prometheus error envelope.
+struct ApiError {
+    status: u16,
+    error_type: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_data(message: impl Into<String>) -> Self {
+        Self {
+            status: 400,
+            error_type: "bad_data",
+            message: message.into(),
+        }
+    }
+}
+
+impl From<RondoError> for ApiError {
+    fn from(err: RondoError) -> Self {
+        let (status, error_type) = match &err {
+            RondoError::Promql(PromqlError::Parse { .. } | PromqlError::Type { .. }) => {
+                (400, "bad_data")
+            }
+            RondoError::Promql(PromqlError::Evaluation { .. }) => (422, "execution"),
+            _ => (500, "internal"),
+        };
+        Self {
+            status,
+            error_type,
+            message: err.to_string(),
+        }
+    }
+}
+
+type ApiResult = std::result::Result<Json, ApiError>;
+
+/// Handles one API request.
+///
+/// `path` is the request path without the query string (e.g.
+/// `/api/v1/query`). `params` are the decoded parameters in request order;
+/// repeated keys such as `match[]` are allowed. `now_ns` is used when a
+/// query omits its evaluation time.
+pub fn handle(store: &Store, path: &str, params: &[(String, String)], now_ns: u64) -> ApiResponse {
+    let Some(endpoint) = path.strip_prefix(API_PREFIX) else {
+        return error_response(&ApiError {
+            status: 404,
+            error_type: "not_found",
+            message: format!("unknown path '{path}'"),
+        });
+    };
+
+    let result = match endpoint {
+        "query" => instant_query(store, params, now_ns),
+        "query_range" => range_query(store, params),
+        "series" => series(store, params),
+        "labels" => label_names(store, params),
+        "status/buildinfo" => Ok(json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "revision": "",
+            "branch": "",
+            "buildUser": "",
+            "buildDate": "",
+            "goVersion": "",
+        })),
+        "metadata" => Ok(json!({})),
+        other => match other
+            .strip_prefix("label/")
+            .and_then(|rest| rest.strip_suffix("/values"))
+        {
+            Some(name) => label_values(store, &percent_decode(name), params),
+            None => Err(ApiError {
+                status: 404,
+                error_type: "not_found",
+                message: format!("unknown endpoint '{path}'"),
+            }),
+        },
+    };
+
+    match result {
+        Ok(data) => ApiResponse {
+            status: 200,
+            body: json!({ "status": "success", "data": data }).to_string(),
+        },
+        Err(err) => error_response(&err),
+    }
+}
+
+fn error_response(err: &ApiError) -> ApiResponse {
+    ApiResponse {
+        status: err.status,
+        body: json!({
+            "status": "error",
+            "errorType": err.error_type,
+            "error": err.message,
+        })
+        .to_string(),
+    }
+}
+
+/// `/api/v1/query?query=<expr>[&time=<ts>]`
+fn instant_query(store: &Store, params: &[(String, String)], now_ns: u64) -> ApiResult {
+    let query = required(params, "query")?;
+    let time_ns = match param(params, "time") {
+        Some(time) => parse_time(time)?,
+        None => now_ns,
+    };
+
+    let value = Engine::new(store).instant_query(query, time_ns)?;
+    Ok(match value {
+        Value::Scalar {
+            timestamp_ns,
+            value,
+        } => json!({
+            "resultType": "scalar",
+            "result": sample_pair(timestamp_ns, value),
+        }),
+        Value::Vector(samples) => json!({
+            "resultType": "vector",
+            "result": samples
+                .iter()
+                .map(|s| json!({
+                    "metric": s.labels,
+                    "value": sample_pair(s.timestamp_ns, s.value),
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        Value::Matrix(series) => matrix(&series),
+    })
+}
+
+/// `/api/v1/query_range?query=<expr>&start=<ts>&end=<ts>&step=<duration>`
+fn range_query(store: &Store, params: &[(String, String)]) -> ApiResult {
+    let query = required(params, "query")?;
+    let start_ns = parse_time(required(params, "start")?)?;
+    let end_ns = parse_time(required(params, "end")?)?;
+    let step = parse_step(required(params, "step")?)?;
+
+    if end_ns < start_ns {
+        return Err(ApiError::bad_data(
+            "end timestamp must not be before start time",
+        ));
+    }
+    if step.is_zero() {
+        return Err(ApiError::bad_data(
+            "zero or negative query resolution step widths are not accepted",
+        ));
+    }
+
+    match Engine::new(store).range_query(query, start_ns, end_ns, step)? {
+        Value::Matrix(series) => Ok(matrix(&series)),
+        _ => Err(ApiError::bad_data("range query did not produce a matrix")),
+    }
+}
+
+/// `/api/v1/series?match[]=<selector>[&start=<ts>&end=<ts>]`
+fn series(store: &Store, params: &[(String, String)]) -> ApiResult {
+    if param(params, "match[]").is_none() {
+        return Err(ApiError::bad_data("no match[] parameter provided"));
+    }
+
+    let labels: Vec<Labels> = matching_series(store, params)?;
+    Ok(json!(labels))
+}
+
+/// `/api/v1/labels[?match[]=<selector>]`
+fn label_names(store: &Store, params: &[(String, String)]) -> ApiResult {
+    let names: BTreeSet<String> = matching_series(store, params)?
+        .into_iter()
+        .flat_map(Labels::into_keys)
+        .collect();
+    Ok(json!(names))
+}
+
+/// `/api/v1/label/<name>/values[?match[]=<selector>]`
+fn label_values(store: &Store, name: &str, params: &[(String, String)]) -> ApiResult {
+    let values: BTreeSet<String> = matching_series(store, params)?
+        .into_iter()
+        .filter_map(|mut labels| labels.remove(name))
+        .collect();
+    Ok(json!(values))
+}
+
+/// Returns the labels of series matching any `match[]` selector (all series
+/// if none is given), restricted to series with data in `[start, end]` when
+/// either bound is given.
+fn matching_series(
+    store: &Store,
+    params: &[(String, String)],
+) -> std::result::Result<Vec<Labels>, ApiError> {
+    let mut selectors = Vec::new();
+    for (_, selector) in params.iter().filter(|(k, _)| k == "match[]") {
+        match promql::parse(selector).map_err(RondoError::from)? {
+            Expr::VectorSelector(selector) => selectors.push(selector),
+            _ => {
+                return Err(ApiError::bad_data(format!(
+                    "match[] must be a series selector, got '{selector}'"
+                )));
+            }
+        }
+    }
+
+    let mut found: Vec<(crate::series::SeriesHandle, Labels)> = if selectors.is_empty() {
+        store
+            .handles()
+            .into_iter()
+            .filter_map(|handle| {
+                let (name, labels) = store.series_info(&handle)?;
+                Some((handle, promql::series_labels(name, labels)))
+            })
+            .collect()
+    } else {
+        let mut found = Vec::new();
+        for selector in &selectors {
+            for (handle, labels) in promql::select_series(store, selector) {
+                if !found.iter().any(|(h, _)| *h == handle) {
+                    found.push((handle, labels));
+                }
+            }
+        }
+        found
+    };
+
+    let start = param(params, "start").map(parse_time).transpose()?;
+    let end = param(params, "end").map(parse_time).transpose()?;
+    if start.is_some() || end.is_some() {
+        let start_ns = start.unwrap_or(0);
+        let end_ns = end.map_or(u64::MAX, |end| end.saturating_add(1));
+        if start_ns >= end_ns {
+            return Err(ApiError::bad_data(
+                "end timestamp must not be before start time",
+            ));
+        }
+
+        let options = QueryOptions::new().with_stitching(true);
+        let mut with_data = Vec::with_capacity(found.len());
+        for (handle, labels) in found {
+            let mut result = store
+                .query_auto_with(handle, start_ns, end_ns, &options)
+                .map_err(ApiError::from)?;
+            if result.next().is_some() {
+                with_data.push((handle, labels));
+            }
+        }
+        found = with_data;
+    }
+
+    let mut labels: Vec<Labels> = found.into_iter().map(|(_, labels)| labels).collect();
+    labels.sort();
+    Ok(labels)
+}
+
+fn matrix(series: &[promql::Series]) -> Json {
+    json!({
+        "resultType": "matrix",
+        "result": series
+            .iter()
+            .map(|s| json!({
+                "metric": s.labels,
+                "values": s.points
+                    .iter()
+                    .map(|&(ts, v)| sample_pair(ts, v))
+                    .collect::<Vec<_>>(),
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Formats a sample as `[<unix seconds>, "<value>"]`.
+fn sample_pair(timestamp_ns: u64, value: f64) -> Json {
+    // Prometheus reports millisecond precision
+    #[allow(clippy::cast_precision_loss)] // Millisecond epochs fit in f64's 52-bit mantissa
+    let seconds = (timestamp_ns / 1_000_000) as f64 / 1000.0;
+    json!([seconds, format_value(value)])
+}
+
+/// Formats a sample value the way Prometheus does.
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn param<'p>(params: &'p [(String, String)], key: &str) -> Option<&'p str> {
+    params
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.as_str())
+}
+
+fn required<'p>(
+    params: &'p [(String, String)],
+    key: &str,
+) -> std::result::Result<&'p str, ApiError> {
+    param(params, key).ok_or_else(|| ApiError::bad_data(format!("missing '{key}' parameter")))
+}
+
+/// Parses a timestamp given as Unix seconds or RFC 3339.
+fn parse_time(value: &str) -> std::result::Result<u64, ApiError> {
+    let invalid = || ApiError::bad_data(format!("cannot parse '{value}' as a timestamp"));
+
+    // Decimal seconds are parsed exactly; f64 would lose sub-microsecond digits
+    if let Some((secs, frac)) = value.split_once('.').or(Some((value, "")))
+        && !secs.is_empty()
+        && secs.bytes().all(|b| b.is_ascii_digit())
+        && frac.bytes().all(|b| b.is_ascii_digit())
+    {
+        let secs: u64 = secs.parse().map_err(|_| invalid())?;
+        let digits: String = frac.chars().chain(std::iter::repeat('0')).take(9).collect();
+        let nanos: u64 = digits.parse().map_err(|_| invalid())?;
+        return secs
+            .checked_mul(1_000_000_000)
+            .and_then(|ns| ns.checked_add(nanos))
+            .ok_or_else(invalid);
+    }
+
+    if let Ok(seconds) = value.parse::<f64>() {
+        if !seconds.is_finite() || seconds < 0.0 {
+            return Err(invalid());
+        }
+        // Float-to-int casts saturate, so out-of-range times clamp to u64::MAX
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // Checked non-negative
+        let ns = (seconds * 1e9).round() as u64;
+        return Ok(ns);
+    }
+
+    parse_rfc3339(value).ok_or_else(invalid)
+}
+
+/// Parses a step given as seconds or as a PromQL duration.
+fn parse_step(value: &str) -> std::result::Result<Duration, ApiError> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(seconds).map_err(|_| {
+            ApiError::bad_data("zero or negative query resolution step widths are not accepted")
+        });
+    }
+    promql::parse_duration(value)
+        .map_err(|_| ApiError::bad_data(format!("cannot parse '{value}' as a duration")))
+}
+
+/// Parses `YYYY-MM-DDTHH:MM:SS[.fraction](Z|±HH:MM)` into Unix nanoseconds.
+fn parse_rfc3339(value: &str) -> Option<u64> {
+    let bytes = value.as_bytes();
+    if bytes.len() < 20 || bytes[4] != b'-' || bytes[7] != b'-' || bytes[13] != b':' {
+        return None;
+    }
+    if !matches!(bytes[10], b'T' | b't' | b' ') || bytes[16] != b':' {
+        return None;
+    }
+
+    let number = |range: std::ops::Range<usize>| -> Option<i64> {
+        let digits = value.get(range)?;
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    let (year, month, day) = (number(0..4)?, number(5..7)?, number(8..10)?);
+    let (hour, minute, second) = (number(11..13)?, number(14..16)?, number(17..19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 {
+        return None;
+    }
+    if second > 60 {
+        return None;
+    }
+
+    let mut pos = 19;
+    let mut nanos: i64 = 0;
+    if bytes[pos] == b'.' {
+        pos += 1;
+        let frac_start = pos;
+        while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+            pos += 1;
+        }
+        let frac = &value[frac_start..pos];
+        if frac.is_empty() {
+            return None;
+        }
+        // Keep nanosecond precision, right-padding shorter fractions
+        let digits: String = frac.chars().chain(std::iter::repeat('0')).take(9).collect();
+        nanos = digits.parse().ok()?;
+    }
+
+    let offset_secs = match value.get(pos..)? {
+        "Z" | "z" => 0,
+        zone if zone.len() == 6 && matches!(zone.as_bytes()[0], b'+' | b'-') => {
+            let hours = number(pos + 1..pos + 3)?;
+            let minutes = number(pos + 4..pos + 6)?;
+            let sign = if zone.starts_with('-') { -1 } else { 1 };
+            sign * (hours * 3600 + minutes * 60)
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    let secs = days * 86_400 + hour * 3600 + minute * 60 + second - offset_secs;
+    let ns = i128::from(secs) * 1_000_000_000 + i128::from(nanos);
+    u64::try_from(ns).ok()
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Decodes an `application/x-www-form-urlencoded` string (a query string or
+/// `POST` body) into key-value pairs, preserving order and repeated keys.
+pub fn parse_form(input: &str) -> Vec<(String, String)> {
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (form_decode(key), form_decode(value))
+        })
+        .collect()
+}
+
+fn form_decode(input: &str) -> String {
+    percent_decode(&input.replace('+', " "))
+}
+
+/// Decodes `%XX` escapes; invalid escapes are kept verbatim.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = input
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            out.push(byte);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{LabelMatcher, SchemaConfig, TierConfig};
+    use tempfile::TempDir;
+
+    const BASE_SECS: u64 = 1_700_000_000;
+
+    fn test_store() -> (TempDir, Store) {
+        let dir = TempDir::new().unwrap();
+        let schemas = vec![SchemaConfig {
+            name: "vmm".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![TierConfig {
+                interval: Duration::from_secs(1),
+                retention: Duration::from_secs(600),
+                consolidation_fn: None,
+            }],
+            max_series: 10,
+        }];
+        let mut store = Store::open(dir.path().join("store"), schemas).unwrap();
+
+        for reason in ["io", "hlt"] {
+            let handle = store
+                .register(
+                    "vcpu_exits_total",
+                    &[("reason".to_string(), reason.to_string())],
+                )
+                .unwrap();
+            for i in 0..60u32 {
+                let ts = (BASE_SECS + u64::from(i)) * 1_000_000_000;
+                store.record(handle, f64::from(i), ts).unwrap();
+            }
+        }
+        store.register("vmm_rss_bytes", &[]).unwrap();
+
+        (dir, store)
+    }
+
+    fn get(store: &Store, path: &str, query: &str) -> (u16, Json) {
+        let now_ns = (BASE_SECS + 59) * 1_000_000_000;
+        let response = handle(store, path, &parse_form(query), now_ns);
+        let body = serde_json::from_str(&response.body).unwrap();
+        (response.status, body)
+    }
+
+    #[test]
+    fn test_query_endpoints() {
+        let (_dir, store) = test_store();
+
+        let (status, body) = get(&store, "/api/v1/query", "query=sum(vcpu_exits_total)");
+        assert_eq!(status, 200);
+        assert_eq!(body["status"], "success");
+        assert_eq!(body["data"]["resultType"], "vector");
+        assert_eq!(
+            body["data"]["result"][0]["value"],
+            json!([1_700_000_059.0, "118"])
+        );
+
+        let (status, body) = get(
+            &store,
+            "/api/v1/query_range",
+            "query=vcpu_exits_total%7Breason%3D%22io%22%7D&start=1700000030&end=1700000059&step=10s",
+        );
+        assert_eq!(status, 200);
+        assert_eq!(body["data"]["resultType"], "matrix");
+        let result = &body["data"]["result"][0];
+        assert_eq!(result["metric"]["__name__"], "vcpu_exits_total");
+        assert_eq!(
+            result["values"],
+            json!([
+                [1_700_000_030.0, "30"],
+                [1_700_000_040.0, "40"],
+                [1_700_000_050.0, "50"]
+            ])
+        );
+
+        let (status, body) = get(&store, "/api/v1/query", "time=1700000059&query=1%2B1");
+        assert_eq!(status, 200);
+        assert_eq!(
+            body["data"],
+            json!({"resultType": "scalar", "result": [1_700_000_059.0, "2"]})
+        );
+    }
+
+    #[test]
+    fn test_metadata_endpoints() {
+        let (_dir, store) = test_store();
+
+        let (status, body) = get(&store, "/api/v1/labels", "");
+        assert_eq!(status, 200);
+        assert_eq!(body["data"], json!(["__name__", "reason"]));
+
+        let (_, body) = get(&store, "/api/v1/label/__name__/values", "");
+        assert_eq!(body["data"], json!(["vcpu_exits_total", "vmm_rss_bytes"]));
+
+        let (_, body) = get(&store, "/api/v1/label/reason/values", "");
+        assert_eq!(body["data"], json!(["hlt", "io"]));
+
+        let (status, body) = get(&store, "/api/v1/series", "match[]=vcpu_exits_total");
+        assert_eq!(status, 200);
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+
+        // vmm_rss_bytes has no samples, so a time-bounded lookup omits it
+        let (_, body) = get(
+            &store,
+            "/api/v1/series",
+            "match[]=%7B__name__%3D~%22.%2B%22%7D&start=1700000000&end=1700000059",
+        );
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+        let (_, body) = get(
+            &store,
+            "/api/v1/series",
+            "match[]=%7B__name__%3D~%22.%2B%22%7D",
+        );
+        assert_eq!(body["data"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_errors() {
+        let (_dir, store) = test_store();
+
+        let (status, body) = get(&store, "/api/v1/query", "query=sum(");
+        assert_eq!(status, 400);
+        assert_eq!(body["status"], "error");
+        assert_eq!(body["errorType"], "bad_data");
+
+        let (status, _) = get(&store, "/api/v1/query", "");
+        assert_eq!(status, 400);
+        let (status, _) = get(&store, "/api/v1/series", "");
+        assert_eq!(status, 400);
+        let (status, _) = get(&store, "/api/v1/series", "match[]=rate(x%5B1m%5D)");
+        assert_eq!(status, 400);
+        let (status, _) = get(
+            &store,
+            "/api/v1/query_range",
+            "query=vmm_rss_bytes&start=10&end=5&step=1",
+        );
+        assert_eq!(status, 400);
+        let (status, body) = get(
+            &store,
+            "/api/v1/query_range",
+            "query=vmm_rss_bytes&start=0&end=1700000000&step=1",
+        );
+        assert_eq!(status, 422);
+        assert_eq!(body["errorType"], "execution");
+        let (status, _) = get(&store, "/api/v1/nope", "");
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn test_parse_form() {
+        assert_eq!(
+            parse_form("query=rate(x%5B5m%5D)+*+2&match[]=a&match%5B%5D=b&empty"),
+            vec![
+                ("query".to_string(), "rate(x[5m]) * 2".to_string()),
+                ("match[]".to_string(), "a".to_string()),
+                ("match[]".to_string(), "b".to_string()),
+                ("empty".to_string(), String::new()),
+            ]
+        );
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(
+            parse_time("1700000000").ok(),
+            Some(1_700_000_000_000_000_000)
+        );
+        assert_eq!(
+            parse_time("1700000000.25").ok(),
+            Some(1_700_000_000_250_000_000)
+        );
+        assert_eq!(
+            parse_time("2023-11-14T22:13:20Z").ok(),
+            Some(1_700_000_000_000_000_000)
+        );
+        assert_eq!(
+            parse_time("2023-11-14T23:13:20.5+01:00").ok(),
+            Some(1_700_000_000_500_000_000)
+        );
+        assert!(parse_time("-1").is_err());
+        assert!(parse_time("yesterday").is_err());
+        assert!(parse_time("2023-13-14T22:13:20Z").is_err());
+    }
+
+    #[test]
+    fn test_parse_step() {
+        assert_eq!(parse_step("15").ok(), Some(Duration::from_secs(15)));
+        assert_eq!(parse_step("0.5").ok(), Some(Duration::from_millis(500)));
+        assert_eq!(parse_step("1m").ok(), Some(Duration::from_secs(60)));
+        assert!(parse_step("-1").is_err());
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(1.5), "1.5");
+        assert_eq!(format_value(3.0), "3");
+        assert_eq!(format_value(f64::NAN), "NaN");
+        assert_eq!(format_value(f64::NEG_INFINITY), "-Inf");
+        assert_eq!(
+            sample_pair(1_700_000_000_123_456_789, 1.0),
+            json!([1_700_000_000.123, "1"])
+        );
+    }
+}
@@ -0,0 +1,551 @@
+//! Abstract syntax tree for the supported PromQL subset.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use regex::Regex;
+
+/// A parsed PromQL expression.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    /// A numeric literal, e.g. `0.95`.
+    Number(f64),
+
+    /// A string literal. Only valid as a function argument.
+    String(String),
+
+    /// An instant vector selector, e.g. `cpu_usage{host="web1"}`.
+    VectorSelector(VectorSelector),
+
+    /// A range vector selector, e.g. `requests_total[5m]`.
+    MatrixSelector {
+        /// The underlying series selector.
+        selector: VectorSelector,
+        /// How far back from the evaluation time the range extends.
+        range: Duration,
+    },
+
+    /// A function call, e.g. `rate(requests_total[5m])`.
+    Call {
+        /// The called function.
+        func: Function,
+        /// The call arguments.
+        args: Vec<Expr>,
+    },
+
+    /// An aggregation, e.g. `sum by (host) (cpu_usage)`.
+    Aggregate {
+        /// The aggregation operator.
+        op: AggregateOp,
+        /// The aggregated expression.
+        expr: Box<Expr>,
+        /// The parameter of `topk`, `bottomk` and `quantile`.
+        param: Option<Box<Expr>>,
+        /// Which labels define the output groups.
+        grouping: Grouping,
+    },
+
+    /// A binary arithmetic expression, e.g. `a / on(host) b`.
+    Binary {
+        /// The arithmetic operator.
+        op: BinaryOp,
+        /// Left-hand operand.
+        lhs: Box<Expr>,
+        /// Right-hand operand.
+        rhs: Box<Expr>,
+        /// How samples of two vectors are matched.
+        matching: VectorMatching,
+    },
+
+    /// Unary negation, e.g. `-rate(x[1m])`.
+    Negate(Box<Expr>),
+
+    /// A parenthesized expression.
+    Paren(Box<Expr>),
+}
+
+/// The type an expression evaluates to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    /// A single floating-point number.
+    Scalar,
+    /// A set of series with one sample each.
+    Vector,
+    /// A set of series with a range of samples each.
+    Matrix,
+    /// A string literal.
+    String,
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Scalar => "scalar",
+            Self::Vector => "instant vector",
+            Self::Matrix => "range vector",
+            Self::String => "string",
+        })
+    }
+}
+
+/// Selects series by metric name and label matchers.
+#[derive(Debug, Clone)]
+pub struct VectorSelector {
+    /// The metric name, if given outside the braces.
+    pub name: Option<String>,
+    /// Label matchers (a metric name is represented as a `__name__` matcher).
+    pub matchers: Vec<Matcher>,
+    /// The `offset` modifier, shifting evaluation into the past.
+    pub offset: Duration,
+}
+
+impl VectorSelector {
+    /// Returns whether a series' labels satisfy every matcher.
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.matchers
+            .iter()
+            .all(|m| m.matches(labels.get(&m.name).map_or("", String::as_str)))
+    }
+}
+
+/// A single label matcher within a selector.
+#[derive(Debug, Clone)]
+pub struct Matcher {
+    /// Label name.
+    pub name: String,
+    /// Match operator.
+    pub op: MatchOp,
+    /// Value (or regular expression) to match against.
+    pub value: String,
+    /// Compiled, fully anchored regex for `=~` and `!~`.
+    regex: Option<Regex>,
+}
+
+impl Matcher {
+    /// Creates a matcher, compiling the regex for `=~`/`!~` operators.
+    ///
+    /// # Errors
+    ///
+    /// Returns the regex error if `value` is not a valid regular expression.
+    pub fn new(
+        name: impl Into<String>,
+        op: MatchOp,
+        value: impl Into<String>,
+    ) -> Result<Self, regex::Error> {
+        let value = value.into();
+        let regex = match op {
+            MatchOp::RegexMatch | MatchOp::RegexNoMatch => {
+                Some(Regex::new(&format!("^(?:{value})$"))?)
+            }
+            MatchOp::Equal | MatchOp::NotEqual => None,
+        };
+
+        Ok(Self {
+            name: name.into(),
+            op,
+            value,
+            regex,
+        })
+    }
+
+    /// Returns whether a label value satisfies this matcher.
+    ///
+    /// A missing label matches as the empty string, as in Prometheus.
+    pub fn matches(&self, value: &str) -> bool {
+        match self.op {
+            MatchOp::Equal => value == self.value,
+            MatchOp::NotEqual => value != self.value,
+            MatchOp::RegexMatch => self.regex.as_ref().is_some_and(|re| re.is_match(value)),
+            MatchOp::RegexNoMatch => !self.regex.as_ref().is_some_and(|re| re.is_match(value)),
+        }
+    }
+}
+
+/// Label match operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchOp {
+    /// `=`
+    Equal,
+    /// `!=`
+    NotEqual,
+    /// `=~`
+    RegexMatch,
+    /// `!~`
+    RegexNoMatch,
+}
+
+/// Supported functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    /// Per-second average rate of increase of a counter.
+    Rate,
+    /// Per-second rate from the last two samples of a counter.
+    Irate,
+    /// Total increase of a counter over the range.
+    Increase,
+    /// Difference between the first and last value of a gauge.
+    Delta,
+    /// Average of all samples in the range.
+    AvgOverTime,
+    /// Minimum of all samples in the range.
+    MinOverTime,
+    /// Maximum of all samples in the range.
+    MaxOverTime,
+    /// Sum of all samples in the range.
+    SumOverTime,
+    /// Number of samples in the range.
+    CountOverTime,
+    /// Most recent sample in the range.
+    LastOverTime,
+    /// φ-quantile from classic histogram buckets.
+    HistogramQuantile,
+    /// Absolute value.
+    Abs,
+    /// Round up to the nearest integer.
+    Ceil,
+    /// Round down to the nearest integer.
+    Floor,
+    /// Round to the nearest integer.
+    Round,
+    /// Clamp to a minimum value.
+    ClampMin,
+    /// Clamp to a maximum value.
+    ClampMax,
+    /// Convert a single-element vector to a scalar.
+    Scalar,
+    /// Convert a scalar to a vector without labels.
+    Vector,
+    /// Evaluation time in seconds since the epoch.
+    Time,
+}
+
+impl Function {
+    /// Looks up a function by name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "rate" => Self::Rate,
+            "irate" => Self::Irate,
+            "increase" => Self::Increase,
+            "delta" => Self::Delta,
+            "avg_over_time" => Self::AvgOverTime,
+            "min_over_time" => Self::MinOverTime,
+            "max_over_time" => Self::MaxOverTime,
+            "sum_over_time" => Self::SumOverTime,
+            "count_over_time" => Self::CountOverTime,
+            "last_over_time" => Self::LastOverTime,
+            "histogram_quantile" => Self::HistogramQuantile,
+            "abs" => Self::Abs,
+            "ceil" => Self::Ceil,
+            "floor" => Self::Floor,
+            "round" => Self::Round,
+            "clamp_min" => Self::ClampMin,
+            "clamp_max" => Self::ClampMax,
+            "scalar" => Self::Scalar,
+            "vector" => Self::Vector,
+            "time" => Self::Time,
+            _ => return None,
+        })
+    }
+
+    /// Returns the PromQL name of the function.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Rate => "rate",
+            Self::Irate => "irate",
+            Self::Increase => "increase",
+            Self::Delta => "delta",
+            Self::AvgOverTime => "avg_over_time",
+            Self::MinOverTime => "min_over_time",
+            Self::MaxOverTime => "max_over_time",
+            Self::SumOverTime => "sum_over_time",
+            Self::CountOverTime => "count_over_time",
+            Self::LastOverTime => "last_over_time",
+            Self::HistogramQuantile => "histogram_quantile",
+            Self::Abs => "abs",
+            Self::Ceil => "ceil",
+            Self::Floor => "floor",
+            Self::Round => "round",
+            Self::ClampMin => "clamp_min",
+            Self::ClampMax => "clamp_max",
+            Self::Scalar => "scalar",
+            Self::Vector => "vector",
+            Self::Time => "time",
+        }
+    }
+}
+
+/// Aggregation operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateOp {
+    /// Sum over dimensions.
+    Sum,
+    /// Average over dimensions.
+    Avg,
+    /// Minimum over dimensions.
+    Min,
+    /// Maximum over dimensions.
+    Max,
+    /// Number of elements.
+    Count,
+    /// Population standard deviation.
+    Stddev,
+    /// Population variance.
+    Stdvar,
+    /// Largest k elements by value.
+    Topk,
+    /// Smallest k elements by value.
+    Bottomk,
+    /// φ-quantile over dimensions.
+    Quantile,
+}
+
+impl AggregateOp {
+    /// Looks up an aggregation operator by name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sum" => Self::Sum,
+            "avg" => Self::Avg,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "count" => Self::Count,
+            "stddev" => Self::Stddev,
+            "stdvar" => Self::Stdvar,
+            "topk" => Self::Topk,
+            "bottomk" => Self::Bottomk,
+            "quantile" => Self::Quantile,
+            _ => return None,
+        })
+    }
+
+    /// Returns the PromQL name of the operator.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sum => "sum",
+            Self::Avg => "avg",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::Count => "count",
+            Self::Stddev => "stddev",
+            Self::Stdvar => "stdvar",
+            Self::Topk => "topk",
+            Self::Bottomk => "bottomk",
+            Self::Quantile => "quantile",
+        }
+    }
+
+    /// Returns whether the operator takes a parameter before the expression.
+    pub fn takes_param(self) -> bool {
+        matches!(self, Self::Topk | Self::Bottomk | Self::Quantile)
+    }
+}
+
+/// Label grouping of an aggregation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Grouping {
+    /// No clause: aggregate everything into a single group.
+    #[default]
+    None,
+    /// `by (labels)`: keep only the listed labels.
+    By(Vec<String>),
+    /// `without (labels)`: drop the listed labels.
+    Without(Vec<String>),
+}
+
+/// Binary arithmetic operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `%`
+    Mod,
+    /// `^`
+    Pow,
+}
+
+impl BinaryOp {
+    /// Applies the operator to two values.
+    pub fn apply(self, lhs: f64, rhs: f64) -> f64 {
+        match self {
+            Self::Add => lhs + rhs,
+            Self::Sub => lhs - rhs,
+            Self::Mul => lhs * rhs,
+            Self::Div => lhs / rhs,
+            Self::Mod => lhs % rhs,
+            Self::Pow => lhs.powf(rhs),
+        }
+    }
+
+    /// Returns the operator symbol.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::Pow => "^",
+        }
+    }
+
+    /// Returns the binding strength of the operator (higher binds tighter).
+    pub(crate) fn precedence(self) -> u8 {
+        match self {
+            Self::Add | Self::Sub => 1,
+            Self::Mul | Self::Div | Self::Mod => 2,
+            Self::Pow => 3,
+        }
+    }
+}
+
+/// Label matching between the two vector operands of a binary expression.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum VectorMatching {
+    /// Match on all labels except the metric name.
+    #[default]
+    All,
+    /// `on (labels)`: match only on the listed labels.
+    On(Vec<String>),
+    /// `ignoring (labels)`: match on all labels except the listed ones.
+    Ignoring(Vec<String>),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "{n}"),
+            Self::String(s) => write!(f, "{s:?}"),
+            Self::VectorSelector(selector) => write!(f, "{selector}"),
+            Self::MatrixSelector { selector, range } => {
+                // Offset goes after the range
+                let inner = VectorSelector {
+                    offset: Duration::ZERO,
+                    ..selector.clone()
+                };
+                write!(f, "{inner}[{}]", format_duration(*range))?;
+                if !selector.offset.is_zero() {
+                    write!(f, " offset {}", format_duration(selector.offset))?;
+                }
+                Ok(())
+            }
+            Self::Call { func, args } => {
+                write!(f, "{}(", func.name())?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                write!(f, ")")
+            }
+            Self::Aggregate {
+                op,
+                expr,
+                param,
+                grouping,
+            } => {
+                write!(f, "{}", op.name())?;
+                match grouping {
+                    Grouping::None => {}
+                    Grouping::By(labels) => write!(f, " by ({})", labels.join(", "))?,
+                    Grouping::Without(labels) => write!(f, " without ({})", labels.join(", "))?,
+                }
+                match param {
+                    Some(param) => write!(f, " ({param}, {expr})"),
+                    None => write!(f, " ({expr})"),
+                }
+            }
+            Self::Binary {
+                op,
+                lhs,
+                rhs,
+                matching,
+            } => {
+                write!(f, "{lhs} {}", op.symbol())?;
+                match matching {
+                    VectorMatching::All => {}
+                    VectorMatching::On(labels) => write!(f, " on ({})", labels.join(", "))?,
+                    VectorMatching::Ignoring(labels) => {
+                        write!(f, " ignoring ({})", labels.join(", "))?;
+                    }
+                }
+                write!(f, " {rhs}")
+            }
+            Self::Negate(expr) => write!(f, "-{expr}"),
+            Self::Paren(expr) => write!(f, "({expr})"),
+        }
+    }
+}
+
+impl fmt::Display for VectorSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write!(f, "{name}")?;
+        }
+
+        let matchers: Vec<_> = self
+            .matchers
+            .iter()
+            .filter(|m| !(self.name.is_some() && m.name == "__name__" && m.op == MatchOp::Equal))
+            .collect();
+        if !matchers.is_empty() || self.name.is_none() {
+            write!(f, "{{")?;
+            for (i, matcher) in matchers.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{matcher}")?;
+            }
+            write!(f, "}}")?;
+        }
+
+        if !self.offset.is_zero() {
+            write!(f, " offset {}", format_duration(self.offset))?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.op {
+            MatchOp::Equal => "=",
+            MatchOp::NotEqual => "!=",
+            MatchOp::RegexMatch => "=~",
+            MatchOp::RegexNoMatch => "!~",
+        };
+        write!(f, "{}{op}{:?}", self.name, self.value)
+    }
+}
+
+/// Formats a duration in PromQL notation, e.g. `1h30m` or `500ms`.
+pub fn format_duration(duration: Duration) -> String {
+    const UNITS: [(&str, u128); 6] = [
+        ("w", 7 * 24 * 3600 * 1000),
+        ("d", 24 * 3600 * 1000),
+        ("h", 3600 * 1000),
+        ("m", 60 * 1000),
+        ("s", 1000),
+        ("ms", 1),
+    ];
+
+    let mut millis = duration.as_millis();
+    if millis == 0 {
+        return "0s".to_string();
+    }
+
+    let mut out = String::new();
+    for (unit, size) in UNITS {
+        if millis >= size {
+            out.push_str(&format!("{}{unit}", millis / size));
+            millis %= size;
+        }
+    }
+    out
+}
@@ -0,0 +1,1170 @@
+//! PromQL evaluation against a [`Store`].
+//!
+//! Evaluation happens in two phases. First, every selector in the expression
+//! is resolved to the matching series and their samples are fetched once for
+//! the whole query window (plus the selector's lookback or range). Then the
+//! expression is evaluated independently at each step timestamp over the
+//! prefetched data.
+//!
+//! Selector data is read with stitched [`Store::query_auto_with`] queries,
+//! so recent samples come from the finest tier and older ones from coarser
+//! tiers. For range queries the step is passed as the query resolution, so a
+//! one-hour step over a week never reads the 1s tier.
+
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use super::ast::{AggregateOp, BinaryOp, Expr, Function, Grouping, VectorMatching, VectorSelector};
+use super::parser::parse;
+use crate::error::{PromqlError, Result};
+use crate::query::QueryOptions;
+use crate::series::SeriesHandle;
+use crate::store::Store;
+
+/// Default lookback for instant vector selectors, matching Prometheus.
+pub const DEFAULT_LOOKBACK_DELTA: Duration = Duration::from_secs(5 * 60);
+
+/// Maximum number of steps a range query may evaluate, matching Prometheus.
+pub const MAX_RANGE_STEPS: u64 = 11_000;
+
+/// The label holding a series' metric name.
+pub const METRIC_NAME_LABEL: &str = "__name__";
+
+/// A series' label set, including the metric name as `__name__`.
+pub type Labels = BTreeMap<String, String>;
+
+/// The result of evaluating a PromQL expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A single number at the evaluation time.
+    Scalar {
+        /// The evaluation timestamp in nanoseconds.
+        timestamp_ns: u64,
+        /// The scalar value.
+        value: f64,
+    },
+
+    /// One sample per series (instant queries).
+    Vector(Vec<Sample>),
+
+    /// A range of samples per series (range queries and range selectors).
+    Matrix(Vec<Series>),
+}
+
+/// A single sample of an instant vector.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+    /// The series labels.
+    pub labels: Labels,
+    /// The sample timestamp in nanoseconds.
+    pub timestamp_ns: u64,
+    /// The sample value.
+    pub value: f64,
+}
+
+/// A series of samples in a matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    /// The series labels.
+    pub labels: Labels,
+    /// `(timestamp_ns, value)` pairs in chronological order.
+    pub points: Vec<(u64, f64)>,
+}
+
+/// Evaluates PromQL expressions against a store.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use rondo::promql::{Engine, Value};
+/// use rondo::store::Store;
+/// use std::time::Duration;
+///
+/// # let store = Store::open("./data", vec![])?;
+/// # let now_ns = 1_640_000_000_000_000_000u64;
+/// let engine = Engine::new(&store);
+/// let hour_ago = now_ns - 3600 * 1_000_000_000;
+/// let result = engine.range_query(
+///     "sum by (host) (rate(requests_total[1m]))",
+///     hour_ago,
+///     now_ns,
+///     Duration::from_secs(60),
+/// )?;
+///
+/// if let Value::Matrix(series) = result {
+///     for s in series {
+///         println!("{:?}: {} points", s.labels, s.points.len());
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Engine<'a> {
+    store: &'a Store,
+    lookback_delta: Duration,
+}
+
+impl<'a> Engine<'a> {
+    /// Creates an engine over a store with the default 5 minute lookback.
+    pub fn new(store: &'a Store) -> Self {
+        Self {
+            store,
+            lookback_delta: DEFAULT_LOOKBACK_DELTA,
+        }
+    }
+
+    /// Sets how far back an instant vector selector looks for a sample.
+    #[must_use]
+    pub fn with_lookback_delta(mut self, lookback_delta: Duration) -> Self {
+        self.lookback_delta = lookback_delta;
+        self
+    }
+
+    /// Evaluates an expression at a single point in time.
+    ///
+    /// Returns a [`Value::Scalar`] or [`Value::Vector`], or a
+    /// [`Value::Matrix`] of raw samples if the expression is a range
+    /// selector such as `cpu_usage[5m]`.
+    ///
+    /// # Errors
+    ///
+    /// - [`PromqlError::Parse`] / [`PromqlError::Type`] for invalid expressions
+    /// - [`PromqlError::Evaluation`] if the expression cannot be evaluated
+    /// - Store query errors while fetching selector data
+    pub fn instant_query(&self, query: &str, time_ns: u64) -> Result<Value> {
+        let expr = parse(query)?;
+        let eval = self.prepare(&expr, time_ns, time_ns, None)?;
+
+        let value = match eval.eval(&expr, time_ns)? {
+            StepValue::Scalar(value) => Value::Scalar {
+                timestamp_ns: time_ns,
+                value,
+            },
+            StepValue::Vector(samples) => {
+                check_unique(&samples)?;
+                let mut samples: Vec<Sample> = samples
+                    .into_iter()
+                    .map(|(labels, value)| Sample {
+                        labels,
+                        timestamp_ns: time_ns,
+                        value,
+                    })
+                    .collect();
+                samples.sort_by(|a, b| a.labels.cmp(&b.labels));
+                Value::Vector(samples)
+            }
+            StepValue::Matrix(series) => Value::Matrix(series),
+            StepValue::String => {
+                return Err(evaluation_error("string expressions cannot be queried").into());
+            }
+        };
+        Ok(value)
+    }
+
+    /// Evaluates an expression at every `step` between `start_ns` and
+    /// `end_ns` (both inclusive).
+    ///
+    /// Always returns a [`Value::Matrix`] with series sorted by labels; a
+    /// scalar expression yields a single series without labels.
+    ///
+    /// # Errors
+    ///
+    /// - [`PromqlError::Parse`] / [`PromqlError::Type`] for invalid expressions
+    /// - [`PromqlError::Evaluation`] for an invalid step or time range, for
+    ///   too many steps, or for expressions that do not yield a scalar or
+    ///   instant vector
+    /// - Store query errors while fetching selector data
+    pub fn range_query(
+        &self,
+        query: &str,
+        start_ns: u64,
+        end_ns: u64,
+        step: Duration,
+    ) -> Result<Value> {
+        let step_ns = duration_ns(step);
+        if step_ns == 0 {
+            return Err(evaluation_error("step must be greater than zero").into());
+        }
+        if start_ns > end_ns {
+            return Err(evaluation_error("range end must not be before its start").into());
+        }
+        if (end_ns - start_ns) / step_ns >= MAX_RANGE_STEPS {
+            return Err(evaluation_error(format!(
+                "range query exceeds maximum of {MAX_RANGE_STEPS} steps; increase the step"
+            ))
+            .into());
+        }
+
+        let expr = parse(query)?;
+        let eval = self.prepare(&expr, start_ns, end_ns, Some(step))?;
+
+        let mut series: BTreeMap<Labels, Vec<(u64, f64)>> = BTreeMap::new();
+        let mut t = start_ns;
+        loop {
+            match eval.eval(&expr, t)? {
+                StepValue::Scalar(value) => {
+                    series.entry(Labels::new()).or_default().push((t, value))
+                }
+                StepValue::Vector(samples) => {
+                    check_unique(&samples)?;
+                    for (labels, value) in samples {
+                        series.entry(labels).or_default().push((t, value));
+                    }
+                }
+                StepValue::Matrix(_) | StepValue::String => {
+                    return Err(evaluation_error(
+                        "range queries require an expression returning a scalar or instant vector",
+                    )
+                    .into());
+                }
+            }
+
+            match t.checked_add(step_ns) {
+                Some(next) if next <= end_ns => t = next,
+                _ => break,
+            }
+        }
+
+        Ok(Value::Matrix(
+            series
+                .into_iter()
+                .map(|(labels, points)| Series { labels, points })
+                .collect(),
+        ))
+    }
+
+    /// Resolves and fetches the data for every selector in the expression.
+    fn prepare(
+        &self,
+        expr: &Expr,
+        start_ns: u64,
+        end_ns: u64,
+        step: Option<Duration>,
+    ) -> Result<Evaluation> {
+        let lookback_ns = duration_ns(self.lookback_delta).max(1);
+
+        let mut windows: HashMap<String, (VectorSelector, u64)> = HashMap::new();
+        collect_selectors(expr, lookback_ns, &mut windows);
+
+        let mut data = HashMap::with_capacity(windows.len());
+        for (key, (selector, window_ns)) in windows {
+            let series = self.fetch(&selector, window_ns, start_ns, end_ns, step)?;
+            data.insert(key, series);
+        }
+
+        Ok(Evaluation { data, lookback_ns })
+    }
+
+    /// Reads every series matching `selector` over the query window.
+    fn fetch(
+        &self,
+        selector: &VectorSelector,
+        window_ns: u64,
+        start_ns: u64,
+        end_ns: u64,
+        step: Option<Duration>,
+    ) -> Result<Vec<SelectedSeries>> {
+        let offset_ns = duration_ns(selector.offset);
+        let fetch_start = start_ns.saturating_sub(offset_ns).saturating_sub(window_ns);
+        let fetch_end = end_ns.saturating_sub(offset_ns).saturating_add(1);
+
+        // Never pick a tier so coarse that the selector's window holds
+        // fewer than two of its points.
+        let window = Duration::from_nanos(window_ns / 2);
+        let mut options = QueryOptions::new().with_stitching(true);
+        if let Some(step) = step {
+            options = options.with_resolution(step.min(window));
+        }
+
+        let mut selected = Vec::new();
+        for (handle, labels) in select_series(self.store, selector) {
+            let points = self
+                .store
+                .query_auto_with(handle, fetch_start, fetch_end, &options)?
+                .collect_all();
+            if !points.is_empty() {
+                selected.push(SelectedSeries { labels, points });
+            }
+        }
+
+        Ok(selected)
+    }
+}
+
+/// Returns every registered series matching a selector, with its labels.
+///
+/// Only the series registry is consulted; series without data are included.
+pub fn select_series(store: &Store, selector: &VectorSelector) -> Vec<(SeriesHandle, Labels)> {
+    store
+        .handles()
+        .into_iter()
+        .filter_map(|handle| {
+            let (name, labels) = store.series_info(&handle)?;
+            let labels = series_labels(name, labels);
+            selector.matches(&labels).then_some((handle, labels))
+        })
+        .collect()
+}
+
+/// Builds a PromQL label set from a rondo series name and labels.
+pub fn series_labels(name: &str, labels: &[(String, String)]) -> Labels {
+    let mut out: Labels = labels.iter().cloned().collect();
+    out.insert(METRIC_NAME_LABEL.to_string(), name.to_string());
+    out
+}
+
+/// A series matched by a selector, with its prefetched samples.
+struct SelectedSeries {
+    labels: Labels,
+    points: Vec<(u64, f64)>,
+}
+
+/// The value of a subexpression at one evaluation step.
+enum StepValue {
+    Scalar(f64),
+    Vector(Vec<(Labels, f64)>),
+    Matrix(Vec<Series>),
+    /// String literals only appear as arguments and carry no numeric value.
+    String,
+}
+
+/// Prefetched selector data for one query.
+struct Evaluation {
+    data: HashMap<String, Vec<SelectedSeries>>,
+    lookback_ns: u64,
+}
+
+impl Evaluation {
+    fn eval(&self, expr: &Expr, t: u64) -> std::result::Result<StepValue, PromqlError> {
+        Ok(match expr {
+            Expr::Number(n) => StepValue::Scalar(*n),
+            Expr::String(_) => StepValue::String,
+            Expr::Paren(inner) => self.eval(inner, t)?,
+            Expr::VectorSelector(selector) => StepValue::Vector(self.instant(selector, t)),
+            Expr::MatrixSelector { selector, range } => StepValue::Matrix(
+                self.range(selector, *range, t)
+                    .map(|(labels, points)| Series {
+                        labels: labels.clone(),
+                        points: points.to_vec(),
+                    })
+                    .collect(),
+            ),
+            Expr::Negate(inner) => match self.eval(inner, t)? {
+                StepValue::Scalar(v) => StepValue::Scalar(-v),
+                StepValue::Vector(samples) => StepValue::Vector(
+                    samples
+                        .into_iter()
+                        .map(|(labels, v)| (drop_name(labels), -v))
+                        .collect(),
+                ),
+                _ => return Err(evaluation_error("unary '-' expects a scalar or vector")),
+            },
+            Expr::Binary {
+                op,
+                lhs,
+                rhs,
+                matching,
+            } => {
+                let lhs = self.eval(lhs, t)?;
+                let rhs = self.eval(rhs, t)?;
+                eval_binary(*op, lhs, rhs, matching)?
+            }
+            Expr::Aggregate {
+                op,
+                expr,
+                param,
+                grouping,
+            } => {
+                let param = match param {
+                    Some(param) => Some(self.scalar(param, t)?),
+                    None => None,
+                };
+                let samples = self.vector(expr, t)?;
+                StepValue::Vector(aggregate(*op, samples, param, grouping))
+            }
+            Expr::Call { func, args } => self.eval_call(*func, args, t)?,
+        })
+    }
+
+    fn scalar(&self, expr: &Expr, t: u64) -> std::result::Result<f64, PromqlError> {
+        match self.eval(expr, t)? {
+            StepValue::Scalar(v) => Ok(v),
+            _ => Err(evaluation_error("expected scalar")),
+        }
+    }
+
+    fn vector(&self, expr: &Expr, t: u64) -> std::result::Result<Vec<(Labels, f64)>, PromqlError> {
+        match self.eval(expr, t)? {
+            StepValue::Vector(samples) => Ok(samples),
+            _ => Err(evaluation_error("expected instant vector")),
+        }
+    }
+
+    /// Returns the prefetched series for a selector.
+    fn selected(&self, selector: &VectorSelector) -> &[SelectedSeries] {
+        self.data
+            .get(&selector.to_string())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Evaluates an instant vector selector: the newest sample of each
+    /// series within the lookback window.
+    fn instant(&self, selector: &VectorSelector, t: u64) -> Vec<(Labels, f64)> {
+        let end = t.saturating_sub(duration_ns(selector.offset));
+        let start = end.saturating_sub(self.lookback_ns);
+
+        self.selected(selector)
+            .iter()
+            .filter_map(|series| {
+                let window = window(&series.points, start, end);
+                window.last().map(|&(_, v)| (series.labels.clone(), v))
+            })
+            .collect()
+    }
+
+    /// Evaluates a range selector: each series' samples in `(t - range, t]`.
+    fn range<'s>(
+        &'s self,
+        selector: &VectorSelector,
+        range: Duration,
+        t: u64,
+    ) -> impl Iterator<Item = (&'s Labels, &'s [(u64, f64)])> {
+        let end = t.saturating_sub(duration_ns(selector.offset));
+        let start = end.saturating_sub(duration_ns(range));
+
+        self.selected(selector).iter().filter_map(move |series| {
+            let points = window(&series.points, start, end);
+            (!points.is_empty()).then_some((&series.labels, points))
+        })
+    }
+
+    fn eval_call(
+        &self,
+        func: Function,
+        args: &[Expr],
+        t: u64,
+    ) -> std::result::Result<StepValue, PromqlError> {
+        let value = match func {
+            Function::Time => StepValue::Scalar(ns_to_secs(t)),
+            Function::Vector => StepValue::Vector(vec![(Labels::new(), self.scalar(&args[0], t)?)]),
+            Function::Scalar => {
+                let samples = self.vector(&args[0], t)?;
+                StepValue::Scalar(match samples.as_slice() {
+                    [(_, v)] => *v,
+                    _ => f64::NAN,
+                })
+            }
+            Function::Abs | Function::Ceil | Function::Floor | Function::Round => {
+                let apply: fn(f64) -> f64 = match func {
+                    Function::Abs => f64::abs,
+                    Function::Ceil => f64::ceil,
+                    Function::Floor => f64::floor,
+                    _ => round_half_up,
+                };
+                map_vector(self.vector(&args[0], t)?, apply)
+            }
+            Function::ClampMin | Function::ClampMax => {
+                let samples = self.vector(&args[0], t)?;
+                let bound = self.scalar(&args[1], t)?;
+                if func == Function::ClampMin {
+                    map_vector(samples, |v| v.max(bound))
+                } else {
+                    map_vector(samples, |v| v.min(bound))
+                }
+            }
+            Function::HistogramQuantile => {
+                let phi = self.scalar(&args[0], t)?;
+                StepValue::Vector(histogram_quantile(phi, self.vector(&args[1], t)?))
+            }
+            _ => {
+                let Expr::MatrixSelector { selector, range } = unwrap_parens(&args[0]) else {
+                    return Err(evaluation_error(format!(
+                        "{}() expects a range vector selector",
+                        func.name()
+                    )));
+                };
+                let range_ns = duration_ns(*range);
+                let end = t.saturating_sub(duration_ns(selector.offset));
+                let start = end.saturating_sub(range_ns);
+
+                StepValue::Vector(
+                    self.range(selector, *range, t)
+                        .filter_map(|(labels, points)| {
+                            let value = over_time(func, points, start, end, range_ns)?;
+                            Some((drop_name(labels.clone()), value))
+                        })
+                        .collect(),
+                )
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// Records the widest window each distinct selector needs.
+fn collect_selectors(
+    expr: &Expr,
+    lookback_ns: u64,
+    out: &mut HashMap<String, (VectorSelector, u64)>,
+) {
+    let mut record = |selector: &VectorSelector, window_ns: u64| {
+        let entry = out
+            .entry(selector.to_string())
+            .or_insert_with(|| (selector.clone(), window_ns));
+        entry.1 = entry.1.max(window_ns);
+    };
+
+    match expr {
+        Expr::VectorSelector(selector) => record(selector, lookback_ns),
+        Expr::MatrixSelector { selector, range } => record(selector, duration_ns(*range)),
+        Expr::Number(_) | Expr::String(_) => {}
+        Expr::Paren(inner) | Expr::Negate(inner) => collect_selectors(inner, lookback_ns, out),
+        Expr::Binary { lhs, rhs, .. } => {
+            collect_selectors(lhs, lookback_ns, out);
+            collect_selectors(rhs, lookback_ns, out);
+        }
+        Expr::Aggregate { expr, param, .. } => {
+            collect_selectors(expr, lookback_ns, out);
+            if let Some(param) = param {
+                collect_selectors(param, lookback_ns, out);
+            }
+        }
+        Expr::Call { args, .. } => {
+            for arg in args {
+                collect_selectors(arg, lookback_ns, out);
+            }
+        }
+    }
+}
+
+fn unwrap_parens(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(inner) => unwrap_parens(inner),
+        other => other,
+    }
+}
+
+/// Returns the points with timestamps in `(start, end]`.
+fn window(points: &[(u64, f64)], start: u64, end: u64) -> &[(u64, f64)] {
+    let lo = points.partition_point(|&(ts, _)| ts <= start);
+    let hi = points.partition_point(|&(ts, _)| ts <= end);
+    &points[lo..hi.max(lo)]
+}
+
+/// Evaluates a range-vector function over one series' window.
+fn over_time(
+    func: Function,
+    points: &[(u64, f64)],
+    range_start: u64,
+    range_end: u64,
+    range_ns: u64,
+) -> Option<f64> {
+    let values = points.iter().map(|&(_, v)| v);
+    #[allow(clippy::cast_precision_loss)] // Sample counts are far below 2^52
+    let count = points.len() as f64;
+
+    match func {
+        Function::Rate => extrapolated_rate(points, range_start, range_end, range_ns, true, true),
+        Function::Increase => {
+            extrapolated_rate(points, range_start, range_end, range_ns, true, false)
+        }
+        Function::Delta => {
+            extrapolated_rate(points, range_start, range_end, range_ns, false, false)
+        }
+        Function::Irate => {
+            let [.., (prev_ts, prev), (last_ts, last)] = points else {
+                return None;
+            };
+            let increase = if last < prev { *last } else { last - prev };
+            Some(increase / ns_to_secs(last_ts - prev_ts))
+        }
+        Function::AvgOverTime => Some(values.sum::<f64>() / count),
+        Function::MinOverTime => values.reduce(f64::min),
+        Function::MaxOverTime => values.reduce(f64::max),
+        Function::SumOverTime => Some(values.sum()),
+        Function::CountOverTime => Some(count),
+        Function::LastOverTime => points.last().map(|&(_, v)| v),
+        _ => None,
+    }
+}
+
+/// Computes `rate`, `increase` or `delta` the way Prometheus does: the
+/// change across the window, corrected for counter resets and extrapolated
+/// towards the window boundaries.
+fn extrapolated_rate(
+    points: &[(u64, f64)],
+    range_start: u64,
+    range_end: u64,
+    range_ns: u64,
+    is_counter: bool,
+    is_rate: bool,
+) -> Option<f64> {
+    let (&(first_ts, first), &(last_ts, last)) = (points.first()?, points.last()?);
+    if points.len() < 2 {
+        return None;
+    }
+
+    let mut result = last - first;
+    if is_counter {
+        for pair in points.windows(2) {
+            if pair[1].1 < pair[0].1 {
+                result += pair[0].1;
+            }
+        }
+    }
+
+    let sampled = ns_to_secs(last_ts - first_ts);
+    #[allow(clippy::cast_precision_loss)] // Sample counts are far below 2^52
+    let average_interval = sampled / (points.len() - 1) as f64;
+    let mut to_start = ns_to_secs(first_ts.saturating_sub(range_start));
+    let to_end = ns_to_secs(range_end.saturating_sub(last_ts));
+
+    // Counters cannot go below zero, so don't extrapolate past that point
+    if is_counter && result > 0.0 && first >= 0.0 {
+        let to_zero = sampled * (first / result);
+        to_start = to_start.min(to_zero);
+    }
+
+    let threshold = average_interval * 1.1;
+    let mut extrapolated = sampled;
+    extrapolated += if to_start < threshold {
+        to_start
+    } else {
+        average_interval / 2.0
+    };
+    extrapolated += if to_end < threshold {
+        to_end
+    } else {
+        average_interval / 2.0
+    };
+
+    let mut factor = extrapolated / sampled;
+    if is_rate {
+        factor /= ns_to_secs(range_ns);
+    }
+    Some(result * factor)
+}
+
+/// Computes a quantile from classic histogram buckets (`le` label), with
+/// linear interpolation inside the bucket containing the rank.
+fn histogram_quantile(phi: f64, samples: Vec<(Labels, f64)>) -> Vec<(Labels, f64)> {
+    let mut groups: BTreeMap<Labels, Vec<(f64, f64)>> = BTreeMap::new();
+    for (mut labels, count) in samples {
+        let Some(le) = labels.remove("le") else {
+            continue;
+        };
+        let Ok(upper) = parse_le(&le) else {
+            continue;
+        };
+        groups
+            .entry(drop_name(labels))
+            .or_default()
+            .push((upper, count));
+    }
+
+    groups
+        .into_iter()
+        .map(|(labels, buckets)| (labels, bucket_quantile(phi, buckets)))
+        .collect()
+}
+
+fn parse_le(le: &str) -> std::result::Result<f64, std::num::ParseFloatError> {
+    match le {
+        "+Inf" | "Inf" | "inf" => Ok(f64::INFINITY),
+        other => other.parse(),
+    }
+}
+
+fn bucket_quantile(phi: f64, mut buckets: Vec<(f64, f64)>) -> f64 {
+    if phi.is_nan() {
+        return f64::NAN;
+    }
+    if phi < 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if phi > 1.0 {
+        return f64::INFINITY;
+    }
+
+    buckets.sort_by(|a, b| a.0.total_cmp(&b.0));
+    if buckets.len() < 2 || buckets.last().is_none_or(|b| b.0 != f64::INFINITY) {
+        return f64::NAN;
+    }
+
+    // Bucket counts are cumulative; repair non-monotonic input from
+    // counters read at slightly different times.
+    let mut running = 0.0_f64;
+    for bucket in &mut buckets {
+        running = running.max(bucket.1);
+        bucket.1 = running;
+    }
+
+    let observations = buckets[buckets.len() - 1].1;
+    if observations == 0.0 {
+        return f64::NAN;
+    }
+
+    let mut rank = phi * observations;
+    let b = buckets
+        .iter()
+        .position(|&(_, count)| count >= rank)
+        .unwrap_or(buckets.len() - 1);
+
+    if b == buckets.len() - 1 {
+        return buckets[buckets.len() - 2].0;
+    }
+    if b == 0 && buckets[0].0 <= 0.0 {
+        return buckets[0].0;
+    }
+
+    let (bucket_end, mut count) = buckets[b];
+    let mut bucket_start = 0.0;
+    if b > 0 {
+        bucket_start = buckets[b - 1].0;
+        count -= buckets[b - 1].1;
+        rank -= buckets[b - 1].1;
+    }
+    bucket_start + (bucket_end - bucket_start) * (rank / count)
+}
+
+/// Applies an aggregation operator to a vector.
+fn aggregate(
+    op: AggregateOp,
+    samples: Vec<(Labels, f64)>,
+    param: Option<f64>,
+    grouping: &Grouping,
+) -> Vec<(Labels, f64)> {
+    let mut groups: BTreeMap<Labels, Vec<(Labels, f64)>> = BTreeMap::new();
+    for (labels, value) in samples {
+        groups
+            .entry(group_labels(&labels, grouping))
+            .or_default()
+            .push((labels, value));
+    }
+
+    let param = param.unwrap_or(f64::NAN);
+    let mut out = Vec::new();
+    for (group, members) in groups {
+        let values = || members.iter().map(|(_, v)| *v);
+        #[allow(clippy::cast_precision_loss)] // Group sizes are far below 2^52
+        let count = members.len() as f64;
+
+        let value = match op {
+            AggregateOp::Sum => values().sum(),
+            AggregateOp::Avg => values().sum::<f64>() / count,
+            AggregateOp::Min => {
+                values().fold(
+                    f64::NAN,
+                    |acc, v| if acc.is_nan() || v < acc { v } else { acc },
+                )
+            }
+            AggregateOp::Max => {
+                values().fold(
+                    f64::NAN,
+                    |acc, v| if acc.is_nan() || v > acc { v } else { acc },
+                )
+            }
+            AggregateOp::Count => count,
+            AggregateOp::Stdvar | AggregateOp::Stddev => {
+                let mean = values().sum::<f64>() / count;
+                let variance = values().map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+                if op == AggregateOp::Stddev {
+                    variance.sqrt()
+                } else {
+                    variance
+                }
+            }
+            AggregateOp::Quantile => quantile(param, values().collect()),
+            AggregateOp::Topk | AggregateOp::Bottomk => {
+                out.extend(select_k(op, param, members));
+                continue;
+            }
+        };
+        out.push((group, value));
+    }
+    out
+}
+
+/// Returns the labels that identify a sample's aggregation group.
+fn group_labels(labels: &Labels, grouping: &Grouping) -> Labels {
+    match grouping {
+        Grouping::None => Labels::new(),
+        Grouping::By(keep) => labels
+            .iter()
+            .filter(|(name, _)| keep.contains(name))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+        Grouping::Without(drop) => labels
+            .iter()
+            .filter(|(name, _)| *name != METRIC_NAME_LABEL && !drop.contains(name))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+    }
+}
+
+/// Keeps the `k` largest (`topk`) or smallest (`bottomk`) samples.
+fn select_k(op: AggregateOp, k: f64, mut members: Vec<(Labels, f64)>) -> Vec<(Labels, f64)> {
+    if k.is_nan() || k < 1.0 {
+        return Vec::new();
+    }
+
+    // NaN sorts last for both directions
+    members.sort_by(|a, b| match (a.1.is_nan(), b.1.is_nan()) {
+        (true, true) => std::cmp::Ordering::Equal,
+        (true, false) => std::cmp::Ordering::Greater,
+        (false, true) => std::cmp::Ordering::Less,
+        (false, false) if op == AggregateOp::Topk => b.1.total_cmp(&a.1),
+        (false, false) => a.1.total_cmp(&b.1),
+    });
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // k >= 1, saturating
+    let k = k as usize;
+    members.truncate(k);
+    members
+}
+
+/// Computes the φ-quantile of a set of values with linear interpolation.
+fn quantile(phi: f64, mut values: Vec<f64>) -> f64 {
+    if values.is_empty() || phi.is_nan() {
+        return f64::NAN;
+    }
+    if phi < 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if phi > 1.0 {
+        return f64::INFINITY;
+    }
+
+    values.sort_by(f64::total_cmp);
+    #[allow(clippy::cast_precision_loss)] // Group sizes are far below 2^52
+    let rank = phi * (values.len() - 1) as f64;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // 0 <= rank < len
+    let lower = rank.floor() as usize;
+    let upper = (lower + 1).min(values.len() - 1);
+    let weight = rank - rank.floor();
+    values[lower] * (1.0 - weight) + values[upper] * weight
+}
+
+/// Applies a binary arithmetic operator.
+fn eval_binary(
+    op: BinaryOp,
+    lhs: StepValue,
+    rhs: StepValue,
+    matching: &VectorMatching,
+) -> std::result::Result<StepValue, PromqlError> {
+    Ok(match (lhs, rhs) {
+        (StepValue::Scalar(l), StepValue::Scalar(r)) => StepValue::Scalar(op.apply(l, r)),
+        (StepValue::Vector(samples), StepValue::Scalar(r)) => {
+            map_vector(samples, |l| op.apply(l, r))
+        }
+        (StepValue::Scalar(l), StepValue::Vector(samples)) => {
+            map_vector(samples, |r| op.apply(l, r))
+        }
+        (StepValue::Vector(lhs), StepValue::Vector(rhs)) => {
+            StepValue::Vector(match_vectors(op, lhs, rhs, matching)?)
+        }
+        _ => {
+            return Err(evaluation_error(format!(
+                "operator '{}' expects scalars or instant vectors",
+                op.symbol()
+            )));
+        }
+    })
+}
+
+/// Applies an operator to one-to-one matched samples of two vectors.
+fn match_vectors(
+    op: BinaryOp,
+    lhs: Vec<(Labels, f64)>,
+    rhs: Vec<(Labels, f64)>,
+    matching: &VectorMatching,
+) -> std::result::Result<Vec<(Labels, f64)>, PromqlError> {
+    let mut right: HashMap<Labels, f64> = HashMap::with_capacity(rhs.len());
+    for (labels, value) in rhs {
+        if right
+            .insert(matching_labels(&labels, matching), value)
+            .is_some()
+        {
+            return Err(many_to_many("right"));
+        }
+    }
+
+    let mut seen = HashMap::with_capacity(lhs.len());
+    let mut out = Vec::new();
+    for (labels, l) in lhs {
+        let signature = matching_labels(&labels, matching);
+        let Some(&r) = right.get(&signature) else {
+            continue;
+        };
+        if seen.insert(signature.clone(), ()).is_some() {
+            return Err(many_to_many("left"));
+        }
+
+        let result_labels = match matching {
+            VectorMatching::On(_) => signature,
+            VectorMatching::All | VectorMatching::Ignoring(_) => {
+                let mut labels = drop_name(labels);
+                if let VectorMatching::Ignoring(ignored) = matching {
+                    labels.retain(|name, _| !ignored.contains(name));
+                }
+                labels
+            }
+        };
+        out.push((result_labels, op.apply(l, r)));
+    }
+    Ok(out)
+}
+
+/// Returns the labels two samples must agree on to be matched.
+fn matching_labels(labels: &Labels, matching: &VectorMatching) -> Labels {
+    labels
+        .iter()
+        .filter(|(name, _)| match matching {
+            VectorMatching::All => *name != METRIC_NAME_LABEL,
+            VectorMatching::On(on) => on.contains(name),
+            VectorMatching::Ignoring(ignored) => {
+                *name != METRIC_NAME_LABEL && !ignored.contains(name)
+            }
+        })
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect()
+}
+
+fn many_to_many(side: &str) -> PromqlError {
+    evaluation_error(format!(
+        "many-to-many matching not allowed: found duplicate series on the {side} side"
+    ))
+}
+
+fn map_vector(samples: Vec<(Labels, f64)>, f: impl Fn(f64) -> f64) -> StepValue {
+    StepValue::Vector(
+        samples
+            .into_iter()
+            .map(|(labels, v)| (drop_name(labels), f(v)))
+            .collect(),
+    )
+}
+
+fn drop_name(mut labels: Labels) -> Labels {
+    labels.remove(METRIC_NAME_LABEL);
+    labels
+}
+
+/// Rejects vectors with two samples for the same label set, which happens
+/// when an operation drops the only label that told two series apart.
+fn check_unique(samples: &[(Labels, f64)]) -> std::result::Result<(), PromqlError> {
+    let mut seen = std::collections::HashSet::with_capacity(samples.len());
+    for (labels, _) in samples {
+        if !seen.insert(labels) {
+            return Err(evaluation_error(format!(
+                "vector cannot contain metrics with the same labelset {labels:?}"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn round_half_up(v: f64) -> f64 {
+    (v + 0.5).floor()
+}
+
+fn duration_ns(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[allow(clippy::cast_precision_loss)] // Sub-nanosecond precision loss is acceptable
+fn ns_to_secs(ns: u64) -> f64 {
+    ns as f64 / 1e9
+}
+
+fn evaluation_error(message: impl Into<String>) -> PromqlError {
+    PromqlError::Evaluation {
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const S: u64 = 1_000_000_000;
+
+    fn labels(pairs: &[(&str, &str)]) -> Labels {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_window_is_left_open() {
+        let points = [(10, 1.0), (20, 2.0), (30, 3.0)];
+        assert_eq!(window(&points, 10, 30), &[(20, 2.0), (30, 3.0)]);
+        assert_eq!(window(&points, 0, 5), &[]);
+        assert_eq!(window(&points, 30, 40), &[]);
+    }
+
+    #[test]
+    fn test_rate_of_steady_counter() {
+        // +10 per 10s, samples covering the whole 60s window
+        let points: Vec<_> = (1..=6u32)
+            .map(|i| (u64::from(i) * 10 * S, f64::from(i) * 10.0))
+            .collect();
+        let rate = extrapolated_rate(&points, 0, 60 * S, 60 * S, true, true).unwrap();
+        assert!((rate - 1.0).abs() < 1e-9, "rate = {rate}");
+
+        let increase = extrapolated_rate(&points, 0, 60 * S, 60 * S, true, false).unwrap();
+        assert!((increase - 60.0).abs() < 1e-9, "increase = {increase}");
+    }
+
+    #[test]
+    fn test_rate_handles_counter_reset() {
+        let points = [
+            (10 * S, 90.0),
+            (20 * S, 100.0),
+            (30 * S, 5.0),
+            (40 * S, 15.0),
+        ];
+        // Raw increase: 10 + 5 (after reset from 100) + 10 = 25 over 30s
+        let increase = extrapolated_rate(&points, 10 * S - 1, 40 * S, 30 * S, true, false).unwrap();
+        assert!(increase > 25.0 && increase < 26.0, "increase = {increase}");
+        assert!(extrapolated_rate(&points[..1], 0, 40 * S, 40 * S, true, true).is_none());
+    }
+
+    #[test]
+    fn test_bucket_quantile() {
+        let buckets = vec![
+            (0.1, 50.0),
+            (0.5, 90.0),
+            (1.0, 100.0),
+            (f64::INFINITY, 100.0),
+        ];
+        assert!((bucket_quantile(0.5, buckets.clone()) - 0.1).abs() < 1e-9);
+        // rank 70 lies halfway through the (0.1, 0.5] bucket
+        assert!((bucket_quantile(0.7, buckets.clone()) - 0.3).abs() < 1e-9);
+        assert!(bucket_quantile(0.5, vec![(0.1, 1.0)]).is_nan());
+        assert_eq!(bucket_quantile(1.5, buckets), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_aggregate_grouping() {
+        let samples = vec![
+            (
+                labels(&[("__name__", "cpu"), ("host", "a"), ("core", "0")]),
+                1.0,
+            ),
+            (
+                labels(&[("__name__", "cpu"), ("host", "a"), ("core", "1")]),
+                3.0,
+            ),
+            (
+                labels(&[("__name__", "cpu"), ("host", "b"), ("core", "0")]),
+                5.0,
+            ),
+        ];
+
+        let by_host = aggregate(
+            AggregateOp::Sum,
+            samples.clone(),
+            None,
+            &Grouping::By(vec!["host".to_string()]),
+        );
+        assert_eq!(
+            by_host,
+            vec![
+                (labels(&[("host", "a")]), 4.0),
+                (labels(&[("host", "b")]), 5.0)
+            ]
+        );
+
+        let without_core = aggregate(
+            AggregateOp::Max,
+            samples.clone(),
+            None,
+            &Grouping::Without(vec!["core".to_string()]),
+        );
+        assert_eq!(
+            without_core,
+            by_host
+                .iter()
+                .map(|(l, _)| l.clone())
+                .zip([3.0, 5.0])
+                .collect::<Vec<_>>()
+        );
+
+        let top = aggregate(
+            AggregateOp::Topk,
+            samples.clone(),
+            Some(2.0),
+            &Grouping::None,
+        );
+        assert_eq!(
+            top.iter().map(|(_, v)| *v).collect::<Vec<_>>(),
+            vec![5.0, 3.0]
+        );
+
+        let stddev = aggregate(AggregateOp::Stddev, samples, None, &Grouping::None);
+        assert!((stddev[0].1 - (8.0_f64 / 3.0).sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_quantile() {
+        assert_eq!(quantile(0.5, vec![1.0, 2.0, 3.0, 4.0]), 2.5);
+        assert_eq!(quantile(1.0, vec![4.0, 1.0]), 4.0);
+        assert!(quantile(0.5, vec![]).is_nan());
+    }
+
+    #[test]
+    fn test_vector_matching() {
+        let lhs = vec![
+            (
+                labels(&[("__name__", "errors"), ("host", "a"), ("code", "500")]),
+                5.0,
+            ),
+            (
+                labels(&[("__name__", "errors"), ("host", "b"), ("code", "500")]),
+                1.0,
+            ),
+        ];
+        let rhs = vec![
+            (labels(&[("__name__", "requests"), ("host", "a")]), 10.0),
+            (labels(&[("__name__", "requests"), ("host", "b")]), 4.0),
+        ];
+
+        let on = match_vectors(
+            BinaryOp::Div,
+            lhs.clone(),
+            rhs.clone(),
+            &VectorMatching::On(vec!["host".to_string()]),
+        )
+        .unwrap();
+        assert_eq!(
+            on,
+            vec![
+                (labels(&[("host", "a")]), 0.5),
+                (labels(&[("host", "b")]), 0.25)
+            ]
+        );
+
+        let ignoring = match_vectors(
+            BinaryOp::Div,
+            lhs.clone(),
+            rhs.clone(),
+            &VectorMatching::Ignoring(vec!["code".to_string()]),
+        )
+        .unwrap();
+        assert_eq!(ignoring, on);
+
+        // Without modifiers, label sets differ by `code` and nothing matches
+        assert!(
+            match_vectors(BinaryOp::Div, lhs.clone(), rhs, &VectorMatching::All)
+                .unwrap()
+                .is_empty()
+        );
+
+        // Duplicate signatures on one side are rejected
+        assert!(
+            match_vectors(BinaryOp::Add, lhs.clone(), lhs, &VectorMatching::On(vec![]),).is_err()
+        );
+    }
+}
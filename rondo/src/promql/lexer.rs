@@ -0,0 +1,409 @@
+//! Tokenizer for the supported PromQL subset.
+
+use std::time::Duration;
+
+use crate::error::PromqlError;
+
+/// A lexical token.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    /// Identifier or keyword (metric names, labels, functions, `by`, ...).
+    Ident(String),
+    /// Numeric literal.
+    Number(f64),
+    /// Duration literal such as `5m` or `1h30m`.
+    Duration(Duration),
+    /// Quoted string literal (quotes removed, escapes resolved).
+    String(String),
+    /// `(`
+    LParen,
+    /// `)`
+    RParen,
+    /// `{`
+    LBrace,
+    /// `}`
+    RBrace,
+    /// `[`
+    LBracket,
+    /// `]`
+    RBracket,
+    /// `,`
+    Comma,
+    /// `:` (only valid inside subquery brackets, which are rejected)
+    Colon,
+    /// `=`
+    Eq,
+    /// `!=`
+    Neq,
+    /// `=~`
+    RegexEq,
+    /// `!~`
+    RegexNe,
+    /// `+`
+    Plus,
+    /// `-`
+    Minus,
+    /// `*`
+    Star,
+    /// `/`
+    Slash,
+    /// `%`
+    Percent,
+    /// `^`
+    Caret,
+    /// End of input.
+    Eof,
+}
+
+/// A token and its byte offset in the input.
+#[derive(Debug, Clone)]
+pub(crate) struct Spanned {
+    pub(crate) token: Token,
+    pub(crate) position: usize,
+}
+
+/// Splits a PromQL expression into tokens.
+pub(crate) fn tokenize(input: &str) -> Result<Vec<Spanned>, PromqlError> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let c = bytes[pos];
+
+        if c.is_ascii_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        // Comments run to the end of the line
+        if c == b'#' {
+            while pos < bytes.len() && bytes[pos] != b'\n' {
+                pos += 1;
+            }
+            continue;
+        }
+
+        let start = pos;
+        let token = match c {
+            b'(' => single(&mut pos, Token::LParen),
+            b')' => single(&mut pos, Token::RParen),
+            b'{' => single(&mut pos, Token::LBrace),
+            b'}' => single(&mut pos, Token::RBrace),
+            b'[' => single(&mut pos, Token::LBracket),
+            b']' => single(&mut pos, Token::RBracket),
+            b',' => single(&mut pos, Token::Comma),
+            b':' if bytes.get(pos + 1).is_some_and(u8::is_ascii_digit) => {
+                single(&mut pos, Token::Colon)
+            }
+            b'+' => single(&mut pos, Token::Plus),
+            b'-' => single(&mut pos, Token::Minus),
+            b'*' => single(&mut pos, Token::Star),
+            b'/' => single(&mut pos, Token::Slash),
+            b'%' => single(&mut pos, Token::Percent),
+            b'^' => single(&mut pos, Token::Caret),
+            b'=' => match bytes.get(pos + 1) {
+                Some(b'~') => double(&mut pos, Token::RegexEq),
+                Some(b'=') => {
+                    return Err(parse_error(pos, "comparison operators are not supported"));
+                }
+                _ => single(&mut pos, Token::Eq),
+            },
+            b'!' => match bytes.get(pos + 1) {
+                Some(b'=') => double(&mut pos, Token::Neq),
+                Some(b'~') => double(&mut pos, Token::RegexNe),
+                _ => return Err(parse_error(pos, "unexpected character '!'")),
+            },
+            b'"' | b'\'' | b'`' => lex_string(input, &mut pos)?,
+            b'0'..=b'9' | b'.' => lex_number_or_duration(input, &mut pos)?,
+            c if c.is_ascii_alphabetic() || c == b'_' || c == b':' => {
+                // Dots are accepted so rondo's dotted names (`cpu.usage`)
+                // can be selected without a `__name__` matcher.
+                while pos < bytes.len() && is_ident_continue(bytes[pos]) {
+                    pos += 1;
+                }
+                let word = &input[start..pos];
+                match word.to_ascii_lowercase().as_str() {
+                    "inf" => Token::Number(f64::INFINITY),
+                    "nan" => Token::Number(f64::NAN),
+                    _ => Token::Ident(word.to_string()),
+                }
+            }
+            _ => {
+                let ch = input[pos..].chars().next().unwrap_or('?');
+                return Err(parse_error(pos, format!("unexpected character '{ch}'")));
+            }
+        };
+
+        tokens.push(Spanned {
+            token,
+            position: start,
+        });
+    }
+
+    tokens.push(Spanned {
+        token: Token::Eof,
+        position: bytes.len(),
+    });
+    Ok(tokens)
+}
+
+/// Parses a standalone PromQL duration such as `5m` or `1h30m`.
+///
+/// # Errors
+///
+/// Returns [`PromqlError::Parse`] if the string is not a valid duration.
+pub fn parse_duration(input: &str) -> Result<Duration, PromqlError> {
+    let mut pos = 0;
+    match lex_number_or_duration(input, &mut pos)? {
+        Token::Duration(duration) if pos == input.len() => Ok(duration),
+        _ => Err(parse_error(0, format!("invalid duration '{input}'"))),
+    }
+}
+
+fn is_ident_continue(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b':' || b == b'.'
+}
+
+fn single(pos: &mut usize, token: Token) -> Token {
+    *pos += 1;
+    token
+}
+
+fn double(pos: &mut usize, token: Token) -> Token {
+    *pos += 2;
+    token
+}
+
+fn parse_error(position: usize, message: impl Into<String>) -> PromqlError {
+    PromqlError::Parse {
+        position,
+        message: message.into(),
+    }
+}
+
+/// Lexes a quoted string. Backtick strings are raw; others support escapes.
+fn lex_string(input: &str, pos: &mut usize) -> Result<Token, PromqlError> {
+    let start = *pos;
+    let mut chars = input[start..].char_indices();
+    let (_, quote) = chars.next().unwrap_or((0, '"'));
+    let mut value = String::new();
+
+    while let Some((offset, ch)) = chars.next() {
+        if ch == quote {
+            *pos = start + offset + ch.len_utf8();
+            return Ok(Token::String(value));
+        }
+
+        if ch == '\\' && quote != '`' {
+            let Some((_, escaped)) = chars.next() else {
+                break;
+            };
+            value.push(match escaped {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                other => other,
+            });
+        } else {
+            value.push(ch);
+        }
+    }
+
+    Err(parse_error(start, "unterminated string literal"))
+}
+
+/// Lexes a number, or a duration if the digits are followed by a time unit.
+fn lex_number_or_duration(input: &str, pos: &mut usize) -> Result<Token, PromqlError> {
+    let bytes = input.as_bytes();
+    let start = *pos;
+
+    let digits_end = |mut p: usize| {
+        while p < bytes.len() && bytes[p].is_ascii_digit() {
+            p += 1;
+        }
+        p
+    };
+
+    let end = digits_end(start);
+    if end > start && is_duration_unit_start(bytes, end) {
+        return lex_duration(input, pos);
+    }
+
+    // Floating-point literal: digits [. digits] [e[+-]digits]
+    let mut p = end;
+    if p < bytes.len() && bytes[p] == b'.' {
+        p = digits_end(p + 1);
+    }
+    if p < bytes.len() && (bytes[p] == b'e' || bytes[p] == b'E') {
+        let mut q = p + 1;
+        if q < bytes.len() && (bytes[q] == b'+' || bytes[q] == b'-') {
+            q += 1;
+        }
+        let exp_end = digits_end(q);
+        if exp_end > q {
+            p = exp_end;
+        }
+    }
+
+    let text = &input[start..p];
+    let value: f64 = text
+        .parse()
+        .map_err(|_| parse_error(start, format!("invalid number '{text}'")))?;
+    *pos = p;
+    Ok(Token::Number(value))
+}
+
+fn is_duration_unit_start(bytes: &[u8], pos: usize) -> bool {
+    matches!(
+        bytes.get(pos),
+        Some(b's' | b'm' | b'h' | b'd' | b'w' | b'y')
+    ) && !bytes
+        .get(pos + 1)
+        .is_some_and(|&b| b.is_ascii_alphabetic() && !(bytes[pos] == b'm' && b == b's'))
+}
+
+/// Lexes a sequence of `<digits><unit>` pairs into a single duration.
+fn lex_duration(input: &str, pos: &mut usize) -> Result<Token, PromqlError> {
+    let bytes = input.as_bytes();
+    let start = *pos;
+    let mut total_ms: u64 = 0;
+    let mut p = start;
+
+    while p < bytes.len() && bytes[p].is_ascii_digit() {
+        let digits_start = p;
+        while p < bytes.len() && bytes[p].is_ascii_digit() {
+            p += 1;
+        }
+        let amount: u64 = input[digits_start..p]
+            .parse()
+            .map_err(|_| parse_error(digits_start, "duration out of range"))?;
+
+        let unit_ms: u64 = match (bytes.get(p), bytes.get(p + 1)) {
+            (Some(b'm'), Some(b's')) => {
+                p += 2;
+                1
+            }
+            (Some(b's'), _) => {
+                p += 1;
+                1000
+            }
+            (Some(b'm'), _) => {
+                p += 1;
+                60 * 1000
+            }
+            (Some(b'h'), _) => {
+                p += 1;
+                3600 * 1000
+            }
+            (Some(b'd'), _) => {
+                p += 1;
+                24 * 3600 * 1000
+            }
+            (Some(b'w'), _) => {
+                p += 1;
+                7 * 24 * 3600 * 1000
+            }
+            (Some(b'y'), _) => {
+                p += 1;
+                365 * 24 * 3600 * 1000
+            }
+            _ => return Err(parse_error(p, "missing duration unit")),
+        };
+
+        total_ms = amount
+            .checked_mul(unit_ms)
+            .and_then(|ms| total_ms.checked_add(ms))
+            .ok_or_else(|| parse_error(start, "duration out of range"))?;
+    }
+
+    *pos = p;
+    Ok(Token::Duration(Duration::from_millis(total_ms)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(input: &str) -> Vec<Token> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.token)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_selector() {
+        assert_eq!(
+            tokens(r#"http_requests_total{job=~"api.*", code!="500"}[5m]"#),
+            vec![
+                Token::Ident("http_requests_total".to_string()),
+                Token::LBrace,
+                Token::Ident("job".to_string()),
+                Token::RegexEq,
+                Token::String("api.*".to_string()),
+                Token::Comma,
+                Token::Ident("code".to_string()),
+                Token::Neq,
+                Token::String("500".to_string()),
+                Token::RBrace,
+                Token::LBracket,
+                Token::Duration(Duration::from_secs(300)),
+                Token::RBracket,
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_dotted_name() {
+        assert_eq!(
+            tokens("vcpu.exits"),
+            vec![Token::Ident("vcpu.exits".to_string()), Token::Eof]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_numbers_and_durations() {
+        assert_eq!(
+            tokens("1.5e3 0.95 1h30m 250ms Inf"),
+            vec![
+                Token::Number(1500.0),
+                Token::Number(0.95),
+                Token::Duration(Duration::from_secs(5400)),
+                Token::Duration(Duration::from_millis(250)),
+                Token::Number(f64::INFINITY),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_string_escapes() {
+        assert_eq!(
+            tokens(r#""a\"b" 'c' `d\n`"#),
+            vec![
+                Token::String("a\"b".to_string()),
+                Token::String("c".to_string()),
+                Token::String("d\\n".to_string()),
+                Token::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        assert!(tokenize(r#"foo{a="b}"#).is_err());
+        assert!(tokenize("a == b").is_err());
+        assert!(tokenize("a ! b").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("15s").unwrap(), Duration::from_secs(15));
+        assert_eq!(parse_duration("1d2h").unwrap(), Duration::from_secs(93_600));
+        assert!(parse_duration("15").is_err());
+        assert!(parse_duration("15x").is_err());
+    }
+}
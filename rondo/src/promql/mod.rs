@@ -0,0 +1,49 @@
+//! PromQL query language support.
+//!
+//! Implements the subset of PromQL needed for dashboards and ad-hoc
+//! queries over a [`Store`](crate::store::Store):
+//!
+//! - Instant and range vector selectors with `=`, `!=`, `=~` and `!~`
+//!   matchers and the `offset` modifier
+//! - Range functions: `rate`, `irate`, `increase`, `delta` and the
+//!   `*_over_time` family
+//! - `histogram_quantile` over classic `le` buckets
+//! - Aggregations (`sum`, `avg`, `min`, `max`, `count`, `stddev`, `stdvar`,
+//!   `topk`, `bottomk`, `quantile`) with `by` / `without`
+//! - Arithmetic (`+ - * / % ^`) between scalars and vectors, with
+//!   one-to-one `on` / `ignoring` vector matching
+//!
+//! Comparison operators, `group_left`/`group_right` and subqueries are not
+//! supported and are rejected at parse time.
+//!
+//! rondo series names become the `__name__` label. Names may contain dots,
+//! so `vcpu.exits{vm="a"}` selects the series registered as `vcpu.exits`.
+//!
+//! # Examples
+//!
+//! ```rust,no_run
+//! use rondo::promql::{Engine, Value};
+//! use rondo::store::Store;
+//!
+//! # let store = Store::open("./data", vec![])?;
+//! # let now_ns = 1_640_000_000_000_000_000u64;
+//! let engine = Engine::new(&store);
+//! if let Value::Vector(samples) = engine.instant_query("avg by (host) (cpu_usage)", now_ns)? {
+//!     for sample in samples {
+//!         println!("{:?} = {}", sample.labels, sample.value);
+//!     }
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod ast;
+mod eval;
+mod lexer;
+mod parser;
+
+pub use eval::{
+    DEFAULT_LOOKBACK_DELTA, Engine, Labels, MAX_RANGE_STEPS, METRIC_NAME_LABEL, Sample, Series,
+    Value, select_series, series_labels,
+};
+pub use lexer::parse_duration;
+pub use parser::parse;
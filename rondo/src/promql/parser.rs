@@ -0,0 +1,600 @@
+//! Recursive-descent parser for the supported PromQL subset.
+//!
+//! Binary operators are parsed by precedence climbing. As in Prometheus,
+//! `^` is right-associative and binds tighter than unary minus, so `-2 ^ 2`
+//! evaluates to `-4`.
+
+use std::time::Duration;
+
+use super::ast::{
+    AggregateOp, BinaryOp, Expr, Function, Grouping, MatchOp, Matcher, ValueType, VectorMatching,
+    VectorSelector,
+};
+use super::lexer::{Spanned, Token, tokenize};
+use crate::error::PromqlError;
+
+/// Parses a PromQL expression into an AST and checks its types.
+///
+/// # Errors
+///
+/// Returns [`PromqlError::Parse`] for syntax errors and unsupported
+/// constructs, and [`PromqlError::Type`] when an operator or function is
+/// applied to the wrong kind of value.
+pub fn parse(input: &str) -> Result<Expr, PromqlError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+
+    let expr = parser.parse_expr(0)?;
+    if parser.peek() != &Token::Eof {
+        return Err(parser.error("unexpected trailing input"));
+    }
+
+    check_types(&expr)?;
+    Ok(expr)
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].token
+    }
+
+    fn peek_at(&self, offset: usize) -> &Token {
+        let index = (self.pos + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].token
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].token.clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error(&self, message: impl Into<String>) -> PromqlError {
+        PromqlError::Parse {
+            position: self.tokens[self.pos].position,
+            message: message.into(),
+        }
+    }
+
+    fn expect(&mut self, expected: &Token, what: &str) -> Result<(), PromqlError> {
+        if self.peek() == expected {
+            self.next();
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {what}")))
+        }
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn parse_expr(&mut self, min_precedence: u8) -> Result<Expr, PromqlError> {
+        let mut lhs = self.parse_unary()?;
+
+        loop {
+            let op = match self.peek() {
+                Token::Plus => BinaryOp::Add,
+                Token::Minus => BinaryOp::Sub,
+                Token::Star => BinaryOp::Mul,
+                Token::Slash => BinaryOp::Div,
+                Token::Percent => BinaryOp::Mod,
+                Token::Caret => BinaryOp::Pow,
+                _ => break,
+            };
+            let precedence = op.precedence();
+            if precedence < min_precedence {
+                break;
+            }
+            self.next();
+
+            let matching = self.parse_vector_matching()?;
+            let next_min = if op == BinaryOp::Pow {
+                precedence
+            } else {
+                precedence + 1
+            };
+            let rhs = self.parse_expr(next_min)?;
+
+            lhs = Expr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                matching,
+            };
+        }
+
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, PromqlError> {
+        match self.peek() {
+            Token::Minus => {
+                self.next();
+                let operand = self.parse_expr(BinaryOp::Pow.precedence())?;
+                // Fold negative literals so `-1` stays a number
+                Ok(match operand {
+                    Expr::Number(n) => Expr::Number(-n),
+                    other => Expr::Negate(Box::new(other)),
+                })
+            }
+            Token::Plus => {
+                self.next();
+                self.parse_expr(BinaryOp::Pow.precedence())
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_vector_matching(&mut self) -> Result<VectorMatching, PromqlError> {
+        if self.peek_keyword("bool") {
+            return Err(self.error("comparison operators are not supported"));
+        }
+
+        let matching = if self.peek_keyword("on") {
+            self.next();
+            VectorMatching::On(self.parse_label_list()?)
+        } else if self.peek_keyword("ignoring") {
+            self.next();
+            VectorMatching::Ignoring(self.parse_label_list()?)
+        } else {
+            return Ok(VectorMatching::All);
+        };
+
+        if self.peek_keyword("group_left") || self.peek_keyword("group_right") {
+            return Err(self.error("group_left/group_right matching is not supported"));
+        }
+        Ok(matching)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, PromqlError> {
+        let expr = match self.peek().clone() {
+            Token::Number(n) => {
+                self.next();
+                Expr::Number(n)
+            }
+            Token::String(s) => {
+                self.next();
+                Expr::String(s)
+            }
+            Token::LParen => {
+                self.next();
+                let inner = self.parse_expr(0)?;
+                self.expect(&Token::RParen, "')'")?;
+                Expr::Paren(Box::new(inner))
+            }
+            Token::LBrace => self.parse_selector(None)?,
+            Token::Ident(name) => self.parse_identifier(name)?,
+            Token::Duration(_) => return Err(self.error("unexpected duration")),
+            Token::Eof => return Err(self.error("unexpected end of input")),
+            _ => return Err(self.error("unexpected token")),
+        };
+
+        if self.peek() == &Token::LBracket {
+            return Err(self.error("subqueries and ranges on non-selectors are not supported"));
+        }
+        Ok(expr)
+    }
+
+    fn parse_identifier(&mut self, name: String) -> Result<Expr, PromqlError> {
+        let next = self.peek_at(1);
+
+        if let Some(op) = AggregateOp::from_name(&name)
+            && (next == &Token::LParen
+                || matches!(next, Token::Ident(w) if w == "by" || w == "without"))
+        {
+            self.next();
+            return self.parse_aggregate(op);
+        }
+
+        if next == &Token::LParen {
+            let Some(func) = Function::from_name(&name) else {
+                return Err(self.error(format!("unknown function '{name}'")));
+            };
+            self.next();
+            return self.parse_call(func);
+        }
+
+        self.next();
+        self.parse_selector(Some(name))
+    }
+
+    fn parse_call(&mut self, func: Function) -> Result<Expr, PromqlError> {
+        self.expect(&Token::LParen, "'('")?;
+        let mut args = Vec::new();
+        if self.peek() != &Token::RParen {
+            loop {
+                args.push(self.parse_expr(0)?);
+                if self.peek() != &Token::Comma {
+                    break;
+                }
+                self.next();
+            }
+        }
+        self.expect(&Token::RParen, "')' after function arguments")?;
+        Ok(Expr::Call { func, args })
+    }
+
+    fn parse_aggregate(&mut self, op: AggregateOp) -> Result<Expr, PromqlError> {
+        let mut grouping = self.parse_grouping()?;
+
+        self.expect(&Token::LParen, "'(' after aggregation")?;
+        let param = if op.takes_param() {
+            let param = self.parse_expr(0)?;
+            self.expect(&Token::Comma, "',' after aggregation parameter")?;
+            Some(Box::new(param))
+        } else {
+            None
+        };
+        let expr = self.parse_expr(0)?;
+        self.expect(&Token::RParen, "')' after aggregation")?;
+
+        // The grouping clause may also trail the expression
+        if grouping == Grouping::None {
+            grouping = self.parse_grouping()?;
+        }
+
+        Ok(Expr::Aggregate {
+            op,
+            expr: Box::new(expr),
+            param,
+            grouping,
+        })
+    }
+
+    fn parse_grouping(&mut self) -> Result<Grouping, PromqlError> {
+        if self.peek_keyword("by") {
+            self.next();
+            Ok(Grouping::By(self.parse_label_list()?))
+        } else if self.peek_keyword("without") {
+            self.next();
+            Ok(Grouping::Without(self.parse_label_list()?))
+        } else {
+            Ok(Grouping::None)
+        }
+    }
+
+    fn parse_label_list(&mut self) -> Result<Vec<String>, PromqlError> {
+        self.expect(&Token::LParen, "'(' before label list")?;
+        let mut labels = Vec::new();
+        while let Token::Ident(label) = self.peek().clone() {
+            self.next();
+            labels.push(label);
+            if self.peek() != &Token::Comma {
+                break;
+            }
+            self.next();
+        }
+        self.expect(&Token::RParen, "')' after label list")?;
+        Ok(labels)
+    }
+
+    fn parse_selector(&mut self, name: Option<String>) -> Result<Expr, PromqlError> {
+        let mut matchers = Vec::new();
+        if let Some(name) = &name {
+            matchers.push(
+                Matcher::new("__name__", MatchOp::Equal, name.as_str())
+                    .map_err(|e| self.error(e.to_string()))?,
+            );
+        }
+
+        if self.peek() == &Token::LBrace {
+            self.next();
+            while let Token::Ident(label) = self.peek().clone() {
+                self.next();
+                let op = match self.next() {
+                    Token::Eq => MatchOp::Equal,
+                    Token::Neq => MatchOp::NotEqual,
+                    Token::RegexEq => MatchOp::RegexMatch,
+                    Token::RegexNe => MatchOp::RegexNoMatch,
+                    _ => return Err(self.error("expected label match operator")),
+                };
+                let Token::String(value) = self.next() else {
+                    return Err(self.error("expected string label value"));
+                };
+                let matcher = Matcher::new(label, op, value)
+                    .map_err(|e| self.error(format!("invalid regex: {e}")))?;
+                matchers.push(matcher);
+
+                if self.peek() != &Token::Comma {
+                    break;
+                }
+                self.next();
+            }
+            self.expect(&Token::RBrace, "'}' after label matchers")?;
+        }
+
+        if !matchers.iter().any(|m| !m.matches("")) {
+            return Err(self.error("selector must contain at least one non-empty matcher"));
+        }
+
+        let range = if self.peek() == &Token::LBracket {
+            self.next();
+            let Token::Duration(range) = self.next() else {
+                return Err(self.error("expected duration in range selector"));
+            };
+            if self.peek() == &Token::Colon {
+                return Err(self.error("subqueries are not supported"));
+            }
+            self.expect(&Token::RBracket, "']' after range")?;
+            Some(range)
+        } else {
+            None
+        };
+
+        let offset = self.parse_offset()?;
+        let selector = VectorSelector {
+            name,
+            matchers,
+            offset,
+        };
+
+        Ok(match range {
+            Some(range) if range.is_zero() => {
+                return Err(self.error("range must be greater than zero"));
+            }
+            Some(range) => Expr::MatrixSelector { selector, range },
+            None => Expr::VectorSelector(selector),
+        })
+    }
+
+    fn parse_offset(&mut self) -> Result<Duration, PromqlError> {
+        if !self.peek_keyword("offset") {
+            return Ok(Duration::ZERO);
+        }
+        self.next();
+        match self.next() {
+            Token::Duration(offset) => Ok(offset),
+            Token::Minus => Err(self.error("negative offsets are not supported")),
+            _ => Err(self.error("expected duration after offset")),
+        }
+    }
+}
+
+/// Checks operand and argument types throughout the expression.
+fn check_types(expr: &Expr) -> Result<ValueType, PromqlError> {
+    let ty = match expr {
+        Expr::Number(_) => ValueType::Scalar,
+        Expr::String(_) => ValueType::String,
+        Expr::VectorSelector(_) => ValueType::Vector,
+        Expr::MatrixSelector { .. } => ValueType::Matrix,
+        Expr::Paren(inner) => check_types(inner)?,
+        Expr::Negate(inner) => {
+            let ty = check_types(inner)?;
+            expect_numeric(ty, "unary '-'")?;
+            ty
+        }
+        Expr::Binary {
+            op,
+            lhs,
+            rhs,
+            matching,
+        } => {
+            let lhs_ty = check_types(lhs)?;
+            let rhs_ty = check_types(rhs)?;
+            expect_numeric(lhs_ty, op.symbol())?;
+            expect_numeric(rhs_ty, op.symbol())?;
+            if *matching != VectorMatching::All
+                && (lhs_ty != ValueType::Vector || rhs_ty != ValueType::Vector)
+            {
+                return Err(type_error(
+                    "vector matching is only allowed between vectors",
+                ));
+            }
+            if lhs_ty == ValueType::Scalar && rhs_ty == ValueType::Scalar {
+                ValueType::Scalar
+            } else {
+                ValueType::Vector
+            }
+        }
+        Expr::Aggregate {
+            op, expr, param, ..
+        } => {
+            expect_type(check_types(expr)?, ValueType::Vector, op.name())?;
+            if let Some(param) = param {
+                expect_type(check_types(param)?, ValueType::Scalar, op.name())?;
+            }
+            ValueType::Vector
+        }
+        Expr::Call { func, args } => {
+            let signature = signature(*func);
+            if args.len() != signature.len() {
+                return Err(type_error(format!(
+                    "{}() expects {} argument(s), got {}",
+                    func.name(),
+                    signature.len(),
+                    args.len()
+                )));
+            }
+            for (arg, expected) in args.iter().zip(signature) {
+                expect_type(check_types(arg)?, *expected, func.name())?;
+            }
+            match func {
+                Function::Scalar | Function::Time => ValueType::Scalar,
+                _ => ValueType::Vector,
+            }
+        }
+    };
+    Ok(ty)
+}
+
+/// Returns the argument types a function expects.
+fn signature(func: Function) -> &'static [ValueType] {
+    use ValueType::{Matrix, Scalar, Vector};
+
+    match func {
+        Function::Rate
+        | Function::Irate
+        | Function::Increase
+        | Function::Delta
+        | Function::AvgOverTime
+        | Function::MinOverTime
+        | Function::MaxOverTime
+        | Function::SumOverTime
+        | Function::CountOverTime
+        | Function::LastOverTime => &[Matrix],
+        Function::HistogramQuantile => &[Scalar, Vector],
+        Function::Abs | Function::Ceil | Function::Floor | Function::Round | Function::Scalar => {
+            &[Vector]
+        }
+        Function::ClampMin | Function::ClampMax => &[Vector, Scalar],
+        Function::Vector => &[Scalar],
+        Function::Time => &[],
+    }
+}
+
+fn expect_numeric(ty: ValueType, context: &str) -> Result<(), PromqlError> {
+    match ty {
+        ValueType::Scalar | ValueType::Vector => Ok(()),
+        _ => Err(type_error(format!(
+            "{context} expects a scalar or instant vector, got {ty}"
+        ))),
+    }
+}
+
+fn expect_type(ty: ValueType, expected: ValueType, context: &str) -> Result<(), PromqlError> {
+    if ty == expected {
+        Ok(())
+    } else {
+        Err(type_error(format!(
+            "{context} expects {expected}, got {ty}"
+        )))
+    }
+}
+
+fn type_error(message: impl Into<String>) -> PromqlError {
+    PromqlError::Type {
+        message: message.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(input: &str) -> String {
+        parse(input).unwrap().to_string()
+    }
+
+    #[test]
+    fn test_parse_selectors() {
+        assert_eq!(roundtrip("cpu_usage"), "cpu_usage");
+        assert_eq!(
+            roundtrip(r#"cpu_usage{host="web1",dc=~"us-.*"}"#),
+            r#"cpu_usage{host="web1", dc=~"us-.*"}"#
+        );
+        assert_eq!(
+            roundtrip(r#"{__name__="vcpu.exits"}"#),
+            r#"{__name__="vcpu.exits"}"#
+        );
+        assert_eq!(
+            roundtrip("requests_total[5m] offset 1h"),
+            "requests_total[5m] offset 1h"
+        );
+    }
+
+    #[test]
+    fn test_parse_calls_and_aggregations() {
+        assert_eq!(
+            roundtrip("sum by (host) (rate(requests_total[1m]))"),
+            "sum by (host) (rate(requests_total[1m]))"
+        );
+        assert_eq!(
+            roundtrip("sum(rate(requests_total[1m])) without (code)"),
+            "sum without (code) (rate(requests_total[1m]))"
+        );
+        assert_eq!(roundtrip("topk(3, cpu_usage)"), "topk (3, cpu_usage)");
+        assert_eq!(
+            roundtrip("histogram_quantile(0.99, sum by (le) (rate(latency_bucket[5m])))"),
+            "histogram_quantile(0.99, sum by (le) (rate(latency_bucket[5m])))"
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let Expr::Binary { op, rhs, .. } = parse("1 + 2 * 3").unwrap() else {
+            panic!("expected binary expression");
+        };
+        assert_eq!(op, BinaryOp::Add);
+        assert!(matches!(
+            *rhs,
+            Expr::Binary {
+                op: BinaryOp::Mul,
+                ..
+            }
+        ));
+
+        // ^ is right-associative
+        let Expr::Binary { op, rhs, .. } = parse("2 ^ 3 ^ 2").unwrap() else {
+            panic!("expected binary expression");
+        };
+        assert_eq!(op, BinaryOp::Pow);
+        assert!(matches!(
+            *rhs,
+            Expr::Binary {
+                op: BinaryOp::Pow,
+                ..
+            }
+        ));
+
+        // Unary minus binds looser than ^
+        assert!(matches!(parse("-2 ^ 2").unwrap(), Expr::Negate(_)));
+        assert!(matches!(parse("-2").unwrap(), Expr::Number(n) if n == -2.0));
+    }
+
+    #[test]
+    fn test_parse_vector_matching() {
+        assert_eq!(roundtrip("a / on(host) b"), "a / on (host) b");
+        assert_eq!(
+            roundtrip("a - ignoring(code, method) b"),
+            "a - ignoring (code, method) b"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for input in [
+            "",
+            "sum(",
+            "cpu{host=web1}",
+            "cpu[5]",
+            r#"{host=~".*"}"#,
+            "foo(bar)",
+            "cpu{host=~\"(\"}",
+            "a + ",
+            "a > b",
+            "rate(x[5m:1m])",
+        ] {
+            assert!(
+                matches!(parse(input), Err(PromqlError::Parse { .. })),
+                "expected parse error for {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_type_errors() {
+        for input in [
+            "rate(cpu)",
+            "sum(cpu[5m])",
+            "cpu[5m] + 1",
+            "histogram_quantile(cpu, cpu)",
+            "abs(1, 2)",
+            "1 + on(host) 2",
+        ] {
+            assert!(
+                matches!(parse(input), Err(PromqlError::Type { .. })),
+                "expected type error for {input:?}"
+            );
+        }
+    }
+}
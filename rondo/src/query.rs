@@ -7,20 +7,26 @@
 //!
 //! # Overview
 //!
-//! The query system supports two main query modes:
+//! The query system supports three main query modes:
 //!
 //! - **Direct tier queries** - Query a specific tier with explicit validation
 //! - **Automatic tier selection** - Choose the best tier based on retention coverage
+//! - **Stitched queries** - Serve each sub-range from the highest-resolution
+//!   tier that covers it (enabled via [`QueryOptions::with_stitching`])
 //!
-//! Both modes return a [`QueryResult`] that wraps the iterator with metadata
-//! about the query execution, including which tier was used and whether data
-//! may be incomplete.
+//! Any mode can additionally cap the number of returned points with
+//! [`QueryOptions::with_max_points`], which downsamples inside the iterator
+//! by keeping the minimum and maximum of each time bucket so spikes survive.
+//!
+//! All modes return a [`QueryResult`] that wraps the iterator with metadata
+//! about the query execution, including which tier(s) were used and whether
+//! data may be incomplete.
 //!
 //! # Example Usage
 //!
 //! ```rust,no_run
 //! # use rondo::store::Store;
-//! # use rondo::schema::SchemaConfig;
+//! # use rondo::query::QueryOptions;
 //! # let mut store = Store::open("./data", vec![])?;
 //! # let handle = store.register("cpu.usage", &[])?;
 //! # let start_ns = 1_640_000_000_000_000_000u64;
@@ -34,11 +40,103 @@
 //! if result.may_be_incomplete() {
 //!     println!("Warning: some data may be outside retention window");
 //! }
+//!
+//! // Stitch tiers: recent data at full resolution, older data from rollups
+//! let options = QueryOptions::new().with_stitching(true);
+//! let result = store.query_auto_with(handle, start_ns, end_ns, &options)?;
+//! for segment in result.segments() {
+//!     println!("tier {} ({}ns) serves {}..{}", segment.tier, segment.interval_ns,
+//!         segment.start_ns, segment.end_ns);
+//! }
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 
+use std::collections::VecDeque;
+use std::time::Duration;
+
 use crate::ring::RingIterator;
 
+/// Options controlling how a query is executed.
+///
+/// The default options reproduce the behavior of [`Store::query_auto`]:
+/// a single tier is selected for the whole range and every point is returned.
+///
+/// [`Store::query_auto`]: crate::store::Store::query_auto
+///
+/// # Examples
+///
+/// ```rust
+/// use rondo::query::QueryOptions;
+///
+/// let options = QueryOptions::new().with_stitching(true).with_max_points(500);
+/// assert!(options.stitch);
+/// assert_eq!(options.max_points, Some(500));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryOptions {
+    /// Serve each sub-range from the highest-resolution tier that covers it
+    /// instead of picking a single tier for the whole range.
+    pub stitch: bool,
+
+    /// Upper bound on the number of points returned. When set, the result is
+    /// downsampled with min/max bucketing. `None` returns every point, and
+    /// the store rejects `Some(0)` with
+    /// [`QueryError::InvalidMaxPoints`](crate::error::QueryError::InvalidMaxPoints).
+    pub max_points: Option<usize>,
+
+    /// Coarsest resolution the caller needs (e.g. a graph's step). Tier
+    /// selection starts at the coarsest tier whose interval does not exceed
+    /// it instead of at tier 0, so wide, coarse queries read fewer points.
+    pub resolution: Option<Duration>,
+}
+
+impl QueryOptions {
+    /// Creates the default query options (single tier, no stitching).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables or disables multi-tier stitching.
+    #[must_use]
+    pub fn with_stitching(mut self, stitch: bool) -> Self {
+        self.stitch = stitch;
+        self
+    }
+
+    /// Limits the result to at most `max_points` points via downsampling.
+    ///
+    /// `max_points` must be at least 1; see [`QueryOptions::max_points`].
+    #[must_use]
+    pub fn with_max_points(mut self, max_points: usize) -> Self {
+        self.max_points = Some(max_points);
+        self
+    }
+
+    /// Prefers tiers no finer than needed for the given resolution.
+    #[must_use]
+    pub fn with_resolution(mut self, resolution: Duration) -> Self {
+        self.resolution = Some(resolution);
+        self
+    }
+}
+
+/// A contiguous sub-range of a query result served from a single tier.
+///
+/// Single-tier queries have exactly one segment covering the requested
+/// range. Stitched queries have one segment per contributing tier, ordered
+/// from oldest to newest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuerySegment {
+    /// Tier index that serves this sub-range.
+    pub tier: usize,
+    /// Resolution of the tier in nanoseconds.
+    pub interval_ns: u64,
+    /// Start of the sub-range in nanoseconds (inclusive).
+    pub start_ns: u64,
+    /// End of the sub-range in nanoseconds (exclusive).
+    pub end_ns: u64,
+}
+
 /// Result of a time-series query operation.
 ///
 /// This struct wraps a ring buffer iterator with metadata about the query
@@ -49,8 +147,14 @@ use crate::ring::RingIterator;
 /// pairs while preserving query metadata for analysis and monitoring.
 #[derive(Debug)]
 pub struct QueryResult<'a> {
-    /// The underlying iterator over time-series data.
-    iterator: RingIterator<'a>,
+    /// The underlying iterators, one per segment, in chronological order.
+    iterators: Vec<RingIterator<'a>>,
+
+    /// Index of the iterator currently being consumed.
+    current: usize,
+
+    /// The tier and time sub-range served by each iterator.
+    segments: Vec<QuerySegment>,
 
     /// Which tier index was used for this query.
     tier_used: usize,
@@ -63,6 +167,9 @@ pub struct QueryResult<'a> {
 
     /// Whether data may be incomplete due to retention limits.
     may_be_incomplete: bool,
+
+    /// Downsampling state, present when a point limit was requested.
+    downsampler: Option<Downsampler>,
 }
 
 impl<'a> QueryResult<'a> {
@@ -82,12 +189,59 @@ impl<'a> QueryResult<'a> {
         requested_range: (u64, u64),
         may_be_incomplete: bool,
     ) -> Self {
+        let segment = QuerySegment {
+            tier: tier_used,
+            interval_ns: iterator.interval_ns(),
+            start_ns: requested_range.0,
+            end_ns: requested_range.1,
+        };
+
         Self {
-            iterator,
+            iterators: vec![iterator],
+            current: 0,
+            segments: vec![segment],
             tier_used,
             available_range,
             requested_range,
             may_be_incomplete,
+            downsampler: None,
+        }
+    }
+
+    /// Creates a query result stitched together from several tiers.
+    ///
+    /// # Arguments
+    ///
+    /// * `parts` - Segment metadata and iterator pairs, oldest segment first
+    /// * `available_range` - The combined time range available across the segments
+    /// * `requested_range` - The time range that was requested
+    /// * `may_be_incomplete` - Whether data may be missing due to retention
+    ///
+    /// # Panics
+    ///
+    /// Panics if `parts` is empty.
+    pub(crate) fn stitched(
+        parts: Vec<(QuerySegment, RingIterator<'a>)>,
+        available_range: (Option<u64>, Option<u64>),
+        requested_range: (u64, u64),
+        may_be_incomplete: bool,
+    ) -> Self {
+        let tier_used = parts
+            .iter()
+            .map(|(segment, _)| segment.tier)
+            .min()
+            .expect("stitched query requires at least one segment");
+        let (segments, iterators) = parts.into_iter().unzip();
+
+        Self {
+            iterators,
+            current: 0,
+            segments,
+            tier_used,
+            available_range,
+            requested_range,
+            may_be_incomplete,
+            downsampler: None,
         }
     }
 
@@ -109,10 +263,25 @@ impl<'a> QueryResult<'a> {
         self.tier_used
     }
 
+    /// Returns the tier and time sub-range served by each part of the result.
+    ///
+    /// Segments are ordered from oldest to newest. For stitched queries,
+    /// [`tier_used`](Self::tier_used) reports the highest-resolution tier
+    /// among the segments.
+    pub fn segments(&self) -> &[QuerySegment] {
+        &self.segments
+    }
+
+    /// Returns whether this result was served from more than one tier.
+    pub fn is_stitched(&self) -> bool {
+        self.segments.len() > 1
+    }
+
     /// Returns the time range actually available in the selected tier.
     ///
     /// Returns `(oldest_timestamp, newest_timestamp)` where either value
-    /// may be `None` if the tier is empty.
+    /// may be `None` if the tier is empty. For stitched queries this spans
+    /// all contributing tiers.
     ///
     /// # Examples
     ///
@@ -181,7 +350,15 @@ impl<'a> QueryResult<'a> {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn count(self) -> usize {
-        self.iterator.count()
+        if self.downsampler.is_some() {
+            return Iterator::count(self);
+        }
+
+        self.iterators
+            .into_iter()
+            .skip(self.current)
+            .map(Iterator::count)
+            .sum()
     }
 
     /// Collects all data points into a vector.
@@ -202,7 +379,54 @@ impl<'a> QueryResult<'a> {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn collect_all(self) -> Vec<(u64, f64)> {
-        self.iterator.collect()
+        self.collect()
+    }
+
+    /// Limits the result to at most `max_points` points.
+    ///
+    /// The time range actually covered by data is split into
+    /// `max_points / 2` equal buckets and each bucket yields its minimum and
+    /// maximum sample in chronological order, so peaks and troughs remain
+    /// visible. With `max_points == 1` a single bucket yields its maximum.
+    /// Downsampling is streaming and happens inside the iterator.
+    ///
+    /// Unlike the store's query methods, which reject a
+    /// [`QueryOptions::max_points`] of 0, this cannot fail: a limit of 0 is
+    /// clamped to 1.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::store::Store;
+    /// # let mut store = Store::open("./data", vec![])?;
+    /// # let handle = store.register("cpu.usage", &[])?;
+    /// # let start_ns = 1_640_000_000_000_000_000u64;
+    /// # let end_ns = start_ns + 3600 * 1_000_000_000;
+    /// let result = store.query(handle, 0, start_ns, end_ns)?.with_max_points(300);
+    /// assert!(result.count() <= 300);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use]
+    pub fn with_max_points(mut self, max_points: usize) -> Self {
+        let (oldest, newest) = self.available_range;
+        let (requested_start, requested_end) = self.requested_range;
+
+        let range_start = oldest.map_or(requested_start, |o| o.max(requested_start));
+        let range_end = newest.map_or(requested_end, |n| n.saturating_add(1).min(requested_end));
+
+        self.downsampler = Some(Downsampler::new(range_start, range_end, max_points));
+        self
+    }
+
+    /// Returns the next point from the underlying segment iterators.
+    fn next_raw(&mut self) -> Option<(u64, f64)> {
+        while let Some(iterator) = self.iterators.get_mut(self.current) {
+            if let Some(point) = iterator.next() {
+                return Some(point);
+            }
+            self.current += 1;
+        }
+        None
     }
 }
 
@@ -210,7 +434,109 @@ impl<'a> Iterator for QueryResult<'a> {
     type Item = (u64, f64);
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iterator.next()
+        let Some(mut downsampler) = self.downsampler.take() else {
+            return self.next_raw();
+        };
+
+        let point = loop {
+            if let Some(point) = downsampler.pending.pop_front() {
+                break Some(point);
+            }
+            match self.next_raw() {
+                Some(point) => downsampler.push(point),
+                None => {
+                    if !downsampler.flush() {
+                        break None;
+                    }
+                }
+            }
+        };
+
+        self.downsampler = Some(downsampler);
+        point
+    }
+}
+
+/// Streaming min/max-per-bucket downsampler used by [`QueryResult::with_max_points`].
+#[derive(Debug)]
+struct Downsampler {
+    /// Start of the first bucket in nanoseconds.
+    range_start: u64,
+    /// Width of each bucket in nanoseconds.
+    bucket_width: u64,
+    /// Whether each bucket emits both its minimum and maximum.
+    emit_pair: bool,
+    /// The bucket currently being accumulated.
+    bucket: Option<Bucket>,
+    /// Points from flushed buckets waiting to be yielded.
+    pending: VecDeque<(u64, f64)>,
+}
+
+/// Accumulated extremes of a single downsampling bucket.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    index: u64,
+    min: (u64, f64),
+    max: (u64, f64),
+}
+
+impl Downsampler {
+    fn new(range_start: u64, range_end: u64, max_points: usize) -> Self {
+        let max_points = max_points.max(1);
+        let bucket_count = (max_points / 2).max(1) as u64;
+        let span = range_end.saturating_sub(range_start);
+
+        Self {
+            range_start,
+            bucket_width: span.div_ceil(bucket_count).max(1),
+            emit_pair: max_points >= 2,
+            bucket: None,
+            pending: VecDeque::with_capacity(2),
+        }
+    }
+
+    /// Adds a point, flushing the current bucket if the point starts a new one.
+    fn push(&mut self, point: (u64, f64)) {
+        let index = point.0.saturating_sub(self.range_start) / self.bucket_width;
+
+        match &mut self.bucket {
+            Some(bucket) if bucket.index == index => {
+                if point.1 < bucket.min.1 {
+                    bucket.min = point;
+                }
+                if point.1 > bucket.max.1 {
+                    bucket.max = point;
+                }
+            }
+            _ => {
+                self.flush();
+                self.bucket = Some(Bucket {
+                    index,
+                    min: point,
+                    max: point,
+                });
+            }
+        }
+    }
+
+    /// Moves the current bucket's extremes into `pending`.
+    ///
+    /// Returns `false` if there was no bucket to flush.
+    fn flush(&mut self) -> bool {
+        let Some(bucket) = self.bucket.take() else {
+            return false;
+        };
+
+        if !self.emit_pair || bucket.min.0 == bucket.max.0 {
+            self.pending.push_back(bucket.max);
+        } else if bucket.min.0 < bucket.max.0 {
+            self.pending.push_back(bucket.min);
+            self.pending.push_back(bucket.max);
+        } else {
+            self.pending.push_back(bucket.max);
+            self.pending.push_back(bucket.min);
+        }
+        true
     }
 }
 
@@ -303,4 +629,51 @@ mod tests {
         assert!(!fully_covered);
         assert!(incomplete);
     }
+
+    fn downsample(points: &[(u64, f64)], range: (u64, u64), max_points: usize) -> Vec<(u64, f64)> {
+        let mut downsampler = Downsampler::new(range.0, range.1, max_points);
+        let mut out = Vec::new();
+        for &point in points {
+            downsampler.push(point);
+            out.extend(downsampler.pending.drain(..));
+        }
+        downsampler.flush();
+        out.extend(downsampler.pending.drain(..));
+        out
+    }
+
+    #[test]
+    fn test_downsampler_keeps_extremes() {
+        // 100 points, one spike and one dip, downsampled to 10 points
+        let mut points: Vec<_> = (0..100u64).map(|i| (i * 10, 1.0)).collect();
+        points[37].1 = 100.0;
+        points[62].1 = -100.0;
+
+        let out = downsample(&points, (0, 1000), 10);
+        assert!(out.len() <= 10);
+        assert!(out.contains(&(370, 100.0)));
+        assert!(out.contains(&(620, -100.0)));
+        assert!(out.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_downsampler_passes_sparse_data_through() {
+        let points = vec![(0, 1.0), (500, 2.0), (900, 3.0)];
+        let out = downsample(&points, (0, 1000), 100);
+        assert_eq!(out, points);
+    }
+
+    #[test]
+    fn test_downsampler_single_point() {
+        let points: Vec<_> = (0..10u32).map(|i| (u64::from(i), f64::from(i))).collect();
+        let out = downsample(&points, (0, 10), 1);
+        assert_eq!(out, vec![(9, 9.0)]);
+    }
+
+    #[test]
+    fn test_downsampler_min_before_max_ordering() {
+        let points = vec![(0, 5.0), (1, 9.0), (2, 1.0), (3, 5.0)];
+        let out = downsample(&points, (0, 4), 2);
+        assert_eq!(out, vec![(1, 9.0), (2, 1.0)]);
+    }
 }
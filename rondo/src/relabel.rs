@@ -0,0 +1,418 @@
+//! Prometheus-style relabeling of exported series.
+//!
+//! A list of [`RelabelConfig`] rules rewrites or filters a series' label set
+//! before an exporter encodes it, like `write_relabel_configs` in a
+//! Prometheus `remote_write` section. Rules see the metric name as the
+//! `__name__` label and run in order; a `keep` or `drop` rule can remove the
+//! series entirely.
+//!
+//! The remote-write, OTLP and Graphite exporters take rules per sink, so one
+//! sink can drop debug series while another still gets everything.
+//!
+//! # Example
+//!
+//! ```rust
+//! use rondo::relabel::{relabel, RelabelConfig};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let rules = vec![
+//!     RelabelConfig::drop(["__name__"], "debug_.*")?,
+//!     RelabelConfig::replace(["instance"], "vmm-(.*)", "vm", "$1")?,
+//!     RelabelConfig::labeldrop("instance")?,
+//! ];
+//!
+//! let mut labels = vec![
+//!     ("__name__".to_string(), "vcpu_exits_total".to_string()),
+//!     ("instance".to_string(), "vmm-7".to_string()),
+//! ];
+//! assert!(relabel(&mut labels, &rules));
+//! assert_eq!(labels[1], ("vm".to_string(), "7".to_string()));
+//! # Ok(())
+//! # }
+//! ```
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::error::{RelabelError, Result};
+
+/// What a [`RelabelConfig`] rule does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelabelAction {
+    /// Sets the target label to the expanded replacement if the source
+    /// value matches. An empty result removes the target label.
+    #[default]
+    Replace,
+    /// Drops the series unless the source value matches.
+    Keep,
+    /// Drops the series if the source value matches.
+    Drop,
+    /// Copies every label whose name matches to the expanded replacement
+    /// name.
+    LabelMap,
+    /// Removes every label whose name matches.
+    LabelDrop,
+    /// Removes every label whose name does not match.
+    LabelKeep,
+}
+
+/// One relabeling rule.
+///
+/// Regular expressions are anchored at both ends, as in Prometheus.
+/// Replacements may refer to capture groups as `$1` or `${name}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "RawRelabelConfig")]
+pub struct RelabelConfig {
+    action: RelabelAction,
+    source_labels: Vec<String>,
+    separator: String,
+    regex: Regex,
+    target_label: String,
+    replacement: String,
+}
+
+impl RelabelConfig {
+    fn new(action: RelabelAction, regex: &str) -> Result<Self> {
+        let anchored =
+            Regex::new(&format!("^(?:{regex})$")).map_err(|e| RelabelError::InvalidRegex {
+                regex: regex.to_string(),
+                source: e,
+            })?;
+        Ok(Self {
+            action,
+            source_labels: Vec::new(),
+            separator: ";".to_string(),
+            regex: anchored,
+            target_label: String::new(),
+            replacement: "$1".to_string(),
+        })
+    }
+
+    fn with_source_labels(mut self, labels: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.source_labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Keeps only series whose joined `source_labels` match `regex`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `regex` is not a valid regular expression.
+    pub fn keep(
+        source_labels: impl IntoIterator<Item = impl Into<String>>,
+        regex: &str,
+    ) -> Result<Self> {
+        Ok(Self::new(RelabelAction::Keep, regex)?.with_source_labels(source_labels))
+    }
+
+    /// Drops series whose joined `source_labels` match `regex`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `regex` is not a valid regular expression.
+    pub fn drop(
+        source_labels: impl IntoIterator<Item = impl Into<String>>,
+        regex: &str,
+    ) -> Result<Self> {
+        Ok(Self::new(RelabelAction::Drop, regex)?.with_source_labels(source_labels))
+    }
+
+    /// Sets `target_label` to `replacement` when the joined
+    /// `source_labels` match `regex`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `regex` is not a valid regular expression or
+    /// `target_label` is empty.
+    pub fn replace(
+        source_labels: impl IntoIterator<Item = impl Into<String>>,
+        regex: &str,
+        target_label: impl Into<String>,
+        replacement: impl Into<String>,
+    ) -> Result<Self> {
+        let target_label = target_label.into();
+        if target_label.is_empty() {
+            return Err(RelabelError::MissingTargetLabel.into());
+        }
+        let mut rule = Self::new(RelabelAction::Replace, regex)?.with_source_labels(source_labels);
+        rule.target_label = target_label;
+        rule.replacement = replacement.into();
+        Ok(rule)
+    }
+
+    /// Copies labels whose names match `regex` to the name `replacement`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `regex` is not a valid regular expression.
+    pub fn labelmap(regex: &str, replacement: impl Into<String>) -> Result<Self> {
+        let mut rule = Self::new(RelabelAction::LabelMap, regex)?;
+        rule.replacement = replacement.into();
+        Ok(rule)
+    }
+
+    /// Removes labels whose names match `regex`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `regex` is not a valid regular expression.
+    pub fn labeldrop(regex: &str) -> Result<Self> {
+        Self::new(RelabelAction::LabelDrop, regex)
+    }
+
+    /// Removes labels whose names do not match `regex`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `regex` is not a valid regular expression.
+    pub fn labelkeep(regex: &str) -> Result<Self> {
+        Self::new(RelabelAction::LabelKeep, regex)
+    }
+
+    /// Sets the string joining source label values (default `;`).
+    #[must_use]
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Returns the rule's action.
+    pub fn action(&self) -> RelabelAction {
+        self.action
+    }
+
+    /// Applies the rule. Returns `false` if the series is dropped.
+    fn apply(&self, labels: &mut Vec<(String, String)>) -> bool {
+        match self.action {
+            RelabelAction::Keep => self.regex.is_match(&self.source_value(labels)),
+            RelabelAction::Drop => !self.regex.is_match(&self.source_value(labels)),
+            RelabelAction::Replace => {
+                let value = self.source_value(labels);
+                if let Some(captures) = self.regex.captures(&value) {
+                    let mut target = String::new();
+                    captures.expand(&self.target_label, &mut target);
+                    let mut replacement = String::new();
+                    captures.expand(&self.replacement, &mut replacement);
+                    labels.retain(|(k, _)| *k != target);
+                    if !target.is_empty() && !replacement.is_empty() {
+                        labels.push((target, replacement));
+                    }
+                }
+                true
+            }
+            RelabelAction::LabelMap => {
+                let mapped: Vec<_> = labels
+                    .iter()
+                    .filter_map(|(name, value)| {
+                        let captures = self.regex.captures(name)?;
+                        let mut target = String::new();
+                        captures.expand(&self.replacement, &mut target);
+                        Some((target, value.clone()))
+                    })
+                    .collect();
+                for (name, value) in mapped {
+                    labels.retain(|(k, _)| *k != name);
+                    labels.push((name, value));
+                }
+                true
+            }
+            RelabelAction::LabelDrop => {
+                labels.retain(|(name, _)| !self.regex.is_match(name));
+                true
+            }
+            RelabelAction::LabelKeep => {
+                labels.retain(|(name, _)| self.regex.is_match(name));
+                true
+            }
+        }
+    }
+
+    /// Joins the source label values; missing labels count as empty.
+    fn source_value(&self, labels: &[(String, String)]) -> String {
+        let values: Vec<&str> = self
+            .source_labels
+            .iter()
+            .map(|source| {
+                labels
+                    .iter()
+                    .find(|(k, _)| k == source)
+                    .map_or("", |(_, v)| v.as_str())
+            })
+            .collect();
+        values.join(&self.separator)
+    }
+}
+
+/// A rule as written in a config file, with Prometheus' defaults.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRelabelConfig {
+    #[serde(default)]
+    source_labels: Vec<String>,
+    separator: Option<String>,
+    regex: Option<String>,
+    target_label: Option<String>,
+    replacement: Option<String>,
+    #[serde(default)]
+    action: RelabelAction,
+}
+
+impl TryFrom<RawRelabelConfig> for RelabelConfig {
+    type Error = crate::RondoError;
+
+    fn try_from(raw: RawRelabelConfig) -> Result<Self> {
+        if raw.action == RelabelAction::Replace && raw.target_label.is_none() {
+            return Err(RelabelError::MissingTargetLabel.into());
+        }
+        let mut rule = Self::new(raw.action, raw.regex.as_deref().unwrap_or("(.*)"))?
+            .with_source_labels(raw.source_labels);
+        if let Some(separator) = raw.separator {
+            rule.separator = separator;
+        }
+        if let Some(target_label) = raw.target_label {
+            rule.target_label = target_label;
+        }
+        if let Some(replacement) = raw.replacement {
+            rule.replacement = replacement;
+        }
+        Ok(rule)
+    }
+}
+
+/// Applies `rules` in order to a label set that includes `__name__`.
+///
+/// Returns `false` if a rule dropped the series; `labels` is then left in
+/// an unspecified state. Otherwise the labels are sorted by name.
+pub fn relabel(labels: &mut Vec<(String, String)>, rules: &[RelabelConfig]) -> bool {
+    for rule in rules {
+        if !rule.apply(labels) {
+            return false;
+        }
+    }
+    labels.sort_unstable();
+    true
+}
+
+/// Relabels a series given as its name and labels.
+///
+/// Returns the new name and the remaining labels, sorted, or `None` if the
+/// series is dropped or loses its `__name__`.
+#[cfg(any(feature = "otlp", feature = "graphite"))]
+pub(crate) fn relabel_series(
+    name: &str,
+    labels: &[(String, String)],
+    rules: &[RelabelConfig],
+) -> Option<(String, Vec<(String, String)>)> {
+    let mut all = Vec::with_capacity(labels.len() + 1);
+    all.push(("__name__".to_string(), name.to_string()));
+    all.extend(labels.iter().filter(|(k, _)| k != "__name__").cloned());
+    if !relabel(&mut all, rules) {
+        return None;
+    }
+    let position = all.iter().position(|(k, _)| k == "__name__")?;
+    let (_, name) = all.remove(position);
+    Some((name, all))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_keep_and_drop() {
+        let series = labels(&[("__name__", "debug_queue_depth"), ("device", "vda")]);
+
+        let drop = [RelabelConfig::drop(["__name__"], "debug_.*").unwrap()];
+        assert!(!relabel(&mut series.clone(), &drop));
+
+        // Anchored: a partial match does not count
+        let drop = [RelabelConfig::drop(["__name__"], "debug").unwrap()];
+        assert!(relabel(&mut series.clone(), &drop));
+
+        let keep = [RelabelConfig::keep(["__name__", "device"], "debug_.*;vd[a-z]").unwrap()];
+        assert!(relabel(&mut series.clone(), &keep));
+        let keep = [RelabelConfig::keep(["missing"], ".+").unwrap()];
+        assert!(!relabel(&mut series.clone(), &keep));
+    }
+
+    #[test]
+    fn test_replace() {
+        let mut series = labels(&[("__name__", "up"), ("instance", "vmm-7:9100")]);
+        let rules = [
+            RelabelConfig::replace(["instance"], "vmm-(\\d+):.*", "vm", "vm${1}").unwrap(),
+            // No match: unchanged
+            RelabelConfig::replace(["instance"], "other", "instance", "x").unwrap(),
+            // Empty replacement removes the target
+            RelabelConfig::replace(["__name__"], ".*", "instance", "").unwrap(),
+        ];
+        assert!(relabel(&mut series, &rules));
+        assert_eq!(series, labels(&[("__name__", "up"), ("vm", "vm7")]));
+
+        assert!(RelabelConfig::replace(["a"], ".*", "", "$1").is_err());
+        assert!(RelabelConfig::keep(["a"], "(").is_err());
+    }
+
+    #[test]
+    fn test_labelmap_labeldrop_labelkeep() {
+        let mut series = labels(&[("__name__", "up"), ("__meta_zone", "eu"), ("pid", "42")]);
+        let rules = [
+            RelabelConfig::labelmap("__meta_(.+)", "$1").unwrap(),
+            RelabelConfig::labeldrop("__meta_.*").unwrap(),
+        ];
+        assert!(relabel(&mut series, &rules));
+        assert_eq!(
+            series,
+            labels(&[("__name__", "up"), ("pid", "42"), ("zone", "eu")])
+        );
+
+        let rules = [RelabelConfig::labelkeep("__name__|zone").unwrap()];
+        assert!(relabel(&mut series, &rules));
+        assert_eq!(series, labels(&[("__name__", "up"), ("zone", "eu")]));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let rules: Vec<RelabelConfig> = serde_json::from_str(
+            r#"[
+                {"source_labels": ["__name__"], "regex": "vcpu_(.*)", "target_label": "__name__", "replacement": "vm_vcpu_$1"},
+                {"action": "labeldrop", "regex": "pid"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(rules[0].action(), RelabelAction::Replace);
+
+        // Replace needs a target label
+        let missing: std::result::Result<Vec<RelabelConfig>, _> =
+            serde_json::from_str(r#"[{"source_labels": ["a"]}]"#);
+        assert!(missing.is_err());
+    }
+
+    #[cfg(any(feature = "otlp", feature = "graphite"))]
+    #[test]
+    fn test_relabel_series() {
+        let rules = [
+            RelabelConfig::replace(["__name__"], "vcpu_(.*)", "__name__", "vm_vcpu_$1").unwrap(),
+            RelabelConfig::labeldrop("pid").unwrap(),
+        ];
+        let (name, rest) = relabel_series(
+            "vcpu_exits_total",
+            &labels(&[("pid", "1"), ("reason", "io")]),
+            &rules,
+        )
+        .unwrap();
+        assert_eq!(name, "vm_vcpu_exits_total");
+        assert_eq!(rest, labels(&[("reason", "io")]));
+
+        // Removing the name drops the series
+        let rules = [RelabelConfig::labeldrop("__name__").unwrap()];
+        assert!(relabel_series("up", &[], &rules).is_none());
+    }
+}
@@ -0,0 +1,456 @@
+//! Prometheus remote-read server for pulling history out of a rondo store.
+//!
+//! Remote-write pushes every point to a central Prometheus. Remote-read is
+//! the pull-based alternative: Prometheus keeps a `remote_read` entry
+//! pointing at the embedding application and fetches only the series and
+//! time ranges its queries touch.
+//!
+//! [`handle`] takes the raw HTTP request body (a snappy-compressed
+//! `ReadRequest`) and returns the snappy-compressed `ReadResponse` to send
+//! back with [`RESPONSE_CONTENT_TYPE`] and `Content-Encoding: snappy`. The
+//! embedding application provides the HTTP server.
+//!
+//! Only the `SAMPLES` response type is supported; streamed XOR chunks are
+//! not. Each query is answered with stitched [`Store::query_auto_with`]
+//! reads, and the query's step hint selects the coarsest tier that still
+//! resolves it, the same way PromQL range queries do.
+//!
+//! This module is only available when the `prometheus-remote-write` feature
+//! is enabled.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::remote_read;
+//! use rondo::store::Store;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let store = Store::open("./data", vec![])?;
+//! # let request_body: Vec<u8> = Vec::new();
+//! let external_labels = vec![("instance".to_string(), "vmm_1".to_string())];
+//! let response_body = remote_read::handle(&store, &request_body, &external_labels)?;
+//! // Reply with Content-Type: application/x-protobuf, Content-Encoding: snappy
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+use prost::Message;
+
+use crate::error::{RemoteReadError, Result};
+use crate::promql::ast::{MatchOp, Matcher, VectorSelector};
+use crate::promql::select_series;
+use crate::query::QueryOptions;
+use crate::remote_write::proto::{self, MatcherType, ResponseType};
+use crate::remote_write::{build_labels, build_samples};
+use crate::store::Store;
+
+/// `Content-Type` of a remote-read response.
+pub const RESPONSE_CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Answers a snappy-compressed remote-read request.
+///
+/// `external_labels` are attached to every returned series, as [`push`]
+/// does for remote-write. Matchers on an external label are checked against
+/// its value instead of the store, so a Prometheus that adds its configured
+/// external labels as matchers still finds the series.
+///
+/// [`push`]: crate::remote_write::push
+///
+/// # Errors
+///
+/// Returns [`RemoteReadError`] if the body cannot be decompressed or
+/// decoded, the client does not accept sample responses, a matcher is
+/// invalid, or the response cannot be encoded. Store read failures are
+/// returned as-is.
+pub fn handle(store: &Store, body: &[u8], external_labels: &[(String, String)]) -> Result<Vec<u8>> {
+    let request = decode_request(body)?;
+    let response = read(store, &request, external_labels)?;
+
+    let mut buf = Vec::with_capacity(response.encoded_len());
+    response
+        .encode(&mut buf)
+        .map_err(|e| RemoteReadError::Serialization { source: e })?;
+
+    snap::raw::Encoder::new()
+        .compress_vec(&buf)
+        .map_err(|e| RemoteReadError::Compression { source: e })
+        .map_err(Into::into)
+}
+
+/// Decodes a snappy-compressed `ReadRequest`.
+///
+/// # Errors
+///
+/// Returns [`RemoteReadError::Decompression`] or [`RemoteReadError::Decode`]
+/// if the body is malformed.
+pub fn decode_request(body: &[u8]) -> Result<proto::ReadRequest> {
+    let decompressed = snap::raw::Decoder::new()
+        .decompress_vec(body)
+        .map_err(|e| RemoteReadError::Decompression { source: e })?;
+    proto::ReadRequest::decode(decompressed.as_slice())
+        .map_err(|e| RemoteReadError::Decode { source: e }.into())
+}
+
+/// Answers a decoded `ReadRequest`, one result per query.
+///
+/// Series without samples in a query's window are omitted from its result.
+///
+/// # Errors
+///
+/// Returns [`RemoteReadError::UnsupportedResponseType`] if the client only
+/// accepts streamed chunks, [`RemoteReadError::InvalidMatcher`] for unknown
+/// matcher types or invalid regular expressions, and any store read error.
+pub fn read(
+    store: &Store,
+    request: &proto::ReadRequest,
+    external_labels: &[(String, String)],
+) -> Result<proto::ReadResponse> {
+    // An empty list means the client predates response negotiation
+    if !request.accepted_response_types.is_empty()
+        && !request
+            .accepted_response_types
+            .contains(&(ResponseType::Samples as i32))
+    {
+        return Err(RemoteReadError::UnsupportedResponseType.into());
+    }
+
+    let results = request
+        .queries
+        .iter()
+        .map(|query| read_query(store, query, external_labels))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(proto::ReadResponse { results })
+}
+
+/// Answers a single query.
+fn read_query(
+    store: &Store,
+    query: &proto::Query,
+    external_labels: &[(String, String)],
+) -> Result<proto::QueryResult> {
+    let mut matchers = Vec::with_capacity(query.matchers.len());
+    for m in &query.matchers {
+        let matcher = convert_matcher(m)?;
+        match external_labels.iter().find(|(name, _)| *name == m.name) {
+            Some((_, value)) if !matcher.matches(value) => {
+                return Ok(proto::QueryResult::default());
+            }
+            Some(_) => {}
+            None => matchers.push(matcher),
+        }
+    }
+
+    // Prometheus timestamps are inclusive milliseconds, store ends exclusive
+    let start_ns = ms_to_ns(query.start_timestamp_ms);
+    let end_ns = ms_to_ns(query.end_timestamp_ms).saturating_add(1);
+    if start_ns >= end_ns {
+        return Ok(proto::QueryResult::default());
+    }
+
+    let mut options = QueryOptions::new().with_stitching(true);
+    if let Some(resolution) = query.hints.as_ref().and_then(hint_resolution) {
+        options = options.with_resolution(resolution);
+    }
+
+    let selector = VectorSelector {
+        name: None,
+        matchers,
+        offset: Duration::ZERO,
+    };
+
+    let mut timeseries = Vec::new();
+    for (handle, _) in select_series(store, &selector) {
+        let points = store
+            .query_auto_with(handle, start_ns, end_ns, &options)?
+            .collect_all();
+        if points.is_empty() {
+            continue;
+        }
+        let Some((name, labels)) = store.series_info(&handle) else {
+            continue;
+        };
+        timeseries.push(proto::TimeSeries {
+            labels: build_labels(name, labels, external_labels),
+            samples: build_samples(&points),
+        });
+    }
+
+    Ok(proto::QueryResult { timeseries })
+}
+
+/// Converts a protobuf label matcher to a PromQL matcher.
+fn convert_matcher(matcher: &proto::LabelMatcher) -> Result<Matcher> {
+    let invalid = |reason: String| RemoteReadError::InvalidMatcher {
+        name: matcher.name.clone(),
+        reason,
+    };
+
+    let op = match MatcherType::try_from(matcher.r#type) {
+        Ok(MatcherType::Eq) => MatchOp::Equal,
+        Ok(MatcherType::Neq) => MatchOp::NotEqual,
+        Ok(MatcherType::Re) => MatchOp::RegexMatch,
+        Ok(MatcherType::Nre) => MatchOp::RegexNoMatch,
+        Err(_) => return Err(invalid(format!("unknown matcher type {}", matcher.r#type)).into()),
+    };
+
+    Matcher::new(matcher.name.clone(), op, matcher.value.clone())
+        .map_err(|e| invalid(e.to_string()).into())
+}
+
+/// Returns the resolution a query's hints ask for, if any.
+///
+/// Like PromQL range queries, the resolution never exceeds half the
+/// selector's range, so range functions still see at least two points.
+fn hint_resolution(hints: &proto::ReadHints) -> Option<Duration> {
+    let step_ms = u64::try_from(hints.step_ms).ok().filter(|&s| s > 0)?;
+    let mut resolution = Duration::from_millis(step_ms);
+    if let Ok(range_ms) = u64::try_from(hints.range_ms)
+        && range_ms > 0
+    {
+        resolution = resolution.min(Duration::from_millis(range_ms / 2));
+    }
+    Some(resolution)
+}
+
+/// Converts epoch milliseconds to nanoseconds, clamping negatives to zero.
+fn ms_to_ns(ms: i64) -> u64 {
+    u64::try_from(ms).unwrap_or(0).saturating_mul(1_000_000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{ConsolidationFn, LabelMatcher, SchemaConfig, TierConfig};
+
+    const S: u64 = 1_000_000_000;
+    const BASE: u64 = 1_700_000_000 * S;
+
+    fn create_test_store(dir: &std::path::Path) -> Store {
+        let schemas = vec![SchemaConfig {
+            name: "test".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig::new(Duration::from_secs(1), Duration::from_secs(600), None).unwrap(),
+                TierConfig::new(
+                    Duration::from_secs(60),
+                    Duration::from_secs(3600),
+                    Some(ConsolidationFn::Average),
+                )
+                .unwrap(),
+            ],
+            max_series: 10,
+        }];
+        let mut store = Store::open(dir.join("store"), schemas).unwrap();
+
+        let a = store
+            .register("cpu", &[("host".to_string(), "a".to_string())])
+            .unwrap();
+        let b = store
+            .register("cpu", &[("host".to_string(), "b".to_string())])
+            .unwrap();
+        let mem = store.register("mem", &[]).unwrap();
+        for i in 0..300u32 {
+            let ts = BASE + u64::from(i) * S;
+            store.record(a, f64::from(i), ts).unwrap();
+            store.record(b, 1.0, ts).unwrap();
+            store.record(mem, 2.0, ts).unwrap();
+        }
+        store.consolidate().unwrap();
+        store
+    }
+
+    fn matcher(r#type: MatcherType, name: &str, value: &str) -> proto::LabelMatcher {
+        proto::LabelMatcher {
+            r#type: r#type as i32,
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap)] // Test timestamps are far below i64::MAX
+    fn query(start_ns: u64, end_ns: u64, matchers: Vec<proto::LabelMatcher>) -> proto::Query {
+        proto::Query {
+            start_timestamp_ms: (start_ns / 1_000_000) as i64,
+            end_timestamp_ms: (end_ns / 1_000_000) as i64,
+            matchers,
+            hints: None,
+        }
+    }
+
+    fn label<'a>(ts: &'a proto::TimeSeries, name: &str) -> Option<&'a str> {
+        ts.labels
+            .iter()
+            .find(|l| l.name == name)
+            .map(|l| l.value.as_str())
+    }
+
+    #[test]
+    fn test_read_matchers_and_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_test_store(dir.path());
+
+        let request = proto::ReadRequest {
+            queries: vec![
+                query(
+                    BASE + 10 * S,
+                    BASE + 19 * S,
+                    vec![
+                        matcher(MatcherType::Eq, "__name__", "cpu"),
+                        matcher(MatcherType::Re, "host", "a|c"),
+                    ],
+                ),
+                query(
+                    BASE,
+                    BASE + 299 * S,
+                    vec![matcher(MatcherType::Neq, "__name__", "cpu")],
+                ),
+            ],
+            accepted_response_types: vec![ResponseType::Samples as i32],
+        };
+
+        let response = read(&store, &request, &[]).unwrap();
+        assert_eq!(response.results.len(), 2);
+
+        let series = &response.results[0].timeseries;
+        assert_eq!(series.len(), 1);
+        assert_eq!(label(&series[0], "host"), Some("a"));
+        // Both ends are inclusive
+        assert_eq!(series[0].samples.len(), 10);
+        assert_eq!(series[0].samples[0].value, 10.0);
+        assert_eq!(series[0].samples[0].timestamp, 1_700_000_010_000);
+
+        let series = &response.results[1].timeseries;
+        assert_eq!(series.len(), 1);
+        assert_eq!(label(&series[0], "__name__"), Some("mem"));
+        assert_eq!(series[0].samples.len(), 300);
+    }
+
+    #[test]
+    fn test_read_external_labels() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_test_store(dir.path());
+        let external = vec![("instance".to_string(), "vmm_1".to_string())];
+
+        let matching = proto::ReadRequest {
+            queries: vec![query(
+                BASE,
+                BASE + 10 * S,
+                vec![
+                    matcher(MatcherType::Eq, "__name__", "mem"),
+                    matcher(MatcherType::Eq, "instance", "vmm_1"),
+                ],
+            )],
+            accepted_response_types: vec![],
+        };
+        let response = read(&store, &matching, &external).unwrap();
+        let series = &response.results[0].timeseries;
+        assert_eq!(series.len(), 1);
+        assert_eq!(label(&series[0], "instance"), Some("vmm_1"));
+
+        let other = proto::ReadRequest {
+            queries: vec![query(
+                BASE,
+                BASE + 10 * S,
+                vec![matcher(MatcherType::Eq, "instance", "vmm_2")],
+            )],
+            accepted_response_types: vec![],
+        };
+        let response = read(&store, &other, &external).unwrap();
+        assert!(response.results[0].timeseries.is_empty());
+    }
+
+    #[test]
+    fn test_read_step_hint_selects_coarser_tier() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_test_store(dir.path());
+
+        let mut q = query(
+            BASE,
+            BASE + 299 * S,
+            vec![matcher(MatcherType::Eq, "__name__", "mem")],
+        );
+        q.hints = Some(proto::ReadHints {
+            step_ms: 60_000,
+            ..proto::ReadHints::default()
+        });
+        let request = proto::ReadRequest {
+            queries: vec![q],
+            accepted_response_types: vec![],
+        };
+
+        let response = read(&store, &request, &[]).unwrap();
+        let samples = &response.results[0].timeseries[0].samples;
+        assert!(
+            !samples.is_empty() && samples.len() <= 5,
+            "got {} samples",
+            samples.len()
+        );
+    }
+
+    #[test]
+    fn test_handle_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_test_store(dir.path());
+
+        let request = proto::ReadRequest {
+            queries: vec![query(
+                BASE,
+                BASE + 4 * S,
+                vec![matcher(MatcherType::Eq, "host", "b")],
+            )],
+            accepted_response_types: vec![ResponseType::Samples as i32],
+        };
+        let body = snap::raw::Encoder::new()
+            .compress_vec(&request.encode_to_vec())
+            .unwrap();
+
+        let response_body = handle(&store, &body, &[]).unwrap();
+        let decompressed = snap::raw::Decoder::new()
+            .decompress_vec(&response_body)
+            .unwrap();
+        let response = proto::ReadResponse::decode(decompressed.as_slice()).unwrap();
+        assert_eq!(response.results[0].timeseries[0].samples.len(), 5);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = create_test_store(dir.path());
+
+        assert!(matches!(
+            handle(&store, b"not snappy", &[]),
+            Err(crate::RondoError::RemoteRead(
+                RemoteReadError::Decompression { .. }
+            ))
+        ));
+
+        let chunks_only = proto::ReadRequest {
+            queries: vec![],
+            accepted_response_types: vec![ResponseType::StreamedXorChunks as i32],
+        };
+        assert!(matches!(
+            read(&store, &chunks_only, &[]),
+            Err(crate::RondoError::RemoteRead(
+                RemoteReadError::UnsupportedResponseType
+            ))
+        ));
+
+        let bad_regex = proto::ReadRequest {
+            queries: vec![query(
+                BASE,
+                BASE + S,
+                vec![matcher(MatcherType::Re, "host", "(")],
+            )],
+            accepted_response_types: vec![],
+        };
+        assert!(matches!(
+            read(&store, &bad_regex, &[]),
+            Err(crate::RondoError::RemoteRead(
+                RemoteReadError::InvalidMatcher { .. }
+            ))
+        ));
+    }
+}
@@ -4,6 +4,19 @@
 //! and pushes it to a configurable endpoint with snappy compression and
 //! basic retry logic.
 //!
+//! Both remote-write 1.0 (`prometheus.WriteRequest`) and 2.0
+//! (`io.prometheus.write.v2.Request`) are supported, chosen with
+//! [`RemoteWriteConfig::with_protocol`]. 2.0 interns label strings and
+//! carries each metric's kind, unit and help from
+//! [`Store::metadata`](crate::store::Store::metadata). A receiver that
+//! answers a 2.0 request with `415 Unsupported Media Type` is retried with
+//! 1.0, as Prometheus does.
+//!
+//! Requests can authenticate with basic auth or a bearer token
+//! ([`RemoteWriteAuth`]) and present a client certificate
+//! ([`TlsConfig`]). Token, certificate and key files are read again on
+//! every push, so rotating them does not need a restart.
+//!
 //! This module is only available when the `prometheus-remote-write` feature
 //! is enabled.
 //!
@@ -27,24 +40,34 @@
 //! let config = RemoteWriteConfig::new("http://localhost:9090/api/v1/write");
 //! let mut cursor = ExportCursor::load_or_new("/tmp/cursor_prom.json")?;
 //!
-//! let exports = store.drain(0, &mut cursor)?;
-//! push(&config, &exports, &store, &[])?;
+//! // Only commit the cursor once the push has succeeded
+//! let pending = store.drain_pending(0, &cursor)?;
+//! push(&config, &pending.exports, &store, &[])?;
+//! cursor.commit(&pending);
 //! cursor.save()?;
 //! # Ok(())
 //! # }
 //! ```
 
-use std::time::Duration;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use prost::Message;
+use serde::Deserialize;
 
-use crate::error::{RemoteWriteError, Result};
-use crate::export::SeriesExport;
+use crate::error::{ExportError, RemoteWriteError, Result};
+use crate::export::{RollupNaming, SeriesExport, rollup_identity};
+use crate::exporter::{Exporter, attempt_timeout};
+use crate::relabel::{self, RelabelConfig};
+use crate::series::{MetricKind, MetricMetadata};
 use crate::store::Store;
 
-/// Prometheus remote-write protobuf types.
+/// Prometheus remote-write and remote-read protobuf types.
 ///
-/// Hand-written types matching `prometheus/prompb/remote.proto`.
+/// Hand-written types matching `prometheus/prompb/remote.proto` and
+/// `prometheus/prompb/types.proto`.
 /// Using prost derives avoids the need for protoc and proto file management.
 pub mod proto {
     /// A write request containing one or more time series.
@@ -53,6 +76,48 @@ pub mod proto {
         /// The time series to write.
         #[prost(message, repeated, tag = "1")]
         pub timeseries: Vec<TimeSeries>,
+        /// Metadata of the metric families in the request.
+        #[prost(message, repeated, tag = "3")]
+        pub metadata: Vec<MetricMetadata>,
+    }
+
+    /// Type, help and unit of a metric family.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct MetricMetadata {
+        /// The metric type.
+        #[prost(enumeration = "MetricType", tag = "1")]
+        pub r#type: i32,
+        /// The metric family name.
+        #[prost(string, tag = "2")]
+        pub metric_family_name: String,
+        /// Help text.
+        #[prost(string, tag = "4")]
+        pub help: String,
+        /// Unit of the values.
+        #[prost(string, tag = "5")]
+        pub unit: String,
+    }
+
+    /// Metric types in 1.0 metadata.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum MetricType {
+        /// Not known.
+        Unknown = 0,
+        /// Monotonic counter.
+        Counter = 1,
+        /// Gauge.
+        Gauge = 2,
+        /// Classic histogram.
+        Histogram = 3,
+        /// Gauge histogram.
+        GaugeHistogram = 4,
+        /// Summary.
+        Summary = 5,
+        /// Info metric.
+        Info = 6,
+        /// State set.
+        StateSet = 7,
     }
 
     /// A single time series with labels and samples.
@@ -87,6 +152,275 @@ pub mod proto {
         #[prost(int64, tag = "2")]
         pub timestamp: i64,
     }
+
+    /// A remote-read request containing one or more queries.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ReadRequest {
+        /// The queries to answer, in order.
+        #[prost(message, repeated, tag = "1")]
+        pub queries: Vec<Query>,
+        /// Response types the client accepts, in order of preference.
+        #[prost(enumeration = "ResponseType", repeated, tag = "2")]
+        pub accepted_response_types: Vec<i32>,
+    }
+
+    /// Remote-read response encodings.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum ResponseType {
+        /// A single `ReadResponse` with raw samples.
+        Samples = 0,
+        /// A stream of XOR-encoded chunks.
+        StreamedXorChunks = 1,
+    }
+
+    /// A single remote-read query.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Query {
+        /// Inclusive start of the query in milliseconds since epoch.
+        #[prost(int64, tag = "1")]
+        pub start_timestamp_ms: i64,
+        /// Inclusive end of the query in milliseconds since epoch.
+        #[prost(int64, tag = "2")]
+        pub end_timestamp_ms: i64,
+        /// Matchers selecting the series to return.
+        #[prost(message, repeated, tag = "3")]
+        pub matchers: Vec<LabelMatcher>,
+        /// Optional hints about the PromQL evaluation behind the query.
+        #[prost(message, optional, tag = "4")]
+        pub hints: Option<ReadHints>,
+    }
+
+    /// A label matcher in a remote-read query.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct LabelMatcher {
+        /// The match operator.
+        #[prost(enumeration = "MatcherType", tag = "1")]
+        pub r#type: i32,
+        /// Label name.
+        #[prost(string, tag = "2")]
+        pub name: String,
+        /// Value or regular expression to match against.
+        #[prost(string, tag = "3")]
+        pub value: String,
+    }
+
+    /// Label matcher operators.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum MatcherType {
+        /// `=`
+        Eq = 0,
+        /// `!=`
+        Neq = 1,
+        /// `=~`
+        Re = 2,
+        /// `!~`
+        Nre = 3,
+    }
+
+    /// Evaluation hints sent along with a remote-read query.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ReadHints {
+        /// Query step size in milliseconds.
+        #[prost(int64, tag = "1")]
+        pub step_ms: i64,
+        /// Function wrapping the selector, if any.
+        #[prost(string, tag = "2")]
+        pub func: String,
+        /// Start of the selector window in milliseconds since epoch.
+        #[prost(int64, tag = "3")]
+        pub start_ms: i64,
+        /// End of the selector window in milliseconds since epoch.
+        #[prost(int64, tag = "4")]
+        pub end_ms: i64,
+        /// Labels of the surrounding aggregation's grouping clause.
+        #[prost(string, repeated, tag = "5")]
+        pub grouping: Vec<String>,
+        /// Whether the grouping is `by` (true) or `without` (false).
+        #[prost(bool, tag = "6")]
+        pub by: bool,
+        /// Range of the selector in milliseconds.
+        #[prost(int64, tag = "7")]
+        pub range_ms: i64,
+    }
+
+    /// A remote-read response, one result per query.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ReadResponse {
+        /// Results in the same order as the request's queries.
+        #[prost(message, repeated, tag = "1")]
+        pub results: Vec<QueryResult>,
+    }
+
+    /// The series returned for one remote-read query.
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct QueryResult {
+        /// Matching series with their samples in the query window.
+        #[prost(message, repeated, tag = "1")]
+        pub timeseries: Vec<TimeSeries>,
+    }
+
+    /// Remote-write 2.0 types, matching `prometheus/prompb/io/prometheus/write/v2/types.proto`.
+    ///
+    /// Native histograms and exemplars are not produced by rondo and are
+    /// left out.
+    pub mod v2 {
+        /// A write request with interned strings.
+        #[derive(Clone, PartialEq, prost::Message)]
+        pub struct Request {
+            /// Deduplicated strings referenced by index; the first is always
+            /// the empty string.
+            #[prost(string, repeated, tag = "4")]
+            pub symbols: Vec<String>,
+            /// The time series to write.
+            #[prost(message, repeated, tag = "5")]
+            pub timeseries: Vec<TimeSeries>,
+        }
+
+        /// A single time series with label references, samples and metadata.
+        #[derive(Clone, PartialEq, prost::Message)]
+        pub struct TimeSeries {
+            /// Alternating name and value indexes into `symbols`.
+            #[prost(uint32, repeated, tag = "1")]
+            pub labels_refs: Vec<u32>,
+            /// Data samples for this series.
+            #[prost(message, repeated, tag = "2")]
+            pub samples: Vec<Sample>,
+            /// Metric metadata.
+            #[prost(message, optional, tag = "5")]
+            pub metadata: Option<Metadata>,
+            /// Creation time of a counter, histogram or summary in
+            /// milliseconds, 0 if unknown.
+            #[prost(int64, tag = "6")]
+            pub created_timestamp: i64,
+        }
+
+        /// A single data sample (value + timestamp).
+        #[derive(Clone, PartialEq, prost::Message)]
+        pub struct Sample {
+            /// The sample value.
+            #[prost(double, tag = "1")]
+            pub value: f64,
+            /// Timestamp in milliseconds since epoch.
+            #[prost(int64, tag = "2")]
+            pub timestamp: i64,
+        }
+
+        /// Metadata of the series' metric.
+        #[derive(Clone, PartialEq, prost::Message)]
+        pub struct Metadata {
+            /// The metric type.
+            #[prost(enumeration = "MetricType", tag = "1")]
+            pub r#type: i32,
+            /// Index of the help text in `symbols`.
+            #[prost(uint32, tag = "3")]
+            pub help_ref: u32,
+            /// Index of the unit in `symbols`.
+            #[prost(uint32, tag = "4")]
+            pub unit_ref: u32,
+        }
+
+        /// Metric type carried in [`Metadata`].
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+        #[repr(i32)]
+        pub enum MetricType {
+            /// Unknown type.
+            Unspecified = 0,
+            /// Counter.
+            Counter = 1,
+            /// Gauge.
+            Gauge = 2,
+            /// Histogram.
+            Histogram = 3,
+            /// Gauge histogram.
+            Gaugehistogram = 4,
+            /// Summary.
+            Summary = 5,
+            /// Info metric.
+            Info = 6,
+            /// State set.
+            Stateset = 7,
+        }
+    }
+}
+
+/// Remote-write protocol version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteWriteProtocol {
+    /// Remote-write 1.0 (`prometheus.WriteRequest`).
+    #[default]
+    V1,
+    /// Remote-write 2.0 (`io.prometheus.write.v2.Request`).
+    V2,
+}
+
+impl RemoteWriteProtocol {
+    /// Returns the request `Content-Type`.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::V1 => "application/x-protobuf",
+            Self::V2 => "application/x-protobuf;proto=io.prometheus.write.v2.Request",
+        }
+    }
+
+    /// Returns the `X-Prometheus-Remote-Write-Version` header value.
+    pub fn version(self) -> &'static str {
+        match self {
+            Self::V1 => "0.1.0",
+            Self::V2 => "2.0.0",
+        }
+    }
+}
+
+/// How remote-write requests authenticate.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteWriteAuth {
+    /// HTTP basic authentication.
+    Basic {
+        /// The user name.
+        username: String,
+        /// The password.
+        password: String,
+    },
+    /// A fixed bearer token.
+    BearerToken(String),
+    /// A bearer token read from a file on every push, so a rotated token
+    /// is picked up without a restart. Surrounding whitespace is ignored.
+    BearerTokenFile(PathBuf),
+}
+
+impl std::fmt::Debug for RemoteWriteAuth {
+    /// Keeps secrets out of logs.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Basic { username, .. } => f
+                .debug_struct("Basic")
+                .field("username", username)
+                .field("password", &"<secret>")
+                .finish(),
+            Self::BearerToken(_) => f.debug_tuple("BearerToken").field(&"<secret>").finish(),
+            Self::BearerTokenFile(path) => f.debug_tuple("BearerTokenFile").field(path).finish(),
+        }
+    }
+}
+
+/// TLS settings for the remote-write connection.
+///
+/// Files are PEM encoded and read on every push.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct TlsConfig {
+    /// CA certificate trusted in addition to the system roots.
+    pub ca_file: Option<PathBuf>,
+    /// Client certificate presented to the server (needs `key_file`).
+    pub cert_file: Option<PathBuf>,
+    /// Private key of the client certificate (needs `cert_file`).
+    pub key_file: Option<PathBuf>,
+    /// Accept any server certificate. Only for testing.
+    pub insecure_skip_verify: bool,
 }
 
 /// Configuration for a Prometheus remote-write endpoint.
@@ -102,12 +436,28 @@ pub struct RemoteWriteConfig {
     pub retry_backoff: Duration,
     /// Optional HTTP headers (e.g., for authentication).
     pub headers: Vec<(String, String)>,
+    /// Maximum number of samples per `WriteRequest`.
+    pub max_samples_per_send: usize,
+    /// Number of shards sending concurrently.
+    pub shards: usize,
+    /// Protocol version to send.
+    pub protocol: RemoteWriteProtocol,
+    /// Request authentication, if any.
+    pub auth: Option<RemoteWriteAuth>,
+    /// TLS settings, if the defaults are not enough.
+    pub tls: Option<TlsConfig>,
+    /// Relabeling rules applied to each series after external labels are
+    /// added; series they drop are not sent.
+    pub write_relabel_configs: Vec<RelabelConfig>,
+    /// How series drained from rollup tiers are marked.
+    pub rollup_naming: RollupNaming,
 }
 
 impl RemoteWriteConfig {
     /// Creates a new config with sensible defaults.
     ///
-    /// Defaults: 30s timeout, 3 retries, 100ms initial backoff.
+    /// Defaults: 30s timeout, 3 retries, 100ms initial backoff, 2000
+    /// samples per send (as in Prometheus), 1 shard, remote-write 1.0.
     pub fn new(endpoint: impl Into<String>) -> Self {
         Self {
             endpoint: endpoint.into(),
@@ -115,6 +465,13 @@ impl RemoteWriteConfig {
             max_retries: 3,
             retry_backoff: Duration::from_millis(100),
             headers: Vec::new(),
+            max_samples_per_send: 2000,
+            shards: 1,
+            protocol: RemoteWriteProtocol::V1,
+            auth: None,
+            tls: None,
+            write_relabel_configs: Vec::new(),
+            rollup_naming: RollupNaming::Label,
         }
     }
 
@@ -138,6 +495,120 @@ impl RemoteWriteConfig {
         self.max_retries = max_retries;
         self
     }
+
+    /// Sets the maximum number of samples per request (at least 1).
+    #[must_use]
+    pub fn with_max_samples_per_send(mut self, max_samples_per_send: usize) -> Self {
+        self.max_samples_per_send = max_samples_per_send.max(1);
+        self
+    }
+
+    /// Sets the number of concurrent shards (at least 1).
+    #[must_use]
+    pub fn with_shards(mut self, shards: usize) -> Self {
+        self.shards = shards.max(1);
+        self
+    }
+
+    /// Sets the protocol version to send.
+    #[must_use]
+    pub fn with_protocol(mut self, protocol: RemoteWriteProtocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Sets the request authentication.
+    #[must_use]
+    pub fn with_auth(mut self, auth: RemoteWriteAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Sets the TLS settings.
+    #[must_use]
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    /// Adds a relabeling rule, applied after the ones already added.
+    #[must_use]
+    pub fn with_write_relabel_config(mut self, rule: RelabelConfig) -> Self {
+        self.write_relabel_configs.push(rule);
+        self
+    }
+
+    /// Sets how series drained from rollup tiers are marked.
+    #[must_use]
+    pub fn with_rollup_naming(mut self, rollup_naming: RollupNaming) -> Self {
+        self.rollup_naming = rollup_naming;
+        self
+    }
+}
+
+/// Remote-write sink for an [`ExportDriver`](crate::exporter::ExportDriver).
+#[derive(Debug, Clone)]
+pub struct RemoteWriteExporter {
+    config: RemoteWriteConfig,
+    external_labels: Vec<(String, String)>,
+}
+
+impl RemoteWriteExporter {
+    /// Creates an exporter for the given endpoint configuration.
+    pub fn new(config: RemoteWriteConfig) -> Self {
+        Self {
+            config,
+            external_labels: Vec::new(),
+        }
+    }
+
+    /// Sets labels merged into every time series.
+    #[must_use]
+    pub fn with_external_labels(mut self, external_labels: Vec<(String, String)>) -> Self {
+        self.external_labels = external_labels;
+        self
+    }
+}
+
+impl Exporter for RemoteWriteExporter {
+    fn name(&self) -> &str {
+        "remote_write"
+    }
+
+    /// Encodes the batch as an uncompressed `WriteRequest`, which
+    /// [`send`](Exporter::send) splits into requests per the config.
+    ///
+    /// The request carries the batch's metadata, so a spooled payload can
+    /// be sent as 2.0 long after it was encoded.
+    fn encode(&mut self, exports: &[SeriesExport], store: &Store) -> Result<Vec<u8>> {
+        let request = build_write_request(
+            exports,
+            store,
+            &self.external_labels,
+            self.config.rollup_naming,
+            &self.config.write_relabel_configs,
+        )?;
+        serialize_write_request(&request)
+    }
+
+    /// Sends the batch, sticking to 1.0 once a receiver has refused 2.0.
+    fn send(&mut self, payload: &[u8]) -> Result<()> {
+        self.send_payload(payload, None)
+    }
+
+    fn send_before(&mut self, payload: &[u8], deadline: Instant) -> Result<()> {
+        self.send_payload(payload, Some(deadline))
+    }
+}
+
+impl RemoteWriteExporter {
+    fn send_payload(&mut self, payload: &[u8], deadline: Option<Instant>) -> Result<()> {
+        let mut request = proto::WriteRequest::decode(payload)
+            .map_err(|e| RemoteWriteError::Decode { source: e })?;
+        let metadata = metadata_from_proto(std::mem::take(&mut request.metadata));
+        self.config.protocol = send_sharded(&self.config, request, &metadata, deadline)?;
+        Ok(())
+    }
 }
 
 /// Pushes series exports to a Prometheus remote-write endpoint.
@@ -145,6 +616,12 @@ impl RemoteWriteConfig {
 /// Converts drain output into the Prometheus remote-write protobuf format,
 /// compresses with snappy, and POSTs to the configured endpoint with retry logic.
 ///
+/// Like Prometheus' queue manager, the data is split into requests of at
+/// most `max_samples_per_send` samples, and series are spread over `shards`
+/// concurrent senders by label hash. Each series stays on one shard, so its
+/// samples arrive in order. If any request fails, the whole push fails and
+/// should be retried; requests that already succeeded are then sent again.
+///
 /// `external_labels` are merged into every time series' label set, allowing
 /// multiple VMM instances to be distinguished in Prometheus (e.g.,
 /// `instance=vmm_1`).
@@ -163,10 +640,15 @@ pub fn push(
         return Ok(0);
     }
 
-    let request = build_write_request(exports, store, external_labels)?;
-    let proto_bytes = serialize_write_request(&request)?;
-    let compressed = compress_snappy(&proto_bytes)?;
-    send_with_retry(config, &compressed)?;
+    let mut request = build_write_request(
+        exports,
+        store,
+        external_labels,
+        config.rollup_naming,
+        &config.write_relabel_configs,
+    )?;
+    let metadata = metadata_from_proto(std::mem::take(&mut request.metadata));
+    send_sharded(config, request, &metadata, None)?;
 
     Ok(exports.len())
 }
@@ -187,18 +669,165 @@ pub fn encode(
     store: &Store,
     external_labels: &[(String, String)],
 ) -> Result<Vec<u8>> {
-    let request = build_write_request(exports, store, external_labels)?;
+    let request = build_write_request(exports, store, external_labels, RollupNaming::Label, &[])?;
     let proto_bytes = serialize_write_request(&request)?;
     compress_snappy(&proto_bytes)
 }
 
-/// Converts `SeriesExport` data to a Prometheus `WriteRequest`.
+/// Encodes series exports as a remote-write 2.0 payload.
+///
+/// Returns the snappy-compressed `io.prometheus.write.v2.Request` bytes,
+/// carrying each metric's metadata from the store.
+///
+/// # Errors
+///
+/// Returns an error if a series handle cannot be found in the store,
+/// or if serialization/compression fails.
+pub fn encode_v2(
+    exports: &[SeriesExport],
+    store: &Store,
+    external_labels: &[(String, String)],
+) -> Result<Vec<u8>> {
+    let mut request =
+        build_write_request(exports, store, external_labels, RollupNaming::Label, &[])?;
+    let metadata = metadata_from_proto(std::mem::take(&mut request.metadata));
+    let request = to_v2(&request, &metadata);
+    compress_snappy(&request.encode_to_vec())
+}
+
+/// Converts metadata to its 1.0 wire form, sorted by metric name.
+fn metadata_to_proto(metadata: &HashMap<String, MetricMetadata>) -> Vec<proto::MetricMetadata> {
+    let mut out: Vec<_> = metadata
+        .iter()
+        .map(|(name, m)| {
+            let metric_type = match m.kind {
+                MetricKind::Unknown => proto::MetricType::Unknown,
+                MetricKind::Counter => proto::MetricType::Counter,
+                MetricKind::Gauge => proto::MetricType::Gauge,
+                MetricKind::Histogram => proto::MetricType::Histogram,
+                MetricKind::Summary => proto::MetricType::Summary,
+            };
+            proto::MetricMetadata {
+                r#type: metric_type as i32,
+                metric_family_name: name.clone(),
+                help: m.help.clone(),
+                unit: m.unit.clone(),
+            }
+        })
+        .collect();
+    out.sort_by(|a, b| a.metric_family_name.cmp(&b.metric_family_name));
+    out
+}
+
+/// Reads metadata back from its 1.0 wire form.
+fn metadata_from_proto(metadata: Vec<proto::MetricMetadata>) -> HashMap<String, MetricMetadata> {
+    metadata
+        .into_iter()
+        .map(|m| {
+            let kind = match m.r#type() {
+                proto::MetricType::Counter => MetricKind::Counter,
+                proto::MetricType::Gauge => MetricKind::Gauge,
+                proto::MetricType::Histogram => MetricKind::Histogram,
+                proto::MetricType::Summary => MetricKind::Summary,
+                _ => MetricKind::Unknown,
+            };
+            let metadata = MetricMetadata::new(kind)
+                .with_unit(m.unit)
+                .with_help(m.help);
+            (m.metric_family_name, metadata)
+        })
+        .collect()
+}
+
+/// Converts a 1.0 `WriteRequest` to a 2.0 request with interned strings.
+///
+/// Metrics without registered metadata are typed by name: `_total` means
+/// counter, anything else is left unspecified.
+fn to_v2(
+    request: &proto::WriteRequest,
+    metadata: &HashMap<String, MetricMetadata>,
+) -> proto::v2::Request {
+    let mut symbols = vec![String::new()];
+    let mut refs: HashMap<String, u32> = HashMap::from([(String::new(), 0)]);
+    let mut intern = |s: &str| -> u32 {
+        if let Some(&r) = refs.get(s) {
+            return r;
+        }
+        #[allow(clippy::cast_possible_truncation)] // Far fewer than 2^32 symbols per request
+        let r = symbols.len() as u32;
+        symbols.push(s.to_string());
+        refs.insert(s.to_string(), r);
+        r
+    };
+
+    let timeseries = request
+        .timeseries
+        .iter()
+        .map(|series| {
+            let mut labels_refs = Vec::with_capacity(series.labels.len() * 2);
+            let mut name = "";
+            for label in &series.labels {
+                if label.name == "__name__" {
+                    name = &label.value;
+                }
+                labels_refs.push(intern(&label.name));
+                labels_refs.push(intern(&label.value));
+            }
+
+            let (kind, unit, help) = match metadata.get(name) {
+                Some(m) => (m.kind, m.unit.as_str(), m.help.as_str()),
+                None if name.ends_with("_total") => (MetricKind::Counter, "", ""),
+                None => (MetricKind::Unknown, "", ""),
+            };
+            let metric_type = match kind {
+                MetricKind::Unknown => proto::v2::MetricType::Unspecified,
+                MetricKind::Counter => proto::v2::MetricType::Counter,
+                MetricKind::Gauge => proto::v2::MetricType::Gauge,
+                MetricKind::Histogram => proto::v2::MetricType::Histogram,
+                MetricKind::Summary => proto::v2::MetricType::Summary,
+            };
+
+            proto::v2::TimeSeries {
+                labels_refs,
+                samples: series
+                    .samples
+                    .iter()
+                    .map(|s| proto::v2::Sample {
+                        value: s.value,
+                        timestamp: s.timestamp,
+                    })
+                    .collect(),
+                metadata: Some(proto::v2::Metadata {
+                    r#type: metric_type as i32,
+                    help_ref: intern(help),
+                    unit_ref: intern(unit),
+                }),
+                created_timestamp: 0,
+            }
+        })
+        .collect();
+
+    proto::v2::Request {
+        symbols,
+        timeseries,
+    }
+}
+
+/// Converts `SeriesExport` data to a Prometheus `WriteRequest`, marking
+/// rollups and skipping series dropped by `relabel_configs`.
+///
+/// The request's metadata is looked up by each series' registered name
+/// and keyed by the `__name__` it is exported under, which rollup naming
+/// and relabeling may have changed.
 fn build_write_request(
     exports: &[SeriesExport],
     store: &Store,
     external_labels: &[(String, String)],
+    rollup_naming: RollupNaming,
+    relabel_configs: &[RelabelConfig],
 ) -> Result<proto::WriteRequest> {
     let mut timeseries = Vec::with_capacity(exports.len());
+    let mut metadata = HashMap::new();
 
     for export in exports {
         let (name, labels) =
@@ -208,23 +837,49 @@ fn build_write_request(
                     schema_index: export.handle.schema_index,
                     column: export.handle.column,
                 })?;
+        let series_metadata = store.metadata(name);
+
+        let rollup = store.rollup(export.handle.schema_index, export.tier);
+        let (name, labels) = rollup_identity(name, labels, rollup, rollup_naming);
+        let mut labels = build_labels(&name, &labels, external_labels);
+        if !relabel_configs.is_empty() {
+            let mut pairs: Vec<_> = labels.into_iter().map(|l| (l.name, l.value)).collect();
+            if !relabel::relabel(&mut pairs, relabel_configs) {
+                continue;
+            }
+            labels = pairs
+                .into_iter()
+                .map(|(name, value)| proto::Label { name, value })
+                .collect();
+        }
+
+        if let Some(series_metadata) = series_metadata
+            && let Some(label) = labels.iter().find(|l| l.name == "__name__")
+        {
+            metadata
+                .entry(label.value.clone())
+                .or_insert_with(|| series_metadata.clone());
+        }
 
         let ts = proto::TimeSeries {
-            labels: build_labels(name, labels, external_labels),
+            labels,
             samples: build_samples(&export.points),
         };
 
         timeseries.push(ts);
     }
 
-    Ok(proto::WriteRequest { timeseries })
+    Ok(proto::WriteRequest {
+        timeseries,
+        metadata: metadata_to_proto(&metadata),
+    })
 }
 
 /// Builds Prometheus labels from series name, series labels, and external labels.
 ///
 /// Adds the required `__name__` label, merges in any external labels, and
 /// sorts the result alphabetically as required by the Prometheus spec.
-fn build_labels(
+pub(crate) fn build_labels(
     name: &str,
     labels: &[(String, String)],
     external_labels: &[(String, String)],
@@ -260,7 +915,7 @@ fn build_labels(
 ///
 /// Timestamps are converted from nanoseconds to milliseconds.
 #[allow(clippy::cast_possible_truncation)] // ns-to-ms conversion is safe for current epoch
-fn build_samples(points: &[(u64, f64)]) -> Vec<proto::Sample> {
+pub(crate) fn build_samples(points: &[(u64, f64)]) -> Vec<proto::Sample> {
     points
         .iter()
         .map(|&(timestamp_ns, value)| proto::Sample {
@@ -288,46 +943,285 @@ fn compress_snappy(data: &[u8]) -> Result<Vec<u8>> {
         .map_err(Into::into)
 }
 
-/// Sends compressed protobuf to the endpoint with exponential backoff retry.
-fn send_with_retry(config: &RemoteWriteConfig, body: &[u8]) -> Result<()> {
-    let client = reqwest::blocking::Client::builder()
-        .timeout(config.timeout)
+/// Splits a `WriteRequest` into per-shard lists of requests holding at most
+/// `max_samples` samples each.
+///
+/// Series are assigned to shards by a hash of their labels. A series with
+/// more samples than fit is continued in the shard's next request.
+fn shard_requests(
+    request: proto::WriteRequest,
+    max_samples: usize,
+    shards: usize,
+) -> Vec<Vec<proto::WriteRequest>> {
+    let max_samples = max_samples.max(1);
+    let shards = shards.max(1);
+    let mut out: Vec<Vec<proto::WriteRequest>> = vec![Vec::new(); shards];
+    let mut fill = vec![max_samples; shards];
+
+    for series in request.timeseries {
+        let mut hasher = DefaultHasher::new();
+        for label in &series.labels {
+            label.name.hash(&mut hasher);
+            label.value.hash(&mut hasher);
+        }
+        #[allow(clippy::cast_possible_truncation)] // Only used modulo the shard count
+        let shard = hasher.finish() as usize % shards;
+
+        let mut samples = series.samples.as_slice();
+        while !samples.is_empty() {
+            if fill[shard] == max_samples {
+                out[shard].push(proto::WriteRequest::default());
+                fill[shard] = 0;
+            }
+            let take = samples.len().min(max_samples - fill[shard]);
+            let (chunk, rest) = samples.split_at(take);
+            out[shard]
+                .last_mut()
+                .expect("a request was just pushed")
+                .timeseries
+                .push(proto::TimeSeries {
+                    labels: series.labels.clone(),
+                    samples: chunk.to_vec(),
+                });
+            fill[shard] += take;
+            samples = rest;
+        }
+    }
+
+    out
+}
+
+/// Sends a `WriteRequest` split by [`shard_requests`], one thread per shard.
+///
+/// Returns the protocol the receiver accepted: a 2.0 push refused with
+/// `415 Unsupported Media Type` is sent again as 1.0.
+///
+/// With a `deadline`, each request's timeout is capped to the time left and
+/// no retry is started once it has passed.
+fn send_sharded(
+    config: &RemoteWriteConfig,
+    request: proto::WriteRequest,
+    metadata: &HashMap<String, MetricMetadata>,
+    deadline: Option<Instant>,
+) -> Result<RemoteWriteProtocol> {
+    let client = build_client(config)?;
+    let auth = Credentials::resolve(config.auth.as_ref())?;
+
+    let shards = shard_requests(request, config.max_samples_per_send, config.shards);
+    let sender = Sender {
+        config,
+        client: &client,
+        auth: auth.as_ref(),
+        deadline,
+    };
+    match sender.send_shards(&shards, metadata, config.protocol) {
+        Err(crate::RondoError::RemoteWrite(RemoteWriteError::HttpStatus {
+            status: 415, ..
+        })) if config.protocol == RemoteWriteProtocol::V2 => {
+            sender.send_shards(&shards, metadata, RemoteWriteProtocol::V1)?;
+            Ok(RemoteWriteProtocol::V1)
+        }
+        result => result.map(|()| config.protocol),
+    }
+}
+
+/// Builds the HTTP client, loading the TLS files afresh.
+fn build_client(config: &RemoteWriteConfig) -> Result<reqwest::blocking::Client> {
+    let mut builder = reqwest::blocking::Client::builder();
+
+    if let Some(tls) = &config.tls {
+        if let Some(ca_file) = &tls.ca_file {
+            let ca = reqwest::Certificate::from_pem(&read_credentials(ca_file)?)
+                .map_err(|e| invalid_tls(ca_file, &e))?;
+            builder = builder.add_root_certificate(ca);
+        }
+        match (&tls.cert_file, &tls.key_file) {
+            (Some(cert_file), Some(key_file)) => {
+                let mut pem = read_credentials(cert_file)?;
+                pem.push(b'\n');
+                pem.extend(read_credentials(key_file)?);
+                let identity =
+                    reqwest::Identity::from_pem(&pem).map_err(|e| invalid_tls(cert_file, &e))?;
+                builder = builder.identity(identity);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(RemoteWriteError::InvalidTls {
+                    reason: "cert_file and key_file must be set together".to_string(),
+                }
+                .into());
+            }
+        }
+        if tls.insecure_skip_verify {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+    }
+
+    builder
         .build()
-        .map_err(|e| RemoteWriteError::ClientCreate { source: e })?;
+        .map_err(|e| RemoteWriteError::ClientCreate { source: e }.into())
+}
+
+fn read_credentials(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| {
+        RemoteWriteError::ReadCredentials {
+            path: path.to_path_buf(),
+            source: e,
+        }
+        .into()
+    })
+}
 
-    let mut last_error = None;
-    let mut backoff = config.retry_backoff;
+fn invalid_tls(path: &Path, error: &reqwest::Error) -> RemoteWriteError {
+    RemoteWriteError::InvalidTls {
+        reason: format!("{}: {error}", path.display()),
+    }
+}
 
-    for attempt in 0..=config.max_retries {
-        let mut request = client
-            .post(&config.endpoint)
-            .header("Content-Encoding", "snappy")
-            .header("Content-Type", "application/x-protobuf")
-            .header("X-Prometheus-Remote-Write-Version", "0.1.0");
+/// Authentication resolved for one push.
+enum Credentials<'a> {
+    Basic(&'a str, &'a str),
+    Bearer(String),
+}
 
-        for (name, value) in &config.headers {
-            request = request.header(name, value);
+impl<'a> Credentials<'a> {
+    /// Resolves the configured auth, reading a token file if there is one.
+    fn resolve(auth: Option<&'a RemoteWriteAuth>) -> Result<Option<Self>> {
+        Ok(match auth {
+            None => None,
+            Some(RemoteWriteAuth::Basic { username, password }) => {
+                Some(Self::Basic(username, password))
+            }
+            Some(RemoteWriteAuth::BearerToken(token)) => Some(Self::Bearer(token.clone())),
+            Some(RemoteWriteAuth::BearerTokenFile(path)) => {
+                let token = read_credentials(path)?;
+                Some(Self::Bearer(
+                    String::from_utf8_lossy(&token).trim().to_string(),
+                ))
+            }
+        })
+    }
+}
+
+/// What every request of one push shares.
+struct Sender<'a> {
+    config: &'a RemoteWriteConfig,
+    client: &'a reqwest::blocking::Client,
+    auth: Option<&'a Credentials<'a>>,
+    deadline: Option<Instant>,
+}
+
+impl Sender<'_> {
+    /// Sends each shard's requests in order, one thread per shard.
+    ///
+    /// Each shard stops at its first failure.
+    fn send_shards(
+        &self,
+        shards: &[Vec<proto::WriteRequest>],
+        metadata: &HashMap<String, MetricMetadata>,
+        protocol: RemoteWriteProtocol,
+    ) -> Result<()> {
+        let send_shard = |requests: &Vec<proto::WriteRequest>| -> Result<()> {
+            for request in requests {
+                let body = match protocol {
+                    RemoteWriteProtocol::V1 => serialize_write_request(request)?,
+                    RemoteWriteProtocol::V2 => to_v2(request, metadata).encode_to_vec(),
+                };
+                self.send_with_retry(protocol, &compress_snappy(&body)?)?;
+            }
+            Ok(())
+        };
+
+        let mut shards = shards.iter().filter(|requests| !requests.is_empty());
+        if self.config.shards <= 1 {
+            return shards.try_for_each(send_shard);
         }
 
-        match request.body(body.to_vec()).send() {
-            Ok(resp) if resp.status().is_success() => return Ok(()),
-            Ok(resp) => {
-                let status = resp.status().as_u16();
-                let body = resp.text().unwrap_or_default();
-                last_error = Some(RemoteWriteError::HttpStatus { status, body });
+        std::thread::scope(|scope| {
+            let send_shard = &send_shard;
+            let workers: Vec<_> = shards
+                .map(|requests| scope.spawn(move || send_shard(requests)))
+                .collect();
+            // Join every worker before reporting the first error
+            let results: Vec<_> = workers
+                .into_iter()
+                .map(|worker| worker.join().expect("shard worker panicked"))
+                .collect();
+            results.into_iter().collect()
+        })
+    }
+
+    /// Sends compressed protobuf to the endpoint with exponential backoff retry.
+    fn send_with_retry(&self, protocol: RemoteWriteProtocol, body: &[u8]) -> Result<()> {
+        let config = self.config;
+        let mut last_error = None;
+        let mut backoff = config.retry_backoff;
+
+        for attempt in 0..=config.max_retries {
+            let Some(timeout) = attempt_timeout(config.timeout, self.deadline) else {
+                break;
+            };
+            let mut request = self
+                .client
+                .post(&config.endpoint)
+                .timeout(timeout)
+                .header("Content-Encoding", "snappy")
+                .header("Content-Type", protocol.content_type())
+                .header("X-Prometheus-Remote-Write-Version", protocol.version());
+
+            for (name, value) in &config.headers {
+                request = request.header(name, value);
             }
-            Err(e) => {
-                last_error = Some(RemoteWriteError::RequestFailed { source: e });
+            request = match self.auth {
+                Some(Credentials::Basic(username, password)) => {
+                    request.basic_auth(username, Some(password))
+                }
+                Some(Credentials::Bearer(token)) => request.bearer_auth(token),
+                None => request,
+            };
+
+            match request.body(body.to_vec()).send() {
+                Ok(resp) if resp.status().is_success() => return Ok(()),
+                Ok(resp) => {
+                    let status = resp.status().as_u16();
+                    let body = resp.text().unwrap_or_default();
+                    // Covers 415: the receiver will not accept this version
+                    if !is_retryable(status) {
+                        return Err(RemoteWriteError::HttpStatus { status, body }.into());
+                    }
+                    last_error = Some(RemoteWriteError::HttpStatus { status, body });
+                }
+                Err(e) => {
+                    last_error = Some(RemoteWriteError::RequestFailed { source: e });
+                }
+            }
+
+            if attempt < config.max_retries {
+                if self
+                    .deadline
+                    .is_some_and(|deadline| Instant::now() + backoff >= deadline)
+                {
+                    break;
+                }
+                std::thread::sleep(backoff);
+                backoff *= 2;
             }
         }
 
-        if attempt < config.max_retries {
-            std::thread::sleep(backoff);
-            backoff *= 2;
+        match last_error {
+            Some(e) => Err(e.into()),
+            None => Err(ExportError::DeadlineExceeded {
+                sink: "remote_write".to_string(),
+            }
+            .into()),
         }
     }
+}
 
-    Err(last_error.expect("at least one attempt was made").into())
+/// Returns whether an HTTP status may succeed on retry: only 5xx and 429,
+/// as in the remote-write spec. Other errors mean the data is bad.
+pub(crate) fn is_retryable(status: u16) -> bool {
+    status == 429 || status >= 500
 }
 
 #[cfg(test)]
@@ -348,6 +1242,19 @@ mod tests {
         Store::open(&store_dir, schemas).unwrap()
     }
 
+    #[test]
+    fn test_retryable_statuses() {
+        assert!(is_retryable(429) && is_retryable(503));
+        assert!(!is_retryable(400) && !is_retryable(415));
+
+        let rejected: crate::RondoError = RemoteWriteError::HttpStatus {
+            status: 400,
+            body: "out of order sample".to_string(),
+        }
+        .into();
+        assert!(!rejected.is_retryable());
+    }
+
     #[test]
     fn test_build_labels() {
         let labels = vec![
@@ -406,7 +1313,7 @@ mod tests {
         let store = create_test_store(dir.path());
 
         let exports: Vec<SeriesExport> = Vec::new();
-        let request = build_write_request(&exports, &store, &[]).unwrap();
+        let request = build_write_request(&exports, &store, &[], RollupNaming::Label, &[]).unwrap();
 
         assert!(request.timeseries.is_empty());
     }
@@ -426,9 +1333,10 @@ mod tests {
                 (1_700_000_000_000_000_000, 85.5),
                 (1_700_000_001_000_000_000, 90.0),
             ],
+            tier: 0,
         }];
 
-        let request = build_write_request(&exports, &store, &[]).unwrap();
+        let request = build_write_request(&exports, &store, &[], RollupNaming::Label, &[]).unwrap();
 
         assert_eq!(request.timeseries.len(), 1);
         let ts = &request.timeseries[0];
@@ -454,6 +1362,7 @@ mod tests {
                     timestamp: 1_700_000_000_000,
                 }],
             }],
+            metadata: Vec::new(),
         };
 
         let proto_bytes = serialize_write_request(&request).unwrap();
@@ -483,6 +1392,7 @@ mod tests {
         let exports = vec![SeriesExport {
             handle,
             points: vec![(1_700_000_000_000_000_000, 99.9)],
+            tier: 0,
         }];
 
         let bytes = encode(&exports, &store, &[]).unwrap();
@@ -523,6 +1433,599 @@ mod tests {
         assert_eq!(config.headers[0].1, "Bearer token123");
     }
 
+    fn request(series: &[(&str, u32)]) -> proto::WriteRequest {
+        proto::WriteRequest {
+            timeseries: series
+                .iter()
+                .map(|&(name, count)| proto::TimeSeries {
+                    labels: vec![proto::Label {
+                        name: "__name__".to_string(),
+                        value: name.to_string(),
+                    }],
+                    samples: (0..count)
+                        .map(|i| proto::Sample {
+                            value: f64::from(i),
+                            timestamp: 1_700_000_000_000 + i64::from(i),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            metadata: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_shard_requests_splits_batches() {
+        let shards = shard_requests(request(&[("a", 5), ("b", 2), ("c", 0)]), 3, 1);
+        assert_eq!(shards.len(), 1);
+        let sizes: Vec<Vec<(String, usize)>> = shards[0]
+            .iter()
+            .map(|r| {
+                r.timeseries
+                    .iter()
+                    .map(|ts| (ts.labels[0].value.clone(), ts.samples.len()))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            sizes,
+            vec![
+                vec![("a".to_string(), 3)],
+                vec![("a".to_string(), 2), ("b".to_string(), 1)],
+                vec![("b".to_string(), 1)],
+            ]
+        );
+
+        // Every series lands on exactly one shard
+        let shards = shard_requests(request(&[("a", 4), ("b", 4), ("c", 4), ("d", 4)]), 2, 3);
+        for name in ["a", "b", "c", "d"] {
+            let holders = shards
+                .iter()
+                .filter(|requests| {
+                    requests
+                        .iter()
+                        .flat_map(|r| &r.timeseries)
+                        .any(|ts| ts.labels[0].value == name)
+                })
+                .count();
+            assert_eq!(holders, 1);
+        }
+    }
+
+    /// A request seen by [`mock_receiver`].
+    struct Received {
+        content_type: String,
+        authorization: Option<String>,
+        /// The decompressed body.
+        body: Vec<u8>,
+    }
+
+    /// Accepts keep-alive HTTP connections and forwards each request.
+    ///
+    /// Answers 204, or 415 to remote-write 2.0 requests unless `accept_v2`.
+    fn mock_receiver(accept_v2: bool) -> (String, std::sync::mpsc::Receiver<Received>) {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/api/v1/write", listener.local_addr().unwrap());
+        let (tx, rx) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = stream.unwrap();
+                let tx = tx.clone();
+                std::thread::spawn(move || {
+                    let mut reader = BufReader::new(&stream);
+                    loop {
+                        let mut content_length = 0;
+                        let mut content_type = String::new();
+                        let mut authorization = None;
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                            return;
+                        }
+                        loop {
+                            line.clear();
+                            reader.read_line(&mut line).unwrap();
+                            if line.trim().is_empty() {
+                                break;
+                            }
+                            let Some((name, value)) = line.split_once(':') else {
+                                continue;
+                            };
+                            if name.eq_ignore_ascii_case("content-length") {
+                                content_length = value.trim().parse().unwrap();
+                            } else if name.eq_ignore_ascii_case("content-type") {
+                                content_type = value.trim().to_string();
+                            } else if name.eq_ignore_ascii_case("authorization") {
+                                authorization = Some(value.trim().to_string());
+                            }
+                        }
+                        let mut body = vec![0u8; content_length];
+                        reader.read_exact(&mut body).unwrap();
+
+                        let is_v2 = content_type == RemoteWriteProtocol::V2.content_type();
+                        let response: &[u8] = if is_v2 && !accept_v2 {
+                            b"HTTP/1.1 415 Unsupported Media Type\r\nContent-Length: 0\r\n\r\n"
+                        } else {
+                            b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n"
+                        };
+                        let body = snap::raw::Decoder::new().decompress_vec(&body).unwrap();
+                        let _ = tx.send(Received {
+                            content_type,
+                            authorization,
+                            body,
+                        });
+                        (&stream).write_all(response).unwrap();
+                    }
+                });
+            }
+        });
+
+        (endpoint, rx)
+    }
+
+    #[test]
+    fn test_push_sharded_to_mock_receiver() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+
+        let base = 1_700_000_000_000_000_000u64;
+        let exports: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|name| SeriesExport {
+                handle: store.register(name, &[]).unwrap(),
+                points: (0..5).map(|i| (base + i * 1_000_000_000, 1.0)).collect(),
+                tier: 0,
+            })
+            .collect();
+
+        let (endpoint, received) = mock_receiver(false);
+        let config = RemoteWriteConfig::new(endpoint)
+            .with_max_samples_per_send(4)
+            .with_shards(2);
+        assert_eq!(push(&config, &exports, &store, &[]).unwrap(), 3);
+
+        let mut per_series: std::collections::HashMap<String, Vec<i64>> =
+            std::collections::HashMap::new();
+        let mut total = 0;
+        while total < 15 {
+            let body = received.recv_timeout(Duration::from_secs(5)).unwrap().body;
+            let request = proto::WriteRequest::decode(body.as_slice()).unwrap();
+            let samples: usize = request.timeseries.iter().map(|ts| ts.samples.len()).sum();
+            assert!(samples <= 4);
+            total += samples;
+            for ts in request.timeseries {
+                per_series
+                    .entry(ts.labels[0].value.clone())
+                    .or_default()
+                    .extend(ts.samples.iter().map(|s| s.timestamp));
+            }
+        }
+
+        // Each series arrives complete and in order
+        assert_eq!(per_series.len(), 3);
+        for timestamps in per_series.values() {
+            assert_eq!(timestamps.len(), 5);
+            assert!(timestamps.is_sorted());
+        }
+    }
+
+    #[test]
+    fn test_push_v2_with_metadata() {
+        use crate::series::{MetricKind, MetricMetadata};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let labels = [("device".to_string(), "vda".to_string())];
+        let read = store.register("blk_bytes_read_total", &labels).unwrap();
+        let rss = store.register("vmm_rss_bytes", &[]).unwrap();
+        store
+            .set_metadata(
+                "vmm_rss_bytes",
+                MetricMetadata::new(MetricKind::Gauge)
+                    .with_unit("bytes")
+                    .with_help("Resident set size"),
+            )
+            .unwrap();
+
+        let exports = vec![
+            SeriesExport {
+                handle: read,
+                points: vec![(1_700_000_000_000_000_000, 4096.0)],
+                tier: 0,
+            },
+            SeriesExport {
+                handle: rss,
+                points: vec![(1_700_000_000_000_000_000, 1e6)],
+                tier: 0,
+            },
+        ];
+
+        let (endpoint, received) = mock_receiver(true);
+        let config = RemoteWriteConfig::new(endpoint).with_protocol(RemoteWriteProtocol::V2);
+        let external = vec![("instance".to_string(), "vmm_1".to_string())];
+        push(&config, &exports, &store, &external).unwrap();
+
+        let Received {
+            content_type, body, ..
+        } = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            content_type,
+            "application/x-protobuf;proto=io.prometheus.write.v2.Request"
+        );
+        let request = proto::v2::Request::decode(body.as_slice()).unwrap();
+        assert_eq!(request.symbols[0], "");
+        let resolve = |refs: &[u32]| -> Vec<String> {
+            refs.iter()
+                .map(|&r| request.symbols[r as usize].clone())
+                .collect()
+        };
+
+        let by_name = |name: &str| {
+            request
+                .timeseries
+                .iter()
+                .find(|ts| resolve(&ts.labels_refs).contains(&name.to_string()))
+                .unwrap()
+        };
+        let read_series = by_name("blk_bytes_read_total");
+        assert_eq!(
+            resolve(&read_series.labels_refs),
+            vec![
+                "__name__",
+                "blk_bytes_read_total",
+                "device",
+                "vda",
+                "instance",
+                "vmm_1"
+            ]
+        );
+        let metadata = read_series.metadata.as_ref().unwrap();
+        assert_eq!(metadata.r#type(), proto::v2::MetricType::Counter);
+        assert_eq!(metadata.unit_ref, 0);
+
+        let rss_series = by_name("vmm_rss_bytes");
+        let metadata = rss_series.metadata.as_ref().unwrap();
+        assert_eq!(metadata.r#type(), proto::v2::MetricType::Gauge);
+        assert_eq!(request.symbols[metadata.unit_ref as usize], "bytes");
+        assert_eq!(
+            request.symbols[metadata.help_ref as usize],
+            "Resident set size"
+        );
+        assert_eq!(rss_series.samples[0].timestamp, 1_700_000_000_000);
+
+        // "vmm_rss_bytes" appears once in the symbol table
+        let interned = request
+            .symbols
+            .iter()
+            .filter(|s| s.as_str() == "vmm_rss_bytes")
+            .count();
+        assert_eq!(interned, 1);
+    }
+
+    #[test]
+    fn test_push_v2_metadata_follows_exported_name() {
+        use crate::schema::ConsolidationFn;
+        use crate::series::{MetricKind, MetricMetadata};
+
+        let dir = tempfile::tempdir().unwrap();
+        let schemas = vec![SchemaConfig {
+            name: "test".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig::new(Duration::from_secs(1), Duration::from_secs(60), None).unwrap(),
+                TierConfig::new(
+                    Duration::from_secs(300),
+                    Duration::from_secs(3600),
+                    Some(ConsolidationFn::Average),
+                )
+                .unwrap(),
+            ],
+            max_series: 10,
+        }];
+        let mut store = Store::open(dir.path().join("store"), schemas).unwrap();
+        let handle = store.register("cpu", &[]).unwrap();
+        store
+            .set_metadata(
+                "cpu",
+                MetricMetadata::new(MetricKind::Gauge)
+                    .with_unit("percent")
+                    .with_help("CPU usage"),
+            )
+            .unwrap();
+        let exports: Vec<_> = [0, 1]
+            .into_iter()
+            .map(|tier| SeriesExport {
+                handle,
+                points: vec![(1_700_000_000_000_000_000, 1.0)],
+                tier,
+            })
+            .collect();
+
+        // Both the rollup suffix and relabeling change the exported name
+        let (endpoint, received) = mock_receiver(true);
+        let config = RemoteWriteConfig::new(endpoint)
+            .with_protocol(RemoteWriteProtocol::V2)
+            .with_rollup_naming(RollupNaming::Suffix)
+            .with_write_relabel_config(
+                RelabelConfig::replace(["__name__"], "(.*)", "__name__", "host_$1").unwrap(),
+            );
+        push(&config, &exports, &store, &[]).unwrap();
+
+        let Received { body, .. } = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        let request = proto::v2::Request::decode(body.as_slice()).unwrap();
+        let mut names = Vec::new();
+        for series in &request.timeseries {
+            names.push(request.symbols[series.labels_refs[1] as usize].clone());
+            let metadata = series.metadata.as_ref().unwrap();
+            assert_eq!(metadata.r#type(), proto::v2::MetricType::Gauge);
+            assert_eq!(request.symbols[metadata.unit_ref as usize], "percent");
+            assert_eq!(request.symbols[metadata.help_ref as usize], "CPU usage");
+        }
+        names.sort();
+        assert_eq!(names, vec!["host_cpu", "host_cpu_avg_5m"]);
+    }
+
+    #[test]
+    fn test_encoded_payload_carries_metadata() {
+        use crate::series::{MetricKind, MetricMetadata};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let rss = store.register("vmm_rss_bytes", &[]).unwrap();
+        store
+            .set_metadata(
+                "vmm_rss_bytes",
+                MetricMetadata::new(MetricKind::Gauge).with_unit("bytes"),
+            )
+            .unwrap();
+        let other = store.register("up", &[]).unwrap();
+
+        let (endpoint, received) = mock_receiver(true);
+        let config = RemoteWriteConfig::new(endpoint).with_protocol(RemoteWriteProtocol::V2);
+        let mut exporter = RemoteWriteExporter::new(config.clone());
+        let export = |handle| SeriesExport {
+            handle,
+            points: vec![(1_700_000_000_000_000_000, 1.0)],
+            tier: 0,
+        };
+        let spooled = exporter.encode(&[export(rss)], &store).unwrap();
+        // A later encode must not change what the earlier payload carries
+        exporter.encode(&[export(other)], &store).unwrap();
+
+        // As after a restart: a fresh exporter sends the spooled payload
+        RemoteWriteExporter::new(config).send(&spooled).unwrap();
+        let Received { body, .. } = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        let request = proto::v2::Request::decode(body.as_slice()).unwrap();
+        let metadata = request.timeseries[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata.r#type(), proto::v2::MetricType::Gauge);
+        assert_eq!(request.symbols[metadata.unit_ref as usize], "bytes");
+    }
+
+    #[test]
+    fn test_v2_falls_back_to_v1_on_415() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+        let exports = vec![SeriesExport {
+            handle,
+            points: vec![(1_700_000_000_000_000_000, 1.0)],
+            tier: 0,
+        }];
+
+        let (endpoint, received) = mock_receiver(false);
+        let config = RemoteWriteConfig::new(endpoint).with_protocol(RemoteWriteProtocol::V2);
+        let mut exporter = RemoteWriteExporter::new(config);
+        let payload = Exporter::encode(&mut exporter, &exports, &store).unwrap();
+        exporter.send(&payload).unwrap();
+
+        let first = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(first.content_type, RemoteWriteProtocol::V2.content_type());
+        let Received {
+            content_type, body, ..
+        } = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(content_type, "application/x-protobuf");
+        assert_eq!(
+            proto::WriteRequest::decode(body.as_slice())
+                .unwrap()
+                .timeseries
+                .len(),
+            1
+        );
+
+        // Later batches go straight to 1.0
+        assert_eq!(exporter.config.protocol, RemoteWriteProtocol::V1);
+        exporter.send(&payload).unwrap();
+        let retried = received.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(retried.content_type, "application/x-protobuf");
+    }
+
+    #[test]
+    fn test_auth_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+        let exports = vec![SeriesExport {
+            handle,
+            points: vec![(1_700_000_000_000_000_000, 1.0)],
+            tier: 0,
+        }];
+        let (endpoint, received) = mock_receiver(false);
+        let authorization = || {
+            received
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap()
+                .authorization
+        };
+
+        let config = RemoteWriteConfig::new(&endpoint).with_auth(RemoteWriteAuth::Basic {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        });
+        push(&config, &exports, &store, &[]).unwrap();
+        assert_eq!(authorization().as_deref(), Some("Basic dXNlcjpwYXNz"));
+        assert!(!format!("{config:?}").contains("pass\""));
+
+        // A rotated token file is picked up by the next push
+        let token_file = dir.path().join("token");
+        std::fs::write(&token_file, "first\n").unwrap();
+        let config = RemoteWriteConfig::new(&endpoint)
+            .with_auth(RemoteWriteAuth::BearerTokenFile(token_file.clone()));
+        push(&config, &exports, &store, &[]).unwrap();
+        assert_eq!(authorization().as_deref(), Some("Bearer first"));
+        std::fs::write(&token_file, "second").unwrap();
+        push(&config, &exports, &store, &[]).unwrap();
+        assert_eq!(authorization().as_deref(), Some("Bearer second"));
+
+        std::fs::remove_file(&token_file).unwrap();
+        let err = push(&config, &exports, &store, &[]).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::RondoError::RemoteWrite(RemoteWriteError::ReadCredentials { .. })
+        ));
+    }
+
+    #[test]
+    fn test_tls_config_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let handle = store.register("up", &[]).unwrap();
+        let exports = vec![SeriesExport {
+            handle,
+            points: vec![(1_700_000_000_000_000_000, 1.0)],
+            tier: 0,
+        }];
+
+        let half_identity = TlsConfig {
+            cert_file: Some(dir.path().join("client.pem")),
+            ..TlsConfig::default()
+        };
+        let config = RemoteWriteConfig::new("https://localhost:1/write").with_tls(half_identity);
+        let err = push(&config, &exports, &store, &[]).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::RondoError::RemoteWrite(RemoteWriteError::InvalidTls { .. })
+        ));
+
+        let bad_ca = dir.path().join("ca.pem");
+        std::fs::write(&bad_ca, "-----BEGIN CERTIFICATE-----\nnot base64!\n").unwrap();
+        let config = RemoteWriteConfig::new("https://localhost:1/write").with_tls(TlsConfig {
+            ca_file: Some(bad_ca),
+            ..TlsConfig::default()
+        });
+        assert!(push(&config, &exports, &store, &[]).is_err());
+    }
+
+    #[test]
+    fn test_write_relabel_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let debug = store.register("debug_queue_depth", &[]).unwrap();
+        let exits = store
+            .register(
+                "vcpu_exits_total",
+                &[("reason".to_string(), "io".to_string())],
+            )
+            .unwrap();
+        let exports: Vec<_> = [debug, exits]
+            .into_iter()
+            .map(|handle| SeriesExport {
+                handle,
+                points: vec![(1_700_000_000_000_000_000, 1.0)],
+                tier: 0,
+            })
+            .collect();
+
+        let rules = [
+            RelabelConfig::drop(["__name__"], "debug_.*").unwrap(),
+            // External labels are visible to the rules
+            RelabelConfig::replace(["instance"], "vmm_(.*)", "vm", "$1").unwrap(),
+            RelabelConfig::labeldrop("instance").unwrap(),
+        ];
+        let external = vec![("instance".to_string(), "vmm_3".to_string())];
+        let request =
+            build_write_request(&exports, &store, &external, RollupNaming::Label, &rules).unwrap();
+
+        assert_eq!(request.timeseries.len(), 1);
+        let labels: Vec<_> = request.timeseries[0]
+            .labels
+            .iter()
+            .map(|l| (l.name.as_str(), l.value.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("__name__", "vcpu_exits_total"),
+                ("reason", "io"),
+                ("vm", "3")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rollup_naming() {
+        use crate::schema::ConsolidationFn;
+
+        let dir = tempfile::tempdir().unwrap();
+        let schemas = vec![SchemaConfig {
+            name: "test".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig::new(Duration::from_secs(1), Duration::from_secs(60), None).unwrap(),
+                TierConfig::new(
+                    Duration::from_secs(300),
+                    Duration::from_secs(3600),
+                    Some(ConsolidationFn::Average),
+                )
+                .unwrap(),
+            ],
+            max_series: 10,
+        }];
+        let mut store = Store::open(dir.path().join("store"), schemas).unwrap();
+        let handle = store.register("cpu", &[]).unwrap();
+        let exports: Vec<_> = [0, 1]
+            .into_iter()
+            .map(|tier| SeriesExport {
+                handle,
+                points: vec![(1_700_000_000_000_000_000, 1.0)],
+                tier,
+            })
+            .collect();
+        let names = |request: &proto::WriteRequest| -> Vec<Vec<(String, String)>> {
+            request
+                .timeseries
+                .iter()
+                .map(|ts| {
+                    ts.labels
+                        .iter()
+                        .map(|l| (l.name.clone(), l.value.clone()))
+                        .collect()
+                })
+                .collect()
+        };
+        let label = |k: &str, v: &str| (k.to_string(), v.to_string());
+
+        let request = build_write_request(&exports, &store, &[], RollupNaming::Label, &[]).unwrap();
+        assert_eq!(
+            names(&request),
+            vec![
+                vec![label("__name__", "cpu")],
+                vec![label("__name__", "cpu"), label("__rollup__", "avg_5m")],
+            ]
+        );
+
+        let request =
+            build_write_request(&exports, &store, &[], RollupNaming::Suffix, &[]).unwrap();
+        assert_eq!(
+            names(&request),
+            vec![
+                vec![label("__name__", "cpu")],
+                vec![label("__name__", "cpu_avg_5m")],
+            ]
+        );
+    }
+
     #[test]
     fn test_series_not_found_error() {
         let dir = tempfile::tempdir().unwrap();
@@ -538,9 +2041,10 @@ mod tests {
         let exports = vec![SeriesExport {
             handle: bogus_handle,
             points: vec![(1_000_000_000_000_000_000, 1.0)],
+            tier: 0,
         }];
 
-        let result = build_write_request(&exports, &store, &[]);
+        let result = build_write_request(&exports, &store, &[], RollupNaming::Label, &[]);
         assert!(result.is_err());
     }
 }
@@ -0,0 +1,517 @@
+//! Prometheus remote-write receiver for ingesting pushed samples into a store.
+//!
+//! The reverse of [`remote_write`](crate::remote_write):
+//! [`RemoteWriteReceiver::handle`] takes the raw HTTP request body (a
+//! snappy-compressed `WriteRequest`), registers any series it has not seen
+//! before with [`Store::register`] and records the samples. This lets rondo
+//! act as a small local remote-write target for agents such as Prometheus in
+//! agent mode, Grafana Alloy or vmagent. The embedding application provides
+//! the HTTP server.
+//!
+//! Each time series maps to a rondo series named after its `__name__` label,
+//! with the remaining labels as rondo labels. Static labels from the config
+//! are added to every series, which lets schema [`LabelMatcher`]s route
+//! pushed data to a dedicated schema. The receiver remembers the series it
+//! registered, so each is registered once rather than on every request, and
+//! caps how many it registers, overall and per metric name, on top of the
+//! schemas' own `max_series`.
+//!
+//! Samples are recorded in timestamp order across the whole request, since
+//! all series of a schema share one ring write cursor. Stale markers are
+//! skipped. Samples that cannot be stored (no `__name__`, schema full, or
+//! invalid value) are counted in the returned [`WriteSummary`] rather than
+//! failing the request, so the caller can decide how to answer.
+//!
+//! This module is only available when the `prometheus-remote-write` feature
+//! is enabled.
+//!
+//! [`LabelMatcher`]: crate::schema::LabelMatcher
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::remote_write_receiver::{RemoteWriteReceiver, RemoteWriteReceiverConfig};
+//! use rondo::store::Store;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let mut store = Store::open("./data", vec![])?;
+//! # let request_body: Vec<u8> = Vec::new();
+//! let config = RemoteWriteReceiverConfig::new()
+//!     .with_label("source", "remote_write")
+//!     .with_max_series(500);
+//! let mut receiver = RemoteWriteReceiver::new(config);
+//!
+//! // For each `POST /api/v1/write`:
+//! let summary = receiver.handle(&mut store, &request_body)?;
+//! if summary.rejected > 0 {
+//!     // Reply 400 with the error so the sender does not retry
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use prost::Message;
+
+use crate::error::{RemoteWriteError, Result};
+use crate::remote_write::proto;
+use crate::series::SeriesHandle;
+use crate::store::Store;
+
+/// `Content-Type` of a remote-write request.
+pub const CONTENT_TYPE: &str = "application/x-protobuf";
+
+/// Bit pattern Prometheus uses to mark a series as stale.
+const STALE_NAN_BITS: u64 = 0x7ff0_0000_0000_0002;
+
+/// Outcome of ingesting one write request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteSummary {
+    /// Samples recorded (stale markers included).
+    pub accepted: u64,
+    /// Samples dropped.
+    pub rejected: u64,
+    /// Reason for the first rejection, if any.
+    pub error: Option<String>,
+}
+
+impl WriteSummary {
+    fn reject(&mut self, count: u64, reason: String) {
+        self.rejected += count;
+        self.error.get_or_insert(reason);
+    }
+}
+
+/// Configuration for a [`RemoteWriteReceiver`].
+#[derive(Debug, Clone)]
+pub struct RemoteWriteReceiverConfig {
+    /// Maximum number of series the receiver registers in total.
+    pub max_series: u32,
+    /// Maximum number of series the receiver registers per metric name.
+    pub max_series_per_metric: u32,
+    /// Labels added to every series (e.g. for schema routing).
+    pub labels: Vec<(String, String)>,
+}
+
+impl Default for RemoteWriteReceiverConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RemoteWriteReceiverConfig {
+    /// Creates a config with sensible defaults.
+    ///
+    /// Defaults: at most 1000 series in total and 100 per metric name.
+    pub fn new() -> Self {
+        Self {
+            max_series: 1000,
+            max_series_per_metric: 100,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of series registered in total.
+    #[must_use]
+    pub fn with_max_series(mut self, max_series: u32) -> Self {
+        self.max_series = max_series;
+        self
+    }
+
+    /// Sets the maximum number of series registered per metric name.
+    #[must_use]
+    pub fn with_max_series_per_metric(mut self, max_series: u32) -> Self {
+        self.max_series_per_metric = max_series;
+        self
+    }
+
+    /// Adds a label to every series.
+    ///
+    /// Static labels take precedence over labels of the same name in the
+    /// request.
+    #[must_use]
+    pub fn with_label(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Ingests Prometheus remote-write requests into a store.
+///
+/// The receiver remembers the series it registered (for the cardinality
+/// limits, and so known series are not registered again), so one receiver
+/// should be kept for the lifetime of the store.
+#[derive(Debug)]
+pub struct RemoteWriteReceiver {
+    config: RemoteWriteReceiverConfig,
+    series: HashMap<Vec<(String, String)>, SeriesHandle>,
+    per_metric: HashMap<String, u32>,
+}
+
+impl RemoteWriteReceiver {
+    /// Creates a receiver with the given limits and labels.
+    pub fn new(config: RemoteWriteReceiverConfig) -> Self {
+        Self {
+            config,
+            series: HashMap::new(),
+            per_metric: HashMap::new(),
+        }
+    }
+
+    /// Returns the number of series registered by this receiver.
+    pub fn series_count(&self) -> usize {
+        self.series.len()
+    }
+
+    /// Ingests a snappy-compressed remote-write request.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RemoteWriteError::Decompression`] or
+    /// [`RemoteWriteError::Decode`] if the body is malformed. Per-sample
+    /// failures are reported in the [`WriteSummary`] instead.
+    pub fn handle(&mut self, store: &mut Store, body: &[u8]) -> Result<WriteSummary> {
+        let request = decode_request(body)?;
+        Ok(self.write(store, &request))
+    }
+
+    /// Records every sample of a decoded `WriteRequest`.
+    pub fn write(&mut self, store: &mut Store, request: &proto::WriteRequest) -> WriteSummary {
+        let mut summary = WriteSummary::default();
+        let mut samples = Vec::new();
+
+        for series in &request.timeseries {
+            let count = series.samples.len() as u64;
+
+            let mut name = None;
+            let mut labels = BTreeMap::new();
+            for label in &series.labels {
+                if label.name == "__name__" {
+                    name = Some(label.value.as_str());
+                } else if !label.value.is_empty() {
+                    labels.insert(label.name.clone(), label.value.clone());
+                }
+            }
+            let Some(name) = name.filter(|n| !n.is_empty()) else {
+                summary.reject(count, "time series without __name__ label".to_string());
+                continue;
+            };
+            for (key, value) in &self.config.labels {
+                labels.insert(key.clone(), value.clone());
+            }
+            let labels: Vec<_> = labels.into_iter().collect();
+
+            let handle = match self.resolve(store, name, labels) {
+                Ok(handle) => handle,
+                Err(reason) => {
+                    summary.reject(count, reason);
+                    continue;
+                }
+            };
+
+            for sample in &series.samples {
+                samples.push((sample.timestamp, handle, sample.value, name));
+            }
+        }
+
+        // Stable, so samples of one series keep their request order
+        samples.sort_by_key(|&(timestamp, ..)| timestamp);
+
+        for (timestamp_ms, handle, value, name) in samples {
+            if value.to_bits() == STALE_NAN_BITS {
+                summary.accepted += 1;
+                continue;
+            }
+            let Some(timestamp_ns) = u64::try_from(timestamp_ms)
+                .ok()
+                .filter(|&ms| ms > 0)
+                .and_then(|ms| ms.checked_mul(1_000_000))
+            else {
+                summary.reject(1, format!("invalid timestamp {timestamp_ms} for '{name}'"));
+                continue;
+            };
+            match store.record(handle, value, timestamp_ns) {
+                Ok(()) => summary.accepted += 1,
+                Err(e) => summary.reject(1, format!("failed to record '{name}': {e}")),
+            }
+        }
+
+        summary
+    }
+
+    /// Returns the handle of a series, registering it within the limits.
+    fn resolve(
+        &mut self,
+        store: &mut Store,
+        name: &str,
+        labels: Vec<(String, String)>,
+    ) -> std::result::Result<SeriesHandle, String> {
+        let mut key = labels;
+        key.push(("__name__".to_string(), name.to_string()));
+        if let Some(&handle) = self.series.get(&key) {
+            return Ok(handle);
+        }
+
+        if self.series.len() >= self.config.max_series as usize {
+            return Err(format!(
+                "series limit of {} reached, dropping '{name}'",
+                self.config.max_series
+            ));
+        }
+        let per_metric = self.per_metric.get(name).copied().unwrap_or(0);
+        if per_metric >= self.config.max_series_per_metric {
+            return Err(format!(
+                "limit of {} series per metric reached for '{name}'",
+                self.config.max_series_per_metric
+            ));
+        }
+
+        let handle = store
+            .register(name, &key[..key.len() - 1])
+            .map_err(|e| format!("failed to register '{name}': {e}"))?;
+        self.series.insert(key, handle);
+        self.per_metric.insert(name.to_string(), per_metric + 1);
+        Ok(handle)
+    }
+}
+
+/// Decodes a snappy-compressed `WriteRequest`.
+///
+/// # Errors
+///
+/// Returns [`RemoteWriteError::Decompression`] or
+/// [`RemoteWriteError::Decode`] if the body is malformed.
+pub fn decode_request(body: &[u8]) -> Result<proto::WriteRequest> {
+    let decompressed = snap::raw::Decoder::new()
+        .decompress_vec(body)
+        .map_err(|e| RemoteWriteError::Decompression { source: e })?;
+    proto::WriteRequest::decode(decompressed.as_slice())
+        .map_err(|e| RemoteWriteError::Decode { source: e }.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{LabelMatcher, SchemaConfig, TierConfig};
+    use std::time::Duration;
+
+    const BASE_MS: i64 = 1_700_000_000_000;
+
+    fn create_test_store(dir: &std::path::Path) -> Store {
+        let tiers =
+            vec![TierConfig::new(Duration::from_secs(1), Duration::from_secs(600), None).unwrap()];
+        let schemas = vec![
+            SchemaConfig {
+                name: "pushed".to_string(),
+                label_matcher: LabelMatcher::new([("source", "remote_write")]),
+                tiers: tiers.clone(),
+                max_series: 2,
+            },
+            SchemaConfig {
+                name: "default".to_string(),
+                label_matcher: LabelMatcher::any(),
+                tiers,
+                max_series: 10,
+            },
+        ];
+        Store::open(dir.join("store"), schemas).unwrap()
+    }
+
+    fn series(labels: &[(&str, &str)], samples: &[(i64, f64)]) -> proto::TimeSeries {
+        proto::TimeSeries {
+            labels: labels
+                .iter()
+                .map(|&(name, value)| proto::Label {
+                    name: name.to_string(),
+                    value: value.to_string(),
+                })
+                .collect(),
+            samples: samples
+                .iter()
+                .map(|&(timestamp, value)| proto::Sample { value, timestamp })
+                .collect(),
+        }
+    }
+
+    fn encode_request(timeseries: Vec<proto::TimeSeries>) -> Vec<u8> {
+        let request = proto::WriteRequest {
+            timeseries,
+            metadata: Vec::new(),
+        };
+        snap::raw::Encoder::new()
+            .compress_vec(&request.encode_to_vec())
+            .unwrap()
+    }
+
+    fn find(store: &Store, name: &str) -> Option<(SeriesHandle, Vec<(String, String)>)> {
+        store.handles().into_iter().find_map(|h| {
+            let (n, labels) = store.series_info(&h)?;
+            (n == name).then(|| (h, labels.to_vec()))
+        })
+    }
+
+    #[test]
+    fn test_handle_registers_and_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+
+        // Series interleave in time; the receiver must order them
+        let body = encode_request(vec![
+            series(
+                &[("__name__", "up"), ("job", "node")],
+                &[(BASE_MS, 1.0), (BASE_MS + 2000, 1.0)],
+            ),
+            series(
+                &[("__name__", "load1"), ("job", "node")],
+                &[(BASE_MS + 1000, 0.5)],
+            ),
+        ]);
+        let summary = RemoteWriteReceiver::new(RemoteWriteReceiverConfig::new())
+            .handle(&mut store, &body)
+            .unwrap();
+        assert_eq!(summary.accepted, 3);
+        assert_eq!(summary.rejected, 0);
+
+        let (up, labels) = find(&store, "up").unwrap();
+        assert_eq!(labels, vec![("job".to_string(), "node".to_string())]);
+        assert_eq!(up.schema_index, 1);
+        let points: Vec<_> = store
+            .query(up, 0, 0, u64::MAX)
+            .unwrap()
+            .map(|(ts, _)| ts)
+            .collect();
+        assert_eq!(
+            points,
+            vec![
+                BASE_MS as u64 * 1_000_000,
+                (BASE_MS as u64 + 2000) * 1_000_000
+            ]
+        );
+
+        let (load, _) = find(&store, "load1").unwrap();
+        assert_eq!(
+            store.latest(load).unwrap(),
+            Some(((BASE_MS as u64 + 1000) * 1_000_000, 0.5))
+        );
+    }
+
+    #[test]
+    fn test_static_labels_route_and_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let mut receiver = RemoteWriteReceiver::new(
+            RemoteWriteReceiverConfig::new().with_label("source", "remote_write"),
+        );
+
+        let body = encode_request(vec![
+            series(&[("__name__", "a")], &[(BASE_MS, 1.0)]),
+            series(&[("__name__", "b"), ("source", "agent")], &[(BASE_MS, 2.0)]),
+            series(
+                &[("__name__", "c")],
+                &[(BASE_MS, 3.0), (BASE_MS + 1000, 4.0)],
+            ),
+        ]);
+        let summary = receiver.handle(&mut store, &body).unwrap();
+
+        // The pushed schema holds two series; c overflows it
+        assert_eq!(summary.accepted, 2);
+        assert_eq!(summary.rejected, 2);
+        assert!(summary.error.unwrap().contains("'c'"));
+
+        let (b, labels) = find(&store, "b").unwrap();
+        assert_eq!(b.schema_index, 0);
+        assert_eq!(
+            labels,
+            vec![("source".to_string(), "remote_write".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_series_limits_persist_across_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let mut receiver = RemoteWriteReceiver::new(
+            RemoteWriteReceiverConfig::new()
+                .with_max_series(2)
+                .with_max_series_per_metric(1),
+        );
+
+        let body = encode_request(vec![
+            series(&[("__name__", "up"), ("job", "a")], &[(BASE_MS, 1.0)]),
+            series(&[("__name__", "up"), ("job", "b")], &[(BASE_MS, 1.0)]),
+            series(&[("__name__", "load1")], &[(BASE_MS, 0.5)]),
+        ]);
+        let summary = receiver.handle(&mut store, &body).unwrap();
+        assert_eq!(summary.accepted, 2);
+        assert_eq!(summary.rejected, 1);
+        assert!(summary.error.unwrap().contains("per metric"));
+        assert_eq!(receiver.series_count(), 2);
+
+        // Known series are still accepted once the limit is reached
+        let body = encode_request(vec![
+            series(
+                &[("__name__", "up"), ("job", "a")],
+                &[(BASE_MS + 1000, 0.0)],
+            ),
+            series(&[("__name__", "mem")], &[(BASE_MS + 1000, 42.0)]),
+        ]);
+        let summary = receiver.handle(&mut store, &body).unwrap();
+        assert_eq!(summary.accepted, 1);
+        assert_eq!(summary.rejected, 1);
+        assert!(summary.error.unwrap().contains("series limit"));
+        assert_eq!(receiver.series_count(), 2);
+        assert_eq!(store.series_count(), 2);
+
+        let (up, _) = find(&store, "up").unwrap();
+        assert_eq!(
+            store.latest(up).unwrap(),
+            Some(((BASE_MS as u64 + 1000) * 1_000_000, 0.0))
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+
+        let body = encode_request(vec![
+            series(&[("job", "node")], &[(BASE_MS, 1.0)]),
+            series(
+                &[("__name__", "up")],
+                &[
+                    (0, 1.0),
+                    (BASE_MS, f64::INFINITY),
+                    (BASE_MS + 1000, f64::from_bits(STALE_NAN_BITS)),
+                ],
+            ),
+        ]);
+        let summary = RemoteWriteReceiver::new(RemoteWriteReceiverConfig::new())
+            .handle(&mut store, &body)
+            .unwrap();
+        assert_eq!(summary.accepted, 1);
+        assert_eq!(summary.rejected, 3);
+        assert!(summary.error.unwrap().contains("__name__"));
+    }
+
+    #[test]
+    fn test_malformed_body() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let mut receiver = RemoteWriteReceiver::new(RemoteWriteReceiverConfig::new());
+
+        assert!(matches!(
+            receiver.handle(&mut store, b"not snappy"),
+            Err(crate::RondoError::RemoteWrite(
+                RemoteWriteError::Decompression { .. }
+            ))
+        ));
+        let garbage = snap::raw::Encoder::new().compress_vec(b"\xff\xff").unwrap();
+        assert!(matches!(
+            receiver.handle(&mut store, &garbage),
+            Err(crate::RondoError::RemoteWrite(
+                RemoteWriteError::Decode { .. }
+            ))
+        ));
+    }
+}
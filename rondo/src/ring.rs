@@ -292,6 +292,266 @@ impl RingBuffer {
         Ok(RingIterator::new(self, series_column, start_ns, end_ns))
     }
 
+    /// Reads values for a series within the given time range into caller-owned
+    /// buffers.
+    ///
+    /// This is the columnar counterpart of [`read`](Self::read): instead of
+    /// yielding points one at a time, the timestamp and value columns for the
+    /// requested slot range are copied out of the mmap in bulk (at most two
+    /// copies per column when the range wraps around the end of the ring),
+    /// then slots outside the time range or without a value are compacted
+    /// away. Points are appended in chronological order, so the same buffers
+    /// can be reused across calls.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_column` - The series column index to read
+    /// * `start_ns` - Start timestamp in nanoseconds (inclusive)
+    /// * `end_ns` - End timestamp in nanoseconds (exclusive)
+    /// * `timestamps` - Buffer that receives the timestamps
+    /// * `values` - Buffer that receives the values
+    ///
+    /// # Returns
+    ///
+    /// The number of points appended to each buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::InvalidTimeRange`] if `start_ns >= end_ns`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `timestamps` and `values` have different lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::ring::RingBuffer;
+    /// # use rondo::slab::Slab;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let slab = Slab::create("test.slab", 0x1234, 100, 10, 1_000_000_000)?;
+    /// let ring = RingBuffer::new(slab);
+    ///
+    /// let mut timestamps = Vec::new();
+    /// let mut values = Vec::new();
+    /// let n = ring.read_into(0, 1_000_000_000, 10_000_000_000, &mut timestamps, &mut values)?;
+    /// let mean = values.iter().sum::<f64>() / n as f64;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_into(
+        &self,
+        series_column: u32,
+        start_ns: u64,
+        end_ns: u64,
+        timestamps: &mut Vec<u64>,
+        values: &mut Vec<f64>,
+    ) -> Result<usize> {
+        if start_ns >= end_ns {
+            return Err(QueryError::InvalidTimeRange {
+                start: start_ns,
+                end: end_ns,
+            }
+            .into());
+        }
+
+        assert_eq!(
+            timestamps.len(),
+            values.len(),
+            "timestamp and value buffers must have equal lengths"
+        );
+
+        let base = timestamps.len();
+        let window = self.slot_window(start_ns, end_ns);
+        if window.front < window.back {
+            let slot_count = self.slab.slot_count();
+            let first = (window.first_slot + window.front) % slot_count;
+            let len = window.back - window.front;
+
+            // Split at the physical end of the ring: at most two contiguous runs
+            let head = len.min(slot_count - first);
+            for (slot, count) in [(first, head), (0, len - head)] {
+                if count > 0 {
+                    self.slab
+                        .read_timestamps_into(slot..slot + count, timestamps);
+                    self.slab
+                        .read_values_into(series_column, slot..slot + count, values);
+                }
+            }
+        }
+
+        // Compact in place, dropping out-of-range and unwritten slots. The
+        // common case (every slot valid) stops after a single scan.
+        let keep = |timestamp: u64, value: f64| {
+            timestamp >= start_ns && timestamp < end_ns && !value.is_nan()
+        };
+        let copied_ts = &mut timestamps[base..];
+        let copied_values = &mut values[base..];
+        let first_invalid = copied_ts
+            .iter()
+            .zip(copied_values.iter())
+            .position(|(&t, &v)| !keep(t, v))
+            .unwrap_or(copied_ts.len());
+
+        let mut kept = first_invalid;
+        for i in first_invalid..copied_ts.len() {
+            let (timestamp, value) = (copied_ts[i], copied_values[i]);
+            if keep(timestamp, value) {
+                copied_ts[kept] = timestamp;
+                copied_values[kept] = value;
+                kept += 1;
+            }
+        }
+        timestamps.truncate(base + kept);
+        values.truncate(base + kept);
+
+        Ok(kept)
+    }
+
+    /// Reads every series column for the slots within a time range.
+    ///
+    /// This is the row-oriented counterpart of [`read_into`](Self::read_into):
+    /// for each slot whose timestamp lies in `[start_ns, end_ns)` and that
+    /// holds at least one value, the timestamp is appended to `timestamps`
+    /// and the values of columns `0..columns` are appended to `values`
+    /// (row-major, so row `i` is `values[i * columns..(i + 1) * columns]`).
+    /// Columns without a value in that slot read as NaN. Rows are returned
+    /// in chronological order, visiting each slot once.
+    ///
+    /// Pass `self.slab().series_count()` as `columns` to read every
+    /// registered series.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Number of leading series columns to read per row
+    /// * `start_ns` - Start timestamp in nanoseconds (inclusive)
+    /// * `end_ns` - End timestamp in nanoseconds (exclusive)
+    /// * `timestamps` - Buffer that receives one timestamp per row
+    /// * `values` - Buffer that receives `columns` values per row
+    ///
+    /// # Returns
+    ///
+    /// The number of rows appended.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`QueryError::InvalidTimeRange`] if `start_ns >= end_ns`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `columns` exceeds the slab's `max_series`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::ring::RingBuffer;
+    /// # use rondo::slab::Slab;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let slab = Slab::create("test.slab", 0x1234, 100, 10, 1_000_000_000)?;
+    /// let ring = RingBuffer::new(slab);
+    /// let columns = ring.slab().series_count();
+    ///
+    /// let mut timestamps = Vec::new();
+    /// let mut values = Vec::new();
+    /// ring.read_rows_into(columns, 0, u64::MAX, &mut timestamps, &mut values)?;
+    /// for (row, timestamp) in values.chunks(columns as usize).zip(&timestamps) {
+    ///     println!("{timestamp}: {row:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn read_rows_into(
+        &self,
+        columns: u32,
+        start_ns: u64,
+        end_ns: u64,
+        timestamps: &mut Vec<u64>,
+        values: &mut Vec<f64>,
+    ) -> Result<usize> {
+        if start_ns >= end_ns {
+            return Err(QueryError::InvalidTimeRange {
+                start: start_ns,
+                end: end_ns,
+            }
+            .into());
+        }
+
+        let window = self.slot_window(start_ns, end_ns);
+        let slot_count = self.slab.slot_count();
+        let mut rows = 0;
+
+        for offset in window.front..window.back {
+            let slot = (window.first_slot + offset) % slot_count;
+            let timestamp = self.slab.read_timestamp(slot);
+            if timestamp < start_ns || timestamp >= end_ns {
+                continue;
+            }
+
+            let row_start = values.len();
+            self.slab.read_row_into(slot, columns, values);
+            if values[row_start..].iter().all(|v| v.is_nan()) {
+                values.truncate(row_start);
+                continue;
+            }
+
+            timestamps.push(timestamp);
+            rows += 1;
+        }
+
+        Ok(rows)
+    }
+
+    /// Computes which slots can hold timestamps in `[start_ns, end_ns)`.
+    ///
+    /// Slots are addressed by offset from the oldest slot. The newest offset
+    /// is the write cursor; each step back from it is one interval earlier,
+    /// so the offsets of a time range follow from its distance to the newest
+    /// timestamp without scanning the ring.
+    #[allow(clippy::cast_possible_truncation)] // Offsets are bounded by slot_count (u32)
+    fn slot_window(&self, start_ns: u64, end_ns: u64) -> SlotWindow {
+        if self.is_empty() {
+            return SlotWindow {
+                first_slot: 0,
+                front: 0,
+                back: 0,
+            };
+        }
+
+        let cursor = self.slab.write_cursor();
+        let (first_slot, slot_count) = if self.has_wrapped {
+            // When wrapped, we need to start from the oldest slot
+            let oldest_slot = (cursor + 1) % self.slab.slot_count();
+            (oldest_slot, self.slab.slot_count())
+        } else {
+            // When not wrapped, start from slot 0
+            (0, cursor + 1)
+        };
+
+        let interval_ns = self.slab.interval_ns();
+        let newest_interval = self.slab.read_timestamp(cursor) / interval_ns;
+        let start_interval = start_ns / interval_ns;
+        let end_interval = end_ns.saturating_sub(1) / interval_ns;
+        let window = u64::from(slot_count);
+
+        if start_interval > newest_interval {
+            return SlotWindow {
+                first_slot,
+                front: slot_count,
+                back: slot_count,
+            };
+        }
+
+        // Offsets counted from the newest slot, clamped to the window
+        let front = (window - 1).saturating_sub(newest_interval - start_interval);
+        let back = window.saturating_sub(newest_interval.saturating_sub(end_interval));
+
+        SlotWindow {
+            first_slot,
+            front: front.min(back) as u32,
+            back: back as u32,
+        }
+    }
+
     /// Returns the timestamp of the oldest data in the ring buffer.
     ///
     /// This is the data that will be overwritten next if the buffer is full.
@@ -395,74 +655,111 @@ impl RingBuffer {
     }
 }
 
+/// Range of ring slots covering a time range, as offsets from the oldest slot.
+#[derive(Debug, Clone, Copy)]
+struct SlotWindow {
+    /// Physical slot index of the oldest slot in the ring.
+    first_slot: u32,
+    /// Offset of the first slot in the range.
+    front: u32,
+    /// Offset one past the last slot in the range.
+    back: u32,
+}
+
 /// Iterator for reading time-series data from a ring buffer.
 ///
 /// This iterator handles wraparound automatically and returns data in
 /// chronological order (oldest to newest). It skips slots with NaN values.
+///
+/// It is also double-ended: [`next_back`](DoubleEndedIterator::next_back)
+/// walks backwards from the newest slot (the write cursor), so reading the
+/// most recent points via `.rev()` only touches the slots it returns.
+///
+/// Because a slot's position is derived from `timestamp / interval`, the
+/// iterator computes the first and last slot of the requested range up
+/// front instead of scanning the whole ring, so the cost of a query is
+/// proportional to the requested range rather than the retention window.
 #[derive(Debug)]
 pub struct RingIterator<'a> {
     ring: &'a RingBuffer,
     series_column: u32,
     start_ns: u64,
     end_ns: u64,
-    current_slot: u32,
-    slots_remaining: u32,
+    /// Physical slot index of the oldest slot in the iteration window.
+    first_slot: u32,
+    /// Offset from `first_slot` of the next slot yielded from the front.
+    front: u32,
+    /// Offset from `first_slot` one past the next slot yielded from the back.
+    back: u32,
 }
 
 impl<'a> RingIterator<'a> {
     /// Creates a new ring iterator.
     fn new(ring: &'a RingBuffer, series_column: u32, start_ns: u64, end_ns: u64) -> Self {
-        if ring.is_empty() {
-            return Self {
-                ring,
-                series_column,
-                start_ns,
-                end_ns,
-                current_slot: 0,
-                slots_remaining: 0,
-            };
-        }
-
-        let (start_slot, slot_count) = if ring.has_wrapped {
-            // When wrapped, we need to start from the oldest slot
-            let cursor = ring.slab.write_cursor();
-            let oldest_slot = (cursor + 1) % ring.slab.slot_count();
-            (oldest_slot, ring.slab.slot_count())
-        } else {
-            // When not wrapped, start from slot 0
-            let cursor = ring.slab.write_cursor();
-            (0, cursor + 1)
-        };
+        let window = ring.slot_window(start_ns, end_ns);
 
         Self {
             ring,
             series_column,
             start_ns,
             end_ns,
-            current_slot: start_slot,
-            slots_remaining: slot_count,
+            first_slot: window.first_slot,
+            front: window.front,
+            back: window.back,
         }
     }
+
+    /// Returns the slot interval of the underlying ring buffer in nanoseconds.
+    pub fn interval_ns(&self) -> u64 {
+        self.ring.slab.interval_ns()
+    }
+
+    /// Reads the slot at `offset` from the oldest slot, returning the point
+    /// if it lies within the time range and holds a value.
+    fn read_at(&self, offset: u32) -> Option<(u64, f64)> {
+        let slot = (self.first_slot + offset) % self.ring.slab.slot_count();
+        let timestamp = self.ring.slab.read_timestamp(slot);
+        if timestamp < self.start_ns || timestamp >= self.end_ns {
+            return None;
+        }
+
+        let value = self.ring.slab.read_value(slot, self.series_column);
+        if value.is_nan() {
+            return None;
+        }
+
+        Some((timestamp, value))
+    }
 }
 
 impl<'a> Iterator for RingIterator<'a> {
     type Item = (u64, f64);
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.slots_remaining > 0 {
-            let timestamp = self.ring.slab.read_timestamp(self.current_slot);
-            let value = self
-                .ring
-                .slab
-                .read_value(self.current_slot, self.series_column);
-
-            // Move to next slot
-            self.current_slot = (self.current_slot + 1) % self.ring.slab.slot_count();
-            self.slots_remaining -= 1;
-
-            // Check if timestamp is within range and value is valid
-            if timestamp >= self.start_ns && timestamp < self.end_ns && !value.is_nan() {
-                return Some((timestamp, value));
+        while self.front < self.back {
+            let offset = self.front;
+            self.front += 1;
+
+            if let Some(point) = self.read_at(offset) {
+                return Some(point);
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some((self.back - self.front) as usize))
+    }
+}
+
+impl<'a> DoubleEndedIterator for RingIterator<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        while self.front < self.back {
+            self.back -= 1;
+
+            if let Some(point) = self.read_at(self.back) {
+                return Some(point);
             }
         }
 
@@ -637,6 +934,205 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_read_reverse() {
+        let mut ring = create_test_ring(3, 1_000_000_000);
+
+        ring.write(0, 10.0, 1_000_000_000).unwrap();
+        ring.write(0, 20.0, 2_000_000_000).unwrap();
+        ring.write(0, 30.0, 3_000_000_000).unwrap();
+        ring.write(0, 40.0, 4_000_000_000).unwrap(); // wraps
+
+        let data: Vec<_> = ring.read(0, 0, 10_000_000_000).unwrap().rev().collect();
+        assert_eq!(
+            data,
+            vec![
+                (4_000_000_000, 40.0),
+                (3_000_000_000, 30.0),
+                (2_000_000_000, 20.0),
+            ]
+        );
+
+        // Range filtering applies from the back as well
+        let data: Vec<_> = ring
+            .read(0, 0, 4_000_000_000)
+            .unwrap()
+            .rev()
+            .take(1)
+            .collect();
+        assert_eq!(data, vec![(3_000_000_000, 30.0)]);
+    }
+
+    #[test]
+    fn test_read_double_ended_meets_in_middle() {
+        let mut ring = create_test_ring(10, 1_000_000_000);
+        for i in 1..=5u32 {
+            ring.write(0, f64::from(i), u64::from(i) * 1_000_000_000)
+                .unwrap();
+        }
+
+        let mut iter = ring.read(0, 0, 10_000_000_000).unwrap();
+        assert_eq!(iter.next(), Some((1_000_000_000, 1.0)));
+        assert_eq!(iter.next_back(), Some((5_000_000_000, 5.0)));
+        assert_eq!(iter.next(), Some((2_000_000_000, 2.0)));
+        assert_eq!(iter.next_back(), Some((4_000_000_000, 4.0)));
+        assert_eq!(iter.next_back(), Some((3_000_000_000, 3.0)));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_read_visits_only_requested_slots() {
+        let mut ring = create_test_ring(1000, 1_000_000_000);
+
+        // Fill the ring one and a half times so it has wrapped
+        for i in 1..=1500u32 {
+            ring.write(0, f64::from(i), u64::from(i) * 1_000_000_000)
+                .unwrap();
+        }
+
+        // 10-second range in the middle of the retained window
+        let iter = ring.read(0, 1_200_000_000_000, 1_210_000_000_000).unwrap();
+        assert_eq!(iter.size_hint(), (0, Some(10)));
+        let data: Vec<_> = iter.collect();
+        assert_eq!(data.len(), 10);
+        assert_eq!(data[0], (1_200_000_000_000, 1200.0));
+        assert_eq!(data[9], (1_209_000_000_000, 1209.0));
+
+        // Range partially before the retained window is clamped to it
+        let iter = ring.read(0, 100_000_000_000, 505_000_000_000).unwrap();
+        assert_eq!(iter.size_hint(), (0, Some(4)));
+        assert_eq!(iter.count(), 4);
+
+        // Ranges entirely outside the window visit nothing
+        let iter = ring.read(0, 1_000_000_000, 400_000_000_000).unwrap();
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+        let iter = ring.read(0, 2_000_000_000_000, 3_000_000_000_000).unwrap();
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+    }
+
+    #[test]
+    fn test_read_unaligned_range() {
+        let mut ring = create_test_ring(10, 1_000_000_000);
+        ring.write(0, 1.0, 1_500_000_000).unwrap();
+        ring.write(0, 2.0, 2_500_000_000).unwrap();
+        ring.write(0, 3.0, 3_500_000_000).unwrap();
+
+        let data: Vec<_> = ring
+            .read(0, 1_600_000_000, 3_500_000_000)
+            .unwrap()
+            .collect();
+        assert_eq!(data, vec![(2_500_000_000, 2.0)]);
+
+        let data: Vec<_> = ring
+            .read(0, 1_500_000_000, 3_500_000_001)
+            .unwrap()
+            .collect();
+        assert_eq!(data.len(), 3);
+    }
+
+    #[test]
+    fn test_read_into_matches_read() {
+        let mut ring = create_test_ring(8, 1_000_000_000);
+
+        // Wrap the ring so the requested range spans the physical end
+        for i in 1..=13u32 {
+            if i % 4 != 0 {
+                ring.write(0, f64::from(i), u64::from(i) * 1_000_000_000)
+                    .unwrap();
+            }
+            ring.write(1, -f64::from(i), u64::from(i) * 1_000_000_000)
+                .unwrap();
+        }
+
+        for (start, end) in [
+            (0, u64::MAX),
+            (7_000_000_000, 12_000_000_000),
+            (20_000_000_000, 30_000_000_000),
+        ] {
+            let expected: Vec<_> = ring.read(0, start, end).unwrap().collect();
+
+            let mut timestamps = Vec::new();
+            let mut values = Vec::new();
+            let n = ring
+                .read_into(0, start, end, &mut timestamps, &mut values)
+                .unwrap();
+
+            assert_eq!(n, expected.len());
+            let actual: Vec<_> = timestamps.into_iter().zip(values).collect();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_read_into_appends() {
+        let mut ring = create_test_ring(10, 1_000_000_000);
+        ring.write(0, 1.0, 1_000_000_000).unwrap();
+        ring.write(0, 2.0, 2_000_000_000).unwrap();
+
+        let mut timestamps = vec![0];
+        let mut values = vec![0.0];
+        let n = ring
+            .read_into(
+                0,
+                2_000_000_000,
+                3_000_000_000,
+                &mut timestamps,
+                &mut values,
+            )
+            .unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(timestamps, vec![0, 2_000_000_000]);
+        assert_eq!(values, vec![0.0, 2.0]);
+
+        assert!(
+            ring.read_into(0, 5, 5, &mut timestamps, &mut values)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_read_rows_into() {
+        let mut ring = create_test_ring(4, 1_000_000_000);
+
+        ring.write_batch(&[(0, 1.0), (1, 2.0)], 1_000_000_000)
+            .unwrap();
+        ring.write(5, 6.0, 2_000_000_000).unwrap(); // only outside the read columns
+        ring.write_batch(&[(1, 20.0)], 3_000_000_000).unwrap();
+        ring.write_batch(&[(0, 40.0), (1, 41.0)], 5_000_000_000)
+            .unwrap(); // wraps, overwrites t=1s
+
+        let mut timestamps = Vec::new();
+        let mut values = Vec::new();
+        let rows = ring
+            .read_rows_into(3, 0, u64::MAX, &mut timestamps, &mut values)
+            .unwrap();
+
+        assert_eq!(rows, 2);
+        assert_eq!(timestamps, vec![3_000_000_000, 5_000_000_000]);
+        assert_eq!(values.len(), 6);
+        assert!(values[0].is_nan());
+        assert_eq!(values[1], 20.0);
+        assert!(values[2].is_nan());
+        assert_eq!(&values[3..5], &[40.0, 41.0]);
+        assert!(values[5].is_nan());
+
+        // Time range filtering
+        timestamps.clear();
+        values.clear();
+        let rows = ring
+            .read_rows_into(
+                3,
+                4_000_000_000,
+                6_000_000_000,
+                &mut timestamps,
+                &mut values,
+            )
+            .unwrap();
+        assert_eq!(rows, 1);
+        assert_eq!(timestamps, vec![5_000_000_000]);
+    }
+
     #[test]
     fn test_read_skips_nan() {
         let mut ring = create_test_ring(10, 1_000_000_000);
@@ -51,7 +51,7 @@
 //! # }
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 use serde::{Deserialize, Serialize};
@@ -101,6 +101,60 @@ impl SeriesHandle {
     }
 }
 
+/// Kind of a metric, as recorded in [`MetricMetadata`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    /// Not known.
+    #[default]
+    Unknown,
+    /// Monotonically increasing value.
+    Counter,
+    /// Value that can go up and down.
+    Gauge,
+    /// Part of a bucketed histogram (`_bucket`, `_sum`, `_count`).
+    Histogram,
+    /// Part of a summary (quantiles, `_sum`, `_count`).
+    Summary,
+}
+
+/// Descriptive metadata for a metric name, shared by all its series.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetricMetadata {
+    /// The metric kind.
+    pub kind: MetricKind,
+    /// Unit of the values (e.g. `bytes`, `seconds`), empty if unknown.
+    #[serde(default)]
+    pub unit: String,
+    /// Help text, empty if none.
+    #[serde(default)]
+    pub help: String,
+}
+
+impl MetricMetadata {
+    /// Creates metadata with the given kind and no unit or help.
+    pub fn new(kind: MetricKind) -> Self {
+        Self {
+            kind,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the unit.
+    #[must_use]
+    pub fn with_unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = unit.into();
+        self
+    }
+
+    /// Sets the help text.
+    #[must_use]
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = help.into();
+        self
+    }
+}
+
 /// Information about a registered series.
 ///
 /// Contains the metadata that identifies a time series, including its name
@@ -163,6 +217,8 @@ pub struct SeriesRegistry {
     next_series_id: Vec<u32>,
     /// Next available column for each schema.
     next_column: Vec<u32>,
+    /// Metadata per metric name.
+    metadata: BTreeMap<String, MetricMetadata>,
 }
 
 /// Key type for looking up series in the registry.
@@ -228,6 +284,7 @@ impl SeriesRegistry {
             series_map: HashMap::new(),
             next_series_id: vec![0; schema_count],
             next_column: vec![0; schema_count],
+            metadata: BTreeMap::new(),
         }
     }
 
@@ -360,6 +417,19 @@ impl SeriesRegistry {
             .find(|info| info.handle() == *handle)
     }
 
+    /// Sets the metadata of a metric name, replacing any previous value.
+    ///
+    /// Metadata is keyed by name alone and may be set before or after the
+    /// name's series are registered.
+    pub fn set_metadata(&mut self, name: &str, metadata: MetricMetadata) {
+        self.metadata.insert(name.to_string(), metadata);
+    }
+
+    /// Returns the metadata of a metric name, if any was set.
+    pub fn metadata(&self, name: &str) -> Option<&MetricMetadata> {
+        self.metadata.get(name)
+    }
+
     /// Returns the number of registered series for a schema.
     ///
     /// # Arguments
@@ -420,6 +490,34 @@ impl SeriesRegistry {
         Ok(())
     }
 
+    /// Updates the slabs of every tier of one schema with current registrations.
+    ///
+    /// Unlike [`sync_to_slabs`](Self::sync_to_slabs), which takes one slab
+    /// per schema, this takes all tier slabs of a single schema so that each
+    /// tier carries the schema's series count and directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema_index` - The schema whose registrations are synced
+    /// * `slabs` - Mutable references to the slab of each tier of the schema
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if slab updates fail.
+    pub fn sync_schema_to_slabs(&self, schema_index: usize, slabs: &mut [&mut Slab]) -> Result<()> {
+        let count = self.series_count(schema_index);
+        for slab in slabs.iter_mut() {
+            slab.set_series_count(count);
+
+            for info in self.series_map.values() {
+                if info.schema_index == schema_index {
+                    slab.set_series_column(info.series_id, info.column);
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Saves the series index to a file.
     ///
     /// The series index is serialized as JSON for simplicity in the MVP.
@@ -438,6 +536,7 @@ impl SeriesRegistry {
             series: self.series_map.values().cloned().collect(),
             next_series_id: self.next_series_id.clone(),
             next_column: self.next_column.clone(),
+            metadata: self.metadata.clone(),
         };
 
         let json = serde_json::to_string_pretty(&index)
@@ -505,6 +604,7 @@ impl SeriesRegistry {
             series_map,
             next_series_id: index.next_series_id,
             next_column: index.next_column,
+            metadata: index.metadata,
         })
     }
 
@@ -580,6 +680,9 @@ struct SeriesIndex {
     next_series_id: Vec<u32>,
     /// Next available column for each schema.
     next_column: Vec<u32>,
+    /// Metadata per metric name (absent in indexes written before it existed).
+    #[serde(default)]
+    metadata: BTreeMap<String, MetricMetadata>,
 }
 
 #[cfg(test)]
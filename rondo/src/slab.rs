@@ -20,6 +20,7 @@
 //! maximum performance.
 
 use std::fs::OpenOptions;
+use std::ops::Range;
 use std::path::Path;
 use std::ptr;
 
@@ -614,6 +615,131 @@ impl Slab {
         unsafe { ptr::read(ptr) }
     }
 
+    /// Appends the timestamps of a contiguous slot range to `out`.
+    ///
+    /// The whole range is copied from the memory-mapped region with a single
+    /// `memcpy`. Uninitialized slots read as 0.
+    ///
+    /// # Arguments
+    ///
+    /// * `slots` - Range of ring buffer slot indices (must not wrap)
+    /// * `out` - Buffer the timestamps are appended to
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slots` extends past `slot_count`.
+    pub fn read_timestamps_into(&self, slots: Range<u32>, out: &mut Vec<u64>) {
+        assert!(
+            slots.start <= slots.end && slots.end <= self.slot_count(),
+            "slot range {slots:?} out of bounds"
+        );
+
+        let offset = self.layout.timestamp_column_offset + (slots.start as usize * TIMESTAMP_SIZE);
+        let count = (slots.end - slots.start) as usize;
+        out.reserve(count);
+
+        // SAFETY: The source range lies within the timestamp column (bounds
+        // checked above against the validated layout) and the destination has
+        // at least `count` elements of spare capacity after `reserve`. The
+        // copy is bytewise, so the source need not be aligned, and any bit
+        // pattern is a valid u64.
+        unsafe {
+            let src = self.mmap.as_ptr().add(offset);
+            let dst = out.as_mut_ptr().add(out.len()).cast::<u8>();
+            ptr::copy_nonoverlapping(src, dst, count * TIMESTAMP_SIZE);
+            out.set_len(out.len() + count);
+        }
+    }
+
+    /// Appends the values of a series column over a contiguous slot range to `out`.
+    ///
+    /// The whole range is copied from the memory-mapped region with a single
+    /// `memcpy`. Uninitialized slots read as NaN.
+    ///
+    /// # Arguments
+    ///
+    /// * `series_column` - Series column index
+    /// * `slots` - Range of ring buffer slot indices (must not wrap)
+    /// * `out` - Buffer the values are appended to
+    ///
+    /// # Panics
+    ///
+    /// Panics if `series_column` is not below `max_series` or `slots` extends
+    /// past `slot_count`.
+    pub fn read_values_into(&self, series_column: u32, slots: Range<u32>, out: &mut Vec<f64>) {
+        assert!(
+            series_column < self.max_series(),
+            "series column {series_column} out of bounds"
+        );
+        assert!(
+            slots.start <= slots.end && slots.end <= self.slot_count(),
+            "slot range {slots:?} out of bounds"
+        );
+
+        let column_offset = self.layout.value_column_offset(series_column);
+        let offset = column_offset + (slots.start as usize * VALUE_SIZE);
+        let count = (slots.end - slots.start) as usize;
+        out.reserve(count);
+
+        // SAFETY: The source range lies within the value column (both indices
+        // bounds checked above against the validated layout) and the
+        // destination has at least `count` elements of spare capacity after
+        // `reserve`. The copy is bytewise, so the source need not be aligned,
+        // and any bit pattern is a valid f64.
+        unsafe {
+            let src = self.mmap.as_ptr().add(offset);
+            let dst = out.as_mut_ptr().add(out.len()).cast::<u8>();
+            ptr::copy_nonoverlapping(src, dst, count * VALUE_SIZE);
+            out.set_len(out.len() + count);
+        }
+    }
+
+    /// Appends the values of columns `0..columns` at a slot to `out`.
+    ///
+    /// This is the row-oriented counterpart of [`read_value`](Self::read_value):
+    /// one call returns every series' value at the slot. Unwritten values
+    /// read as NaN.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot_index` - Ring buffer slot index
+    /// * `columns` - Number of leading series columns to read
+    /// * `out` - Buffer the values are appended to
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slot_index` is not below `slot_count` or `columns` exceeds
+    /// `max_series`.
+    pub fn read_row_into(&self, slot_index: u32, columns: u32, out: &mut Vec<f64>) {
+        assert!(
+            slot_index < self.slot_count(),
+            "slot {slot_index} out of bounds"
+        );
+        assert!(
+            columns <= self.max_series(),
+            "column count {columns} out of bounds"
+        );
+
+        out.reserve(columns as usize);
+        for column in 0..columns {
+            out.push(self.read_value(slot_index, column));
+        }
+    }
+
+    /// Returns the values of every registered series column at a slot.
+    ///
+    /// The row has [`series_count`](Self::series_count) entries, indexed by
+    /// column. Unwritten values read as NaN.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slot_index` is not below `slot_count`.
+    pub fn read_row(&self, slot_index: u32) -> Vec<f64> {
+        let mut row = Vec::new();
+        self.read_row_into(slot_index, self.series_count(), &mut row);
+        row
+    }
+
     /// Gets the column offset for a series from the series directory.
     ///
     /// # Arguments
@@ -834,6 +960,68 @@ mod tests {
         assert!(slab.read_value(50, 0).is_nan());
     }
 
+    #[test]
+    fn test_bulk_column_reads() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let slab_path = temp_dir.path().join("test.slab");
+
+        // Odd max_series leaves the data region unaligned to 8 bytes
+        let mut slab = Slab::create(&slab_path, 0x1234567890abcdef, 10, 3, 1_000_000_000).unwrap();
+        for slot in 0..10u32 {
+            slab.write_timestamp(slot, u64::from(slot) + 1);
+            slab.write_value(slot, 1, f64::from(slot) * 2.0);
+        }
+
+        let mut timestamps = vec![99];
+        slab.read_timestamps_into(2..5, &mut timestamps);
+        assert_eq!(timestamps, vec![99, 3, 4, 5]);
+
+        let mut values = Vec::new();
+        slab.read_values_into(1, 8..10, &mut values);
+        assert_eq!(values, vec![16.0, 18.0]);
+
+        values.clear();
+        slab.read_values_into(2, 0..2, &mut values);
+        assert!(values.iter().all(|v| v.is_nan()));
+
+        slab.read_timestamps_into(3..3, &mut timestamps);
+        assert_eq!(timestamps.len(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_bulk_read_out_of_bounds() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let slab_path = temp_dir.path().join("test.slab");
+        let slab = Slab::create(&slab_path, 0x1234567890abcdef, 10, 3, 1_000_000_000).unwrap();
+        slab.read_timestamps_into(5..11, &mut Vec::new());
+    }
+
+    #[test]
+    fn test_row_reads() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let slab_path = temp_dir.path().join("test.slab");
+
+        let mut slab = Slab::create(&slab_path, 0x1234567890abcdef, 10, 4, 1_000_000_000).unwrap();
+        slab.write_value(3, 0, 1.0);
+        slab.write_value(3, 2, 3.0);
+        slab.write_value(3, 3, 4.0);
+
+        let mut row = Vec::new();
+        slab.read_row_into(3, 3, &mut row);
+        assert_eq!(row[0], 1.0);
+        assert!(row[1].is_nan());
+        assert_eq!(row[2], 3.0);
+        assert_eq!(row.len(), 3);
+
+        // read_row covers only registered columns
+        assert!(slab.read_row(3).is_empty());
+        slab.set_series_count(4);
+        let row = slab.read_row(3);
+        assert_eq!(row.len(), 4);
+        assert_eq!(row[3], 4.0);
+    }
+
     #[test]
     fn test_series_directory() {
         let temp_dir = tempfile::tempdir().unwrap();
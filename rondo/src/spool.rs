@@ -0,0 +1,301 @@
+//! Bounded on-disk spool for export batches a sink could not deliver.
+//!
+//! The store only keeps raw data for the retention of tier 0 (10 minutes in
+//! the demo VMM). If an endpoint stays down longer than that, data is lost
+//! when the ring wraps, however carefully the export cursor is managed. A
+//! [`Spool`] closes that gap: the [`ExportDriver`](crate::exporter::ExportDriver)
+//! writes each undelivered batch to disk as an encoded payload, advances the
+//! sink's cursor, and replays the spooled batches oldest-first once the
+//! endpoint accepts data again.
+//!
+//! The spool is bounded by total bytes and, optionally, by batch count.
+//! When a new batch would exceed a cap, the oldest batches are evicted
+//! first, so a long outage loses the start of the outage rather than its
+//! end.
+//!
+//! Each batch is one file named by a sequence number, written to a
+//! temporary name and renamed into place, so a crash never leaves a
+//! truncated batch behind. Spooled batches survive restarts.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::spool::Spool;
+//!
+//! # fn main() -> rondo::Result<()> {
+//! let mut spool = Spool::open("/tmp/spool_example", 64 * 1024 * 1024)?.with_max_batches(1000);
+//!
+//! spool.push(b"payload")?;
+//! while let Some(payload) = spool.front()? {
+//!     // Deliver the payload; stop on the first failure.
+//!     # let _ = payload;
+//!     spool.pop_front()?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+
+use crate::error::{ExportError, Result};
+
+/// File extension of spooled batches.
+const BATCH_EXTENSION: &str = "batch";
+
+/// File extension of batches that are still being written.
+const TMP_EXTENSION: &str = "tmp";
+
+/// A bounded, ordered queue of encoded export batches in a directory.
+#[derive(Debug)]
+pub struct Spool {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_batches: Option<usize>,
+    /// Sequence number and size of each spooled batch, oldest first.
+    batches: VecDeque<(u64, u64)>,
+    bytes: u64,
+    next_seq: u64,
+    evicted: u64,
+}
+
+impl Spool {
+    /// Opens (or creates) a spool directory holding at most `max_bytes` of
+    /// batches.
+    ///
+    /// Batches left by an earlier run are picked up in order. Partially
+    /// written batches from a crash are removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created or read.
+    pub fn open<P: AsRef<Path>>(dir: P, max_bytes: u64) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(|e| spool_io(&dir, e))?;
+
+        let mut batches = Vec::new();
+        for entry in std::fs::read_dir(&dir).map_err(|e| spool_io(&dir, e))? {
+            let entry = entry.map_err(|e| spool_io(&dir, e))?;
+            let path = entry.path();
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some(BATCH_EXTENSION) => {}
+                Some(TMP_EXTENSION) => {
+                    std::fs::remove_file(&path).map_err(|e| spool_io(&path, e))?;
+                    continue;
+                }
+                _ => continue,
+            }
+            let Some(seq) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            let size = entry.metadata().map_err(|e| spool_io(&path, e))?.len();
+            batches.push((seq, size));
+        }
+        batches.sort_unstable();
+
+        let bytes = batches.iter().map(|&(_, size)| size).sum();
+        let next_seq = batches.last().map_or(0, |&(seq, _)| seq + 1);
+        Ok(Self {
+            dir,
+            max_bytes,
+            max_batches: None,
+            batches: batches.into(),
+            bytes,
+            next_seq,
+            evicted: 0,
+        })
+    }
+
+    /// Caps the number of spooled batches in addition to their total size.
+    ///
+    /// The newest batch is always kept, so a cap of 0 behaves like 1.
+    #[must_use]
+    pub fn with_max_batches(mut self, max_batches: usize) -> Self {
+        self.max_batches = Some(max_batches);
+        self
+    }
+
+    /// Returns the spool directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the number of spooled batches.
+    pub fn len(&self) -> usize {
+        self.batches.len()
+    }
+
+    /// Returns `true` if no batches are spooled.
+    pub fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+
+    /// Returns the total size of the spooled batches in bytes.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// Returns the number of batches evicted since the spool was opened.
+    pub fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    /// Appends a batch, evicting the oldest batches to stay within the caps.
+    ///
+    /// Returns the number of batches evicted to make room.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ExportError::SpoolFull`] if the payload alone is larger
+    /// than the byte cap (nothing is evicted then), or an error if the
+    /// batch cannot be written.
+    pub fn push(&mut self, payload: &[u8]) -> Result<usize> {
+        let size = payload.len() as u64;
+        if size > self.max_bytes {
+            return Err(ExportError::SpoolFull {
+                size,
+                max_bytes: self.max_bytes,
+            }
+            .into());
+        }
+
+        let mut evicted = 0;
+        while !self.batches.is_empty()
+            && (self.bytes + size > self.max_bytes
+                || self
+                    .max_batches
+                    .is_some_and(|max| self.batches.len() >= max))
+        {
+            self.pop_front()?;
+            evicted += 1;
+        }
+        self.evicted += evicted as u64;
+
+        let seq = self.next_seq;
+        let path = self.batch_path(seq);
+        let tmp = path.with_extension(TMP_EXTENSION);
+        std::fs::write(&tmp, payload).map_err(|e| spool_io(&tmp, e))?;
+        std::fs::rename(&tmp, &path).map_err(|e| spool_io(&path, e))?;
+
+        self.batches.push_back((seq, size));
+        self.bytes += size;
+        self.next_seq += 1;
+        Ok(evicted)
+    }
+
+    /// Reads the oldest spooled batch, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch file cannot be read.
+    pub fn front(&self) -> Result<Option<Vec<u8>>> {
+        let Some(&(seq, _)) = self.batches.front() else {
+            return Ok(None);
+        };
+        let path = self.batch_path(seq);
+        let payload = std::fs::read(&path).map_err(|e| spool_io(&path, e))?;
+        Ok(Some(payload))
+    }
+
+    /// Removes the oldest spooled batch, once it has been delivered.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the batch file cannot be removed.
+    pub fn pop_front(&mut self) -> Result<()> {
+        let Some(&(seq, size)) = self.batches.front() else {
+            return Ok(());
+        };
+        let path = self.batch_path(seq);
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(spool_io(&path, e)),
+        }
+        self.batches.pop_front();
+        self.bytes -= size;
+        Ok(())
+    }
+
+    fn batch_path(&self, seq: u64) -> PathBuf {
+        self.dir.join(format!("{seq:020}.{BATCH_EXTENSION}"))
+    }
+}
+
+fn spool_io(path: &Path, source: std::io::Error) -> crate::RondoError {
+    ExportError::SpoolIo {
+        path: path.to_path_buf(),
+        source,
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replays_in_order_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("spool");
+
+        let mut spool = Spool::open(&path, 1024).unwrap();
+        assert!(spool.is_empty());
+        assert_eq!(spool.front().unwrap(), None);
+        for payload in [&b"one"[..], b"two", b"three"] {
+            assert_eq!(spool.push(payload).unwrap(), 0);
+        }
+        assert_eq!((spool.len(), spool.bytes()), (3, 11));
+        assert_eq!(spool.front().unwrap().as_deref(), Some(&b"one"[..]));
+        spool.pop_front().unwrap();
+
+        // A leftover temporary file from a crash is discarded
+        std::fs::write(path.join("00000000000000000009.tmp"), b"partial").unwrap();
+        drop(spool);
+
+        let mut spool = Spool::open(&path, 1024).unwrap();
+        assert_eq!((spool.len(), spool.bytes()), (2, 8));
+        assert!(!path.join("00000000000000000009.tmp").exists());
+        spool.push(b"four").unwrap();
+
+        let mut replayed = Vec::new();
+        while let Some(payload) = spool.front().unwrap() {
+            replayed.push(String::from_utf8(payload).unwrap());
+            spool.pop_front().unwrap();
+        }
+        assert_eq!(replayed, ["two", "three", "four"]);
+        assert_eq!(spool.bytes(), 0);
+    }
+
+    #[test]
+    fn test_evicts_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut spool = Spool::open(dir.path(), 10).unwrap();
+
+        spool.push(b"aaaa").unwrap();
+        spool.push(b"bbbb").unwrap();
+        // 4 + 4 + 4 > 10: the oldest batch goes
+        assert_eq!(spool.push(b"cccc").unwrap(), 1);
+        assert_eq!(spool.front().unwrap().as_deref(), Some(&b"bbbb"[..]));
+        assert_eq!((spool.len(), spool.bytes(), spool.evicted()), (2, 8, 1));
+
+        // A batch larger than the whole spool is refused without evicting
+        assert!(matches!(
+            spool.push(&[0; 11]),
+            Err(crate::RondoError::Export(ExportError::SpoolFull {
+                size: 11,
+                ..
+            }))
+        ));
+        assert_eq!(spool.len(), 2);
+
+        let mut spool = spool.with_max_batches(2);
+        assert_eq!(spool.push(b"d").unwrap(), 1);
+        assert_eq!(spool.front().unwrap().as_deref(), Some(&b"cccc"[..]));
+        assert_eq!(spool.len(), 2);
+    }
+}
@@ -0,0 +1,643 @@
+//! StatsD/DogStatsD listener that aggregates packets into a store.
+//!
+//! Parses the StatsD text protocol with the DogStatsD extensions:
+//!
+//! ```text
+//! <name>:<value>|<type>[|@<sample_rate>][|#<tag>:<value>,...]
+//! ```
+//!
+//! Metrics are aggregated in memory and written to the store once per flush
+//! interval, all at the flush timestamp:
+//!
+//! | Type | Recorded as |
+//! |------|-------------|
+//! | `c` (counter) | sum over the interval, scaled by `1 / sample_rate` |
+//! | `g` (gauge) | last value; `+n`/`-n` adjust the previous value |
+//! | `ms`, `h`, `d` (timer, histogram, distribution) | per-interval histogram: `<name>_bucket{le}`, `<name>_sum`, `<name>_count` |
+//! | `s` (set) | number of distinct values in the interval |
+//!
+//! Because histograms are per interval, quantiles come straight from the
+//! buckets: `histogram_quantile(0.99, name_bucket)` needs no `rate()`.
+//! Metric names are sanitized like Prometheus names (`api.latency` becomes
+//! `api_latency`) and DogStatsD tags become labels; tags without a value,
+//! events (`_e{...}`) and service checks (`_sc|...`) are ignored.
+//!
+//! Series are registered through [`Store::register`] on first flush, with
+//! the config's static labels added so a [`LabelMatcher`] can route them to
+//! their own schema, and the number of series registered is capped.
+//!
+//! rondo has no background threads, so the embedding application drives
+//! the [`StatsdListener`] from a thread of its own.
+//!
+//! This module is only available when the `statsd` feature is enabled.
+//!
+//! [`LabelMatcher`]: crate::schema::LabelMatcher
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use rondo::statsd::{StatsdConfig, StatsdListener};
+//! use rondo::store::Store;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let mut store = Store::open("./data", vec![])?;
+//! let config = StatsdConfig::new().with_label("source", "statsd");
+//! let mut listener = StatsdListener::bind("127.0.0.1:8125", config)?;
+//!
+//! loop {
+//!     listener.wait_for_flush()?;
+//!     # let now_ns = 1_700_000_000_000_000_000;
+//!     let summary = listener.flush(&mut store, now_ns);
+//!     println!("recorded {} values", summary.recorded);
+//! }
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::error::{Result, StatsdError};
+use crate::exposition::sanitize_name;
+use crate::series::SeriesHandle;
+use crate::store::Store;
+
+/// Largest UDP datagram read from the socket.
+const MAX_PACKET_BYTES: usize = 65_535;
+
+/// Default timer buckets, in milliseconds.
+const DEFAULT_BUCKETS: [f64; 11] = [
+    5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0,
+];
+
+/// Configuration for a [`StatsdAggregator`].
+#[derive(Debug, Clone)]
+pub struct StatsdConfig {
+    /// How often aggregates are written to the store.
+    pub flush_interval: Duration,
+    /// Upper bounds of the histogram buckets for timers, in ascending order.
+    pub buckets: Vec<f64>,
+    /// Maximum number of series registered in total.
+    pub max_series: u32,
+    /// Labels added to every series (e.g. for schema routing).
+    pub labels: Vec<(String, String)>,
+}
+
+impl Default for StatsdConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatsdConfig {
+    /// Creates a config with sensible defaults.
+    ///
+    /// Defaults: 10s flush interval, buckets from 5ms to 10s, at most 1000
+    /// series.
+    pub fn new() -> Self {
+        Self {
+            flush_interval: Duration::from_secs(10),
+            buckets: DEFAULT_BUCKETS.to_vec(),
+            max_series: 1000,
+            labels: Vec::new(),
+        }
+    }
+
+    /// Sets the flush interval.
+    #[must_use]
+    pub fn with_flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Sets the timer histogram buckets (sorted; `+Inf` is implied).
+    #[must_use]
+    pub fn with_buckets(mut self, mut buckets: Vec<f64>) -> Self {
+        buckets.retain(|b| b.is_finite());
+        buckets.sort_by(f64::total_cmp);
+        buckets.dedup();
+        self.buckets = buckets;
+        self
+    }
+
+    /// Sets the maximum number of series registered in total.
+    #[must_use]
+    pub fn with_max_series(mut self, max_series: u32) -> Self {
+        self.max_series = max_series;
+        self
+    }
+
+    /// Adds a label to every series.
+    ///
+    /// Static labels take precedence over tags with the same name.
+    #[must_use]
+    pub fn with_label(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Outcome of one flush.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlushSummary {
+    /// Values written to the store.
+    pub recorded: u64,
+    /// Values dropped (series limit, schema full, invalid value).
+    pub rejected: u64,
+    /// Malformed lines received since the previous flush.
+    pub malformed: u64,
+    /// Reason for the first rejection, if any.
+    pub error: Option<String>,
+}
+
+/// A metric name plus sorted labels.
+type Key = (String, Vec<(String, String)>);
+
+/// In-memory aggregate of one metric for the current interval.
+#[derive(Debug)]
+enum Aggregate {
+    Counter(f64),
+    Gauge { value: f64, updated: bool },
+    Timer(Histogram),
+    Set(HashSet<String>),
+}
+
+/// Per-interval histogram of timer values.
+#[derive(Debug)]
+struct Histogram {
+    /// Weighted counts per bucket, plus a final `+Inf` bucket.
+    counts: Vec<f64>,
+    sum: f64,
+    count: f64,
+}
+
+/// Aggregates StatsD lines and flushes them into a store.
+#[derive(Debug)]
+pub struct StatsdAggregator {
+    config: StatsdConfig,
+    // Ordered so flushes, and which series hit the limit, are deterministic
+    aggregates: BTreeMap<Key, Aggregate>,
+    series: HashMap<Key, SeriesHandle>,
+    malformed: u64,
+}
+
+impl StatsdAggregator {
+    /// Creates an aggregator with the given config.
+    pub fn new(config: StatsdConfig) -> Self {
+        Self {
+            config,
+            aggregates: BTreeMap::new(),
+            series: HashMap::new(),
+            malformed: 0,
+        }
+    }
+
+    /// Returns the config.
+    pub fn config(&self) -> &StatsdConfig {
+        &self.config
+    }
+
+    /// Aggregates every line of a packet.
+    ///
+    /// Returns the number of metric lines accepted; malformed lines are
+    /// counted and reported by the next [`flush`](Self::flush).
+    pub fn ingest(&mut self, packet: &str) -> usize {
+        let mut accepted = 0;
+        for line in packet.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("_e{") || line.starts_with("_sc|") {
+                continue;
+            }
+            if self.ingest_line(line).is_some() {
+                accepted += 1;
+            } else {
+                self.malformed += 1;
+            }
+        }
+        accepted
+    }
+
+    /// Writes the current aggregates to the store at `timestamp_ns`.
+    ///
+    /// Counters, timers and sets start over afterwards; gauges keep their
+    /// value so relative updates continue from it, but are only recorded
+    /// again once updated.
+    pub fn flush(&mut self, store: &mut Store, timestamp_ns: u64) -> FlushSummary {
+        let mut summary = FlushSummary {
+            malformed: std::mem::take(&mut self.malformed),
+            ..FlushSummary::default()
+        };
+
+        let mut samples = Vec::new();
+        for ((name, labels), aggregate) in &mut self.aggregates {
+            match aggregate {
+                Aggregate::Counter(sum) => samples.push((name.clone(), labels.clone(), *sum)),
+                Aggregate::Gauge { value, updated } => {
+                    if std::mem::take(updated) {
+                        samples.push((name.clone(), labels.clone(), *value));
+                    }
+                }
+                #[allow(clippy::cast_precision_loss)] // Set sizes are small
+                Aggregate::Set(values) => {
+                    samples.push((name.clone(), labels.clone(), values.len() as f64));
+                }
+                Aggregate::Timer(histogram) => {
+                    let mut cumulative = 0.0;
+                    for (i, count) in histogram.counts.iter().enumerate() {
+                        cumulative += count;
+                        let le = self
+                            .config
+                            .buckets
+                            .get(i)
+                            .map_or_else(|| "+Inf".to_string(), ToString::to_string);
+                        let mut bucket_labels = labels.clone();
+                        bucket_labels.push(("le".to_string(), le));
+                        bucket_labels.sort();
+                        samples.push((format!("{name}_bucket"), bucket_labels, cumulative));
+                    }
+                    samples.push((format!("{name}_sum"), labels.clone(), histogram.sum));
+                    samples.push((format!("{name}_count"), labels.clone(), histogram.count));
+                }
+            }
+        }
+        self.aggregates
+            .retain(|_, aggregate| matches!(aggregate, Aggregate::Gauge { .. }));
+
+        for (name, labels, value) in samples {
+            let result = self.resolve(store, &name, labels).and_then(|handle| {
+                store
+                    .record(handle, value, timestamp_ns)
+                    .map_err(|e| format!("failed to record '{name}': {e}"))
+            });
+            match result {
+                Ok(()) => summary.recorded += 1,
+                Err(reason) => {
+                    summary.rejected += 1;
+                    summary.error.get_or_insert(reason);
+                }
+            }
+        }
+
+        summary
+    }
+
+    /// Parses and aggregates one line, returning `None` if it is malformed.
+    fn ingest_line(&mut self, line: &str) -> Option<()> {
+        let (name, rest) = line.split_once(':')?;
+        if name.is_empty() {
+            return None;
+        }
+        let mut sections = rest.split('|');
+        let raw_value = sections.next()?;
+        let kind = sections.next()?;
+
+        let mut sample_rate = 1.0;
+        let mut labels = Vec::new();
+        for section in sections {
+            if let Some(rate) = section.strip_prefix('@') {
+                sample_rate = rate.parse::<f64>().ok().filter(|r| *r > 0.0 && *r <= 1.0)?;
+            } else if let Some(tags) = section.strip_prefix('#') {
+                for tag in tags.split(',') {
+                    if let Some((key, value)) = tag.split_once(':')
+                        && !key.is_empty()
+                    {
+                        labels.push((sanitize_name(key, false), value.to_string()));
+                    }
+                }
+            }
+            // Other extensions (container IDs, timestamps) are ignored
+        }
+        for (key, value) in &self.config.labels {
+            labels.retain(|(k, _)| k != key);
+            labels.push((key.clone(), value.clone()));
+        }
+        labels.sort();
+        labels.dedup_by(|a, b| a.0 == b.0);
+
+        let key = (sanitize_name(name, true), labels);
+        let buckets = &self.config.buckets;
+
+        match kind {
+            "c" => {
+                let value = parse_number(raw_value)? / sample_rate;
+                match self
+                    .aggregates
+                    .entry(key)
+                    .or_insert(Aggregate::Counter(0.0))
+                {
+                    Aggregate::Counter(sum) => *sum += value,
+                    _ => return None,
+                }
+            }
+            "g" => {
+                let value = parse_number(raw_value)?;
+                let relative = raw_value.starts_with(['+', '-']);
+                let entry = self.aggregates.entry(key).or_insert(Aggregate::Gauge {
+                    value: 0.0,
+                    updated: false,
+                });
+                match entry {
+                    Aggregate::Gauge {
+                        value: current,
+                        updated,
+                    } => {
+                        *current = if relative { *current + value } else { value };
+                        *updated = true;
+                    }
+                    _ => return None,
+                }
+            }
+            "ms" | "h" | "d" => {
+                // DogStatsD packs several values into one line with `:`
+                let values = raw_value
+                    .split(':')
+                    .map(parse_number)
+                    .collect::<Option<Vec<_>>>()?;
+                let entry = self.aggregates.entry(key).or_insert_with(|| {
+                    Aggregate::Timer(Histogram {
+                        counts: vec![0.0; buckets.len() + 1],
+                        sum: 0.0,
+                        count: 0.0,
+                    })
+                });
+                let Aggregate::Timer(histogram) = entry else {
+                    return None;
+                };
+                let weight = 1.0 / sample_rate;
+                for value in values {
+                    let bucket = buckets.partition_point(|&bound| bound < value);
+                    histogram.counts[bucket] += weight;
+                    histogram.sum += value * weight;
+                    histogram.count += weight;
+                }
+            }
+            "s" => {
+                let entry = self
+                    .aggregates
+                    .entry(key)
+                    .or_insert_with(|| Aggregate::Set(HashSet::new()));
+                let Aggregate::Set(values) = entry else {
+                    return None;
+                };
+                values.insert(raw_value.to_string());
+            }
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    /// Returns the handle of a series, registering it within the limit.
+    fn resolve(
+        &mut self,
+        store: &mut Store,
+        name: &str,
+        labels: Vec<(String, String)>,
+    ) -> std::result::Result<SeriesHandle, String> {
+        let key = (name.to_string(), labels);
+        if let Some(&handle) = self.series.get(&key) {
+            return Ok(handle);
+        }
+        if self.series.len() >= self.config.max_series as usize {
+            return Err(format!(
+                "series limit of {} reached, dropping '{name}'",
+                self.config.max_series
+            ));
+        }
+
+        let handle = store
+            .register(name, &key.1)
+            .map_err(|e| format!("failed to register '{name}': {e}"))?;
+        self.series.insert(key, handle);
+        Ok(handle)
+    }
+}
+
+/// A UDP socket feeding a [`StatsdAggregator`].
+#[derive(Debug)]
+pub struct StatsdListener {
+    socket: UdpSocket,
+    aggregator: StatsdAggregator,
+    next_flush: Instant,
+}
+
+impl StatsdListener {
+    /// Binds a UDP socket for StatsD traffic.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StatsdError::Bind`] if the socket cannot be bound.
+    pub fn bind(addr: impl ToSocketAddrs + std::fmt::Debug, config: StatsdConfig) -> Result<Self> {
+        let socket = UdpSocket::bind(&addr).map_err(|e| StatsdError::Bind {
+            addr: format!("{addr:?}"),
+            source: e,
+        })?;
+        let next_flush = Instant::now() + config.flush_interval;
+
+        Ok(Self {
+            socket,
+            aggregator: StatsdAggregator::new(config),
+            next_flush,
+        })
+    }
+
+    /// Returns the address the socket is bound to.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StatsdError::Receive`] if the address cannot be read.
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr> {
+        self.socket
+            .local_addr()
+            .map_err(|e| StatsdError::Receive { source: e }.into())
+    }
+
+    /// Receives and aggregates packets until the next flush is due.
+    ///
+    /// Returns the number of packets received.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StatsdError::Receive`] if reading from the socket fails.
+    pub fn wait_for_flush(&mut self) -> Result<u64> {
+        let mut buf = vec![0u8; MAX_PACKET_BYTES];
+        let mut packets = 0;
+
+        loop {
+            let remaining = self.next_flush.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            self.socket
+                .set_read_timeout(Some(remaining))
+                .map_err(|e| StatsdError::Receive { source: e })?;
+
+            match self.socket.recv(&mut buf) {
+                Ok(len) => {
+                    packets += 1;
+                    self.aggregator
+                        .ingest(&String::from_utf8_lossy(&buf[..len]));
+                }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => return Err(StatsdError::Receive { source: e }.into()),
+            }
+        }
+
+        self.next_flush += self.aggregator.config.flush_interval;
+        Ok(packets)
+    }
+
+    /// Writes the aggregates to the store, see [`StatsdAggregator::flush`].
+    pub fn flush(&mut self, store: &mut Store, timestamp_ns: u64) -> FlushSummary {
+        self.aggregator.flush(store, timestamp_ns)
+    }
+}
+
+/// Parses a numeric value, rejecting NaN and infinities.
+fn parse_number(raw: &str) -> Option<f64> {
+    raw.parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::{LabelMatcher, SchemaConfig, TierConfig};
+
+    const BASE: u64 = 1_700_000_000_000_000_000;
+    const S: u64 = 1_000_000_000;
+
+    fn create_test_store(dir: &std::path::Path) -> Store {
+        let schemas = vec![SchemaConfig {
+            name: "test".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig::new(Duration::from_secs(1), Duration::from_secs(600), None).unwrap(),
+            ],
+            max_series: 30,
+        }];
+        Store::open(dir.join("store"), schemas).unwrap()
+    }
+
+    fn latest(store: &Store, name: &str, labels: &[(&str, &str)]) -> Option<(u64, f64)> {
+        let handle = store.handles().into_iter().find(|h| {
+            store.series_info(h).is_some_and(|(n, l)| {
+                n == name
+                    && l.len() == labels.len()
+                    && l.iter()
+                        .zip(labels)
+                        .all(|((k, v), (ek, ev))| k == ek && v == ev)
+            })
+        })?;
+        store.latest(handle).unwrap()
+    }
+
+    #[test]
+    fn test_counters_gauges_and_sets() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let mut aggregator = StatsdAggregator::new(StatsdConfig::new());
+
+        let accepted = aggregator.ingest(
+            "api.hits:3|c|#route:/users,env:prod\n\
+             api.hits:1|c|@0.5|#env:prod,route:/users\n\
+             temp:20|g\ntemp:+5|g\n\
+             users:alice|s\nusers:bob|s\nusers:alice|s\n\
+             bad line\n_e{5,4}:title|text",
+        );
+        assert_eq!(accepted, 7);
+
+        let summary = aggregator.flush(&mut store, BASE);
+        assert_eq!(summary.recorded, 3);
+        assert_eq!(summary.malformed, 1);
+
+        assert_eq!(
+            latest(&store, "api_hits", &[("env", "prod"), ("route", "/users")]),
+            Some((BASE, 5.0))
+        );
+        assert_eq!(latest(&store, "temp", &[]), Some((BASE, 25.0)));
+        assert_eq!(latest(&store, "users", &[]), Some((BASE, 2.0)));
+
+        // Counters start over; gauges are only recorded when updated
+        aggregator.ingest("api.hits:1|c|#env:prod,route:/users\ntemp:-10|g");
+        let summary = aggregator.flush(&mut store, BASE + S);
+        assert_eq!(summary.recorded, 2);
+        assert_eq!(
+            latest(&store, "api_hits", &[("env", "prod"), ("route", "/users")]),
+            Some((BASE + S, 1.0))
+        );
+        assert_eq!(latest(&store, "temp", &[]), Some((BASE + S, 15.0)));
+
+        aggregator.ingest("temp:1|c");
+        assert_eq!(aggregator.flush(&mut store, BASE + 2 * S).malformed, 1);
+    }
+
+    #[test]
+    fn test_timers_into_histograms() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let config = StatsdConfig::new().with_buckets(vec![100.0, 10.0]);
+        let mut aggregator = StatsdAggregator::new(config);
+
+        aggregator.ingest("db.query:5|ms\ndb.query:50:60|d\ndb.query:500|ms|@0.5");
+        let summary = aggregator.flush(&mut store, BASE);
+        assert_eq!(summary.recorded, 5);
+
+        assert_eq!(
+            latest(&store, "db_query_bucket", &[("le", "10")]),
+            Some((BASE, 1.0))
+        );
+        assert_eq!(
+            latest(&store, "db_query_bucket", &[("le", "100")]),
+            Some((BASE, 3.0))
+        );
+        assert_eq!(
+            latest(&store, "db_query_bucket", &[("le", "+Inf")]),
+            Some((BASE, 5.0))
+        );
+        assert_eq!(latest(&store, "db_query_count", &[]), Some((BASE, 5.0)));
+        assert_eq!(latest(&store, "db_query_sum", &[]), Some((BASE, 1115.0)));
+    }
+
+    #[test]
+    fn test_static_labels_and_series_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let config = StatsdConfig::new()
+            .with_label("source", "statsd")
+            .with_max_series(2);
+        let mut aggregator = StatsdAggregator::new(config);
+
+        aggregator.ingest("a:1|c|#source:app\nb:1|c\nc:1|c");
+        let summary = aggregator.flush(&mut store, BASE);
+        assert_eq!(summary.recorded, 2);
+        assert_eq!(summary.rejected, 1);
+        assert!(summary.error.unwrap().contains("series limit"));
+        assert_eq!(
+            latest(&store, "a", &[("source", "statsd")]),
+            Some((BASE, 1.0))
+        );
+    }
+
+    #[test]
+    fn test_listener_receives_packets() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut store = create_test_store(dir.path());
+        let config = StatsdConfig::new().with_flush_interval(Duration::from_millis(200));
+        let mut listener = StatsdListener::bind("127.0.0.1:0", config).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+        client.send_to(b"jobs:2|c", addr).unwrap();
+        client.send_to(b"jobs:3|c\nqueue:7|g", addr).unwrap();
+
+        assert_eq!(listener.wait_for_flush().unwrap(), 2);
+        let summary = listener.flush(&mut store, BASE);
+        assert_eq!(summary.recorded, 2);
+        assert_eq!(latest(&store, "jobs", &[]), Some((BASE, 5.0)));
+        assert_eq!(latest(&store, "queue", &[]), Some((BASE, 7.0)));
+    }
+}
@@ -78,10 +78,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::consolidate::ConsolidationEngine;
 use crate::error::{QueryError, Result, StoreError};
-use crate::query::{QueryResult, analyze_coverage};
-use crate::ring::RingBuffer;
+use crate::export::Rollup;
+use crate::query::{QueryOptions, QueryResult, QuerySegment, analyze_coverage};
+use crate::ring::{RingBuffer, RingIterator};
 use crate::schema::SchemaConfig;
-use crate::series::{SeriesHandle, SeriesRegistry};
+use crate::series::{MetricMetadata, SeriesHandle, SeriesRegistry};
 use crate::slab::Slab;
 
 /// Metadata file format version.
@@ -378,6 +379,17 @@ impl Store {
             SeriesRegistry::new(schemas.clone())
         };
 
+        // Stores written before every tier was synced on registration can
+        // have stale series counts in their rollup tiers; repair them so
+        // consolidation reads every registered column
+        for (schema_index, schema_rings) in rings.iter_mut().enumerate() {
+            let mut slab_refs: Vec<&mut Slab> = schema_rings
+                .iter_mut()
+                .map(|ring| ring.slab_mut())
+                .collect();
+            registry.sync_schema_to_slabs(schema_index, &mut slab_refs)?;
+        }
+
         Ok(Self {
             path,
             schemas,
@@ -428,7 +440,8 @@ impl Store {
             .map(|ring| ring.slab_mut())
             .collect();
 
-        self.registry.sync_to_slabs(&mut slab_refs)?;
+        self.registry
+            .sync_schema_to_slabs(schema_index, &mut slab_refs)?;
 
         // Persist the updated series registry
         let series_index_path = self.path.join(SERIES_INDEX_FILE);
@@ -555,11 +568,40 @@ impl Store {
         })
     }
 
+    /// Returns the rollup a tier stores, or `None` for the highest
+    /// resolution tier and unknown tiers.
+    pub fn rollup(&self, schema_index: usize, tier_index: usize) -> Option<Rollup> {
+        let tier = self.schemas.get(schema_index)?.tiers.get(tier_index)?;
+        Some(Rollup {
+            function: tier.consolidation_fn?,
+            interval: tier.interval,
+        })
+    }
+
     /// Returns the series count for a specific schema.
     pub fn schema_series_count(&self, schema_index: usize) -> u32 {
         self.registry.series_count(schema_index)
     }
 
+    /// Sets the kind, unit and help text of a metric name.
+    ///
+    /// The metadata applies to every series with that name and is persisted
+    /// with the series registry. Exporters that carry metadata, such as
+    /// remote-write 2.0, pick it up from here.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the series registry cannot be saved.
+    pub fn set_metadata(&mut self, name: &str, metadata: MetricMetadata) -> Result<()> {
+        self.registry.set_metadata(name, metadata);
+        self.registry.save(self.path.join(SERIES_INDEX_FILE))
+    }
+
+    /// Returns the metadata of a metric name, if any was set.
+    pub fn metadata(&self, name: &str) -> Option<&MetricMetadata> {
+        self.registry.metadata(name)
+    }
+
     /// Returns all registered series handles.
     pub fn handles(&self) -> Vec<SeriesHandle> {
         self.registry.handles()
@@ -659,6 +701,46 @@ impl Store {
         ))
     }
 
+    /// Queries a specific tier, controlled by [`QueryOptions`].
+    ///
+    /// Behaves like [`query`](Self::query) but honors
+    /// [`QueryOptions::max_points`] by downsampling the result inside the
+    /// iterator. Stitching does not apply to explicit tier queries.
+    ///
+    /// # Errors
+    ///
+    /// - [`QueryError::InvalidTier`] if tier index is out of range
+    /// - [`QueryError::InvalidTimeRange`] if start >= end
+    /// - [`QueryError::InvalidMaxPoints`] if `max_points` is 0
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::store::Store;
+    /// # use rondo::query::QueryOptions;
+    /// # let mut store = Store::open("./data", vec![])?;
+    /// # let handle = store.register("cpu.usage", &[])?;
+    /// # let current_time_ns = 1_640_000_000_000_000_000u64;
+    /// // Enough points for a Grafana panel, regardless of the range
+    /// let one_day_ago = current_time_ns - 24 * 3600 * 1_000_000_000;
+    /// let options = QueryOptions::new().with_max_points(500);
+    /// let result = store.query_with(handle, 0, one_day_ago, current_time_ns, &options)?;
+    /// assert!(result.count() <= 500);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn query_with(
+        &self,
+        handle: SeriesHandle,
+        tier: usize,
+        start_ns: u64,
+        end_ns: u64,
+        options: &QueryOptions,
+    ) -> Result<QueryResult<'_>> {
+        validate_max_points(options)?;
+        let result = self.query(handle, tier, start_ns, end_ns)?;
+        Ok(apply_max_points(result, options))
+    }
+
     /// Queries data with automatic tier selection based on retention coverage.
     ///
     /// This method automatically selects the best tier to serve the query by
@@ -715,6 +797,102 @@ impl Store {
         handle: SeriesHandle,
         start_ns: u64,
         end_ns: u64,
+    ) -> Result<QueryResult<'_>> {
+        self.query_auto_with(handle, start_ns, end_ns, &QueryOptions::default())
+    }
+
+    /// Queries data with automatic tier selection, controlled by [`QueryOptions`].
+    ///
+    /// With default options this behaves exactly like [`query_auto`](Self::query_auto).
+    /// When stitching is enabled, the range is split into segments and each
+    /// segment is served from the highest-resolution tier that still retains
+    /// it: the most recent part comes from tier 0, older parts from coarser
+    /// tiers. The per-segment tier and resolution are reported through
+    /// [`QueryResult::segments`].
+    ///
+    /// With [`QueryOptions::with_resolution`], tiers finer than needed for
+    /// the requested resolution are skipped in either mode.
+    ///
+    /// # Arguments
+    ///
+    /// * `handle` - The series handle obtained from registration
+    /// * `start_ns` - Start timestamp in nanoseconds (inclusive)
+    /// * `end_ns` - End timestamp in nanoseconds (exclusive)
+    /// * `options` - Query execution options
+    ///
+    /// # Errors
+    ///
+    /// - [`QueryError::InvalidTimeRange`] if start >= end
+    /// - [`QueryError::InvalidMaxPoints`] if `max_points` is 0
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::store::Store;
+    /// # use rondo::query::QueryOptions;
+    /// # let mut store = Store::open("./data", vec![])?;
+    /// # let handle = store.register("cpu.usage", &[])?;
+    /// # let current_time_ns = 1_640_000_000_000_000_000u64;
+    /// let last_week = current_time_ns - 7 * 24 * 3600 * 1_000_000_000;
+    /// let options = QueryOptions::new().with_stitching(true);
+    /// let result = store.query_auto_with(handle, last_week, current_time_ns, &options)?;
+    ///
+    /// for segment in result.segments() {
+    ///     println!("tier {} serves {}..{}", segment.tier, segment.start_ns, segment.end_ns);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn query_auto_with(
+        &self,
+        handle: SeriesHandle,
+        start_ns: u64,
+        end_ns: u64,
+        options: &QueryOptions,
+    ) -> Result<QueryResult<'_>> {
+        validate_max_points(options)?;
+
+        let first_tier = self.first_tier_for(handle, options);
+        let result = if options.stitch {
+            self.query_stitched(handle, first_tier, start_ns, end_ns)?
+        } else {
+            self.query_auto_single(handle, first_tier, start_ns, end_ns, options)?
+        };
+
+        Ok(apply_max_points(result, options))
+    }
+
+    /// Returns the finest tier a query may use given its requested resolution.
+    ///
+    /// This is the coarsest tier whose interval does not exceed
+    /// [`QueryOptions::resolution`], or tier 0 if no resolution is set.
+    /// Data newer than that tier's last consolidation is still read from
+    /// tier 0.
+    fn first_tier_for(&self, handle: SeriesHandle, options: &QueryOptions) -> usize {
+        let Some(resolution) = options.resolution else {
+            return 0;
+        };
+
+        self.schemas[handle.schema_index]
+            .tiers
+            .iter()
+            .rposition(|tier| tier.interval <= resolution)
+            .unwrap_or(0)
+    }
+
+    /// Selects a single tier (no finer than `first_tier`) for the whole range
+    /// and queries it.
+    ///
+    /// If the selected rollup tier lags behind tier 0, the recent tail would
+    /// be missing. A query with a [`QueryOptions::resolution`] is then served
+    /// from tier 0 when tier 0 still covers the whole range; otherwise the
+    /// tail is stitched on from tier 0 so no history is dropped.
+    fn query_auto_single(
+        &self,
+        handle: SeriesHandle,
+        first_tier: usize,
+        start_ns: u64,
+        end_ns: u64,
+        options: &QueryOptions,
     ) -> Result<QueryResult<'_>> {
         // Validate time range
         if start_ns >= end_ns {
@@ -726,11 +904,11 @@ impl Store {
         }
 
         let schema = &self.schemas[handle.schema_index];
-        let mut selected_tier = 0;
+        let mut selected_tier = first_tier;
         let mut best_coverage = false;
 
         // Find the best tier based on retention coverage
-        for (tier_index, _tier_config) in schema.tiers.iter().enumerate() {
+        for tier_index in first_tier..schema.tiers.len() {
             let ring = &self.rings[handle.schema_index][tier_index];
             let oldest = ring.oldest_timestamp();
             let newest = ring.newest_timestamp();
@@ -758,10 +936,256 @@ impl Store {
             .into());
         }
 
+        // A coarse tier only holds data up to the last consolidation; if
+        // tier 0 has newer points in the range, they must not be dropped
+        if selected_tier > 0
+            && self.covered_end(handle, selected_tier) < self.covered_end(handle, 0).min(end_ns)
+        {
+            let tier0_covers_start = self.rings[handle.schema_index][0]
+                .oldest_timestamp()
+                .is_some_and(|oldest| oldest <= start_ns);
+            if options.resolution.is_some() && tier0_covers_start {
+                selected_tier = 0;
+            } else {
+                return self.query_stitched(handle, selected_tier, start_ns, end_ns);
+            }
+        }
+
         // Query the selected tier
         self.query(handle, selected_tier, start_ns, end_ns)
     }
 
+    /// Returns the end of the time range a tier holds data for: one interval
+    /// past its newest slot, or 0 if it is empty.
+    fn covered_end(&self, handle: SeriesHandle, tier: usize) -> u64 {
+        let ring = &self.rings[handle.schema_index][tier];
+        ring.newest_timestamp()
+            .map_or(0, |newest| newest + ring.slab().interval_ns())
+    }
+
+    /// Serves a query by stitching together segments from multiple tiers.
+    ///
+    /// Walks tiers from highest to lowest resolution, starting at
+    /// `first_tier`. Each tier with data serves the part of the remaining
+    /// range (working backwards from `end_ns`) that falls within its
+    /// retention window; the rest is handed to the next, coarser tier.
+    ///
+    /// When `first_tier` is a rollup, the recent part it has not been
+    /// consolidated into yet is served from tier 0 first.
+    fn query_stitched(
+        &self,
+        handle: SeriesHandle,
+        first_tier: usize,
+        start_ns: u64,
+        end_ns: u64,
+    ) -> Result<QueryResult<'_>> {
+        if start_ns >= end_ns {
+            return Err(QueryError::InvalidTimeRange {
+                start: start_ns,
+                end: end_ns,
+            }
+            .into());
+        }
+
+        let schema = &self.schemas[handle.schema_index];
+        if schema.tiers.is_empty() {
+            return Err(QueryError::InvalidTier {
+                tier: 0,
+                max_tiers: 0,
+            }
+            .into());
+        }
+
+        let mut parts = Vec::new();
+        let mut overall_oldest: Option<u64> = None;
+        let mut overall_newest: Option<u64> = None;
+        let mut segment_end = end_ns;
+
+        // Tail not yet consolidated into first_tier, newest first
+        let tail_tiers = if first_tier > 0 {
+            let tail_start = self.covered_end(handle, first_tier).max(start_ns);
+            (tail_start < end_ns).then_some((0, tail_start))
+        } else {
+            None
+        };
+        let tiers = tail_tiers
+            .into_iter()
+            .chain((first_tier..schema.tiers.len()).map(|tier| (tier, start_ns)));
+
+        for (tier_index, floor_ns) in tiers {
+            if segment_end <= start_ns {
+                break;
+            }
+
+            let ring = &self.rings[handle.schema_index][tier_index];
+            let (Some(oldest), Some(newest)) = (ring.oldest_timestamp(), ring.newest_timestamp())
+            else {
+                continue;
+            };
+
+            overall_oldest = Some(overall_oldest.map_or(oldest, |o| o.min(oldest)));
+            overall_newest = Some(overall_newest.map_or(newest, |n| n.max(newest)));
+
+            let segment_start = floor_ns.max(oldest);
+            if segment_start >= segment_end {
+                continue;
+            }
+
+            let segment = QuerySegment {
+                tier: tier_index,
+                interval_ns: ring.slab().interval_ns(),
+                start_ns: segment_start,
+                end_ns: segment_end,
+            };
+            parts.push((
+                segment,
+                ring.read(handle.column, segment_start, segment_end)?,
+            ));
+            segment_end = segment_start;
+        }
+
+        if parts.is_empty() {
+            // No tier has data for the range; fall back to a plain query
+            return self.query(handle, first_tier, start_ns, end_ns);
+        }
+
+        // Segments were collected newest-first; serve them chronologically
+        parts.reverse();
+
+        // Any range left over after the coarsest tier is outside all retention
+        Ok(QueryResult::stitched(
+            parts,
+            (overall_oldest, overall_newest),
+            (start_ns, end_ns),
+            segment_end > start_ns,
+        ))
+    }
+
+    /// Reads a series from a specific tier into caller-owned column buffers.
+    ///
+    /// This is the bulk counterpart of [`query`](Self::query) for exporters
+    /// and analytics that process whole blocks: timestamps and values are
+    /// copied out of the slab with [`RingBuffer::read_into`] and appended to
+    /// `timestamps` and `values` in chronological order.
+    ///
+    /// # Returns
+    ///
+    /// The number of points appended to each buffer.
+    ///
+    /// # Errors
+    ///
+    /// - [`QueryError::InvalidTier`] if tier index is out of range
+    /// - [`QueryError::InvalidTimeRange`] if start >= end
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::store::Store;
+    /// # let mut store = Store::open("./data", vec![])?;
+    /// # let handle = store.register("cpu.usage", &[])?;
+    /// # let start_ns = 1_640_000_000_000_000_000u64;
+    /// # let end_ns = start_ns + 3600 * 1_000_000_000;
+    /// let mut timestamps = Vec::new();
+    /// let mut values = Vec::new();
+    /// store.read_into(handle, 0, start_ns, end_ns, &mut timestamps, &mut values)?;
+    /// let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn read_into(
+        &self,
+        handle: SeriesHandle,
+        tier: usize,
+        start_ns: u64,
+        end_ns: u64,
+        timestamps: &mut Vec<u64>,
+        values: &mut Vec<f64>,
+    ) -> Result<usize> {
+        let schema = &self.schemas[handle.schema_index];
+        if tier >= schema.tiers.len() {
+            return Err(QueryError::InvalidTier {
+                tier,
+                max_tiers: schema.tiers.len(),
+            }
+            .into());
+        }
+
+        self.rings[handle.schema_index][tier].read_into(
+            handle.column,
+            start_ns,
+            end_ns,
+            timestamps,
+            values,
+        )
+    }
+
+    /// Returns the most recent data point of a series.
+    ///
+    /// Reads tier 0 backwards from the write cursor, so the cost is
+    /// proportional to the number of trailing slots without a value for this
+    /// series rather than to the size of the ring.
+    ///
+    /// # Returns
+    ///
+    /// `Some((timestamp_ns, value))`, or `None` if the series has no data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the series' schema has no tiers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::store::Store;
+    /// # let mut store = Store::open("./data", vec![])?;
+    /// # let handle = store.register("cpu.usage", &[])?;
+    /// if let Some((timestamp, value)) = store.latest(handle)? {
+    ///     println!("CPU at {}: {}%", timestamp, value);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn latest(&self, handle: SeriesHandle) -> Result<Option<(u64, f64)>> {
+        Ok(self.read_newest_first(handle)?.next())
+    }
+
+    /// Returns up to `n` of the most recent data points of a series.
+    ///
+    /// Like [`latest`](Self::latest), this walks tier 0 backwards from the
+    /// write cursor and stops as soon as `n` points have been found. The
+    /// returned points are in chronological order (oldest to newest).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the series' schema has no tiers.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::store::Store;
+    /// # let mut store = Store::open("./data", vec![])?;
+    /// # let handle = store.register("cpu.usage", &[])?;
+    /// for (timestamp, value) in store.last_n(handle, 5)? {
+    ///     println!("CPU at {}: {}%", timestamp, value);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn last_n(&self, handle: SeriesHandle, n: usize) -> Result<Vec<(u64, f64)>> {
+        let mut points: Vec<_> = self.read_newest_first(handle)?.take(n).collect();
+        points.reverse();
+        Ok(points)
+    }
+
+    /// Returns an iterator over all tier 0 points of a series, newest first.
+    fn read_newest_first(&self, handle: SeriesHandle) -> Result<std::iter::Rev<RingIterator<'_>>> {
+        let ring = self.rings[handle.schema_index]
+            .first()
+            .ok_or(QueryError::InvalidTier {
+                tier: 0,
+                max_tiers: 0,
+            })?;
+
+        Ok(ring.read(handle.column, 0, u64::MAX)?.rev())
+    }
+
     /// Performs consolidation across all schemas and tier pairs.
     ///
     /// This method creates a consolidation engine and runs consolidation for all
@@ -812,7 +1236,11 @@ impl Store {
     /// series.
     ///
     /// This is designed for periodic push to a remote TSDB. Each call returns only
-    /// new data since the last drain.
+    /// new data since the last drain. Because the cursor moves before the data is
+    /// delivered, a failed push loses the batch unless the caller restores the
+    /// cursor; use [`drain_pending`](Self::drain_pending) and
+    /// [`ExportCursor::commit`](crate::export::ExportCursor::commit) for
+    /// at-least-once delivery.
     ///
     /// # Arguments
     ///
@@ -846,6 +1274,44 @@ impl Store {
         tier: usize,
         cursor: &mut crate::export::ExportCursor,
     ) -> Result<Vec<crate::export::SeriesExport>> {
+        let pending = self.drain_pending(tier, cursor)?;
+        cursor.commit(&pending);
+        Ok(pending.exports)
+    }
+
+    /// Reads new data at the specified tier without advancing the cursor.
+    ///
+    /// The first phase of a two-phase drain: push `pending.exports`, then
+    /// call [`ExportCursor::commit`](crate::export::ExportCursor::commit)
+    /// once the push succeeds. Until then, every call returns the same data
+    /// (plus anything recorded since), so a failed push is simply retried
+    /// from the same position.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from the ring buffer fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::store::Store;
+    /// # use rondo::export::ExportCursor;
+    /// # fn push(_: &[rondo::export::SeriesExport]) -> Result<(), std::io::Error> { Ok(()) }
+    /// # let store = Store::open("./data", vec![])?;
+    /// let mut cursor = ExportCursor::load_or_new("./data/export_cursor.json")?;
+    ///
+    /// let pending = store.drain_pending(0, &cursor)?;
+    /// if push(&pending.exports).is_ok() {
+    ///     cursor.commit(&pending);
+    ///     cursor.save()?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn drain_pending(
+        &self,
+        tier: usize,
+        cursor: &crate::export::ExportCursor,
+    ) -> Result<crate::export::PendingExport> {
         let handles = self.registry.handles();
         let mut all_exports = Vec::new();
 
@@ -860,7 +1326,7 @@ impl Store {
                 continue;
             }
 
-            let exports = crate::export::drain_tier(
+            let exports = crate::export::pending_tier(
                 &self.rings,
                 schema_index,
                 tier,
@@ -870,7 +1336,66 @@ impl Store {
             all_exports.extend(exports);
         }
 
-        Ok(all_exports)
+        Ok(crate::export::PendingExport::new(all_exports))
+    }
+
+    /// Flushes every slab's memory-mapped data to disk.
+    ///
+    /// Writes land in the page cache immediately and survive a process exit
+    /// without this; flushing makes them durable against a host crash or
+    /// power loss.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SlabIoError::SyncFailed`](crate::error::SlabIoError::SyncFailed)
+    /// if a slab cannot be synced.
+    pub fn flush(&self) -> Result<()> {
+        for ring in self.rings.iter().flatten() {
+            ring.slab().sync()?;
+        }
+        Ok(())
+    }
+
+    /// Closes the store: runs a final consolidation pass, then flushes all
+    /// slabs to disk.
+    ///
+    /// Call this when shutting down, after any final
+    /// [`ExportDriver::flush`](crate::exporter::ExportDriver::flush).
+    /// Dropping the store does neither: the slabs are written back by the
+    /// OS, but not synced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if consolidation or flushing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rondo::store::Store;
+    /// let mut store = Store::open("./data", vec![])?;
+    /// // ... record data ...
+    /// store.close()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn close(mut self) -> Result<()> {
+        self.consolidate()?;
+        self.flush()
+    }
+}
+
+/// Rejects a zero point limit.
+fn validate_max_points(options: &QueryOptions) -> Result<()> {
+    match options.max_points {
+        Some(0) => Err(QueryError::InvalidMaxPoints { max_points: 0 }.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Applies the downsampling requested by `options`, if any.
+fn apply_max_points<'a>(result: QueryResult<'a>, options: &QueryOptions) -> QueryResult<'a> {
+    match options.max_points {
+        Some(max_points) => result.with_max_points(max_points),
+        None => result,
     }
 }
 
@@ -1648,6 +2173,30 @@ mod tests {
         assert_eq!(operations2, 0, "Second consolidation should be a no-op");
     }
 
+    #[test]
+    fn test_close_consolidates_and_flushes() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("close_store");
+        let labels = [("type".to_string(), "cpu".to_string())];
+
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+        let handle = store.register("cpu.usage", &labels).unwrap();
+        let base_time = 1_000_000_000_000_000_000u64;
+        for i in 0..90 {
+            store
+                .record(handle, 1.0, base_time + i * 1_000_000_000)
+                .unwrap();
+        }
+        store.flush().unwrap();
+        store.close().unwrap();
+
+        // The final consolidation pass reached tier 1 before closing
+        let store = Store::open(&store_path, create_test_schemas()).unwrap();
+        let handle = store.handles()[0];
+        assert_eq!(store.query(handle, 0, 0, u64::MAX).unwrap().count(), 90);
+        assert!(store.query(handle, 1, 0, u64::MAX).unwrap().count() > 0);
+    }
+
     #[test]
     fn test_consolidation_with_no_multi_tier_schemas() {
         let temp_dir = tempdir().unwrap();
@@ -1871,6 +2420,414 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_query_auto_stitches_tiers() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("stitched_store");
+
+        let schemas = vec![SchemaConfig {
+            name: "stitched".to_string(),
+            label_matcher: LabelMatcher::any(),
+            tiers: vec![
+                TierConfig {
+                    interval: Duration::from_secs(1),
+                    retention: Duration::from_secs(10),
+                    consolidation_fn: None,
+                },
+                TierConfig {
+                    interval: Duration::from_secs(5),
+                    retention: Duration::from_secs(300),
+                    consolidation_fn: Some(ConsolidationFn::Average),
+                },
+            ],
+            max_series: 10,
+        }];
+
+        let mut store = Store::open(&store_path, schemas).unwrap();
+        let handle = store.register("metric", &[]).unwrap();
+
+        let base_time = 1_000_000_000_000_000_000u64;
+        for i in 0..30 {
+            store
+                .record(handle, 1.0, base_time + i * 1_000_000_000)
+                .unwrap();
+            store.consolidate().unwrap();
+        }
+
+        let start = base_time;
+        let end = base_time + 30_000_000_000;
+
+        // Without stitching a single (coarse) tier serves the whole range
+        let single = store.query_auto(handle, start, end).unwrap();
+        assert_eq!(single.segments().len(), 1);
+        assert!(!single.is_stitched());
+
+        let options = QueryOptions::new().with_stitching(true);
+        let result = store.query_auto_with(handle, start, end, &options).unwrap();
+        assert!(result.is_stitched());
+        assert_eq!(result.tier_used(), 0);
+        assert!(!result.may_be_incomplete());
+
+        let tier0_oldest = store.rings[0][0].oldest_timestamp().unwrap();
+        let segments = result.segments().to_vec();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(
+            segments[0],
+            QuerySegment {
+                tier: 1,
+                interval_ns: 5_000_000_000,
+                start_ns: start,
+                end_ns: tier0_oldest,
+            }
+        );
+        assert_eq!(
+            segments[1],
+            QuerySegment {
+                tier: 0,
+                interval_ns: 1_000_000_000,
+                start_ns: tier0_oldest,
+                end_ns: end,
+            }
+        );
+
+        let points = result.collect_all();
+        assert!(points.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(points.first().unwrap().0, base_time);
+        assert_eq!(points.last().unwrap().0, base_time + 29_000_000_000);
+        assert!(points.iter().filter(|(ts, _)| *ts >= tier0_oldest).count() >= 9);
+    }
+
+    #[test]
+    fn test_query_with_max_points() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("max_points_store");
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+        let handle = store
+            .register("cpu.usage", &[("type".to_string(), "cpu".to_string())])
+            .unwrap();
+
+        let base_time = 1_000_000_000_000_000_000u64;
+        for i in 0..1000u32 {
+            let value = if i == 500 { 1000.0 } else { f64::from(i % 10) };
+            store
+                .record(handle, value, base_time + u64::from(i) * 1_000_000_000)
+                .unwrap();
+        }
+
+        let start = base_time;
+        let end = base_time + 1000 * 1_000_000_000;
+        let options = QueryOptions::new().with_max_points(100);
+
+        let points = store
+            .query_with(handle, 0, start, end, &options)
+            .unwrap()
+            .collect_all();
+        assert!(points.len() <= 100);
+        assert!(points.len() >= 90);
+        assert!(points.contains(&(base_time + 500 * 1_000_000_000, 1000.0)));
+
+        let count = store
+            .query_auto_with(handle, start, end, &options)
+            .unwrap()
+            .count();
+        assert_eq!(count, points.len());
+
+        // Zero is rejected
+        let options = QueryOptions::new().with_max_points(0);
+        assert!(matches!(
+            store.query_with(handle, 0, start, end, &options),
+            Err(crate::error::RondoError::Query(
+                QueryError::InvalidMaxPoints { max_points: 0 }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_latest_and_last_n() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("latest_store");
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+        let labels = [("type".to_string(), "cpu".to_string())];
+        let handle = store.register("cpu.usage", &labels).unwrap();
+        let other = store.register("cpu.idle", &labels).unwrap();
+
+        assert_eq!(store.latest(handle).unwrap(), None);
+        assert!(store.last_n(handle, 5).unwrap().is_empty());
+
+        let base_time = 1_000_000_000_000_000_000u64;
+        for i in 0..10u32 {
+            store
+                .record(
+                    handle,
+                    f64::from(i),
+                    base_time + u64::from(i) * 1_000_000_000,
+                )
+                .unwrap();
+        }
+        // A newer point for a different series must not be returned
+        store
+            .record(other, 99.0, base_time + 20 * 1_000_000_000)
+            .unwrap();
+
+        assert_eq!(
+            store.latest(handle).unwrap(),
+            Some((base_time + 9 * 1_000_000_000, 9.0))
+        );
+        assert_eq!(
+            store.last_n(handle, 3).unwrap(),
+            vec![
+                (base_time + 7 * 1_000_000_000, 7.0),
+                (base_time + 8 * 1_000_000_000, 8.0),
+                (base_time + 9 * 1_000_000_000, 9.0),
+            ]
+        );
+        assert_eq!(store.last_n(handle, 100).unwrap().len(), 10);
+        assert!(store.last_n(handle, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_into() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("read_into_store");
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+        let handle = store
+            .register("cpu.usage", &[("type".to_string(), "cpu".to_string())])
+            .unwrap();
+
+        let base_time = 1_000_000_000_000_000_000u64;
+        for i in 0..20u32 {
+            store
+                .record(
+                    handle,
+                    f64::from(i),
+                    base_time + u64::from(i) * 1_000_000_000,
+                )
+                .unwrap();
+        }
+
+        let start = base_time + 5 * 1_000_000_000;
+        let end = base_time + 15 * 1_000_000_000;
+        let mut timestamps = Vec::new();
+        let mut values = Vec::new();
+        let n = store
+            .read_into(handle, 0, start, end, &mut timestamps, &mut values)
+            .unwrap();
+
+        let expected: Vec<_> = store.query(handle, 0, start, end).unwrap().collect();
+        assert_eq!(n, 10);
+        assert_eq!(
+            timestamps.into_iter().zip(values).collect::<Vec<_>>(),
+            expected
+        );
+
+        assert!(
+            store
+                .read_into(handle, 5, start, end, &mut Vec::new(), &mut Vec::new())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_register_syncs_all_tier_slabs() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("tier_sync_store");
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+
+        let cpu = [("type".to_string(), "cpu".to_string())];
+        store.register("cpu.user", &cpu).unwrap();
+        store.register("cpu.system", &cpu).unwrap();
+        store
+            .register("mem.used", &[("type".to_string(), "memory".to_string())])
+            .unwrap();
+
+        for ring in &store.rings[0] {
+            assert_eq!(ring.slab().series_count(), 2);
+            assert_eq!(ring.slab().get_series_column(1), Some(1));
+        }
+        assert_eq!(store.rings[1][0].slab().series_count(), 1);
+    }
+
+    #[test]
+    fn test_open_repairs_stale_tier_headers() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("stale_header_store");
+        let cpu = [("type".to_string(), "cpu".to_string())];
+        let base_time = 1_000_000_000_000_000_000u64.next_multiple_of(60_000_000_000);
+
+        let handles = {
+            let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+            let user = store.register("cpu.user", &cpu).unwrap();
+            let system = store.register("cpu.system", &cpu).unwrap();
+            for i in 0..120u64 {
+                let ts = base_time + i * 1_000_000_000;
+                store.record(user, 1.0, ts).unwrap();
+                store.record(system, 2.0, ts).unwrap();
+            }
+            // What older releases left behind: headers counting too few series
+            store.rings[0][0].slab_mut().set_series_count(1);
+            store.rings[0][1].slab_mut().set_series_count(0);
+            [user, system]
+        };
+
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+        for ring in &store.rings[0] {
+            assert_eq!(ring.slab().series_count(), 2);
+        }
+        store.consolidate().unwrap();
+        for (handle, value) in handles.into_iter().zip([1.0, 2.0]) {
+            let points: Vec<_> = store
+                .query(handle, 1, base_time, base_time + 120 * 1_000_000_000)
+                .unwrap()
+                .collect();
+            assert_eq!(
+                points,
+                [(base_time, value), (base_time + 60_000_000_000, value)]
+            );
+        }
+    }
+
+    #[test]
+    fn test_query_auto_with_resolution() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("resolution_store");
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+        let handle = store
+            .register("cpu.usage", &[("type".to_string(), "cpu".to_string())])
+            .unwrap();
+
+        let base_time = 1_000_000_000_000_000_000u64;
+        for i in 0..180u32 {
+            store
+                .record(handle, 1.0, base_time + u64::from(i) * 1_000_000_000)
+                .unwrap();
+        }
+        store.consolidate().unwrap();
+
+        let start = base_time;
+        let end = base_time + 179 * 1_000_000_000;
+
+        // A 1-minute step is served from the 60s tier even though tier 0 covers it
+        let options = QueryOptions::new().with_resolution(Duration::from_secs(60));
+        let result = store.query_auto_with(handle, start, end, &options).unwrap();
+        assert_eq!(result.tier_used(), 1);
+
+        // A finer step than any coarse tier keeps tier 0
+        let options = QueryOptions::new().with_resolution(Duration::from_secs(15));
+        let result = store.query_auto_with(handle, start, end, &options).unwrap();
+        assert_eq!(result.tier_used(), 0);
+
+        let options = options.with_stitching(true);
+        let result = store.query_auto_with(handle, start, end, &options).unwrap();
+        assert_eq!(result.segments()[0].tier, 0);
+    }
+
+    #[test]
+    fn test_query_auto_with_resolution_reads_unconsolidated_tail() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("resolution_tail_store");
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+        let handle = store
+            .register("cpu.usage", &[("type".to_string(), "cpu".to_string())])
+            .unwrap();
+
+        // Aligned to the 60s tier's windows
+        let base_time = 1_000_000_000_000_000_000u64.next_multiple_of(60_000_000_000);
+        let record = |store: &mut Store, seconds: std::ops::Range<u64>| {
+            for i in seconds {
+                store
+                    .record(handle, 1.0, base_time + i * 1_000_000_000)
+                    .unwrap();
+            }
+        };
+        let start = base_time;
+        let end = base_time + 240 * 1_000_000_000;
+        let options = QueryOptions::new()
+            .with_resolution(Duration::from_secs(60))
+            .with_stitching(true);
+
+        // Nothing consolidated yet: everything comes from tier 0
+        record(&mut store, 0..120);
+        let result = store.query_auto_with(handle, start, end, &options).unwrap();
+        assert_eq!(result.segments()[0].tier, 0);
+        assert_eq!(result.count(), 120);
+
+        // Consolidated minutes come from the 60s tier, newer points from tier 0
+        store.consolidate().unwrap();
+        record(&mut store, 120..180);
+        let result = store.query_auto_with(handle, start, end, &options).unwrap();
+        let segments: Vec<_> = result
+            .segments()
+            .iter()
+            .map(|s| (s.tier, s.start_ns, s.end_ns))
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                (1, start, base_time + 120 * 1_000_000_000),
+                (0, base_time + 120 * 1_000_000_000, end),
+            ]
+        );
+        assert_eq!(result.count(), 2 + 60);
+
+        // A single tier falls back to tier 0 rather than drop the tail
+        let options = options.with_stitching(false);
+        let result = store.query_auto_with(handle, start, end, &options).unwrap();
+        assert_eq!(result.tier_used(), 0);
+        assert_eq!(result.count(), 180);
+    }
+
+    #[test]
+    fn test_query_auto_keeps_history_with_unconsolidated_tail() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("auto_tail_store");
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+        let handle = store
+            .register("cpu.usage", &[("type".to_string(), "cpu".to_string())])
+            .unwrap();
+
+        // Two hours of data: the first hour ages out of tier 0 (1h retention)
+        let base_time = 1_000_000_000_000_000_000u64.next_multiple_of(60_000_000_000);
+        for i in 0..7200u64 {
+            store
+                .record(handle, 1.0, base_time + i * 1_000_000_000)
+                .unwrap();
+            // Consolidate every 10 minutes, the last time at 6000s
+            if i % 600 == 0 && i <= 6000 {
+                store.consolidate().unwrap();
+            }
+        }
+
+        let start = base_time;
+        let end = base_time + 7200 * 1_000_000_000;
+        let result = store.query_auto(handle, start, end).unwrap();
+        let segments: Vec<_> = result.segments().iter().map(|s| s.tier).collect();
+        assert_eq!(segments, [1, 0]);
+
+        // 101 consolidated minutes from tier 1, the rest from tier 0
+        let tier1_end = base_time + 101 * 60 * 1_000_000_000;
+        assert_eq!(result.segments()[0].end_ns, tier1_end);
+        assert_eq!(result.count(), 101 + (7200 - 101 * 60));
+    }
+
+    #[test]
+    fn test_query_auto_stitched_empty_series() {
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("stitched_empty_store");
+        let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+        let handle = store
+            .register("cpu.usage", &[("type".to_string(), "cpu".to_string())])
+            .unwrap();
+
+        let options = QueryOptions::new().with_stitching(true);
+        let result = store.query_auto_with(handle, 1000, 2000, &options).unwrap();
+        assert_eq!(result.tier_used(), 0);
+        assert!(result.may_be_incomplete());
+        assert_eq!(result.segments().len(), 1);
+        assert_eq!(result.count(), 0);
+
+        assert!(store.query_auto_with(handle, 2000, 1000, &options).is_err());
+    }
+
     #[test]
     fn test_consolidation_empty_store() {
         let temp_dir = tempdir().unwrap();
@@ -1907,4 +2864,53 @@ mod tests {
         let operations = store.consolidate().unwrap();
         assert_eq!(operations, 0);
     }
+
+    #[test]
+    fn test_drain_pending_and_commit() {
+        let temp_dir = tempdir().unwrap();
+        let mut store = Store::open(temp_dir.path().join("store"), create_test_schemas()).unwrap();
+        let cpu = store
+            .register("cpu.usage", &[("type".to_string(), "cpu".to_string())])
+            .unwrap();
+
+        let base = 1_640_995_200_000_000_000u64;
+        store.record(cpu, 1.0, base).unwrap();
+
+        let mut cursor = crate::export::ExportCursor::new();
+        let pending = store.drain_pending(0, &cursor).unwrap();
+        assert_eq!(pending.exports[0].points, vec![(base, 1.0)]);
+
+        // Without a commit (e.g. the push failed) the same data comes back
+        store.record(cpu, 2.0, base + 1_000_000_000).unwrap();
+        let retry = store.drain_pending(0, &cursor).unwrap();
+        assert_eq!(retry.exports[0].points.len(), 2);
+
+        cursor.commit(&retry);
+        assert!(store.drain_pending(0, &cursor).unwrap().is_empty());
+
+        // Committing an older batch does not move the cursor back
+        cursor.commit(&pending);
+        assert!(store.drain(0, &mut cursor).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_metadata_persists() {
+        use crate::series::MetricKind;
+
+        let temp_dir = tempdir().unwrap();
+        let store_path = temp_dir.path().join("store");
+        let metadata = MetricMetadata::new(MetricKind::Counter)
+            .with_unit("bytes")
+            .with_help("Bytes read");
+        {
+            let mut store = Store::open(&store_path, create_test_schemas()).unwrap();
+            assert!(store.metadata("blk_bytes_read_total").is_none());
+            store
+                .set_metadata("blk_bytes_read_total", metadata.clone())
+                .unwrap();
+        }
+
+        let store = Store::open(&store_path, create_test_schemas()).unwrap();
+        assert_eq!(store.metadata("blk_bytes_read_total"), Some(&metadata));
+    }
 }
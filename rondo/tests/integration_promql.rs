@@ -0,0 +1,311 @@
+//! Integration tests for PromQL evaluation against a store.
+
+use rondo::error::{PromqlError, RondoError};
+use rondo::promql::{Engine, Labels, Value};
+use rondo::schema::{ConsolidationFn, LabelMatcher, SchemaConfig, TierConfig};
+use rondo::store::Store;
+use std::time::Duration;
+use tempfile::tempdir;
+
+const S: u64 = 1_000_000_000;
+const BASE: u64 = 1_640_000_000 * S;
+
+fn labels(pairs: &[(&str, &str)]) -> Labels {
+    pairs
+        .iter()
+        .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+        .collect()
+}
+
+fn label_pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+        .collect()
+}
+
+/// Builds a store with 10 minutes of 1s data:
+///
+/// - `requests_total{host="a"}` counting +2/s, `{host="b"}` counting +1/s
+/// - `cpu.usage{host="a"}` = 40, `{host="b"}` = 60
+/// - `latency_bucket{le=...}` cumulative buckets growing 10/s in total
+fn populated_store(path: &std::path::Path) -> Store {
+    let schemas = vec![SchemaConfig {
+        name: "all".to_string(),
+        label_matcher: LabelMatcher::any(),
+        tiers: vec![
+            TierConfig {
+                interval: Duration::from_secs(1),
+                retention: Duration::from_secs(3600),
+                consolidation_fn: None,
+            },
+            TierConfig {
+                interval: Duration::from_secs(60),
+                retention: Duration::from_secs(86400),
+                consolidation_fn: Some(ConsolidationFn::Last),
+            },
+        ],
+        max_series: 100,
+    }];
+    let mut store = Store::open(path, schemas).unwrap();
+
+    let req_a = store
+        .register("requests_total", &label_pairs(&[("host", "a")]))
+        .unwrap();
+    let req_b = store
+        .register("requests_total", &label_pairs(&[("host", "b")]))
+        .unwrap();
+    let cpu_a = store
+        .register("cpu.usage", &label_pairs(&[("host", "a")]))
+        .unwrap();
+    let cpu_b = store
+        .register("cpu.usage", &label_pairs(&[("host", "b")]))
+        .unwrap();
+    let buckets = [("0.1", 5.0), ("0.5", 9.0), ("+Inf", 10.0)].map(|(le, per_sec)| {
+        let handle = store
+            .register("latency_bucket", &label_pairs(&[("le", le)]))
+            .unwrap();
+        (handle, per_sec)
+    });
+
+    for i in 0u32..600 {
+        let ts = BASE + u64::from(i) * S;
+        let n = f64::from(i);
+        store.record(req_a, 2.0 * n, ts).unwrap();
+        store.record(req_b, n, ts).unwrap();
+        store.record(cpu_a, 40.0, ts).unwrap();
+        store.record(cpu_b, 60.0, ts).unwrap();
+        for (handle, per_sec) in buckets {
+            store.record(handle, per_sec * n, ts).unwrap();
+        }
+    }
+    store.consolidate().unwrap();
+
+    store
+}
+
+fn vector(value: Value) -> Vec<(Labels, f64)> {
+    match value {
+        Value::Vector(samples) => samples.into_iter().map(|s| (s.labels, s.value)).collect(),
+        other => panic!("expected vector, got {other:?}"),
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn test_instant_selectors_and_aggregation() {
+    let dir = tempdir().unwrap();
+    let store = populated_store(&dir.path().join("store"));
+    let engine = Engine::new(&store);
+    let t = BASE + 599 * S;
+
+    let result = vector(engine.instant_query(r#"cpu.usage{host="a"}"#, t).unwrap());
+    assert_eq!(
+        result,
+        vec![(labels(&[("__name__", "cpu.usage"), ("host", "a")]), 40.0)]
+    );
+
+    let result = vector(
+        engine
+            .instant_query(r#"requests_total{host=~"a|b"}"#, t)
+            .unwrap(),
+    );
+    assert_eq!(result.len(), 2);
+
+    let result = vector(engine.instant_query("avg(cpu.usage)", t).unwrap());
+    assert_eq!(result, vec![(Labels::new(), 50.0)]);
+
+    let result = vector(
+        engine
+            .instant_query("max by (host) (cpu.usage)", t)
+            .unwrap(),
+    );
+    assert_eq!(
+        result,
+        vec![
+            (labels(&[("host", "a")]), 40.0),
+            (labels(&[("host", "b")]), 60.0)
+        ]
+    );
+
+    // Lookback: nothing is found long after the last sample
+    let result = vector(engine.instant_query("cpu.usage", t + 600 * S).unwrap());
+    assert!(result.is_empty());
+}
+
+#[test]
+fn test_rate_and_arithmetic() {
+    let dir = tempdir().unwrap();
+    let store = populated_store(&dir.path().join("store"));
+    let engine = Engine::new(&store);
+    let t = BASE + 599 * S;
+
+    let result = vector(engine.instant_query("rate(requests_total[1m])", t).unwrap());
+    assert_eq!(result.len(), 2);
+    assert_eq!(result[0].0, labels(&[("host", "a")]));
+    assert_close(result[0].1, 2.0);
+    assert_close(result[1].1, 1.0);
+
+    let result = vector(
+        engine
+            .instant_query("sum(rate(requests_total[1m])) * 60", t)
+            .unwrap(),
+    );
+    assert_close(result[0].1, 180.0);
+
+    // Per-host request rate divided by CPU, matched on host
+    let result = vector(
+        engine
+            .instant_query("rate(requests_total[1m]) / on(host) cpu.usage", t)
+            .unwrap(),
+    );
+    assert_eq!(result[0].0, labels(&[("host", "a")]));
+    assert_close(result[0].1, 2.0 / 40.0);
+
+    let result = vector(
+        engine
+            .instant_query("avg_over_time(cpu.usage[5m])", t)
+            .unwrap(),
+    );
+    assert_close(result[1].1, 60.0);
+
+    match engine.instant_query("2 ^ 3 - 1", t).unwrap() {
+        Value::Scalar { value, .. } => assert_close(value, 7.0),
+        other => panic!("expected scalar, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_histogram_quantile() {
+    let dir = tempdir().unwrap();
+    let store = populated_store(&dir.path().join("store"));
+    let engine = Engine::new(&store);
+    let t = BASE + 599 * S;
+
+    // Rates: 5/s <= 0.1, 9/s <= 0.5, 10/s total. The median (rank 5) is the
+    // upper bound of the first bucket; p70 (rank 7) is halfway into the next.
+    let result = vector(
+        engine
+            .instant_query("histogram_quantile(0.5, rate(latency_bucket[1m]))", t)
+            .unwrap(),
+    );
+    assert_eq!(result.len(), 1);
+    assert_close(result[0].1, 0.1);
+
+    let result = vector(
+        engine
+            .instant_query(
+                "histogram_quantile(0.7, sum by (le) (rate(latency_bucket[1m])))",
+                t,
+            )
+            .unwrap(),
+    );
+    assert_close(result[0].1, 0.3);
+}
+
+#[test]
+fn test_range_query() {
+    let dir = tempdir().unwrap();
+    let store = populated_store(&dir.path().join("store"));
+    let engine = Engine::new(&store);
+
+    let start = BASE + 120 * S;
+    let end = BASE + 599 * S;
+    let Value::Matrix(series) = engine
+        .range_query(
+            "sum by (host) (rate(requests_total[1m]))",
+            start,
+            end,
+            Duration::from_secs(60),
+        )
+        .unwrap()
+    else {
+        panic!("expected matrix");
+    };
+
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[0].labels, labels(&[("host", "a")]));
+    assert_eq!(series[0].points.len(), 8);
+    assert_eq!(series[0].points[0].0, start);
+    for &(_, value) in &series[1].points {
+        assert_close(value, 1.0);
+    }
+
+    // Scalars become a single unlabeled series
+    let Value::Matrix(series) = engine
+        .range_query("time()", start, start + 10 * S, Duration::from_secs(5))
+        .unwrap()
+    else {
+        panic!("expected matrix");
+    };
+    assert_eq!(series.len(), 1);
+    assert!(series[0].labels.is_empty());
+    assert_eq!(series[0].points.len(), 3);
+}
+
+#[test]
+fn test_range_query_step_selects_coarser_tier() {
+    let dir = tempdir().unwrap();
+    let store = populated_store(&dir.path().join("store"));
+    let engine = Engine::new(&store);
+
+    // With a 10m step and a 20m window, the 1s tier may not be used: only
+    // the 60s consolidated points are visible, so the count over a 20m
+    // window is bounded by 20 rather than 600.
+    let end = BASE + 599 * S;
+    let Value::Matrix(series) = engine
+        .range_query(
+            r#"count_over_time(cpu.usage{host="a"}[20m])"#,
+            end - 600 * S,
+            end,
+            Duration::from_secs(600),
+        )
+        .unwrap()
+    else {
+        panic!("expected matrix");
+    };
+
+    assert_eq!(series.len(), 1);
+    let &(_, count) = series[0].points.last().unwrap();
+    assert!(count > 0.0 && count <= 20.0, "count = {count}");
+
+    // An instant query reads the full-resolution tier
+    let result = vector(
+        engine
+            .instant_query(r#"count_over_time(cpu.usage{host="a"}[5m])"#, end)
+            .unwrap(),
+    );
+    assert_close(result[0].1, 300.0);
+}
+
+#[test]
+fn test_query_errors() {
+    let dir = tempdir().unwrap();
+    let store = populated_store(&dir.path().join("store"));
+    let engine = Engine::new(&store);
+    let t = BASE + 599 * S;
+
+    assert!(matches!(
+        engine.instant_query("sum(", t),
+        Err(RondoError::Promql(PromqlError::Parse { .. }))
+    ));
+    assert!(matches!(
+        engine.instant_query("rate(cpu.usage)", t),
+        Err(RondoError::Promql(PromqlError::Type { .. }))
+    ));
+    // Dropping __name__ from two metrics with equal labels collides
+    assert!(matches!(
+        engine.instant_query(r#"abs({__name__=~"cpu.usage|requests_total"})"#, t),
+        Err(RondoError::Promql(PromqlError::Evaluation { .. }))
+    ));
+    assert!(matches!(
+        engine.range_query("cpu.usage", t, t + 3600 * S, Duration::from_millis(100)),
+        Err(RondoError::Promql(PromqlError::Evaluation { .. }))
+    ));
+}